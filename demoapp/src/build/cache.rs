@@ -21,6 +21,12 @@ pub struct BuildCache {
     entries: HashMap<PathBuf, CacheEntry>,
     /// Template directory hash (if templates change, rebuild all).
     template_hash: Option<String>,
+    /// Data directory hash (if data files change, rebuild all).
+    #[serde(default)]
+    data_hash: Option<String>,
+    /// Paginated listing pages written by the last build, relative to the output directory.
+    #[serde(default)]
+    pages: Vec<PathBuf>,
 }
 
 /// Cache entry for a single document.
@@ -133,17 +139,26 @@ impl BuildCache {
         );
     }
 
-    /// Remove entries for documents that no longer exist.
-    pub fn prune(&mut self, existing_sources: &[PathBuf]) {
+    /// Remove entries for documents that are no longer built.
+    ///
+    /// Returns the output paths of the removed entries, so their pages can
+    /// be deleted.
+    pub fn prune(&mut self, existing_sources: &[PathBuf]) -> Vec<PathBuf> {
         let existing: std::collections::HashSet<_> = existing_sources.iter().collect();
-        let before = self.entries.len();
+        let mut removed = Vec::new();
 
-        self.entries.retain(|path, _| existing.contains(path));
+        self.entries.retain(|path, entry| {
+            let keep = existing.contains(path);
+            if !keep {
+                removed.push(entry.output_path.clone());
+            }
+            keep
+        });
 
-        let removed = before - self.entries.len();
-        if removed > 0 {
-            debug!("Pruned {} stale cache entries", removed);
+        if !removed.is_empty() {
+            debug!("Pruned {} stale cache entries", removed.len());
         }
+        removed
     }
 
     /// Get the template hash.
@@ -164,10 +179,40 @@ impl BuildCache {
         }
     }
 
+    /// Set the data directory hash.
+    pub fn set_data_hash(&mut self, hash: String) {
+        self.data_hash = Some(hash);
+    }
+
+    /// Check if data files have changed.
+    pub fn data_changed(&self, current_hash: &str) -> bool {
+        match &self.data_hash {
+            Some(cached) => cached != current_hash,
+            None => true,
+        }
+    }
+
     /// Clear all entries (force full rebuild).
     pub fn clear(&mut self) {
         self.entries.clear();
         self.template_hash = None;
+        self.data_hash = None;
+    }
+
+    /// Record the paginated listing pages written by a build.
+    ///
+    /// Returns the pages from the previous build that were not written
+    /// this time, which should be removed from the output.
+    pub fn update_pages(&mut self, pages: Vec<PathBuf>) -> Vec<PathBuf> {
+        let current: std::collections::HashSet<_> = pages.iter().collect();
+        let stale = self
+            .pages
+            .iter()
+            .filter(|p| !current.contains(p))
+            .cloned()
+            .collect();
+        self.pages = pages;
+        stale
     }
 
     /// Get statistics about the cache.
@@ -191,29 +236,44 @@ pub struct CacheStats {
     pub has_template_hash: bool,
 }
 
-/// Compute a hash for the templates directory.
-pub fn hash_templates(templates_dir: &Path) -> Result<String> {
+/// Compute a hash for the template directories: the collection's and its themes'.
+pub fn hash_templates(template_dirs: &[PathBuf]) -> Result<String> {
     use rustmax::blake3::Hasher;
-    use rustmax::walkdir::WalkDir;
 
     let mut hasher = Hasher::new();
 
-    if !templates_dir.exists() {
+    if !template_dirs.iter().any(|dir| dir.exists()) {
         return Ok("no-templates".to_string());
     }
 
-    // Hash all template files in sorted order for determinism.
-    let mut paths: Vec<_> = WalkDir::new(templates_dir)
-        .into_iter()
-        .filter_map(|e| e.ok())
-        .filter(|e| e.path().is_file())
-        .map(|e| e.path().to_path_buf())
-        .collect();
+    for dir in template_dirs.iter().filter(|dir| dir.exists()) {
+        for path in sorted_files(dir) {
+            if let Ok(content) = std::fs::read(&path) {
+                hasher.update(&content);
+            }
+        }
+    }
 
-    paths.sort();
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Compute a hash for the data directory.
+///
+/// File paths are hashed along with their content, since a data file's
+/// path determines its key in the `data` template variable.
+pub fn hash_data(data_dir: &Path) -> Result<String> {
+    use rustmax::blake3::Hasher;
 
-    for path in paths {
+    let mut hasher = Hasher::new();
+
+    if !data_dir.exists() {
+        return Ok("no-data".to_string());
+    }
+
+    for path in sorted_files(data_dir) {
         if let Ok(content) = std::fs::read(&path) {
+            let relative = path.strip_prefix(data_dir).unwrap_or(&path);
+            hasher.update(relative.to_string_lossy().as_bytes());
             hasher.update(&content);
         }
     }
@@ -221,6 +281,21 @@ pub fn hash_templates(templates_dir: &Path) -> Result<String> {
     Ok(hasher.finalize().to_hex().to_string())
 }
 
+/// All files under a directory, in sorted order for determinism.
+fn sorted_files(dir: &Path) -> Vec<PathBuf> {
+    use rustmax::walkdir::WalkDir;
+
+    let mut paths: Vec<_> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .map(|e| e.path().to_path_buf())
+        .collect();
+
+    paths.sort();
+    paths
+}
+
 /// Build result with cache statistics.
 #[derive(Debug, Clone)]
 pub struct IncrementalBuildResult {
@@ -311,6 +386,16 @@ mod tests {
         assert_eq!(loaded.template_hash(), Some("template-hash"));
     }
 
+    #[test]
+    fn test_cache_update_pages() {
+        let mut cache = BuildCache::new();
+        let stale = cache.update_pages(vec![PathBuf::from("page/2"), PathBuf::from("page/3")]);
+        assert!(stale.is_empty());
+
+        let stale = cache.update_pages(vec![PathBuf::from("page/2")]);
+        assert_eq!(stale, vec![PathBuf::from("page/3")]);
+    }
+
     #[test]
     fn test_cache_prune() {
         let mut cache = BuildCache::new();
@@ -328,8 +413,9 @@ mod tests {
 
         assert_eq!(cache.entries.len(), 2);
 
-        cache.prune(&[PathBuf::from("keep.md")]);
+        let removed = cache.prune(&[PathBuf::from("keep.md")]);
 
+        assert_eq!(removed, vec![PathBuf::from("out/remove")]);
         assert_eq!(cache.entries.len(), 1);
         assert!(cache.entries.contains_key(&PathBuf::from("keep.md")));
     }
@@ -358,17 +444,45 @@ mod tests {
 
         std::fs::write(templates_dir.join("default.html"), "<html></html>").unwrap();
 
-        let hash1 = hash_templates(&templates_dir).unwrap();
+        let hash1 = hash_templates(std::slice::from_ref(&templates_dir)).unwrap();
         assert!(!hash1.is_empty());
 
         // Same content, same hash.
-        let hash2 = hash_templates(&templates_dir).unwrap();
+        let hash2 = hash_templates(std::slice::from_ref(&templates_dir)).unwrap();
         assert_eq!(hash1, hash2);
 
         // Different content, different hash.
         std::fs::write(templates_dir.join("default.html"), "<html>changed</html>").unwrap();
-        let hash3 = hash_templates(&templates_dir).unwrap();
+        let hash3 = hash_templates(std::slice::from_ref(&templates_dir)).unwrap();
         assert_ne!(hash1, hash3);
+
+        // Theme templates count too.
+        let theme_dir = dir.path().join("themes/plain/templates");
+        std::fs::create_dir_all(&theme_dir).unwrap();
+        std::fs::write(theme_dir.join("base.html"), "<main></main>").unwrap();
+        let hash4 = hash_templates(&[templates_dir, theme_dir]).unwrap();
+        assert_ne!(hash3, hash4);
+    }
+
+    #[test]
+    fn test_hash_data() {
+        let dir = tempdir().unwrap();
+        let data_dir = dir.path().join("data");
+        assert_eq!(hash_data(&data_dir).unwrap(), "no-data");
+
+        std::fs::create_dir(&data_dir).unwrap();
+        std::fs::write(data_dir.join("nav.toml"), "a = 1").unwrap();
+        let hash1 = hash_data(&data_dir).unwrap();
+
+        let mut cache = BuildCache::new();
+        assert!(cache.data_changed(&hash1));
+        cache.set_data_hash(hash1.clone());
+        assert!(!cache.data_changed(&hash1));
+
+        // Renaming a file changes its key, so it changes the hash.
+        std::fs::rename(data_dir.join("nav.toml"), data_dir.join("menu.toml")).unwrap();
+        let hash2 = hash_data(&data_dir).unwrap();
+        assert!(cache.data_changed(&hash2));
     }
 
     #[test]
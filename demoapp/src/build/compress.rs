@@ -1,33 +1,160 @@
-//! Asset compression using flate2.
+//! Asset precompression using flate2, brotli, and zstd.
 
 use rustmax::prelude::*;
 use rustmax::flate2::write::GzEncoder;
 use rustmax::flate2::Compression;
 use rustmax::walkdir::WalkDir;
 use rustmax::log::{info, debug};
-use std::io::Write;
-use std::path::Path;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::fs;
 
 use crate::Result;
 
+/// A precompression algorithm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressionAlgorithm {
+    /// gzip, written as `.gz`.
+    Gzip,
+    /// Brotli, written as `.br`.
+    Brotli,
+    /// Zstandard, written as `.zst`.
+    Zstd,
+}
+
+impl CompressionAlgorithm {
+    /// All supported algorithms.
+    pub const ALL: [Self; 3] = [Self::Gzip, Self::Brotli, Self::Zstd];
+
+    /// Parse an algorithm name (e.g. "gzip", "br", "zstd").
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "gzip" | "gz" => Some(Self::Gzip),
+            "br" | "brotli" => Some(Self::Brotli),
+            "zstd" | "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Parse a comma-separated list of algorithm names.
+    pub fn parse_list(s: &str) -> std::result::Result<Vec<Self>, String> {
+        let mut algorithms = Vec::new();
+        for name in s.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+            let algorithm = Self::from_name(name)
+                .ok_or_else(|| format!("unknown compression algorithm `{}` (expected gzip, br, or zstd)", name))?;
+            if !algorithms.contains(&algorithm) {
+                algorithms.push(algorithm);
+            }
+        }
+        Ok(algorithms)
+    }
+
+    /// Name used in `Content-Encoding` and `Accept-Encoding` headers.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Brotli => "br",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    /// File extension appended to compressed variants.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Brotli => "br",
+            Self::Zstd => "zst",
+        }
+    }
+
+    /// Compress data at the highest compression level.
+    pub fn compress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Self::Brotli => {
+                let mut encoder = brotli::CompressorWriter::new(Vec::new(), 4096, 11, 22);
+                encoder.write_all(data)?;
+                Ok(encoder.into_inner())
+            }
+            Self::Zstd => Ok(zstd::encode_all(data, 19)?),
+        }
+    }
+
+    /// Decompress data produced by [`CompressionAlgorithm::compress`].
+    pub fn decompress(self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Gzip => decompress(data),
+            Self::Brotli => {
+                let mut decompressed = Vec::new();
+                brotli::Decompressor::new(data, 4096).read_to_end(&mut decompressed)?;
+                Ok(decompressed)
+            }
+            Self::Zstd => Ok(zstd::decode_all(data)?),
+        }
+    }
+
+    /// Path of the compressed variant of a file.
+    pub fn variant_path(self, path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".");
+        name.push(self.extension());
+        PathBuf::from(name)
+    }
+}
+
+/// File extensions compressed by default.
+pub const DEFAULT_COMPRESS_EXTENSIONS: &[&str] = &["html", "css", "js", "json", "xml", "svg", "txt", "md"];
+
+/// Options for precompressing build output.
+#[derive(Debug, Clone)]
+pub struct CompressOptions {
+    /// Algorithms to produce variants for.
+    pub algorithms: Vec<CompressionAlgorithm>,
+    /// Files smaller than this many bytes are not compressed.
+    pub min_size: u64,
+    /// Extensions of files to compress, without the dot.
+    pub extensions: Vec<String>,
+}
+
+impl Default for CompressOptions {
+    fn default() -> Self {
+        Self {
+            algorithms: vec![CompressionAlgorithm::Gzip],
+            min_size: 256,
+            extensions: DEFAULT_COMPRESS_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+        }
+    }
+}
+
 /// Compress all eligible files in a directory.
 ///
 /// Creates `.gz` versions of HTML, CSS, JS, JSON, and XML files.
 pub fn compress_output(output_dir: &Path) -> Result<CompressStats> {
-    let mut stats = CompressStats::default();
+    compress_output_with(output_dir, &CompressOptions::default())
+}
+
+/// Compress eligible files in a directory with the given options.
+///
+/// Each file gets one variant per algorithm, kept only when it is smaller
+/// than the original. Variants left over from earlier builds are removed
+/// when they are no longer produced.
+pub fn compress_output_with(output_dir: &Path, options: &CompressOptions) -> Result<CompressStats> {
+    let mut stats = CompressStats {
+        algorithms: options.algorithms.iter().map(|&a| AlgorithmStats::new(a)).collect(),
+        ..Default::default()
+    };
 
     for entry in WalkDir::new(output_dir) {
         let entry = entry?;
         let path = entry.path();
 
-        if path.is_file() && should_compress(path) {
-            match compress_file(path) {
-                Ok(saved) => {
-                    stats.files_compressed += 1;
-                    stats.bytes_saved += saved;
-                    debug!("Compressed: {} (saved {} bytes)", path.display(), saved);
-                }
+        if path.is_file() && should_compress(path, &options.extensions) {
+            match compress_file(path, options) {
+                Ok(sizes) => stats.add(&sizes),
                 Err(e) => {
                     debug!("Failed to compress {}: {}", path.display(), e);
                 }
@@ -35,16 +162,13 @@ pub fn compress_output(output_dir: &Path) -> Result<CompressStats> {
         }
     }
 
-    if stats.files_compressed > 0 {
+    for algorithm in stats.algorithms.iter().filter(|a| a.files_compressed > 0) {
         info!(
-            "Compressed {} files, saved {} bytes ({:.1}%)",
-            stats.files_compressed,
-            stats.bytes_saved,
-            if stats.original_size > 0 {
-                (stats.bytes_saved as f64 / stats.original_size as f64) * 100.0
-            } else {
-                0.0
-            }
+            "{}: compressed {} files, saved {} bytes ({:.1}%)",
+            algorithm.algorithm.name(),
+            algorithm.files_compressed,
+            algorithm.bytes_saved(),
+            algorithm.compression_ratio() * 100.0
         );
     }
 
@@ -52,12 +176,20 @@ pub fn compress_output(output_dir: &Path) -> Result<CompressStats> {
 }
 
 /// Compression statistics.
+///
+/// The totals count each file once, at the size of its smallest variant.
 #[derive(Debug, Default)]
 pub struct CompressStats {
+    /// Files with at least one compressed variant.
     pub files_compressed: usize,
+    /// Original size of those files.
     pub original_size: u64,
+    /// Size of the smallest variant of each file.
     pub compressed_size: u64,
+    /// Bytes saved by serving the smallest variants.
     pub bytes_saved: u64,
+    /// Statistics for each algorithm, in the order requested.
+    pub algorithms: Vec<AlgorithmStats>,
 }
 
 impl CompressStats {
@@ -68,48 +200,121 @@ impl CompressStats {
             1.0 - (self.compressed_size as f64 / self.original_size as f64)
         }
     }
+
+    /// Statistics for one algorithm, if it was requested.
+    pub fn algorithm(&self, algorithm: CompressionAlgorithm) -> Option<&AlgorithmStats> {
+        self.algorithms.iter().find(|a| a.algorithm == algorithm)
+    }
+
+    fn add(&mut self, file: &FileSizes) {
+        let mut smallest = None;
+        for &(algorithm, compressed_size) in &file.variants {
+            if let Some(stats) = self.algorithms.iter_mut().find(|a| a.algorithm == algorithm) {
+                stats.files_compressed += 1;
+                stats.original_size += file.original_size;
+                stats.compressed_size += compressed_size;
+            }
+            smallest = Some(smallest.map_or(compressed_size, |s: u64| s.min(compressed_size)));
+        }
+
+        if let Some(smallest) = smallest {
+            self.files_compressed += 1;
+            self.original_size += file.original_size;
+            self.compressed_size += smallest;
+            self.bytes_saved += file.original_size - smallest;
+        }
+    }
+}
+
+/// Compression statistics for a single algorithm.
+#[derive(Debug)]
+pub struct AlgorithmStats {
+    /// The algorithm.
+    pub algorithm: CompressionAlgorithm,
+    /// Files with a variant for this algorithm.
+    pub files_compressed: usize,
+    /// Original size of those files.
+    pub original_size: u64,
+    /// Total size of the variants.
+    pub compressed_size: u64,
+}
+
+impl AlgorithmStats {
+    fn new(algorithm: CompressionAlgorithm) -> Self {
+        Self {
+            algorithm,
+            files_compressed: 0,
+            original_size: 0,
+            compressed_size: 0,
+        }
+    }
+
+    /// Bytes saved by this algorithm's variants.
+    pub fn bytes_saved(&self) -> u64 {
+        self.original_size - self.compressed_size
+    }
+
+    pub fn compression_ratio(&self) -> f64 {
+        if self.original_size == 0 {
+            0.0
+        } else {
+            1.0 - (self.compressed_size as f64 / self.original_size as f64)
+        }
+    }
+}
+
+/// Sizes of a file and the variants written for it.
+struct FileSizes {
+    original_size: u64,
+    variants: Vec<(CompressionAlgorithm, u64)>,
 }
 
 /// Check if a file should be compressed.
-fn should_compress(path: &Path) -> bool {
+fn should_compress(path: &Path, extensions: &[String]) -> bool {
     let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
-    matches!(ext, "html" | "css" | "js" | "json" | "xml" | "svg" | "txt" | "md")
+
+    // Never compress existing variants.
+    if CompressionAlgorithm::ALL.iter().any(|a| a.extension() == ext) {
+        return false;
+    }
+    extensions.iter().any(|e| e.eq_ignore_ascii_case(ext))
 }
 
-/// Compress a single file, creating a `.gz` version.
+/// Compress a single file, writing a variant per algorithm.
 ///
-/// Returns the number of bytes saved.
-fn compress_file(path: &Path) -> Result<u64> {
+/// Variants that would not be smaller than the original are removed.
+fn compress_file(path: &Path, options: &CompressOptions) -> Result<FileSizes> {
     let content = fs::read(path)?;
     let original_size = content.len() as u64;
+    let mut variants = Vec::new();
 
-    // Skip small files.
-    if original_size < 256 {
-        return Ok(0);
-    }
+    for algorithm in CompressionAlgorithm::ALL {
+        let variant_path = algorithm.variant_path(path);
+        let selected = options.algorithms.contains(&algorithm) && original_size >= options.min_size;
 
-    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
-    encoder.write_all(&content)?;
-    let compressed = encoder.finish()?;
-    let compressed_size = compressed.len() as u64;
+        let compressed = if selected { Some(algorithm.compress(&content)?) } else { None };
 
-    // Only save if we actually achieved compression.
-    if compressed_size < original_size {
-        let gz_path = path.with_extension(format!(
-            "{}.gz",
-            path.extension().and_then(|e| e.to_str()).unwrap_or("")
-        ));
-        fs::write(&gz_path, compressed)?;
-        Ok(original_size - compressed_size)
-    } else {
-        Ok(0)
+        // Only save if we actually achieved compression.
+        match compressed.filter(|c| (c.len() as u64) < original_size) {
+            Some(compressed) => {
+                fs::write(&variant_path, &compressed)?;
+                debug!("Compressed: {} ({} bytes)", variant_path.display(), compressed.len());
+                variants.push((algorithm, compressed.len() as u64));
+            }
+            None => {
+                if variant_path.exists() {
+                    fs::remove_file(&variant_path)?;
+                }
+            }
+        }
     }
+
+    Ok(FileSizes { original_size, variants })
 }
 
 /// Decompress gzip content.
 pub fn decompress(data: &[u8]) -> Result<Vec<u8>> {
     use rustmax::flate2::read::GzDecoder;
-    use std::io::Read;
 
     let mut decoder = GzDecoder::new(data);
     let mut decompressed = Vec::new();
@@ -148,12 +353,78 @@ mod tests {
 
     #[test]
     fn test_should_compress() {
-        assert!(should_compress(Path::new("index.html")));
-        assert!(should_compress(Path::new("style.css")));
-        assert!(should_compress(Path::new("app.js")));
-        assert!(should_compress(Path::new("data.json")));
-        assert!(!should_compress(Path::new("image.png")));
-        assert!(!should_compress(Path::new("photo.jpg")));
+        let extensions = CompressOptions::default().extensions;
+        assert!(should_compress(Path::new("index.html"), &extensions));
+        assert!(should_compress(Path::new("style.css"), &extensions));
+        assert!(should_compress(Path::new("app.js"), &extensions));
+        assert!(should_compress(Path::new("data.json"), &extensions));
+        assert!(!should_compress(Path::new("image.png"), &extensions));
+        assert!(!should_compress(Path::new("photo.jpg"), &extensions));
+
+        let extensions = vec!["gz".to_string(), "WASM".to_string()];
+        assert!(should_compress(Path::new("app.wasm"), &extensions));
+        assert!(!should_compress(Path::new("index.html.gz"), &extensions));
+    }
+
+    #[test]
+    fn test_algorithm_round_trip() {
+        let original = b"Hello, World! ".repeat(100);
+        for algorithm in CompressionAlgorithm::ALL {
+            let compressed = algorithm.compress(&original).unwrap();
+            assert!(compressed.len() < original.len());
+            assert_eq!(algorithm.decompress(&compressed).unwrap(), original);
+        }
+    }
+
+    #[test]
+    fn test_parse_algorithm_list() {
+        use CompressionAlgorithm::*;
+
+        assert_eq!(CompressionAlgorithm::parse_list("gzip, br,zstd").unwrap(), vec![Gzip, Brotli, Zstd]);
+        assert_eq!(CompressionAlgorithm::parse_list("Brotli,gz,br").unwrap(), vec![Brotli, Gzip]);
+        assert!(CompressionAlgorithm::parse_list("gzip,lzma").unwrap_err().contains("lzma"));
+        assert_eq!(Zstd.variant_path(Path::new("out/app.js")), Path::new("out/app.js.zst"));
+    }
+
+    #[test]
+    fn test_compress_output_with_options() {
+        use rustmax::tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let big = "<p>Hello, World!</p>\n".repeat(100);
+        fs::write(dir.path().join("index.html"), &big).unwrap();
+        fs::write(dir.path().join("small.css"), "a{}").unwrap();
+        fs::write(dir.path().join("data.bin"), &big).unwrap();
+        fs::write(dir.path().join("small.css.gz"), "stale").unwrap();
+
+        let options = CompressOptions {
+            algorithms: vec![CompressionAlgorithm::Brotli, CompressionAlgorithm::Zstd],
+            min_size: 100,
+            extensions: vec!["html".to_string(), "css".to_string()],
+        };
+        let stats = compress_output_with(dir.path(), &options).unwrap();
+
+        assert!(dir.path().join("index.html.br").exists());
+        assert!(dir.path().join("index.html.zst").exists());
+        assert!(!dir.path().join("index.html.gz").exists());
+        assert!(!dir.path().join("small.css.br").exists());
+        assert!(!dir.path().join("small.css.gz").exists(), "stale variant should be removed");
+        assert!(!dir.path().join("data.bin.br").exists());
+
+        assert_eq!(stats.files_compressed, 1);
+        assert_eq!(stats.original_size, big.len() as u64);
+        assert_eq!(stats.bytes_saved, stats.original_size - stats.compressed_size);
+        assert_eq!(stats.algorithms.len(), 2);
+
+        let br = stats.algorithm(CompressionAlgorithm::Brotli).unwrap();
+        assert_eq!(br.files_compressed, 1);
+        assert!(br.compression_ratio() > 0.5);
+        assert!(stats.algorithm(CompressionAlgorithm::Gzip).is_none());
+
+        let decompressed = CompressionAlgorithm::Zstd
+            .decompress(&fs::read(dir.path().join("index.html.zst")).unwrap())
+            .unwrap();
+        assert_eq!(decompressed, big.as_bytes());
     }
 
     #[test]
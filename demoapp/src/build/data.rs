@@ -0,0 +1,164 @@
+//! Data files exposed to templates as `data`.
+//!
+//! Every TOML, JSON, and YAML file under the collection's `data/`
+//! directory is loaded into one object keyed by its path, so
+//! `data/authors.toml` becomes `data.authors` and
+//! `data/team/roster.yaml` becomes `data.team.roster`.
+
+use rustmax::prelude::*;
+use rustmax::serde_json::{Map, Value};
+use rustmax::walkdir::WalkDir;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::template::toml_to_tera_value;
+use crate::{Error, Result};
+
+/// Directory holding data files, relative to the collection root.
+pub const DATA_DIR: &str = "data";
+
+/// Check whether a path has a data file extension.
+pub fn is_data_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("toml" | "json" | "yaml" | "yml")
+    )
+}
+
+/// Load every data file under `data_dir` into a single object.
+///
+/// A missing directory yields an empty object. Files with other
+/// extensions and hidden files are ignored.
+pub fn load_data(data_dir: &Path) -> Result<Value> {
+    let mut files: BTreeMap<Vec<String>, PathBuf> = BTreeMap::new();
+
+    if !data_dir.exists() {
+        return Ok(Value::Object(Map::new()));
+    }
+
+    for entry in WalkDir::new(data_dir).sort_by_file_name() {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file() || !is_data_file(path) {
+            continue;
+        }
+
+        let relative = path.strip_prefix(data_dir).unwrap_or(path);
+        let mut key: Vec<String> = relative
+            .parent()
+            .into_iter()
+            .flat_map(|p| p.components())
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        key.push(
+            path.file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        );
+        if key.iter().any(|k| k.starts_with('.')) {
+            continue;
+        }
+
+        // `nav.toml` and `nav.json`, or `team.toml` and `team/`, would
+        // both claim the same key.
+        if let Some((_, other)) = files
+            .iter()
+            .find(|(k, _)| k.starts_with(&key) || key.starts_with(k))
+        {
+            return Err(Error::data(
+                path,
+                format!("conflicts with {} for `data.{}`", other.display(), key.join(".")),
+            ));
+        }
+
+        files.insert(key, path.to_path_buf());
+    }
+
+    let mut data = Map::new();
+    for (key, path) in &files {
+        let value = parse_data_file(path)?;
+        let (name, parents) = key.split_last().expect("data keys are never empty");
+        let mut table = &mut data;
+        for parent in parents {
+            table = match table
+                .entry(parent.clone())
+                .or_insert_with(|| Value::Object(Map::new()))
+            {
+                Value::Object(map) => map,
+                _ => unreachable!("conflicting data keys are rejected above"),
+            };
+        }
+        table.insert(name.clone(), value);
+    }
+
+    Ok(Value::Object(data))
+}
+
+/// Parse one data file according to its extension.
+fn parse_data_file(path: &Path) -> Result<Value> {
+    let source = std::fs::read_to_string(path)?;
+
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => {
+            let value: rustmax::toml::Value = rustmax::toml::from_str(&source)
+                .map_err(|e| Error::data(path, e.to_string()))?;
+            Ok(toml_to_tera_value(&value))
+        }
+        Some("json") => {
+            rustmax::serde_json::from_str(&source).map_err(|e| Error::data(path, e.to_string()))
+        }
+        _ => crate::yaml::parse(&source).map_err(|e| Error::data(path, e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustmax::serde_json::json;
+    use rustmax::tempfile::tempdir;
+
+    #[test]
+    fn test_load_data() {
+        let dir = tempdir().unwrap();
+        let data_dir = dir.path().join(DATA_DIR);
+        std::fs::create_dir_all(data_dir.join("team")).unwrap();
+
+        std::fs::write(data_dir.join("site.toml"), "name = \"Docs\"\nyear = 2024\n").unwrap();
+        std::fs::write(data_dir.join("links.json"), r#"[{"url": "/a/"}]"#).unwrap();
+        std::fs::write(data_dir.join("team/roster.yaml"), "- name: Ada\n- name: Grace\n").unwrap();
+        std::fs::write(data_dir.join("notes.txt"), "ignored").unwrap();
+
+        let data = load_data(&data_dir).unwrap();
+        assert_eq!(
+            data,
+            json!({
+                "site": {"name": "Docs", "year": 2024},
+                "links": [{"url": "/a/"}],
+                "team": {"roster": [{"name": "Ada"}, {"name": "Grace"}]},
+            })
+        );
+    }
+
+    #[test]
+    fn test_load_data_missing_dir() {
+        let dir = tempdir().unwrap();
+        assert_eq!(load_data(&dir.path().join(DATA_DIR)).unwrap(), json!({}));
+    }
+
+    #[test]
+    fn test_load_data_errors() {
+        let dir = tempdir().unwrap();
+        let data_dir = dir.path().join(DATA_DIR);
+        std::fs::create_dir_all(&data_dir).unwrap();
+
+        std::fs::write(data_dir.join("nav.toml"), "a = 1\n").unwrap();
+        std::fs::write(data_dir.join("nav.yml"), "a: 1\n").unwrap();
+        let err = load_data(&data_dir).unwrap_err();
+        assert!(err.to_string().contains("data.nav"));
+
+        std::fs::remove_file(data_dir.join("nav.yml")).unwrap();
+        std::fs::write(data_dir.join("bad.json"), "{").unwrap();
+        let err = load_data(&data_dir).unwrap_err();
+        assert!(err.to_string().contains("bad.json"));
+    }
+}
@@ -3,6 +3,10 @@
 //! Uses the image crate for resizing, format conversion, and optimization.
 
 use rustmax::image::{self, DynamicImage, ImageFormat, imageops::FilterType, GenericImageView};
+use rustmax::blake3;
+use rustmax::rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::fs;
 use crate::Result;
@@ -27,6 +31,8 @@ pub struct ImageConfig {
     pub generate_thumbnails: bool,
     /// Preserve original images.
     pub preserve_originals: bool,
+    /// Widths of resized copies to generate for `srcset`.
+    pub widths: Vec<u32>,
 }
 
 impl Default for ImageConfig {
@@ -40,6 +46,7 @@ impl Default for ImageConfig {
             generate_webp: true,
             generate_thumbnails: true,
             preserve_originals: true,
+            widths: vec![480, 960, 1440],
         }
     }
 }
@@ -55,6 +62,8 @@ pub struct ImageResult {
     pub thumbnail: Option<PathBuf>,
     /// WebP path (if generated).
     pub webp: Option<PathBuf>,
+    /// Resized copies for `srcset`, smallest first.
+    pub variants: Vec<ImageVariant>,
     /// Original dimensions.
     pub original_size: (u32, u32),
     /// Output dimensions.
@@ -65,6 +74,19 @@ pub struct ImageResult {
     pub output_bytes: u64,
 }
 
+/// A resized copy of an image.
+#[derive(Debug)]
+pub struct ImageVariant {
+    /// Variant width.
+    pub width: u32,
+    /// Variant height.
+    pub height: u32,
+    /// Variant file path.
+    pub path: PathBuf,
+    /// WebP version of the variant (if generated).
+    pub webp: Option<PathBuf>,
+}
+
 /// Supported image formats.
 fn is_supported_format(path: &Path) -> bool {
     match path.extension().and_then(|e| e.to_str()) {
@@ -174,7 +196,12 @@ pub fn process_image(
 
     save_image(&processed, &output_path, ext, config.jpeg_quality)?;
 
-    let output_bytes = fs::metadata(&output_path)?.len();
+    // A re-encode at the same size that came out larger is no optimization.
+    let mut output_bytes = fs::metadata(&output_path)?.len();
+    if (out_width, out_height) == (orig_width, orig_height) && output_bytes >= original_bytes {
+        fs::copy(source, &output_path)?;
+        output_bytes = original_bytes;
+    }
 
     // Generate thumbnail if configured.
     let thumbnail = if config.generate_thumbnails {
@@ -197,11 +224,37 @@ pub fn process_image(
         None
     };
 
+    // Generate smaller copies for srcset.
+    let mut widths = config.widths.clone();
+    widths.sort_unstable();
+    widths.dedup();
+
+    let mut variants = Vec::new();
+    for width in widths.into_iter().filter(|&w| w > 0 && w < out_width) {
+        let height = ((out_height as u64 * width as u64) / out_width as u64).max(1) as u32;
+        let resized = processed.resize_exact(width, height, FilterType::Lanczos3);
+        let stem = source.file_stem().unwrap().to_string_lossy();
+
+        let path = output_dir.join(format!("{}-{}w.{}", stem, width, ext));
+        save_image(&resized, &path, ext, config.jpeg_quality)?;
+
+        let webp = if config.generate_webp {
+            let webp_path = output_dir.join(format!("{}-{}w.webp", stem, width));
+            save_image(&resized, &webp_path, "webp", config.jpeg_quality)?;
+            Some(webp_path)
+        } else {
+            None
+        };
+
+        variants.push(ImageVariant { width, height, path, webp });
+    }
+
     Ok(ImageResult {
         source: source.to_path_buf(),
         output: output_path,
         thumbnail,
         webp,
+        variants,
         original_size: (orig_width, orig_height),
         output_size: (out_width, out_height),
         original_bytes,
@@ -274,6 +327,222 @@ pub fn process_directory(
     Ok(results)
 }
 
+/// Generated files for one source image, as stored in the image cache.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessedImage {
+    /// Main image file name.
+    pub file: String,
+    /// Main image width.
+    pub width: u32,
+    /// Main image height.
+    pub height: u32,
+    /// WebP version of the main image.
+    pub webp: Option<String>,
+    /// Resized copies as (width, file, webp file), smallest first.
+    pub variants: Vec<(u32, String, Option<String>)>,
+}
+
+impl ProcessedImage {
+    fn from_result(result: &ImageResult) -> Self {
+        let name = |p: &Path| p.file_name().unwrap().to_string_lossy().into_owned();
+        Self {
+            file: name(&result.output),
+            width: result.output_size.0,
+            height: result.output_size.1,
+            webp: result.webp.as_deref().map(name),
+            variants: result
+                .variants
+                .iter()
+                .map(|v| (v.width, name(&v.path), v.webp.as_deref().map(name)))
+                .collect(),
+        }
+    }
+}
+
+/// An optimized image as referenced from HTML.
+#[derive(Debug, Clone)]
+pub struct ResponsiveImage {
+    /// Intrinsic width of the main image.
+    pub width: u32,
+    /// Intrinsic height of the main image.
+    pub height: u32,
+    /// Candidates in the original format as (url, width), smallest first.
+    pub srcset: Vec<(String, u32)>,
+    /// WebP candidates as (url, width), smallest first.
+    pub webp_srcset: Vec<(String, u32)>,
+}
+
+impl ResponsiveImage {
+    fn new(url_dir: &str, image: &ProcessedImage) -> Self {
+        let url = |file: &str| format!("{}{}", url_dir, file);
+
+        let mut srcset: Vec<_> = image.variants.iter().map(|(w, f, _)| (url(f), *w)).collect();
+        srcset.push((url(&image.file), image.width));
+
+        let webp_srcset = match &image.webp {
+            Some(webp) => {
+                let mut set: Vec<_> = image
+                    .variants
+                    .iter()
+                    .filter_map(|(w, _, webp)| webp.as_deref().map(|f| (url(f), *w)))
+                    .collect();
+                set.push((url(webp), image.width));
+                set
+            }
+            None => Vec::new(),
+        };
+
+        Self {
+            width: image.width,
+            height: image.height,
+            srcset,
+            webp_srcset,
+        }
+    }
+}
+
+/// Optimized images keyed by the URL path of their source (e.g. `/img/a.jpg`).
+pub type ImageManifest = HashMap<String, ResponsiveImage>;
+
+/// Name of the per-image record in a cache entry.
+const CACHE_ENTRY_FILE: &str = "image.json";
+
+/// Every image under `source_dirs`, paired with its path in the output.
+///
+/// Each source directory maps to the output root, so `static/img/a.png`
+/// is written to `img/a.png`.
+pub fn image_sources(source_dirs: &[PathBuf]) -> Result<Vec<(PathBuf, PathBuf)>> {
+    use rustmax::walkdir::WalkDir;
+
+    let mut sources = Vec::new();
+    for source_dir in source_dirs.iter().filter(|d| d.exists()) {
+        for entry in WalkDir::new(source_dir) {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_file() && is_supported_format(path) {
+                let relative = path.strip_prefix(source_dir).unwrap().to_path_buf();
+                sources.push((path.to_path_buf(), relative));
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Optimize images into `output_dir`.
+///
+/// `sources` pairs each image with its path relative to `output_dir`, as
+/// [`image_sources`] does; where two have the same output path, the later
+/// one's image is used. Files that aren't supported images are skipped.
+/// Results are cached in `cache_dir` by a hash of the image contents and the
+/// configuration, and unchanged images are copied from the cache instead of
+/// being re-encoded. Images that fail to process are copied unchanged.
+pub fn optimize_images(
+    sources: &[(PathBuf, PathBuf)],
+    output_dir: &Path,
+    cache_dir: &Path,
+    config: &ImageConfig,
+) -> Result<(ImageManifest, ImageStats)> {
+    let mut sources: Vec<&(PathBuf, PathBuf)> =
+        sources.iter().filter(|(path, _)| is_supported_format(path)).collect();
+    let mut seen = HashSet::new();
+    sources.reverse();
+    sources.retain(|(_, relative)| seen.insert(relative.clone()));
+
+    // Each source yields its URL, responsive image, and fresh result if not cached.
+    type Optimized = (String, ResponsiveImage, Option<ImageResult>);
+    let results: Vec<Result<Option<Optimized>>> = sources
+        .par_iter()
+        .map(|(path, relative)| {
+            let out_subdir = match relative.parent() {
+                Some(parent) => output_dir.join(parent),
+                None => output_dir.to_path_buf(),
+            };
+
+            match process_cached(path, cache_dir, config) {
+                Ok((entry_dir, image, result)) => {
+                    copy_cache_entry(&entry_dir, &out_subdir)?;
+                    let url = url_path(relative);
+                    let url_dir = &url[..=url.rfind('/').unwrap()];
+                    let responsive = ResponsiveImage::new(url_dir, &image);
+                    Ok(Some((url, responsive, result)))
+                }
+                Err(e) => {
+                    rustmax::log::warn!("Failed to process {}: {}", path.display(), e);
+                    fs::create_dir_all(&out_subdir)?;
+                    fs::copy(path, output_dir.join(relative))?;
+                    Ok(None)
+                }
+            }
+        })
+        .collect();
+
+    let mut manifest = ImageManifest::new();
+    let mut stats = ImageStats::default();
+    for result in results {
+        if let Some((url, responsive, processed)) = result? {
+            match processed {
+                Some(processed) => stats.add(&processed),
+                None => stats.cached += 1,
+            }
+            manifest.insert(url, responsive);
+        }
+    }
+
+    Ok((manifest, stats))
+}
+
+/// Process an image into its cache entry, reusing the entry if present.
+///
+/// Returns the entry directory, its record, and the processing result if
+/// the image was not already cached.
+fn process_cached(
+    source: &Path,
+    cache_dir: &Path,
+    config: &ImageConfig,
+) -> Result<(PathBuf, ProcessedImage, Option<ImageResult>)> {
+    let data = fs::read(source)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&data);
+    hasher.update(source.file_name().unwrap().as_encoded_bytes());
+    hasher.update(format!("{:?}", config).as_bytes());
+    let entry_dir = cache_dir.join(hasher.finalize().to_hex().as_str());
+    let entry_file = entry_dir.join(CACHE_ENTRY_FILE);
+
+    if let Ok(json) = fs::read_to_string(&entry_file)
+        && let Ok(image) = rustmax::serde_json::from_str(&json)
+    {
+        return Ok((entry_dir, image, None));
+    }
+
+    // Start from an empty entry so stale partial output is not reused.
+    if entry_dir.exists() {
+        fs::remove_dir_all(&entry_dir)?;
+    }
+    let result = process_image(source, &entry_dir, config)?;
+    let image = ProcessedImage::from_result(&result);
+    fs::write(&entry_file, rustmax::serde_json::to_string(&image)?)?;
+
+    Ok((entry_dir, image, Some(result)))
+}
+
+/// Copy the generated files of a cache entry into an output directory.
+fn copy_cache_entry(entry_dir: &Path, out_dir: &Path) -> Result<()> {
+    fs::create_dir_all(out_dir)?;
+    for entry in fs::read_dir(entry_dir)? {
+        let entry = entry?;
+        if entry.file_name() != CACHE_ENTRY_FILE {
+            fs::copy(entry.path(), out_dir.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Root-relative URL path for a file relative to the output root.
+fn url_path(relative: &Path) -> String {
+    let parts: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+    format!("/{}", parts.join("/"))
+}
+
 /// Statistics from image processing.
 #[derive(Debug, Default)]
 pub struct ImageStats {
@@ -287,6 +556,8 @@ pub struct ImageStats {
     pub original_bytes: u64,
     /// Total output bytes.
     pub output_bytes: u64,
+    /// Number of images reused from the image cache.
+    pub cached: usize,
 }
 
 impl ImageStats {
@@ -387,6 +658,66 @@ mod tests {
         assert!(config.generate_thumbnails);
     }
 
+    #[test]
+    fn test_process_image_variants() {
+        use image::{RgbImage, ImageBuffer};
+
+        let dir = tempdir().unwrap();
+        let input_path = dir.path().join("wide.png");
+        let img: RgbImage = ImageBuffer::from_fn(200, 100, |x, _| image::Rgb([x as u8, 0, 0]));
+        img.save(&input_path).unwrap();
+
+        let config = ImageConfig {
+            widths: vec![150, 50, 400],
+            generate_thumbnails: false,
+            generate_webp: false,
+            ..Default::default()
+        };
+
+        let result = process_image(&input_path, &dir.path().join("out"), &config).unwrap();
+
+        // Widths at or above the image width are skipped.
+        let widths: Vec<_> = result.variants.iter().map(|v| (v.width, v.height)).collect();
+        assert_eq!(widths, vec![(50, 25), (150, 75)]);
+        assert!(result.variants.iter().all(|v| v.path.exists()));
+    }
+
+    #[test]
+    fn test_optimize_images_uses_cache() {
+        use image::{RgbImage, ImageBuffer};
+
+        let dir = tempdir().unwrap();
+        let static_dir = dir.path().join("static");
+        let cache_dir = dir.path().join("cache");
+        fs::create_dir_all(static_dir.join("img")).unwrap();
+        let img: RgbImage = ImageBuffer::from_fn(64, 32, |x, y| image::Rgb([x as u8, y as u8, 0]));
+        img.save(static_dir.join("img/a.png")).unwrap();
+
+        let config = ImageConfig {
+            widths: vec![32],
+            generate_thumbnails: false,
+            ..Default::default()
+        };
+
+        let sources = image_sources(&[static_dir]).unwrap();
+        let out1 = dir.path().join("out1");
+        let (manifest, stats) = optimize_images(&sources, &out1, &cache_dir, &config).unwrap();
+        assert_eq!(stats.processed, 1);
+        assert_eq!(stats.cached, 0);
+
+        let image = &manifest["/img/a.png"];
+        assert_eq!((image.width, image.height), (64, 32));
+        assert_eq!(image.srcset, vec![("/img/a-32w.png".to_string(), 32), ("/img/a.png".to_string(), 64)]);
+        assert_eq!(image.webp_srcset.last().unwrap().0, "/img/a.webp");
+        assert!(out1.join("img/a-32w.webp").exists());
+
+        let out2 = dir.path().join("out2");
+        let (_, stats) = optimize_images(&sources, &out2, &cache_dir, &config).unwrap();
+        assert_eq!(stats.processed, 0);
+        assert_eq!(stats.cached, 1);
+        assert!(out2.join("img/a-32w.png").exists());
+    }
+
     #[test]
     fn test_image_stats() {
         let mut stats = ImageStats::default();
@@ -433,6 +764,33 @@ mod tests {
         assert_eq!(result.output_size, (50, 50));
     }
 
+    #[test]
+    fn test_process_image_keeps_smaller_original() {
+        use image::{RgbImage, ImageBuffer};
+
+        let dir = tempdir().unwrap();
+        let img: RgbImage = ImageBuffer::from_fn(64, 64, |x, y| image::Rgb([(x * 4) as u8, (y * 4) as u8, 128]));
+        let input_path = dir.path().join("low.jpg");
+        let mut file = fs::File::create(&input_path).unwrap();
+        img.write_with_encoder(image::codecs::jpeg::JpegEncoder::new_with_quality(&mut file, 10)).unwrap();
+        drop(file);
+
+        // Re-encoding at quality 100 only makes the file larger.
+        let config = ImageConfig {
+            jpeg_quality: 100,
+            generate_thumbnails: false,
+            generate_webp: false,
+            ..Default::default()
+        };
+        let result = process_image(&input_path, &dir.path().join("out"), &config).unwrap();
+        assert_eq!(fs::read(&result.output).unwrap(), fs::read(&input_path).unwrap());
+        assert_eq!(result.output_bytes, result.original_bytes);
+
+        let mut stats = ImageStats::default();
+        stats.add(&result);
+        assert_eq!(stats.bytes_saved(), 0);
+    }
+
     #[test]
     fn test_process_image_with_thumbnail() {
         use image::{RgbImage, ImageBuffer};
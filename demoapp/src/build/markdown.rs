@@ -29,6 +29,14 @@ pub fn render_markdown_highlighted(content: &str, options: &HighlightOptions) ->
 
 /// Render markdown with custom comrak options.
 fn render_markdown_internal(content: &str, _base_options: &Options) -> String {
+    markdown_to_html(content, &markdown_options())
+}
+
+/// The comrak options used to render documents.
+///
+/// Exporters that walk the markdown AST parse with these so they see the
+/// same extensions as the HTML build.
+pub fn markdown_options() -> Options<'static> {
     let mut options = Options::default();
 
     // Enable common extensions.
@@ -39,7 +47,7 @@ fn render_markdown_internal(content: &str, _base_options: &Options) -> String {
         autolink: true,
         tasklist: true,
         superscript: true,
-        header_ids: Some("heading-".to_string()),
+        header_ids: Some(String::new()),
         footnotes: true,
         description_lists: true,
         front_matter_delimiter: None,
@@ -71,7 +79,7 @@ fn render_markdown_internal(content: &str, _base_options: &Options) -> String {
         ..Default::default()
     };
 
-    markdown_to_html(content, &options)
+    options
 }
 
 /// Apply syntax highlighting to code blocks in HTML.
@@ -0,0 +1,553 @@
+//! HTML, CSS, and JavaScript minification.
+//!
+//! The minifiers are conservative: they strip comments and collapse
+//! whitespace, but never rename or reorder anything.
+
+use rustmax::prelude::*;
+use rustmax::walkdir::WalkDir;
+use rustmax::log::{info, debug};
+use std::path::Path;
+use std::fs;
+
+use crate::Result;
+
+/// Excerpt marker that must survive comment stripping.
+const MORE_MARKER: &str = "<!--more-->";
+
+/// Elements whose contents are copied verbatim.
+const RAW_ELEMENTS: &[&str] = &["pre", "code", "textarea"];
+
+/// Generated assets minified alongside HTML pages.
+const GENERATED_ASSETS: &[&str] = &["highlight.css", "toc.css", "search.js"];
+
+/// Minify all HTML files and generated assets in a directory.
+pub fn minify_output(output_dir: &Path) -> Result<MinifyStats> {
+    let mut stats = MinifyStats::default();
+
+    for entry in WalkDir::new(output_dir) {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.is_file() && path.extension().is_some_and(|e| e == "html") {
+            minify_file(path, minify_html, &mut stats)?;
+        }
+    }
+
+    for name in GENERATED_ASSETS {
+        let path = output_dir.join(name);
+        if path.is_file() {
+            let minify = if name.ends_with(".css") { minify_css } else { minify_js };
+            minify_file(&path, minify, &mut stats)?;
+        }
+    }
+
+    if stats.files_minified > 0 {
+        info!(
+            "Minified {} files, saved {} bytes ({:.1}%)",
+            stats.files_minified,
+            stats.bytes_saved,
+            stats.savings_percent()
+        );
+    }
+
+    Ok(stats)
+}
+
+/// Minification statistics.
+#[derive(Debug, Default)]
+pub struct MinifyStats {
+    pub files_minified: usize,
+    pub original_size: u64,
+    pub minified_size: u64,
+    pub bytes_saved: u64,
+}
+
+impl MinifyStats {
+    pub fn savings_percent(&self) -> f64 {
+        if self.original_size == 0 {
+            0.0
+        } else {
+            (self.bytes_saved as f64 / self.original_size as f64) * 100.0
+        }
+    }
+}
+
+/// Minify a single file in place.
+fn minify_file(path: &Path, minify: fn(&str) -> String, stats: &mut MinifyStats) -> Result<()> {
+    let original = match fs::read_to_string(path) {
+        Ok(original) => original,
+        Err(e) => {
+            debug!("Skipping minification of {}: {}", path.display(), e);
+            return Ok(());
+        }
+    };
+    let minified = minify(&original);
+
+    let original_size = original.len() as u64;
+    let minified_size = minified.len() as u64;
+
+    stats.files_minified += 1;
+    stats.original_size += original_size;
+    stats.minified_size += minified_size;
+    stats.bytes_saved += original_size.saturating_sub(minified_size);
+
+    if minified_size < original_size {
+        fs::write(path, minified)?;
+        debug!("Minified: {} (saved {} bytes)", path.display(), original_size - minified_size);
+    }
+
+    Ok(())
+}
+
+/// Minify HTML.
+///
+/// Collapses whitespace outside `<pre>`, `<code>`, and `<textarea>`, and
+/// strips comments other than the `<!--more-->` excerpt marker. Inline
+/// `<style>` and `<script>` contents are minified as CSS and JavaScript.
+pub fn minify_html(html: &str) -> String {
+    // ASCII lowercasing keeps byte offsets identical.
+    let lower = html.to_ascii_lowercase();
+    let mut out = String::with_capacity(html.len());
+    let mut pending_space = false;
+    let mut pos = 0;
+
+    while pos < html.len() {
+        let rest = &html[pos..];
+
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map(|i| i + 3).unwrap_or(rest.len());
+            let comment = &rest[..end];
+            if comment == MORE_MARKER {
+                push_pending_space(&mut out, &mut pending_space);
+                out.push_str(comment);
+            }
+            pos += end;
+            continue;
+        }
+
+        if is_tag_start(rest) {
+            let tag = &rest[..tag_len(rest)];
+            push_pending_space(&mut out, &mut pending_space);
+            out.push_str(tag);
+            pos += tag.len();
+
+            let name = tag_name(tag);
+            let is_open = !tag.starts_with("</") && !tag.ends_with("/>");
+            let is_raw = RAW_ELEMENTS.contains(&name.as_str()) || name == "script" || name == "style";
+
+            if is_open && is_raw {
+                let close = format!("</{}", name);
+                let end = lower[pos..].find(&close).map(|i| pos + i).unwrap_or(html.len());
+                let content = &html[pos..end];
+
+                match name.as_str() {
+                    "style" => out.push_str(&minify_css(content)),
+                    "script" if is_javascript(tag) => out.push_str(&minify_js(content)),
+                    _ => out.push_str(content),
+                }
+                pos = end;
+            }
+            continue;
+        }
+
+        // Collapse runs of whitespace in text.
+        let c = rest.chars().next().unwrap();
+        if c.is_whitespace() {
+            pending_space = true;
+        } else {
+            push_pending_space(&mut out, &mut pending_space);
+            out.push(c);
+        }
+        pos += c.len_utf8();
+    }
+
+    out
+}
+
+/// Emit a single collapsed space, except at the start of output.
+fn push_pending_space(out: &mut String, pending_space: &mut bool) {
+    if *pending_space && !out.is_empty() {
+        out.push(' ');
+    }
+    *pending_space = false;
+}
+
+/// Check whether text starts with a tag, closing tag, or doctype.
+fn is_tag_start(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next() == Some('<')
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '/' || c == '!')
+}
+
+/// Length of the tag at the start of `s`, including the closing `>`.
+fn tag_len(s: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in s.char_indices().skip(1) {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    s.len()
+}
+
+/// Lowercase element name of a tag.
+fn tag_name(tag: &str) -> String {
+    tag.trim_start_matches('<')
+        .trim_start_matches('/')
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// Check whether a `<script>` tag holds JavaScript rather than data or templates.
+fn is_javascript(tag: &str) -> bool {
+    let tag = tag.to_ascii_lowercase();
+    match tag.find("type=") {
+        Some(i) => {
+            let value = &tag[i + 5..];
+            value.contains("javascript") || value.trim_start_matches(['"', '\'']).starts_with("module")
+        }
+        None => true,
+    }
+}
+
+/// Minify CSS.
+///
+/// Strips comments and removes whitespace that is not needed to separate
+/// tokens. Quoted strings are preserved.
+pub fn minify_css(css: &str) -> String {
+    let mut out = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    let mut pending_space = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                pending_space = true;
+            }
+            c if c.is_whitespace() => pending_space = true,
+            _ => {
+                if pending_space && needs_css_space(out.chars().last(), c) {
+                    out.push(' ');
+                }
+                pending_space = false;
+
+                // Drop the optional last semicolon in a block.
+                if c == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(c);
+
+                if c == '"' || c == '\'' {
+                    copy_quoted(&mut chars, c, &mut out);
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// Check whether whitespace between two CSS characters is significant.
+fn needs_css_space(prev: Option<char>, next: char) -> bool {
+    match prev {
+        None => false,
+        Some(prev) => !"{};,>:".contains(prev) && !"{};,>".contains(next),
+    }
+}
+
+/// Copy the rest of a quoted string, including the closing quote.
+fn copy_quoted(chars: &mut impl Iterator<Item = char>, quote: char, out: &mut String) {
+    let mut escaped = false;
+    for c in chars {
+        out.push(c);
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == quote || c == '\n' {
+            break;
+        }
+    }
+}
+
+/// Minify JavaScript.
+///
+/// Strips comments, indentation, and blank lines, and collapses other
+/// whitespace. Line breaks that automatic semicolon insertion may depend
+/// on are kept, and strings, template literals, and regular expression
+/// literals are copied verbatim.
+pub fn minify_js(js: &str) -> String {
+    let chars: Vec<char> = js.chars().collect();
+    let mut out = String::with_capacity(js.len());
+    // Pending whitespace; `Some(true)` if it contained a line break.
+    let mut pending: Option<bool> = None;
+    // Brace depth at each open `${` in a template literal.
+    let mut templates: Vec<usize> = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        // Comments and whitespace only update the pending separator.
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c == '/' && next == Some('*') {
+            let mut newline = pending == Some(true);
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                newline |= chars[i] == '\n';
+                i += 1;
+            }
+            i += 2;
+            pending = Some(newline);
+            continue;
+        }
+        if c.is_whitespace() {
+            pending = Some(pending == Some(true) || c == '\n');
+            i += 1;
+            continue;
+        }
+
+        if let Some(newline) = pending.take() {
+            let prev = out.chars().last();
+            if newline && prev.is_some_and(|p| !"{;,([".contains(p)) && c != '}' {
+                out.push('\n');
+            } else if needs_js_space(prev, c) {
+                out.push(' ');
+            }
+        }
+
+        match c {
+            '"' | '\'' => {
+                out.push(c);
+                let mut rest = chars[i + 1..].iter().copied();
+                let before = out.len();
+                copy_quoted(&mut rest, c, &mut out);
+                i += 1 + out[before..].chars().count();
+            }
+            '`' => {
+                out.push(c);
+                i = copy_template(&chars, i + 1, &mut out, &mut templates, &mut depth);
+            }
+            '/' if regex_allowed(&out) => {
+                i = copy_regex(&chars, i, &mut out);
+            }
+            '{' => {
+                depth += 1;
+                out.push(c);
+                i += 1;
+            }
+            '}' if templates.last() == Some(&depth) => {
+                // End of a `${...}` expression; resume the template literal.
+                templates.pop();
+                depth -= 1;
+                out.push(c);
+                i = copy_template(&chars, i + 1, &mut out, &mut templates, &mut depth);
+            }
+            '}' => {
+                depth = depth.saturating_sub(1);
+                out.push(c);
+                i += 1;
+            }
+            _ => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+/// Check whether whitespace between two JavaScript characters is significant.
+fn needs_js_space(prev: Option<char>, next: char) -> bool {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '$' || !c.is_ascii();
+    match prev {
+        None => false,
+        Some(prev) => {
+            (is_word(prev) && is_word(next))
+                || (prev == '+' && next == '+')
+                || (prev == '-' && next == '-')
+        }
+    }
+}
+
+/// Copy template literal text up to its end or the next `${`.
+///
+/// Returns the index after the last character copied.
+fn copy_template(
+    chars: &[char],
+    mut i: usize,
+    out: &mut String,
+    templates: &mut Vec<usize>,
+    depth: &mut usize,
+) -> usize {
+    while i < chars.len() {
+        let c = chars[i];
+        out.push(c);
+        i += 1;
+
+        match c {
+            '\\' => {
+                if let Some(&escaped) = chars.get(i) {
+                    out.push(escaped);
+                    i += 1;
+                }
+            }
+            '`' => break,
+            '$' if chars.get(i) == Some(&'{') => {
+                out.push('{');
+                i += 1;
+                *depth += 1;
+                templates.push(*depth);
+                break;
+            }
+            _ => {}
+        }
+    }
+    i
+}
+
+/// Check whether a `/` at the end of `out` would start a regex literal.
+fn regex_allowed(out: &str) -> bool {
+    const KEYWORDS: &[&str] = &[
+        "return", "typeof", "case", "do", "else", "in", "of", "new", "delete", "void", "throw",
+    ];
+
+    let trimmed = out.trim_end();
+    match trimmed.chars().last() {
+        None => true,
+        Some(c) if "(,=:[!&|?{};+-*%<>~^".contains(c) => true,
+        Some(c) if c.is_alphanumeric() || c == '_' || c == '$' => {
+            let word_start = trimmed
+                .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            KEYWORDS.contains(&&trimmed[word_start..])
+        }
+        Some(_) => false,
+    }
+}
+
+/// Copy a regex literal starting at `i`.
+///
+/// Returns the index after the closing `/`; flags are copied as ordinary
+/// identifier characters.
+fn copy_regex(chars: &[char], mut i: usize, out: &mut String) -> usize {
+    out.push(chars[i]);
+    i += 1;
+
+    let mut in_class = false;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            break;
+        }
+        out.push(c);
+        i += 1;
+
+        match c {
+            '\\' => {
+                if let Some(&escaped) = chars.get(i) {
+                    out.push(escaped);
+                    i += 1;
+                }
+            }
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => break,
+            _ => {}
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_minify_html_collapses_whitespace() {
+        let html = "<html>\n  <body>\n    <p>Hello   <b>big</b>\n  world</p>\n  </body>\n</html>\n";
+        assert_eq!(
+            minify_html(html),
+            "<html> <body> <p>Hello <b>big</b> world</p> </body> </html>"
+        );
+    }
+
+    #[test]
+    fn test_minify_html_preserves_raw_elements() {
+        let html = "<div>\n  <pre><code>fn main() {\n    println!();\n}</code></pre>\n  <textarea>  a\n  b</textarea>\n</div>";
+        let minified = minify_html(html);
+        assert!(minified.contains("<pre><code>fn main() {\n    println!();\n}</code></pre>"));
+        assert!(minified.contains("<textarea>  a\n  b</textarea>"));
+    }
+
+    #[test]
+    fn test_minify_html_comments() {
+        let html = "<p>Intro</p>\n<!--more-->\n<!-- note to self -->\n<p>Rest</p>";
+        assert_eq!(minify_html(html), "<p>Intro</p> <!--more--> <p>Rest</p>");
+    }
+
+    #[test]
+    fn test_minify_css() {
+        let css = "/* Theme */\n.code-block {\n  color: #fff;\n  font-family: \"Fira  Code\", monospace;\n}\n\n@media (max-width: 600px) {\n  a :hover { margin: 0 auto; }\n}\n";
+        assert_eq!(
+            minify_css(css),
+            ".code-block{color:#fff;font-family:\"Fira  Code\",monospace}@media (max-width:600px){a :hover{margin:0 auto}}"
+        );
+    }
+
+    #[test]
+    fn test_minify_js() {
+        let js = "// Comment.\nfunction f(a, b) {\n  /* block */\n  const re = /[aeiou]\\/x/g; // trailing\n  const s = 'a // not a comment';\n  return a / b\n}\n";
+        assert_eq!(
+            minify_js(js),
+            "function f(a,b){const re=/[aeiou]\\/x/g;const s='a // not a comment';return a/b}"
+        );
+    }
+
+    #[test]
+    fn test_minify_js_template_literals() {
+        let js = "const html = `<p>\n  ${items.map(i => `<b>${ i }</b>`).join(' ')}\n</p>`;\nx  =  1";
+        assert_eq!(
+            minify_js(js),
+            "const html=`<p>\n  ${items.map(i=>`<b>${i}</b>`).join(' ')}\n</p>`;x=1"
+        );
+    }
+
+    #[test]
+    fn test_minify_js_keeps_asi_newlines() {
+        let js = "let a = 1\nlet b = a\n++b\n";
+        assert_eq!(minify_js(js), "let a=1\nlet b=a\n++b");
+    }
+
+    #[test]
+    fn test_minify_search_js() {
+        let js = super::super::generate_search_js();
+        let minified = minify_js(&js);
+        assert!(minified.len() < js.len());
+        assert!(minified.contains("window.AnthologySearch"));
+        assert!(minified.contains(".split(/\\W+/)"));
+    }
+}
@@ -6,24 +6,32 @@ mod compress;
 mod rewrite;
 mod encoding;
 mod cache;
+mod data;
 mod highlight;
 mod toc;
 mod search_js;
 mod progress;
 mod images;
+mod minify;
+mod paginate;
 
-pub use markdown::{render_markdown, render_markdown_highlighted, apply_syntax_highlighting, generate_highlight_css};
+pub use markdown::{render_markdown, render_markdown_highlighted, apply_syntax_highlighting, generate_highlight_css, markdown_options};
 pub use template::TemplateEngine;
-pub use compress::{compress_output, compress, decompress, compress_with_level, CompressStats};
+pub use compress::{
+    compress_output, compress_output_with, compress, decompress, compress_with_level,
+    CompressStats, AlgorithmStats, CompressOptions, CompressionAlgorithm, DEFAULT_COMPRESS_EXTENSIONS,
+};
 pub use rewrite::{
     UrlRewriter, make_urls_absolute, rewrite_md_links, extract_urls,
-    slugify, is_valid_slug, replace_pattern, find_internal_links, verify_links
+    slugify, is_valid_slug, replace_pattern, find_internal_links, verify_links,
+    inline_small_images, rewrite_picture_tags, resolve_url,
 };
 pub use encoding::{
     to_base64, from_base64, to_hex, from_hex, create_data_url, file_to_data_url,
     guess_mime_type, AssetBuffer, format_hash_short, format_size
 };
-pub use cache::{BuildCache, CacheStatus, IncrementalBuildResult, hash_templates};
+pub use cache::{BuildCache, CacheStatus, IncrementalBuildResult, hash_templates, hash_data};
+pub use data::{DATA_DIR, load_data, is_data_file};
 pub use highlight::{
     Highlighter, Theme, ThemeColors, Token, TokenType,
     HighlightOptions, tokenize,
@@ -38,31 +46,39 @@ pub use search_js::{generate_search_js, generate_search_page};
 pub use progress::{
     BuildProgress, document_progress, incremental_progress,
     asset_spinner, template_spinner, search_spinner, feed_spinner, compress_spinner,
-    finish_with_check,
+    minify_spinner, finish_with_check,
 };
+pub use paginate::{Pager, Paginator, PageLink, paginate, page_url, page_path, split_page_url};
+pub use minify::{minify_output, minify_html, minify_css, minify_js, MinifyStats};
 pub use images::{
-    ImageConfig, ImageResult, ImageStats,
-    process_image, process_directory,
+    ImageConfig, ImageResult, ImageStats, ImageVariant, ImageManifest,
+    ProcessedImage, ResponsiveImage,
+    process_image, process_directory, image_sources, optimize_images,
 };
 
 use rustmax::prelude::*;
 use rustmax::rayon::prelude::*;
-use rustmax::log::{info, debug};
+use rustmax::log::{info, debug, warn};
 use rustmax::jiff::Zoned;
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::fs;
 
-use crate::collection::{Collection, Config, Document};
-use crate::Result;
+use crate::collection::{is_valid_taxonomy, language_prefix, taxonomy_title, term_slug, term_url, Collection, Config, Document, Section};
+use crate::diagnostic::Diagnostic;
+use crate::features::BuildFeatures;
+use crate::search::{RelatedDocument, RelatedIndex};
+use crate::shortcode::expand_shortcodes_with_templates;
+use crate::{Error, Result};
 
 /// Build the collection to static output.
 pub fn build(
     collection: &Collection,
     config: &Config,
     output_dir: &Path,
-    include_drafts: bool,
+    features: BuildFeatures,
 ) -> Result<()> {
     // Clean and create output directory.
     if output_dir.exists() {
@@ -71,22 +87,19 @@ pub fn build(
     fs::create_dir_all(output_dir)?;
 
     // Initialize template engine.
-    let templates_dir = collection.root.join("templates");
-    let engine = TemplateEngine::new(&templates_dir)?;
+    let engine = TemplateEngine::for_collection(&collection.root, config)?;
+    engine.set_data(load_data(&collection.root.join(DATA_DIR))?);
 
     // Filter documents.
-    let documents: Vec<&Document> = if include_drafts {
-        collection.all_sorted()
-    } else {
-        collection.published()
-    };
+    let documents = visible_documents(collection, features);
+    let related = RelatedIndex::build(&documents, config.content.related);
 
     info!("Building {} documents", documents.len());
 
     // Build documents in parallel.
     let results: Vec<Result<()>> = documents
         .par_iter()
-        .map(|doc| build_document(doc, config, &engine, output_dir))
+        .map(|doc| build_document(doc, collection, config, &engine, &related, output_dir, features))
         .collect();
 
     // Check for errors.
@@ -94,34 +107,11 @@ pub fn build(
         result?;
     }
 
-    // Build index page.
-    build_index(&documents, config, &engine, output_dir)?;
-
-    // Build tag pages.
-    build_tag_pages(collection, config, &engine, output_dir, include_drafts)?;
-
-    // Copy static assets.
-    let static_dir = collection.root.join("static");
-    if static_dir.exists() {
-        copy_static(&static_dir, output_dir)?;
-    }
-
-    // Generate syntax highlighting CSS if enabled.
-    if config.highlight.enabled {
-        let css = generate_highlight_css(&config.highlight.to_options());
-        fs::write(output_dir.join("highlight.css"), css)?;
-    }
-
-    // Generate TOC CSS.
-    fs::write(output_dir.join("toc.css"), generate_toc_css())?;
-
-    // Generate client-side search assets.
-    fs::write(output_dir.join("search.js"), generate_search_js())?;
+    // Build index, taxonomy, and section listing pages.
+    build_listings(collection, &documents, config, &engine, output_dir, features)?;
 
-    // Build and write search index.
-    let search_index = crate::search::SearchIndex::build(collection);
-    let search_json = rustmax::serde_json::to_string(&search_index)?;
-    fs::write(output_dir.join("search-index.json"), search_json)?;
+    // Write assets, search index, and feeds.
+    write_site_files(collection, config, output_dir, features, false)?;
 
     Ok(())
 }
@@ -131,7 +121,7 @@ pub fn build_with_progress(
     collection: &Collection,
     config: &Config,
     output_dir: &Path,
-    include_drafts: bool,
+    features: BuildFeatures,
 ) -> Result<()> {
     use progress::finish_with_check;
 
@@ -143,16 +133,13 @@ pub fn build_with_progress(
 
     // Initialize template engine with spinner.
     let template_pb = template_spinner();
-    let templates_dir = collection.root.join("templates");
-    let engine = TemplateEngine::new(&templates_dir)?;
+    let engine = TemplateEngine::for_collection(&collection.root, config)?;
+    engine.set_data(load_data(&collection.root.join(DATA_DIR))?);
     finish_with_check(&template_pb, "Templates compiled");
 
     // Filter documents.
-    let documents: Vec<&Document> = if include_drafts {
-        collection.all_sorted()
-    } else {
-        collection.published()
-    };
+    let documents = visible_documents(collection, features);
+    let related = RelatedIndex::build(&documents, config.content.related);
 
     // Build documents in parallel with progress bar.
     let doc_pb = document_progress(documents.len());
@@ -161,7 +148,7 @@ pub fn build_with_progress(
     let results: Vec<Result<()>> = documents
         .par_iter()
         .map(|doc| {
-            let result = build_document(doc, config, &engine, output_dir);
+            let result = build_document(doc, collection, config, &engine, &related, output_dir, features);
             doc_count.fetch_add(1, Ordering::Relaxed);
             doc_pb.set_position(doc_count.load(Ordering::Relaxed) as u64);
             result
@@ -174,38 +161,11 @@ pub fn build_with_progress(
     }
     finish_with_check(&doc_pb, &format!("Built {} documents", documents.len()));
 
-    // Build index page.
-    build_index(&documents, config, &engine, output_dir)?;
+    // Build index, taxonomy, and section listing pages.
+    build_listings(collection, &documents, config, &engine, output_dir, features)?;
 
-    // Build tag pages.
-    build_tag_pages(collection, config, &engine, output_dir, include_drafts)?;
-
-    // Copy static assets with spinner.
-    let static_dir = collection.root.join("static");
-    if static_dir.exists() {
-        let asset_pb = asset_spinner();
-        copy_static(&static_dir, output_dir)?;
-        finish_with_check(&asset_pb, "Static assets copied");
-    }
-
-    // Generate syntax highlighting CSS if enabled.
-    if config.highlight.enabled {
-        let css = generate_highlight_css(&config.highlight.to_options());
-        fs::write(output_dir.join("highlight.css"), css)?;
-    }
-
-    // Generate TOC CSS.
-    fs::write(output_dir.join("toc.css"), generate_toc_css())?;
-
-    // Generate client-side search assets.
-    fs::write(output_dir.join("search.js"), generate_search_js())?;
-
-    // Build and write search index with spinner.
-    let search_pb = search_spinner();
-    let search_index = crate::search::SearchIndex::build(collection);
-    let search_json = rustmax::serde_json::to_string(&search_index)?;
-    fs::write(output_dir.join("search-index.json"), search_json)?;
-    finish_with_check(&search_pb, "Search index built");
+    // Write assets, search index, and feeds.
+    write_site_files(collection, config, output_dir, features, true)?;
 
     Ok(())
 }
@@ -215,33 +175,37 @@ pub fn build_incremental(
     collection: &Collection,
     config: &Config,
     output_dir: &Path,
-    include_drafts: bool,
+    features: BuildFeatures,
 ) -> Result<IncrementalBuildResult> {
     // Load existing cache.
     let mut cache = BuildCache::load(&collection.root);
 
     // Check if templates have changed.
-    let templates_dir = collection.root.join("templates");
-    let template_hash = hash_templates(&templates_dir)?;
+    let template_dirs = crate::theme::template_dirs(&collection.root, &config.themes);
+    let template_hash = hash_templates(&template_dirs)?;
     let templates_changed = cache.templates_changed(&template_hash);
 
+    // Check if data files have changed.
+    let data_hash = hash_data(&collection.root.join(DATA_DIR))?;
+
     if templates_changed {
         info!("Templates changed, forcing full rebuild");
         cache.clear();
+    } else if cache.data_changed(&data_hash) {
+        info!("Data files changed, forcing full rebuild");
+        cache.clear();
     }
 
     // Ensure output directory exists.
     fs::create_dir_all(output_dir)?;
 
     // Initialize template engine.
-    let engine = TemplateEngine::new(&templates_dir)?;
+    let engine = TemplateEngine::for_collection(&collection.root, config)?;
+    engine.set_data(load_data(&collection.root.join(DATA_DIR))?);
 
     // Filter documents.
-    let documents: Vec<&Document> = if include_drafts {
-        collection.all_sorted()
-    } else {
-        collection.published()
-    };
+    let documents = visible_documents(collection, features);
+    let related = RelatedIndex::build(&documents, config.content.related);
 
     // Track build results.
     let result = Mutex::new(IncrementalBuildResult::new());
@@ -251,14 +215,11 @@ pub fn build_incremental(
     let build_results: Vec<Result<Option<(PathBuf, String, String)>>> = documents
         .par_iter()
         .map(|doc| {
-            let output_path = output_dir.join(doc.slug()).join("index.html");
+            let output_path = output_dir.join(doc.output_path()).join("index.html");
 
             // Check cache.
-            let status = cache.check(
-                &doc.source_path,
-                &doc.content_hash,
-                &output_path,
-            );
+            let hash = page_hash(doc, related.get(doc));
+            let status = document_status(&cache, doc, &hash, output_dir);
 
             if status == CacheStatus::Fresh {
                 debug!("Skipping (cached): {}", doc.source_path.display());
@@ -267,12 +228,12 @@ pub fn build_incremental(
             }
 
             // Build the document.
-            build_document(doc, config, &engine, output_dir)?;
+            build_document(doc, collection, config, &engine, &related, output_dir, features)?;
             result.lock().unwrap().rebuilt += 1;
 
             Ok(Some((
                 doc.source_path.clone(),
-                doc.content_hash.clone(),
+                hash,
                 output_path.to_string_lossy().to_string(),
             )))
         })
@@ -285,41 +246,21 @@ pub fn build_incremental(
         }
     }
 
-    // Build index page (always, since document list may have changed).
-    build_index(&documents, config, &engine, output_dir)?;
-
-    // Build tag pages.
-    build_tag_pages(collection, config, &engine, output_dir, include_drafts)?;
-
-    // Copy static assets.
-    let static_dir = collection.root.join("static");
-    if static_dir.exists() {
-        copy_static(&static_dir, output_dir)?;
-    }
-
-    // Generate syntax highlighting CSS if enabled.
-    if config.highlight.enabled {
-        let css = generate_highlight_css(&config.highlight.to_options());
-        fs::write(output_dir.join("highlight.css"), css)?;
-    }
+    // Build listing pages (always, since document list may have changed),
+    // removing pages the listings no longer need.
+    let pages = build_listings(collection, &documents, config, &engine, output_dir, features)?;
+    remove_stale_pages(output_dir, &cache.update_pages(pages))?;
 
-    // Generate TOC CSS.
-    fs::write(output_dir.join("toc.css"), generate_toc_css())?;
+    // Write assets, search index, and feeds.
+    write_site_files(collection, config, output_dir, features, false)?;
 
-    // Generate client-side search assets.
-    fs::write(output_dir.join("search.js"), generate_search_js())?;
-
-    // Build and write search index.
-    let search_index = crate::search::SearchIndex::build(collection);
-    let search_json = rustmax::serde_json::to_string(&search_index)?;
-    fs::write(output_dir.join("search-index.json"), search_json)?;
-
-    // Prune deleted documents from cache.
+    // Prune documents no longer built, e.g. deleted or expired, and their pages.
     let source_paths: Vec<_> = documents.iter().map(|d| d.source_path.clone()).collect();
-    cache.prune(&source_paths);
+    remove_stale_pages(output_dir, &unbuilt_pages(output_dir, cache.prune(&source_paths)))?;
 
-    // Update template hash and save cache.
+    // Update template and data hashes and save cache.
     cache.set_template_hash(template_hash);
+    cache.set_data_hash(data_hash);
     cache.save(&collection.root)?;
 
     let final_result = result.into_inner().unwrap();
@@ -333,7 +274,7 @@ pub fn build_incremental_with_progress(
     collection: &Collection,
     config: &Config,
     output_dir: &Path,
-    include_drafts: bool,
+    features: BuildFeatures,
 ) -> Result<IncrementalBuildResult> {
     use progress::finish_with_check;
 
@@ -342,28 +283,32 @@ pub fn build_incremental_with_progress(
 
     // Check if templates have changed.
     let template_pb = template_spinner();
-    let templates_dir = collection.root.join("templates");
-    let template_hash = hash_templates(&templates_dir)?;
+    let template_dirs = crate::theme::template_dirs(&collection.root, &config.themes);
+    let template_hash = hash_templates(&template_dirs)?;
     let templates_changed = cache.templates_changed(&template_hash);
 
+    // Check if data files have changed.
+    let data_hash = hash_data(&collection.root.join(DATA_DIR))?;
+
     if templates_changed {
         info!("Templates changed, forcing full rebuild");
         cache.clear();
+    } else if cache.data_changed(&data_hash) {
+        info!("Data files changed, forcing full rebuild");
+        cache.clear();
     }
 
     // Ensure output directory exists.
     fs::create_dir_all(output_dir)?;
 
     // Initialize template engine.
-    let engine = TemplateEngine::new(&templates_dir)?;
+    let engine = TemplateEngine::for_collection(&collection.root, config)?;
+    engine.set_data(load_data(&collection.root.join(DATA_DIR))?);
     finish_with_check(&template_pb, "Templates compiled");
 
     // Filter documents.
-    let documents: Vec<&Document> = if include_drafts {
-        collection.all_sorted()
-    } else {
-        collection.published()
-    };
+    let documents = visible_documents(collection, features);
+    let related = RelatedIndex::build(&documents, config.content.related);
 
     // Track build results.
     let result = Mutex::new(IncrementalBuildResult::new());
@@ -377,14 +322,11 @@ pub fn build_incremental_with_progress(
     let build_results: Vec<Result<Option<(PathBuf, String, String)>>> = documents
         .par_iter()
         .map(|doc| {
-            let output_path = output_dir.join(doc.slug()).join("index.html");
+            let output_path = output_dir.join(doc.output_path()).join("index.html");
 
             // Check cache.
-            let status = cache.check(
-                &doc.source_path,
-                &doc.content_hash,
-                &output_path,
-            );
+            let hash = page_hash(doc, related.get(doc));
+            let status = document_status(&cache, doc, &hash, output_dir);
 
             let res = if status == CacheStatus::Fresh {
                 debug!("Skipping (cached): {}", doc.source_path.display());
@@ -392,12 +334,12 @@ pub fn build_incremental_with_progress(
                 Ok(None)
             } else {
                 // Build the document.
-                build_document(doc, config, &engine, output_dir)?;
+                build_document(doc, collection, config, &engine, &related, output_dir, features)?;
                 result.lock().unwrap().rebuilt += 1;
 
                 Ok(Some((
                     doc.source_path.clone(),
-                    doc.content_hash.clone(),
+                    hash,
                     output_path.to_string_lossy().to_string(),
                 )))
             };
@@ -422,66 +364,109 @@ pub fn build_incremental_with_progress(
     );
     drop(interim);
 
-    // Build index page (always, since document list may have changed).
-    build_index(&documents, config, &engine, output_dir)?;
-
-    // Build tag pages.
-    build_tag_pages(collection, config, &engine, output_dir, include_drafts)?;
-
-    // Copy static assets with spinner.
-    let static_dir = collection.root.join("static");
-    if static_dir.exists() {
-        let asset_pb = asset_spinner();
-        copy_static(&static_dir, output_dir)?;
-        finish_with_check(&asset_pb, "Static assets copied");
-    }
+    // Build listing pages (always, since document list may have changed),
+    // removing pages the listings no longer need.
+    let pages = build_listings(collection, &documents, config, &engine, output_dir, features)?;
+    remove_stale_pages(output_dir, &cache.update_pages(pages))?;
 
-    // Generate syntax highlighting CSS if enabled.
-    if config.highlight.enabled {
-        let css = generate_highlight_css(&config.highlight.to_options());
-        fs::write(output_dir.join("highlight.css"), css)?;
-    }
+    // Write assets, search index, and feeds.
+    write_site_files(collection, config, output_dir, features, true)?;
 
-    // Generate TOC CSS.
-    fs::write(output_dir.join("toc.css"), generate_toc_css())?;
-
-    // Generate client-side search assets.
-    fs::write(output_dir.join("search.js"), generate_search_js())?;
-
-    // Build and write search index with spinner.
-    let search_pb = search_spinner();
-    let search_index = crate::search::SearchIndex::build(collection);
-    let search_json = rustmax::serde_json::to_string(&search_index)?;
-    fs::write(output_dir.join("search-index.json"), search_json)?;
-    finish_with_check(&search_pb, "Search index built");
-
-    // Prune deleted documents from cache.
+    // Prune documents no longer built, e.g. deleted or expired, and their pages.
     let source_paths: Vec<_> = documents.iter().map(|d| d.source_path.clone()).collect();
-    cache.prune(&source_paths);
+    remove_stale_pages(output_dir, &unbuilt_pages(output_dir, cache.prune(&source_paths)))?;
 
-    // Update template hash and save cache.
+    // Update template and data hashes and save cache.
     cache.set_template_hash(template_hash);
+    cache.set_data_hash(data_hash);
     cache.save(&collection.root)?;
 
     let final_result = result.into_inner().unwrap();
     Ok(final_result)
 }
 
+/// Render a document's markdown content to HTML.
+///
+/// Shortcodes are expanded first when `BuildFeatures::SHORTCODES` is set,
+/// using the collection's `templates/shortcodes/` before the built-ins.
+/// Unknown shortcodes are reported with their source file and line.
+pub fn render_document(
+    doc: &Document,
+    config: &Config,
+    engine: &TemplateEngine,
+    features: BuildFeatures,
+) -> Result<String> {
+    render_content(&doc.content, &doc.source_path, doc.body_line, config, engine, features)
+}
+
+/// Render the markdown content of a section's `_index.md` to HTML.
+///
+/// Rendered like [`render_document`]; sections without an index render empty.
+pub fn render_section(
+    section: &Section,
+    config: &Config,
+    engine: &TemplateEngine,
+    features: BuildFeatures,
+) -> Result<String> {
+    match &section.source_path {
+        Some(path) => render_content(&section.content, path, section.body_line, config, engine, features),
+        None => Ok(String::new()),
+    }
+}
+
+/// Render markdown from a source file, starting at `body_line`.
+fn render_content(
+    content: &str,
+    source_path: &Path,
+    body_line: usize,
+    config: &Config,
+    engine: &TemplateEngine,
+    features: BuildFeatures,
+) -> Result<String> {
+    let markdown = if features.contains(BuildFeatures::SHORTCODES) {
+        Cow::Owned(expand_content_shortcodes(content, source_path, body_line, engine)?)
+    } else {
+        Cow::Borrowed(content)
+    };
+
+    // Render markdown with optional syntax highlighting.
+    let highlight = features.contains(BuildFeatures::SYNTAX_HIGHLIGHT) && config.highlight.enabled;
+    let html_content = if highlight {
+        render_markdown_highlighted(&markdown, &config.highlight.to_options())
+    } else {
+        render_markdown(&markdown)
+    };
+
+    Ok(html_content)
+}
+
+/// Expand the shortcodes in markdown from `source_path`, whose body starts
+/// at `body_line`, reporting errors at their place in the file.
+pub fn expand_content_shortcodes(
+    content: &str,
+    source_path: &Path,
+    body_line: usize,
+    engine: &TemplateEngine,
+) -> Result<String> {
+    expand_shortcodes_with_templates(content, engine).map_err(|e| {
+        let diagnostic = Diagnostic::new(source_path, body_line + e.line - 1, e.column, e.message);
+        Error::shortcode(diagnostic.with_source_file())
+    })
+}
+
 /// Build a single document.
 fn build_document(
     doc: &Document,
+    collection: &Collection,
     config: &Config,
     engine: &TemplateEngine,
+    related: &RelatedIndex,
     output_dir: &Path,
+    features: BuildFeatures,
 ) -> Result<()> {
     debug!("Building: {}", doc.source_path.display());
 
-    // Render markdown with optional syntax highlighting.
-    let html_content = if config.highlight.enabled {
-        render_markdown_highlighted(&doc.content, &config.highlight.to_options())
-    } else {
-        render_markdown(&doc.content)
-    };
+    let html_content = render_document(doc, config, engine, features)?;
 
     let template_name = doc
         .frontmatter
@@ -489,64 +474,531 @@ fn build_document(
         .as_deref()
         .unwrap_or(&config.content.default_template);
 
-    let context = engine.document_context(doc, config, &html_content);
+    let mut context = engine.document_context(doc, collection, config, &html_content);
+    engine.insert_related(&mut context, related.get(doc));
     let rendered = engine.render(template_name, &context)?;
 
     // Write to output.
-    let doc_dir = output_dir.join(doc.slug());
+    let doc_dir = output_dir.join(doc.output_path());
     fs::create_dir_all(&doc_dir)?;
     fs::write(doc_dir.join("index.html"), rendered)?;
 
+    // Copy bundle assets next to the page so relative links resolve.
+    copy_bundle_assets(doc, &doc_dir)?;
+
     Ok(())
 }
 
+/// Copy a page bundle's assets into the document's output directory.
+fn copy_bundle_assets(doc: &Document, doc_dir: &Path) -> Result<()> {
+    let Some(bundle_dir) = &doc.bundle_dir else {
+        return Ok(());
+    };
+
+    for asset in &doc.assets {
+        let dest = doc_dir.join(&asset.path);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(bundle_dir.join(&asset.path), dest)?;
+    }
+
+    Ok(())
+}
+
+/// Hash of what a document's page is built from, for the build cache.
+///
+/// Related documents are listed on the page, so a change in which
+/// documents they are, or in their titles, makes the page stale. Scores
+/// are left out since they shift with any edit to the collection.
+fn page_hash(doc: &Document, related: &[RelatedDocument]) -> String {
+    if related.is_empty() {
+        return doc.cache_hash();
+    }
+
+    let mut hasher = rustmax::blake3::Hasher::new();
+    hasher.update(doc.cache_hash().as_bytes());
+    for r in related {
+        hasher.update(r.url.as_bytes());
+        hasher.update(r.title.as_bytes());
+    }
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Check whether a document must be rebuilt in an incremental build.
+///
+/// Bundle assets count as part of the document, so a changed or missing
+/// asset makes the document stale.
+fn document_status(cache: &BuildCache, doc: &Document, hash: &str, output_dir: &Path) -> CacheStatus {
+    let doc_dir = output_dir.join(doc.output_path());
+    let status = cache.check(&doc.source_path, hash, &doc_dir.join("index.html"));
+
+    if status == CacheStatus::Fresh && !doc.assets.iter().all(|a| doc_dir.join(&a.path).exists()) {
+        debug!("Bundle asset missing: {}", doc.source_path.display());
+        return CacheStatus::Stale;
+    }
+
+    status
+}
+
+/// The documents a build with `features` publishes, newest first.
+///
+/// Drafts need `BuildFeatures::DRAFTS` and documents scheduled for later
+/// `BuildFeatures::FUTURE`; expired documents are always left out.
+fn visible_documents(collection: &Collection, features: BuildFeatures) -> Vec<&Document> {
+    collection.visible(
+        features.contains(BuildFeatures::DRAFTS),
+        features.contains(BuildFeatures::FUTURE),
+    )
+}
+
+/// Build the index, taxonomy, and section listing pages.
+///
+/// Returns the paginated pages written after the first of each listing.
+fn build_listings(
+    collection: &Collection,
+    documents: &[&Document],
+    config: &Config,
+    engine: &TemplateEngine,
+    output_dir: &Path,
+    features: BuildFeatures,
+) -> Result<Vec<PathBuf>> {
+    let mut pages = build_index(documents, config, engine, output_dir)?;
+    pages.extend(build_taxonomy_pages(collection, config, engine, output_dir, features)?);
+    pages.extend(build_section_pages(collection, config, engine, output_dir, features)?);
+    Ok(pages)
+}
+
 /// Build the index page.
+///
+/// Returns the paginated pages written after the first.
+///
+/// Each language gets its own index under its URL prefix, listing the
+/// documents in that language.
 fn build_index(
     documents: &[&Document],
     config: &Config,
     engine: &TemplateEngine,
     output_dir: &Path,
-) -> Result<()> {
-    let context = engine.index_context(documents, config);
-    let rendered = engine.render("index.html", &context).or_else(|_| {
-        // Fall back to default template with listing.
-        engine.render("default.html", &context)
-    })?;
+) -> Result<Vec<PathBuf>> {
+    let mut pages = Vec::new();
+
+    for language in site_languages(config) {
+        let documents = in_language(documents, language);
+        let base_url = format!("{}/", language_prefix(language));
+        let dir = output_dir.join(language.unwrap_or(""));
+        pages.extend(write_listing(&documents, config.content.paginate, &base_url, &dir, output_dir, |docs, pager| {
+            let mut context = engine.language_index_context(docs, config, language);
+            engine.insert_paginator(&mut context, pager, docs);
+            engine.render("index.html", &context).or_else(|_| {
+                // Fall back to default template with listing.
+                engine.render("default.html", &context)
+            })
+        })?);
+    }
 
-    fs::write(output_dir.join("index.html"), rendered)?;
-    Ok(())
+    Ok(pages)
+}
+
+/// The languages of a collection: `None` for the default language, then
+/// each translation language.
+fn site_languages(config: &Config) -> Vec<Option<&str>> {
+    std::iter::once(None)
+        .chain(config.languages.keys().filter(|code| **code != config.collection.language).map(|code| Some(code.as_str())))
+        .collect()
+}
+
+/// Documents in a language, `None` being the default language.
+fn in_language<'a>(documents: &[&'a Document], language: Option<&str>) -> Vec<&'a Document> {
+    documents
+        .iter()
+        .copied()
+        .filter(|d| d.language.as_deref() == language)
+        .collect()
 }
 
-/// Build tag index pages.
-fn build_tag_pages(
+/// Build term pages and a term index for each configured taxonomy.
+///
+/// Term pages list documents at `/{taxonomy}/{slug}/`, named by
+/// [`term_slug`], with
+/// [`TemplateEngine::term_template`]; the index at `/{taxonomy}/` uses
+/// `taxonomy.html`. Returns the paginated pages written after the first of
+/// each term.
+fn build_taxonomy_pages(
     collection: &Collection,
     config: &Config,
     engine: &TemplateEngine,
     output_dir: &Path,
-    include_drafts: bool,
-) -> Result<()> {
-    let tags_dir = output_dir.join("tags");
-    fs::create_dir_all(&tags_dir)?;
+    features: BuildFeatures,
+) -> Result<Vec<PathBuf>> {
+    let include_drafts = features.contains(BuildFeatures::DRAFTS);
+    let include_future = features.contains(BuildFeatures::FUTURE);
 
-    for tag in collection.tags() {
-        let documents: Vec<&Document> = collection
-            .by_tag(&tag)
-            .into_iter()
-            .filter(|d| include_drafts || !d.frontmatter.draft)
-            .collect();
+    let mut pages = Vec::new();
 
-        if documents.is_empty() {
-            continue;
+    for taxonomy in &config.content.taxonomies {
+        if !is_valid_taxonomy(taxonomy) {
+            return Err(Error::config(format!("[content] taxonomies: invalid name '{}'", taxonomy)));
         }
 
-        let context = engine.tag_context(&tag, &documents, config);
-        let rendered = engine.render("tag.html", &context).or_else(|_| {
+        let taxonomy_dir = output_dir.join(taxonomy);
+        fs::create_dir_all(&taxonomy_dir)?;
+
+        let mut terms = Vec::new();
+        for term in collection.terms(taxonomy) {
+            let documents: Vec<&Document> = collection
+                .by_term(taxonomy, &term)
+                .into_iter()
+                .filter(|d| d.is_visible(include_drafts, include_future))
+                .collect();
+
+            if documents.is_empty() {
+                continue;
+            }
+
+            let base_url = term_url(taxonomy, &term);
+            let term_dir = taxonomy_dir.join(term_slug(&term));
+            let template = engine.term_template(taxonomy);
+            pages.extend(write_listing(&documents, config.content.paginate, &base_url, &term_dir, output_dir, |docs, pager| {
+                let mut context = engine.term_context(taxonomy, &term, docs, config);
+                engine.insert_paginator(&mut context, pager, docs);
+                engine.render(template, &context).or_else(|_| {
+                    engine.render("default.html", &context)
+                })
+            })?);
+
+            terms.push((term, documents.len()));
+        }
+
+        let context = engine.taxonomy_context(taxonomy, &terms, config);
+        let rendered = engine.render("taxonomy.html", &context).or_else(|_| {
             engine.render("default.html", &context)
         })?;
+        fs::write(taxonomy_dir.join("index.html"), rendered)?;
+    }
+
+    Ok(pages)
+}
+
+/// Build section listing pages.
+///
+/// Each section except the root (which is the index page) is rendered with
+/// its `_index.md` template, `section.html`, or `default.html`. A `paginate`
+/// key in `_index.md` overrides `[content] paginate` for the section.
+/// Returns the paginated pages written after the first of each section.
+fn build_section_pages(
+    collection: &Collection,
+    config: &Config,
+    engine: &TemplateEngine,
+    output_dir: &Path,
+    features: BuildFeatures,
+) -> Result<Vec<PathBuf>> {
+    let include_drafts = features.contains(BuildFeatures::DRAFTS);
+    let include_future = features.contains(BuildFeatures::FUTURE);
+
+    let mut pages = Vec::new();
+    for section in collection.sections.iter().filter(|s| !s.is_root()) {
+        if section.frontmatter.draft && !include_drafts {
+            continue;
+        }
 
-        let tag_dir = tags_dir.join(&tag);
-        fs::create_dir_all(&tag_dir)?;
-        fs::write(tag_dir.join("index.html"), rendered)?;
+        let url = section.url_path();
+        if collection.documents.iter().any(|d| d.url_path() == url) {
+            warn!("Section {} conflicts with a document; skipping section page", url);
+            continue;
+        }
+
+        let documents = collection.section_documents(section, include_drafts, include_future);
+        let html_content = render_section(section, config, engine, features)?;
+        let template = section.frontmatter.template.as_deref().unwrap_or("section.html");
+        let per_page = section.paginate().unwrap_or(config.content.paginate);
+
+        let section_dir = output_dir.join(&section.path);
+        pages.extend(write_listing(&documents, per_page, &url, &section_dir, output_dir, |docs, pager| {
+            let mut context = engine.section_context(section, docs, collection, config, &html_content);
+            engine.insert_paginator(&mut context, pager, docs);
+            engine.render(template, &context).or_else(|_| {
+                engine.render("default.html", &context)
+            })
+        })?);
+    }
+
+    Ok(pages)
+}
+
+/// Render every page of a listing into `dir`.
+///
+/// Page 1 is written to `dir` and later pages to `dir/page/N/`. Returns the
+/// later pages' directories relative to `output_dir`.
+fn write_listing<F>(
+    documents: &[&Document],
+    per_page: usize,
+    base_url: &str,
+    dir: &Path,
+    output_dir: &Path,
+    render: F,
+) -> Result<Vec<PathBuf>>
+where
+    F: Fn(&[&Document], &Pager) -> Result<String>,
+{
+    let mut pages = Vec::new();
+
+    for pager in paginate(documents.len(), per_page, base_url) {
+        let rendered = render(&documents[pager.range()], &pager)?;
+
+        let page_dir = dir.join(pager.output_path());
+        fs::create_dir_all(&page_dir)?;
+        fs::write(page_dir.join("index.html"), rendered)?;
+
+        if pager.number > 1 {
+            pages.push(page_dir.strip_prefix(output_dir).unwrap_or(&page_dir).to_path_buf());
+        }
+    }
+
+    Ok(pages)
+}
+
+/// Remove paginated pages left over from an earlier build.
+fn remove_stale_pages(output_dir: &Path, stale: &[PathBuf]) -> Result<()> {
+    for page in stale {
+        let page_dir = output_dir.join(page);
+        let index = page_dir.join("index.html");
+        if index.exists() {
+            debug!("Removing stale page: {}", index.display());
+            fs::remove_file(&index)?;
+        }
+
+        // Remove directories emptied by the removal, up to the output root.
+        let mut dir = Some(page_dir.as_path());
+        while let Some(d) = dir.filter(|d| *d != output_dir) {
+            if fs::remove_dir(d).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+    }
+
+    Ok(())
+}
+
+/// Page directories, relative to `output_dir`, of documents' cached
+/// `index.html` output paths.
+///
+/// Paths outside `output_dir`, from builds to another directory, are skipped.
+fn unbuilt_pages(output_dir: &Path, output_paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    output_paths
+        .iter()
+        .filter_map(|path| path.parent()?.strip_prefix(output_dir).ok())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Largest image inlined as a data URL by `BuildFeatures::INLINE_IMAGES`.
+const INLINE_IMAGE_MAX_BYTES: u64 = 4 * 1024;
+
+/// Directory, relative to the collection root, holding optimized images.
+const IMAGE_CACHE_DIR: &str = ".anthology-cache/images";
+
+/// Write everything besides the rendered pages.
+///
+/// Static assets are always copied; every other stage runs only when its
+/// feature is enabled. Minification and compression run last so they
+/// cover all output.
+fn write_site_files(
+    collection: &Collection,
+    config: &Config,
+    output_dir: &Path,
+    features: BuildFeatures,
+    show_progress: bool,
+) -> Result<()> {
+    // Copy static assets, the collection's over its themes'.
+    let static_dirs: Vec<PathBuf> = static_dirs(collection, config).into_iter().filter(|d| d.exists()).collect();
+    if !static_dirs.is_empty() {
+        let asset_pb = show_progress.then(asset_spinner);
+        for static_dir in &static_dirs {
+            copy_static(static_dir, output_dir)?;
+        }
+        if let Some(pb) = asset_pb {
+            finish_with_check(&pb, "Static assets copied");
+        }
+    }
+
+    // Optimize static images and those of published bundles.
+    let manifest = if features.contains(BuildFeatures::IMAGES) {
+        Some(build_images(collection, &visible_documents(collection, features), config, output_dir)?)
+    } else {
+        None
+    };
+
+    // Inline small images into pages.
+    if features.contains(BuildFeatures::INLINE_IMAGES) {
+        inline_images(output_dir)?;
+    }
+
+    // Rewrite optimized images as responsive pictures.
+    if let Some(manifest) = manifest.filter(|m| !m.is_empty()) {
+        rewrite_images(output_dir, &manifest, &config.images.sizes)?;
+    }
+
+    // Generate syntax highlighting CSS if enabled.
+    if features.contains(BuildFeatures::SYNTAX_HIGHLIGHT) && config.highlight.enabled {
+        let css = generate_highlight_css(&config.highlight.to_options());
+        fs::write(output_dir.join("highlight.css"), css)?;
+    }
+
+    // Generate TOC CSS.
+    if features.contains(BuildFeatures::TOC) {
+        fs::write(output_dir.join("toc.css"), generate_toc_css())?;
+    }
+
+    // Generate client-side search assets and index.
+    if features.contains(BuildFeatures::SEARCH_INDEX) {
+        let search_pb = show_progress.then(search_spinner);
+        fs::write(output_dir.join("search.js"), generate_search_js())?;
+
+        // One index per language, next to that language's index page.
+        let documents = visible_documents(collection, features);
+        let format = config.search.to_format()?;
+        for language in site_languages(config) {
+            let search_index = crate::search::SearchIndex::from_documents(&in_language(&documents, language));
+            let dir = output_dir.join(language.unwrap_or(""));
+            fs::create_dir_all(&dir)?;
+            crate::search::write_index(&search_index, &dir, format)?;
+        }
+        if let Some(pb) = search_pb {
+            finish_with_check(&pb, "Search index built");
+        }
+    }
+
+    // Generate feed and sitemap.
+    if features.intersects(BuildFeatures::RSS | BuildFeatures::SITEMAP) {
+        let feed_pb = show_progress.then(feed_spinner);
+        if features.contains(BuildFeatures::RSS) {
+            fs::write(output_dir.join("rss.xml"), generate_rss(collection, config)?)?;
+            for code in config.translation_languages() {
+                let dir = output_dir.join(&code);
+                fs::create_dir_all(&dir)?;
+                fs::write(dir.join("rss.xml"), generate_language_rss(collection, config, &code)?)?;
+            }
+            write_term_feeds(collection, config, output_dir)?;
+        }
+        if features.contains(BuildFeatures::SITEMAP) {
+            fs::write(output_dir.join("sitemap.xml"), generate_sitemap(collection, config)?)?;
+        }
+        if let Some(pb) = feed_pb {
+            finish_with_check(&pb, "Feeds generated");
+        }
+    }
+
+    // Minify pages and generated assets.
+    if features.contains(BuildFeatures::MINIFY) {
+        let minify_pb = show_progress.then(minify_spinner);
+        let stats = minify_output(output_dir)?;
+        if let Some(pb) = minify_pb {
+            finish_with_check(&pb, &format!("Minified {} files, saved {}", stats.files_minified, format_size(stats.bytes_saved)));
+        }
+    }
+
+    // Compress output.
+    if features.contains(BuildFeatures::COMPRESS) {
+        let compress_pb = show_progress.then(compress_spinner);
+        let stats = compress_output_with(output_dir, &config.compress.to_options()?)?;
+        if let Some(pb) = compress_pb {
+            let algorithms: Vec<_> = stats.algorithms.iter().map(|a| a.algorithm.name()).collect();
+            finish_with_check(&pb, &format!(
+                "Compressed {} files ({}), saved {}",
+                stats.files_compressed,
+                algorithms.join(", "),
+                format_size(stats.bytes_saved)
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Static file directories, lowest priority first: the themes' from the
+/// furthest parent down, then the collection's `static/`.
+fn static_dirs(collection: &Collection, config: &Config) -> Vec<PathBuf> {
+    config
+        .themes
+        .iter()
+        .rev()
+        .map(|theme| theme.static_dir())
+        .chain([collection.root.join("static")])
+        .collect()
+}
+
+/// Optimize images from `static/`, themes' `static/` and the bundles of
+/// `documents` into the output directory.
+///
+/// Bundle images are written next to their document's page, so drafts and
+/// other documents the build leaves out don't publish their images.
+fn build_images(
+    collection: &Collection,
+    documents: &[&Document],
+    config: &Config,
+    output_dir: &Path,
+) -> Result<ImageManifest> {
+    let mut sources = image_sources(&static_dirs(collection, config))?;
+    for doc in documents {
+        let Some(bundle_dir) = &doc.bundle_dir else { continue };
+        let doc_dir = doc.output_path();
+        sources.extend(doc.assets.iter().map(|a| (bundle_dir.join(&a.path), doc_dir.join(&a.path))));
+    }
+    let cache_dir = collection.root.join(IMAGE_CACHE_DIR);
+    let (manifest, stats) = optimize_images(&sources, output_dir, &cache_dir, &config.images.to_image_config())?;
+
+    if stats.processed > 0 {
+        info!(
+            "Optimized {} images, saved {} bytes ({:.1}%)",
+            stats.processed,
+            stats.bytes_saved(),
+            stats.savings_percent()
+        );
+    }
+    if stats.cached > 0 {
+        debug!("Reused {} cached images", stats.cached);
+    }
+
+    Ok(manifest)
+}
+
+/// Rewrite `<img>` tags in built HTML pages to use optimized images.
+fn rewrite_images(output_dir: &Path, manifest: &ImageManifest, sizes: &str) -> Result<()> {
+    use rustmax::walkdir::WalkDir;
+
+    for entry in WalkDir::new(output_dir) {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.is_file() && path.extension().is_some_and(|e| e == "html") {
+            let relative = path.strip_prefix(output_dir).unwrap();
+            let page_url = format!("/{}", relative.to_string_lossy().replace('\\', "/"));
+            let html = fs::read_to_string(path)?;
+            if let Cow::Owned(rewritten) = rewrite_picture_tags(&html, &page_url, manifest, sizes) {
+                fs::write(path, rewritten)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Inline small images referenced by built HTML pages.
+fn inline_images(output_dir: &Path) -> Result<()> {
+    use rustmax::walkdir::WalkDir;
+
+    for entry in WalkDir::new(output_dir) {
+        let entry = entry?;
+        let path = entry.path();
+
+        if path.is_file() && path.extension().is_some_and(|e| e == "html") {
+            let html = fs::read_to_string(path)?;
+            if let Cow::Owned(inlined) = inline_small_images(&html, output_dir, INLINE_IMAGE_MAX_BYTES) {
+                fs::write(path, inlined)?;
+            }
+        }
     }
 
     Ok(())
@@ -574,9 +1026,62 @@ fn copy_static(static_dir: &Path, output_dir: &Path) -> Result<()> {
     Ok(())
 }
 
-/// Generate RSS feed.
+/// Write an RSS feed for every taxonomy term, next to the term's page.
+fn write_term_feeds(collection: &Collection, config: &Config, output_dir: &Path) -> Result<()> {
+    for taxonomy in &config.content.taxonomies {
+        for term in collection.terms(taxonomy) {
+            let term_dir = output_dir.join(taxonomy).join(term_slug(&term));
+            if term_dir.exists() {
+                fs::write(term_dir.join("rss.xml"), generate_term_rss(collection, config, taxonomy, &term)?)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Generate RSS feed of the default language.
 pub fn generate_rss(collection: &Collection, config: &Config) -> Result<String> {
-    let documents = collection.published();
+    rss_channel(
+        &in_language(&collection.published(), None),
+        &config.collection.title,
+        &config.collection.base_url,
+        config,
+    )
+}
+
+/// Generate the RSS feed of a translation language.
+pub fn generate_language_rss(collection: &Collection, config: &Config, language: &str) -> Result<String> {
+    let link = format!("{}{}/", config.collection.base_url, language_prefix(Some(language)));
+    rss_channel(
+        &in_language(&collection.published(), Some(language)),
+        config.language_title(Some(language)),
+        &link,
+        config,
+    )
+}
+
+/// Generate the RSS feed of a taxonomy term.
+pub fn generate_term_rss(
+    collection: &Collection,
+    config: &Config,
+    taxonomy: &str,
+    term: &str,
+) -> Result<String> {
+    let slug = term_slug(term);
+    let documents: Vec<&Document> = collection
+        .published()
+        .into_iter()
+        .filter(|d| d.has_term(taxonomy, &slug))
+        .collect();
+    let title = format!("{} - {}: {}", config.collection.title, taxonomy_title(taxonomy), term);
+    let link = format!("{}{}", config.collection.base_url, term_url(taxonomy, term));
+
+    rss_channel(&documents, &title, &link, config)
+}
+
+/// Generate an RSS channel of the 20 newest `documents`.
+fn rss_channel(documents: &[&Document], title: &str, link: &str, config: &Config) -> Result<String> {
     let now = Zoned::now();
 
     let mut items = String::new();
@@ -616,8 +1121,8 @@ pub fn generate_rss(collection: &Collection, config: &Config) -> Result<String>
 {}  </channel>
 </rss>
 "#,
-        html_escape(&config.collection.title),
-        config.collection.base_url,
+        html_escape(title),
+        link,
         html_escape(&config.collection.description),
         now.strftime("%a, %d %b %Y %H:%M:%S %z"),
         items
@@ -629,22 +1134,79 @@ pub fn generate_rss(collection: &Collection, config: &Config) -> Result<String>
 /// Generate sitemap.
 pub fn generate_sitemap(collection: &Collection, config: &Config) -> Result<String> {
     let documents = collection.published();
+    let base_url = &config.collection.base_url;
+
+    // Later pages of paginated listings are listed alongside their first page.
+    let per_page = config.content.paginate;
+    let mut listings = Vec::new();
+    for language in site_languages(config) {
+        let count = in_language(&documents, language).len();
+        let pagers = paginate(count, per_page, &format!("{}/", language_prefix(language)));
+        listings.extend(pagers.iter().skip(1).map(Pager::url));
+    }
+
+    for section in collection.sections.iter().filter(|s| !s.is_root() && !s.frontmatter.draft) {
+        let count = collection.section_documents(section, false, false).len();
+        let pagers = paginate(count, section.paginate().unwrap_or(per_page), &section.url_path());
+        listings.extend(pagers.iter().map(Pager::url));
+    }
+
+    for taxonomy in &config.content.taxonomies {
+        for term in collection.terms(taxonomy) {
+            let count = collection.by_term(taxonomy, &term).iter().filter(|d| d.is_published()).count();
+            if count == 0 {
+                continue;
+            }
+            let pagers = paginate(count, per_page, &term_url(taxonomy, &term));
+            listings.extend(pagers.iter().map(Pager::url));
+        }
+    }
 
     let mut urls = String::new();
     for doc in &documents {
+        // Translated documents list every version, including their own.
+        let mut alternates = String::new();
+        let translations: Vec<&Document> = collection
+            .translations(doc)
+            .into_iter()
+            .filter(|t| t.is_published())
+            .collect();
+        if !translations.is_empty() {
+            for version in std::iter::once(*doc).chain(translations) {
+                alternates.push_str(&format!(
+                    "    <xhtml:link rel=\"alternate\" hreflang=\"{}\" href=\"{}{}\" />\n",
+                    config.language_code(version.language.as_deref()),
+                    base_url,
+                    version.url_path()
+                ));
+            }
+        }
+
+        urls.push_str(&format!(
+            r#"  <url>
+    <loc>{}{}</loc>
+{}  </url>
+"#,
+            base_url,
+            doc.url_path(),
+            alternates
+        ));
+    }
+
+    for url in listings {
         urls.push_str(&format!(
             r#"  <url>
     <loc>{}{}</loc>
   </url>
 "#,
-            config.collection.base_url,
-            doc.url_path()
+            base_url,
+            url
         ));
     }
 
     let sitemap = format!(
         r#"<?xml version="1.0" encoding="UTF-8"?>
-<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9" xmlns:xhtml="http://www.w3.org/1999/xhtml">
 {}
 </urlset>
 "#,
@@ -0,0 +1,208 @@
+//! Pagination of listing pages.
+//!
+//! A listing at `/tags/rust/` with more documents than fit on one page is
+//! split into `/tags/rust/`, `/tags/rust/page/2/`, and so on.
+
+use rustmax::prelude::*;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Path segment that introduces a page number.
+const PAGE_SEGMENT: &str = "page";
+
+/// One page of a paginated listing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pager {
+    /// Page number, starting at 1.
+    pub number: usize,
+    /// Total number of pages.
+    pub total: usize,
+    /// Items per page (0 = unpaginated).
+    pub per_page: usize,
+    /// Total number of items across all pages.
+    pub total_items: usize,
+    /// URL of the first page, ending in `/`.
+    pub base_url: String,
+}
+
+impl Pager {
+    /// Range of item indices on this page.
+    pub fn range(&self) -> std::ops::Range<usize> {
+        if self.per_page == 0 {
+            return 0..self.total_items;
+        }
+        let start = (self.number - 1) * self.per_page;
+        start..(start + self.per_page).min(self.total_items)
+    }
+
+    /// URL of this page.
+    pub fn url(&self) -> String {
+        page_url(&self.base_url, self.number)
+    }
+
+    /// Output directory of this page, relative to the listing's directory.
+    pub fn output_path(&self) -> PathBuf {
+        page_path(self.number)
+    }
+
+    /// Template value for this page, listing `items`.
+    pub fn to_paginator<T: Serialize>(&self, items: Vec<T>) -> Paginator<T> {
+        let url = |n: usize| page_url(&self.base_url, n);
+        Paginator {
+            current: self.number,
+            total: self.total,
+            per_page: self.per_page,
+            total_items: self.total_items,
+            url: self.url(),
+            first_url: url(1),
+            last_url: url(self.total),
+            prev_url: (self.number > 1).then(|| url(self.number - 1)),
+            next_url: (self.number < self.total).then(|| url(self.number + 1)),
+            pages: (1..=self.total)
+                .map(|number| PageLink { number, url: url(number), current: number == self.number })
+                .collect(),
+            items,
+        }
+    }
+}
+
+/// The `paginator` object available to listing templates.
+#[derive(Debug, Serialize)]
+pub struct Paginator<T> {
+    /// Current page number, starting at 1.
+    pub current: usize,
+    /// Total number of pages.
+    pub total: usize,
+    /// Items per page (0 = unpaginated).
+    pub per_page: usize,
+    /// Total number of items across all pages.
+    pub total_items: usize,
+    /// URL of the current page.
+    pub url: String,
+    /// URL of the first page.
+    pub first_url: String,
+    /// URL of the last page.
+    pub last_url: String,
+    /// URL of the previous page, if any.
+    pub prev_url: Option<String>,
+    /// URL of the next page, if any.
+    pub next_url: Option<String>,
+    /// Links to every page.
+    pub pages: Vec<PageLink>,
+    /// Items on the current page.
+    pub items: Vec<T>,
+}
+
+/// A link to one page of a listing.
+#[derive(Debug, Serialize)]
+pub struct PageLink {
+    /// Page number, starting at 1.
+    pub number: usize,
+    /// Page URL.
+    pub url: String,
+    /// Whether this is the current page.
+    pub current: bool,
+}
+
+/// Split a listing of `total_items` into pages of `per_page` items.
+///
+/// A `per_page` of 0 disables pagination. There is always at least one
+/// page, even for an empty listing.
+pub fn paginate(total_items: usize, per_page: usize, base_url: &str) -> Vec<Pager> {
+    let total = if per_page == 0 {
+        1
+    } else {
+        total_items.div_ceil(per_page).max(1)
+    };
+
+    (1..=total)
+        .map(|number| Pager {
+            number,
+            total,
+            per_page,
+            total_items,
+            base_url: base_url.to_string(),
+        })
+        .collect()
+}
+
+/// URL of page `number` of the listing at `base_url`.
+pub fn page_url(base_url: &str, number: usize) -> String {
+    if number <= 1 {
+        base_url.to_string()
+    } else {
+        format!("{}{}/{}/", base_url, PAGE_SEGMENT, number)
+    }
+}
+
+/// Directory of page `number`, relative to the listing's directory.
+pub fn page_path(number: usize) -> PathBuf {
+    if number <= 1 {
+        PathBuf::new()
+    } else {
+        PathBuf::from(PAGE_SEGMENT).join(number.to_string())
+    }
+}
+
+/// Split a URL like `/blog/page/3/` into its listing URL and page number.
+///
+/// URLs without a page suffix are page 1.
+pub fn split_page_url(url: &str) -> (&str, usize) {
+    let trimmed = url.trim_end_matches('/');
+    if let Some((rest, number)) = trimmed.rsplit_once('/')
+        && let Some(base) = rest.strip_suffix(PAGE_SEGMENT)
+        && base.ends_with('/')
+        && let Ok(number) = number.parse::<usize>()
+        && number >= 2
+    {
+        return (base, number);
+    }
+    (url, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paginate() {
+        let pages = paginate(25, 10, "/blog/");
+        assert_eq!(pages.len(), 3);
+        assert_eq!(pages[0].range(), 0..10);
+        assert_eq!(pages[2].range(), 20..25);
+        assert_eq!(pages[0].url(), "/blog/");
+        assert_eq!(pages[2].url(), "/blog/page/3/");
+        assert_eq!(pages[2].output_path(), PathBuf::from("page/3"));
+        assert_eq!(pages[0].output_path(), PathBuf::new());
+
+        // Empty and unpaginated listings have a single page.
+        assert_eq!(paginate(0, 10, "/").len(), 1);
+        let all = paginate(25, 0, "/");
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].range(), 0..25);
+    }
+
+    #[test]
+    fn test_paginator() {
+        let pages = paginate(5, 2, "/");
+        let paginator = pages[1].to_paginator(vec!["c", "d"]);
+        assert_eq!(paginator.current, 2);
+        assert_eq!(paginator.total, 3);
+        assert_eq!(paginator.prev_url.as_deref(), Some("/"));
+        assert_eq!(paginator.next_url.as_deref(), Some("/page/3/"));
+        assert_eq!(paginator.last_url, "/page/3/");
+        assert!(paginator.pages[1].current);
+
+        let first = pages[0].to_paginator(Vec::<&str>::new());
+        assert_eq!(first.prev_url, None);
+    }
+
+    #[test]
+    fn test_split_page_url() {
+        assert_eq!(split_page_url("/page/2/"), ("/", 2));
+        assert_eq!(split_page_url("/blog/2026/page/10/"), ("/blog/2026/", 10));
+        assert_eq!(split_page_url("/blog/"), ("/blog/", 1));
+        assert_eq!(split_page_url("/page/1/"), ("/page/1/", 1));
+        assert_eq!(split_page_url("/homepage/2/"), ("/homepage/2/", 1));
+    }
+}
@@ -74,6 +74,15 @@ pub fn feed_spinner() -> ProgressBar {
     pb
 }
 
+/// Create a spinner for minification.
+pub fn minify_spinner() -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(spinner_style());
+    pb.set_message("Minifying output...");
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb
+}
+
 /// Create a spinner for compression.
 pub fn compress_spinner() -> ProgressBar {
     let pb = ProgressBar::new_spinner();
@@ -4,6 +4,11 @@ use rustmax::prelude::*;
 use rustmax::regex;
 use rustmax::regex::{Regex, Captures};
 use std::borrow::Cow;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use super::encoding::file_to_data_url;
+use super::images::{ImageManifest, ResponsiveImage};
 
 /// URL rewriter for transforming links in content.
 pub struct UrlRewriter {
@@ -71,6 +76,112 @@ pub fn rewrite_md_links(content: &str) -> String {
     }).to_string()
 }
 
+static IMG_SRC_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(<img\b[^>]*?\bsrc=")(/[^"]+)(")"#).expect("invalid regex"));
+static IMG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"<img\b[^>]*>"#).expect("invalid regex"));
+static SRC_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"\bsrc="([^"]+)""#).expect("invalid regex"));
+
+/// Inline images no larger than `max_bytes` as data URLs.
+///
+/// Only root-relative `<img src="/...">` references to files under `root`
+/// are inlined; anything else is left unchanged.
+pub fn inline_small_images<'a>(content: &'a str, root: &Path, max_bytes: u64) -> Cow<'a, str> {
+    IMG_SRC_RE.replace_all(content, |caps: &Captures| {
+        let src = &caps[2];
+        let path = root.join(src.trim_start_matches('/'));
+        let small = !src.contains("..")
+            && std::fs::metadata(&path).map(|m| m.is_file() && m.len() <= max_bytes).unwrap_or(false);
+
+        match small.then(|| file_to_data_url(&path)) {
+            Some(Ok(data_url)) => format!("{}{}{}", &caps[1], data_url, &caps[3]),
+            _ => caps[0].to_string(),
+        }
+    })
+}
+
+/// Rewrite `<img>` tags for optimized images as responsive `<picture>` elements.
+///
+/// Each image listed in `images` gets a WebP `<source>`, a `srcset` of
+/// generated sizes, and `width`/`height` attributes if it had none.
+/// Relative `src` values are resolved against `page_url`. Images that
+/// already have a `srcset` or sit inside a `<picture>` are left alone.
+pub fn rewrite_picture_tags<'a>(
+    content: &'a str,
+    page_url: &str,
+    images: &ImageManifest,
+    sizes: &str,
+) -> Cow<'a, str> {
+    IMG_RE.replace_all(content, |caps: &Captures| {
+        let tag = &caps[0];
+        let start = caps.get(0).unwrap().start();
+        let in_picture = content[..start]
+            .rfind('<')
+            .is_some_and(|i| content[i..].starts_with("<source") || content[i..].starts_with("<picture"));
+
+        let image = SRC_RE
+            .captures(tag)
+            .filter(|_| !in_picture && !tag.contains("srcset="))
+            .and_then(|src| images.get(&resolve_url(page_url, &src[1])));
+
+        match image {
+            Some(image) => picture_html(tag, image, sizes),
+            None => tag.to_string(),
+        }
+    })
+}
+
+/// Build a `<picture>` element around an `<img>` tag.
+fn picture_html(tag: &str, image: &ResponsiveImage, sizes: &str) -> String {
+    let srcset = |set: &[(String, u32)]| {
+        set.iter()
+            .map(|(url, width)| format!("{} {}w", url, width))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let mut attrs = format!(r#" srcset="{}" sizes="{}""#, srcset(&image.srcset), sizes);
+    if !tag.contains("width=") && !tag.contains("height=") {
+        attrs.push_str(&format!(r#" width="{}" height="{}""#, image.width, image.height));
+    }
+
+    let body = tag.trim_end_matches('>').trim_end_matches('/').trim_end();
+    let img = format!("{}{}>", body, attrs);
+
+    if image.webp_srcset.is_empty() {
+        return format!("<picture>{}</picture>", img);
+    }
+
+    format!(
+        r#"<picture><source type="image/webp" srcset="{}" sizes="{}">{}</picture>"#,
+        srcset(&image.webp_srcset),
+        sizes,
+        img
+    )
+}
+
+/// Resolve a URL against a root-relative page URL, returning its path.
+pub fn resolve_url(page_url: &str, url: &str) -> String {
+    let url = url.split(['?', '#']).next().unwrap_or("");
+    if url.starts_with('/') {
+        return url.to_string();
+    }
+
+    let mut parts: Vec<&str> = page_url.split('/').filter(|p| !p.is_empty()).collect();
+    if !page_url.ends_with('/') {
+        parts.pop();
+    }
+    for part in url.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    format!("/{}", parts.join("/"))
+}
+
 /// Extract all URLs from content.
 pub fn extract_urls(content: &str) -> Vec<String> {
     let url_pattern = Regex::new(r#"https?://[^\s<>"')\]]+[^\s<>"')\].,!?;:]"#).unwrap();
@@ -94,6 +205,8 @@ pub fn is_valid_slug(slug: &str) -> bool {
     slug_pattern.is_match(slug)
 }
 
+static DASHES_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"-+").expect("invalid regex"));
+
 /// Transform a title into a URL-safe slug.
 pub fn slugify(title: &str) -> String {
     // Remove accents and special characters, convert to lowercase.
@@ -112,8 +225,7 @@ pub fn slugify(title: &str) -> String {
         .collect();
 
     // Collapse multiple dashes and trim.
-    let dash_pattern = Regex::new(r"-+").unwrap();
-    let result = dash_pattern.replace_all(&cleaned, "-");
+    let result = DASHES_RE.replace_all(&cleaned, "-");
     result.trim_matches('-').to_string()
 }
 
@@ -142,6 +254,52 @@ pub fn verify_links(content: &str, valid_paths: &[&str]) -> Vec<String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_rewrite_picture_tags() {
+        let mut images = ImageManifest::new();
+        images.insert("/posts/cat.jpg".to_string(), ResponsiveImage {
+            width: 1200,
+            height: 800,
+            srcset: vec![("/posts/cat-480w.jpg".to_string(), 480), ("/posts/cat.jpg".to_string(), 1200)],
+            webp_srcset: vec![("/posts/cat-480w.webp".to_string(), 480), ("/posts/cat.webp".to_string(), 1200)],
+        });
+
+        let html = r#"<p><img src="../posts/cat.jpg" alt="Cat"> <img src="/other.png"></p>"#;
+        let result = rewrite_picture_tags(html, "/hello/", &images, "100vw");
+
+        assert!(result.starts_with(r#"<p><picture><source type="image/webp" srcset="/posts/cat-480w.webp 480w, /posts/cat.webp 1200w" sizes="100vw">"#));
+        assert!(result.contains(r#"<img src="../posts/cat.jpg" alt="Cat" srcset="/posts/cat-480w.jpg 480w, /posts/cat.jpg 1200w" sizes="100vw" width="1200" height="800"></picture>"#));
+        assert!(result.contains(r#"<img src="/other.png">"#));
+
+        // Rewriting is idempotent.
+        let again = rewrite_picture_tags(&result, "/hello/", &images, "100vw");
+        assert_eq!(again, result);
+    }
+
+    #[test]
+    fn test_resolve_url() {
+        assert_eq!(resolve_url("/post/", "img/a.png"), "/post/img/a.png");
+        assert_eq!(resolve_url("/post/", "../a.png?v=1"), "/a.png");
+        assert_eq!(resolve_url("/post/index.html", "a.png"), "/post/a.png");
+        assert_eq!(resolve_url("/post/", "/static/a.png"), "/static/a.png");
+    }
+
+    #[test]
+    fn test_inline_small_images() {
+        use rustmax::tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("dot.png"), [0u8; 16]).unwrap();
+        std::fs::write(dir.path().join("big.png"), [0u8; 64]).unwrap();
+
+        let html = r#"<img src="/dot.png" alt="dot"><img src="/big.png"><img src="/missing.png">"#;
+        let result = inline_small_images(html, dir.path(), 32);
+
+        assert!(result.contains(r#"<img src="data:image/png;base64,"#));
+        assert!(result.contains(r#"<img src="/big.png">"#));
+        assert!(result.contains(r#"<img src="/missing.png">"#));
+    }
+
     #[test]
     fn test_url_rewriter() {
         let mut rewriter = UrlRewriter::new();
@@ -13,10 +13,19 @@ pub fn generate_search_page() -> String {
 const SEARCH_JS: &str = r#"/**
  * Anthology Client-Side Search
  * Provides instant search functionality for static sites.
+ *
+ * Multilingual sites pick a language's index with
+ * <script src="/search.js" data-index="/de/search-index.json">.
+ * The index is either one JSON file or the manifest of a sharded
+ * index, whose files are fetched as queries need them.
  */
 (function() {
   'use strict';
 
+  // Index URL from the script tag, read before the script finishes loading.
+  const script = document.currentScript;
+  const defaultIndexUrl = (script && script.dataset.index) || '/search-index.json';
+
   // Stop words (same as server).
   const STOP_WORDS = new Set([
     'a', 'an', 'and', 'are', 'as', 'at', 'be', 'by', 'for', 'from',
@@ -58,7 +67,96 @@ const SEARCH_JS: &str = r#"/**
       .map(stem);
   }
 
-  // Search index class.
+  // Match weights relative to an exact match (same as server).
+  const PREFIX_WEIGHT = 0.5;
+  const TYPO_WEIGHT = 0.3;
+
+  // Typos tolerated in a query term; short terms must match exactly.
+  function maxTypos(term) {
+    const len = Array.from(term).length;
+    if (len <= 3) return 0;
+    if (len <= 7) return 1;
+    return 2;
+  }
+
+  // Edit distance with adjacent transpositions, or null if above max.
+  function editDistance(a, b, max) {
+    if (max === 0) return null;
+    a = Array.from(a);
+    b = Array.from(b);
+    if (Math.abs(a.length - b.length) > max) return null;
+
+    let prevPrev = [];
+    let prev = Array.from({ length: b.length + 1 }, (_, j) => j);
+    for (let i = 1; i <= a.length; i++) {
+      const row = new Array(b.length + 1).fill(i);
+      for (let j = 1; j <= b.length; j++) {
+        const cost = a[i - 1] === b[j - 1] ? 0 : 1;
+        row[j] = Math.min(prev[j] + 1, row[j - 1] + 1, prev[j - 1] + cost);
+        if (i > 1 && j > 1 && a[i - 1] === b[j - 2] && a[i - 2] === b[j - 1]) {
+          row[j] = Math.min(row[j], prevPrev[j - 2] + 1);
+        }
+      }
+      if (row.every(d => d > max)) return null;
+      prevPrev = prev;
+      prev = row;
+    }
+    const distance = prev[b.length];
+    return distance <= max ? distance : null;
+  }
+
+  // Snippet length in words, and words of context before the first match.
+  const SNIPPET_WORDS = 30;
+  const SNIPPET_CONTEXT = 5;
+
+  // HTML excerpt of text around the best cluster of matched words, which
+  // are wrapped in <mark> (same rules as the server).
+  function snippet(text, terms) {
+    text = text || '';
+    const words = Array.from(text.matchAll(/\w+/g), m => {
+      const lower = m[0].toLowerCase();
+      let matched = -1;
+      if (lower.length >= 2 && !STOP_WORDS.has(lower)) {
+        const stemmed = stem(lower);
+        matched = terms.findIndex(t => stemmed.startsWith(t) ||
+          editDistance(t, stemmed, maxTypos(t)) !== null);
+      }
+      return { start: m.index, word: m[0], matched };
+    });
+
+    let best = null;
+    words.forEach((w, i) => {
+      if (w.matched < 0) return;
+      const start = Math.max(0, i - SNIPPET_CONTEXT);
+      const hits = words.slice(start, start + SNIPPET_WORDS).filter(w => w.matched >= 0);
+      const distinct = new Set(hits.map(w => w.matched)).size;
+      if (!best || distinct > best.distinct || (distinct === best.distinct && hits.length > best.total)) {
+        best = { start, distinct, total: hits.length };
+      }
+    });
+
+    const start = best ? best.start : 0;
+    const end = Math.min(words.length, start + SNIPPET_WORDS);
+    let out = start > 0 ? '… ' : '';
+    for (let i = start; i < end; i++) {
+      if (i > start) {
+        const prev = words[i - 1];
+        out += escapeHtml(cleanGap(text.slice(prev.start + prev.word.length, words[i].start)));
+      }
+      const word = escapeHtml(words[i].word);
+      out += words[i].matched >= 0 ? `<mark>${word}</mark>` : word;
+    }
+    if (end < words.length) return out + ' …';
+    const last = words[words.length - 1];
+    return last ? out + escapeHtml(cleanGap(text.slice(last.start + last.word.length)).trimEnd()) : out;
+  }
+
+  // Collapse whitespace and drop Markdown markup between words.
+  function cleanGap(gap) {
+    return gap.replace(/[#*_`~|\[\]]/g, '').replace(/\s+/g, ' ');
+  }
+
+  // Search index loaded from a single JSON file.
   class SearchIndex {
     constructor(data) {
       this.documents = data.documents || [];
@@ -66,66 +164,202 @@ const SEARCH_JS: &str = r#"/**
       this.docLengths = data.doc_lengths || [];
     }
 
-    search(query, maxResults = 20) {
+    // Term -> postings for at least the terms the query could match.
+    async postingsFor(terms) {
+      return this.wordIndex;
+    }
+
+    // Document entries by index.
+    async documentsAt(indices) {
+      return indices.map(idx => this.documents[idx]);
+    }
+
+    async search(query, maxResults = 20) {
       const terms = tokenize(query);
       if (terms.length === 0) return [];
 
+      const wordIndex = await this.postingsFor(terms);
       const scores = new Map();
-      const numDocs = this.documents.length;
+      const numDocs = this.docLengths.length;
       const avgDocLen = this.docLengths.reduce((a, b) => a + b, 0) / numDocs || 1;
       const k1 = 1.2;
       const b = 0.75;
 
+      // Add BM25 scores for one matching term, with its tf scaled by weight.
+      const addScores = (postings, weight) => {
+        const df = postings.length;
+        const idf = Math.log((numDocs - df + 0.5) / (df + 0.5) + 1);
+
+        for (const [docIdx, tf] of postings) {
+          const docLen = this.docLengths[docIdx] || 1;
+          const adjTf = tf * weight;
+          const score = idf * (adjTf * (k1 + 1)) / (adjTf + k1 * (1 - b + b * docLen / avgDocLen));
+          scores.set(docIdx, (scores.get(docIdx) || 0) + score);
+        }
+      };
+
       for (const term of terms) {
         // Exact match.
-        const postings = this.wordIndex[term] || [];
+        const postings = wordIndex[term] || [];
         if (postings.length > 0) {
-          const df = postings.length;
-          const idf = Math.log((numDocs - df + 0.5) / (df + 0.5) + 1);
-
-          for (const [docIdx, tf] of postings) {
-            const docLen = this.docLengths[docIdx] || 1;
-            const score = idf * (tf * (k1 + 1)) / (tf + k1 * (1 - b + b * docLen / avgDocLen));
-            scores.set(docIdx, (scores.get(docIdx) || 0) + score);
-          }
+          addScores(postings, 1);
         }
 
-        // Prefix matching.
-        for (const [indexedTerm, postings] of Object.entries(this.wordIndex)) {
-          if (indexedTerm.startsWith(term) && indexedTerm !== term) {
-            const df = postings.length;
-            const idf = Math.log((numDocs - df + 0.5) / (df + 0.5) + 1);
-
-            for (const [docIdx, tf] of postings) {
-              const docLen = this.docLengths[docIdx] || 1;
-              const adjTf = tf * 0.5; // Reduced weight for prefix.
-              const score = idf * (adjTf * (k1 + 1)) / (adjTf + k1 * (1 - b + b * docLen / avgDocLen));
-              scores.set(docIdx, (scores.get(docIdx) || 0) + score);
+        // Prefix matching, then typo matching.
+        const allowed = maxTypos(term);
+        for (const [indexedTerm, postings] of Object.entries(wordIndex)) {
+          if (indexedTerm === term) continue;
+          if (indexedTerm.startsWith(term)) {
+            addScores(postings, PREFIX_WEIGHT);
+          } else {
+            const distance = editDistance(term, indexedTerm, allowed);
+            if (distance !== null) {
+              addScores(postings, TYPO_WEIGHT / distance);
             }
           }
         }
       }
 
-      // Sort and return top results.
-      return Array.from(scores.entries())
+      // Sort, then fetch entries for the top results.
+      const ranked = Array.from(scores.entries())
         .sort((a, b) => b[1] - a[1])
-        .slice(0, maxResults)
-        .map(([idx, score]) => ({
-          ...this.documents[idx],
-          score: Math.round(score * 100)
-        }));
+        .slice(0, maxResults);
+      const documents = await this.documentsAt(ranked.map(([idx]) => idx));
+      return ranked.map(([idx, score], i) => ({
+        ...documents[i],
+        snippet: snippet(documents[i].excerpt, terms),
+        score: Math.round(score * 100)
+      }));
+    }
+  }
+
+  // Search index split into term-prefix shards and document chunks,
+  // fetched as queries need them. Typos are matched within the shards
+  // a query loads, so they are not found in a term's first characters.
+  class ShardedIndex extends SearchIndex {
+    constructor(manifest, manifestUrl) {
+      super({});
+      this.manifest = manifest;
+      this.base = new URL(manifest.base, new URL(manifestUrl, location.href));
+      this.shards = new Map();
+      this.chunks = new Map();
+    }
+
+    // Fetch a file from the index directory, gunzipping if needed.
+    async fetchFile(name) {
+      const response = await fetch(new URL(name, this.base));
+      if (!response.ok) throw new Error(`Failed to load ${name}: ${response.status}`);
+      if (!this.manifest.gzip) return response;
+      return new Response(response.body.pipeThrough(new DecompressionStream('gzip')));
+    }
+
+    async load() {
+      const reader = new VarintReader(await (await this.fetchFile(this.manifest.lengths)).arrayBuffer());
+      this.docLengths = Array.from({ length: reader.next() }, () => reader.next());
+    }
+
+    // Shard keys holding the terms that start with a query term.
+    shardKeys(term) {
+      const chars = Array.from(term);
+      if (chars.length >= this.manifest.prefix_len) {
+        return [chars.slice(0, this.manifest.prefix_len).join('')];
+      }
+      return Object.keys(this.manifest.shards).filter(key => key.startsWith(term));
+    }
+
+    async shard(key) {
+      if (!this.shards.has(key)) {
+        const file = this.manifest.shards[key];
+        this.shards.set(key, file
+          ? this.fetchFile(file).then(r => r.arrayBuffer()).then(decodeShard)
+          : Promise.resolve({}));
+      }
+      return this.shards.get(key);
+    }
+
+    async postingsFor(terms) {
+      const keys = new Set(terms.flatMap(term => this.shardKeys(term)));
+      const shards = await Promise.all(Array.from(keys, key => this.shard(key)));
+      return Object.assign({}, ...shards);
+    }
+
+    async documentsAt(indices) {
+      const size = this.manifest.docs_per_chunk;
+      const chunks = await Promise.all(indices.map(idx => {
+        const n = Math.floor(idx / size);
+        if (!this.chunks.has(n)) {
+          this.chunks.set(n, this.fetchFile(this.manifest.chunks[n]).then(r => r.json()));
+        }
+        return this.chunks.get(n);
+      }));
+      return indices.map((idx, i) => chunks[i][idx % size]);
     }
   }
 
+  // Reads unsigned LEB128 varints.
+  class VarintReader {
+    constructor(buffer) {
+      this.bytes = new Uint8Array(buffer);
+      this.pos = 0;
+    }
+
+    next() {
+      let n = 0;
+      let scale = 1;
+      for (;;) {
+        const byte = this.bytes[this.pos++];
+        n += (byte & 0x7f) * scale;
+        if (byte < 0x80) return n;
+        scale *= 128;
+      }
+    }
+
+    take(len) {
+      this.pos += len;
+      return this.bytes.subarray(this.pos - len, this.pos);
+    }
+  }
+
+  // Decode a shard into term -> [[docIdx, tf], ...].
+  function decodeShard(buffer) {
+    const reader = new VarintReader(buffer);
+    const decoder = new TextDecoder();
+    const terms = {};
+    let prev = new Uint8Array(0);
+    for (let count = reader.next(); count > 0; count--) {
+      const shared = reader.next();
+      const suffix = reader.take(reader.next());
+      const bytes = new Uint8Array(shared + suffix.length);
+      bytes.set(prev.subarray(0, shared));
+      bytes.set(suffix, shared);
+      prev = bytes;
+
+      const postings = [];
+      let docIdx = 0;
+      for (let n = reader.next(); n > 0; n--) {
+        docIdx += reader.next();
+        postings.push([docIdx, reader.next()]);
+      }
+      terms[decoder.decode(bytes)] = postings;
+    }
+    return terms;
+  }
+
   // Global search instance.
   let searchIndex = null;
 
-  // Initialize search.
-  async function initSearch(indexUrl = '/search-index.json') {
+  // Initialize search from a JSON index or a sharded index's manifest.
+  async function initSearch(indexUrl = defaultIndexUrl) {
     try {
       const response = await fetch(indexUrl);
       const data = await response.json();
-      searchIndex = new SearchIndex(data);
+      if (data.format === 'sharded') {
+        const index = new ShardedIndex(data, indexUrl);
+        await index.load();
+        searchIndex = index;
+      } else {
+        searchIndex = new SearchIndex(data);
+      }
       return true;
     } catch (e) {
       console.error('Failed to load search index:', e);
@@ -133,8 +367,8 @@ const SEARCH_JS: &str = r#"/**
     }
   }
 
-  // Perform search.
-  function search(query) {
+  // Perform search, resolving to the results.
+  async function search(query) {
     if (!searchIndex) {
       console.warn('Search index not loaded');
       return [];
@@ -160,8 +394,8 @@ const SEARCH_JS: &str = r#"/**
 
     container.innerHTML = results.map(r => `
       <article class="search-result">
-        <h3><a href="/${r.slug}/">${escapeHtml(r.title)}</a></h3>
-        <p class="preview">${escapeHtml(r.content_preview)}</p>
+        <h3><a href="${r.url || `/${r.slug}/`}">${escapeHtml(r.title)}</a></h3>
+        <p class="preview">${r.snippet || escapeHtml(r.content_preview)}</p>
         <p class="meta">
           ${r.tags.map(t => `<span class="tag">${escapeHtml(t)}</span>`).join(' ')}
         </p>
@@ -184,14 +418,23 @@ const SEARCH_JS: &str = r#"/**
     const input = form.querySelector('input[type="search"], input[type="text"]');
     if (!input) return;
 
-    const doSearch = debounce(() => {
+    // Only the latest query's results are shown.
+    let latest = 0;
+    const doSearch = debounce(async () => {
       const query = input.value.trim();
+      const current = ++latest;
       if (query.length < 2) {
         results.innerHTML = '';
         return;
       }
-      const searchResults = search(query);
-      renderResults(searchResults, results);
+      try {
+        const searchResults = await search(query);
+        if (current === latest) {
+          renderResults(searchResults, results);
+        }
+      } catch (e) {
+        console.error('Search failed:', e);
+      }
     }, 150);
 
     input.addEventListener('input', doSearch);
@@ -286,6 +529,11 @@ const SEARCH_PAGE: &str = r#"<!DOCTYPE html>
             color: var(--fg-muted);
             margin: 0.5rem 0;
         }
+        .search-result mark {
+            background: #fde68a;
+            color: inherit;
+            border-radius: 2px;
+        }
         .search-result .tag {
             display: inline-block;
             background: var(--code-bg);
@@ -334,6 +582,10 @@ mod tests {
         assert!(js.contains("initSearch"));
         assert!(js.contains("STOP_WORDS"));
         assert!(js.contains("stem"));
+        assert!(js.contains("editDistance"));
+        assert!(js.contains("<mark>"));
+        assert!(js.contains("ShardedIndex"));
+        assert!(js.contains("decodeShard"));
     }
 
     #[test]
@@ -3,16 +3,24 @@
 use rustmax::prelude::*;
 use rustmax::tera::{self, Tera, Context, Value};
 use rustmax::jiff::Zoned;
-use std::path::Path;
-use std::collections::HashMap;
-
-use crate::collection::{Config, Document};
-use crate::build::{extract_headings_html, TableOfContents, TocOptions};
-use crate::Result;
+use rustmax::log::warn;
+use rustmax::walkdir::WalkDir;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use crate::collection::{language_prefix, taxonomy_title, taxonomy_url, term_slug, term_url, Collection, Config, Document, Section, TAGS};
+use crate::build::{extract_headings_html, Pager, TableOfContents, TocOptions};
+use crate::search::RelatedDocument;
+use crate::diagnostic::Diagnostic;
+use crate::shortcode::Shortcode;
+use crate::{Error, Result};
 
 /// Template engine wrapping Tera.
 pub struct TemplateEngine {
     tera: Tera,
+    /// Contents of the collection's data files, available as `data`.
+    data: RwLock<Value>,
 }
 
 impl TemplateEngine {
@@ -21,33 +29,138 @@ impl TemplateEngine {
         let pattern = templates_dir.join("**/*.html");
         let pattern_str = pattern.to_string_lossy();
 
-        let mut tera = Tera::new(&pattern_str)?;
+        let tera = Tera::new(&pattern_str).map_err(|e| template_error(None, e))?;
+        Self::from_tera(tera)
+    }
+
+    /// Create a template engine for a collection, whose `templates/`
+    /// override those of its themes file by file.
+    ///
+    /// A template in any layer can extend or include one from another, and
+    /// themes' `shortcodes/` templates are shortcodes like the collection's.
+    pub fn for_collection(root: &Path, config: &Config) -> Result<Self> {
+        if config.themes.is_empty() {
+            return Self::new(&root.join("templates"));
+        }
+
+        // Lowest priority first, so that later layers replace earlier ones.
+        let mut files: BTreeMap<String, PathBuf> = BTreeMap::new();
+        for dir in crate::theme::template_dirs(root, &config.themes).iter().rev() {
+            for entry in WalkDir::new(dir).into_iter().filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if entry.file_type().is_file() && path.extension().is_some_and(|e| e == "html") {
+                    let name = path.strip_prefix(dir).unwrap().to_string_lossy().replace('\\', "/");
+                    files.insert(name, path.to_path_buf());
+                }
+            }
+        }
+
+        let mut tera = Tera::default();
+        tera.add_template_files(files.into_iter().map(|(name, path)| (path, Some(name))))
+            .map_err(|e| template_error(Some(&tera), e))?;
+        Self::from_tera(tera)
+    }
 
+    fn from_tera(mut tera: Tera) -> Result<Self> {
         // Register custom filters.
         tera.register_filter("date_format", filter_date_format);
         tera.register_filter("word_count", filter_word_count);
         tera.register_filter("reading_time", filter_reading_time);
         tera.register_filter("truncate_words", filter_truncate_words);
+        tera.register_filter("term_slug", filter_term_slug);
 
         // Add built-in templates as fallback.
         tera.add_raw_template("_builtin/default.html", BUILTIN_DEFAULT_TEMPLATE)?;
 
-        Ok(Self { tera })
+        Ok(Self {
+            tera,
+            data: RwLock::new(Value::Object(tera::Map::new())),
+        })
+    }
+
+    /// Replace the data exposed to every template and shortcode as `data`.
+    ///
+    /// Takes `&self` so the development server can reload data files
+    /// while requests are being rendered.
+    pub fn set_data(&self, data: Value) {
+        *self.data.write().unwrap() = data;
     }
 
     /// Render a template with the given context.
     pub fn render(&self, template_name: &str, context: &Context) -> Result<String> {
+        let mut context = context.clone();
+        self.insert_data(&mut context);
+
         // Try the requested template, fall back to builtin.
-        let result = self.tera.render(template_name, context).or_else(|_| {
-            self.tera.render("_builtin/default.html", context)
-        })?;
-        Ok(result)
+        let result = self.tera.render(template_name, &context).or_else(|e| {
+            if self.has_template(template_name) {
+                warn!("{}; using the built-in template", template_error(Some(&self.tera), e));
+            }
+            self.tera.render("_builtin/default.html", &context)
+        });
+        result.map_err(|e| template_error(Some(&self.tera), e))
+    }
+
+    fn insert_data(&self, ctx: &mut Context) {
+        ctx.insert("data", &*self.data.read().unwrap());
+    }
+
+    /// Check whether the collection defines a template.
+    pub fn has_template(&self, name: &str) -> bool {
+        self.tera.get_template_names().any(|n| n == name)
+    }
+
+    /// Template for a taxonomy term page.
+    ///
+    /// Tags keep using `tag.html` when the collection has one; otherwise
+    /// every taxonomy uses `term.html`.
+    pub fn term_template(&self, taxonomy: &str) -> &'static str {
+        if taxonomy == TAGS && self.has_template("tag.html") {
+            "tag.html"
+        } else {
+            "term.html"
+        }
+    }
+
+    /// Check whether the collection defines a template for a shortcode.
+    pub fn has_shortcode(&self, name: &str) -> bool {
+        self.has_template(&shortcode_template_name(name))
+    }
+
+    /// Render a shortcode with `templates/shortcodes/<name>.html`.
+    ///
+    /// Named arguments are available as top-level variables and in `args`,
+    /// positional arguments as `positional`, block content as `content`,
+    /// and the collection's data files as `data`.
+    pub fn render_shortcode(&self, shortcode: &Shortcode) -> Result<String> {
+        let mut ctx = Context::new();
+
+        for (key, value) in &shortcode.args {
+            ctx.insert(key, value);
+        }
+
+        ctx.insert("name", &shortcode.name);
+        ctx.insert("args", &shortcode.args);
+        ctx.insert("positional", &shortcode.positional);
+        ctx.insert("is_block", &shortcode.is_block);
+        self.insert_data(&mut ctx);
+        if let Some(ref content) = shortcode.content {
+            ctx.insert("content", content);
+        }
+
+        self.tera
+            .render(&shortcode_template_name(&shortcode.name), &ctx)
+            .map_err(|e| template_error(Some(&self.tera), e))
     }
 
     /// Build template context for a document.
+    ///
+    /// `section` is the document's section, `parent` that section's parent,
+    /// and `children` its subsections.
     pub fn document_context(
         &self,
         doc: &Document,
+        collection: &Collection,
         config: &Config,
         html_content: &str,
     ) -> Context {
@@ -88,6 +201,48 @@ impl TemplateEngine {
             ctx.insert(key, &toml_to_tera_value(value));
         }
 
+        // Taxonomy terms with their listing URLs.
+        let taxonomies: HashMap<&str, Vec<HashMap<&str, String>>> = config
+            .content
+            .taxonomies
+            .iter()
+            .map(|taxonomy| {
+                let terms = doc
+                    .terms(taxonomy)
+                    .into_iter()
+                    .map(|term| {
+                        HashMap::from([
+                            ("name", term.to_string()),
+                            ("url", term_url(taxonomy, term)),
+                        ])
+                    })
+                    .collect();
+                (taxonomy.as_str(), terms)
+            })
+            .collect();
+        ctx.insert("taxonomies", &taxonomies);
+
+        // Section navigation.
+        if let Some(section) = collection.section_in(doc.language.as_deref(), &doc.section) {
+            insert_section_vars(&mut ctx, section, collection);
+        }
+
+        // Language and published translations.
+        self.insert_language(&mut ctx, config, doc.language.as_deref());
+        let translations: Vec<HashMap<&str, String>> = collection
+            .translations(doc)
+            .into_iter()
+            .filter(|t| t.is_published())
+            .map(|t| {
+                HashMap::from([
+                    ("lang", config.language_code(t.language.as_deref()).to_string()),
+                    ("url", t.url_path()),
+                    ("title", t.frontmatter.title.clone()),
+                ])
+            })
+            .collect();
+        ctx.insert("translations", &translations);
+
         // Generate table of contents.
         let toc_options = TocOptions::default();
         let headings = extract_headings_html(html_content);
@@ -113,38 +268,87 @@ impl TemplateEngine {
         ctx.insert("site_author", &config.collection.author);
         ctx.insert("base_url", &config.collection.base_url);
         ctx.insert("title", &config.collection.title);
+        ctx.insert("documents", &document_summaries(documents));
+        ctx.insert("is_index", &true);
 
-        let docs: Vec<HashMap<&str, Value>> = documents
-            .iter()
-            .map(|doc| {
-                let mut map = HashMap::new();
-                map.insert("title", Value::String(doc.frontmatter.title.clone()));
-                map.insert("slug", Value::String(doc.slug()));
-                map.insert("url", Value::String(doc.url_path()));
-                map.insert("draft", Value::Bool(doc.frontmatter.draft));
-                map.insert(
-                    "tags",
-                    Value::Array(
-                        doc.frontmatter
-                            .tags
-                            .iter()
-                            .map(|t| Value::String(t.clone()))
-                            .collect(),
-                    ),
-                );
-                if let Some(date) = doc.frontmatter.date {
-                    map.insert("date", Value::String(date.to_string()));
-                }
-                map
-            })
-            .collect();
+        ctx
+    }
 
-        ctx.insert("documents", &docs);
-        ctx.insert("is_index", &true);
+    /// Build template context for the index page of a language.
+    pub fn language_index_context(
+        &self,
+        documents: &[&Document],
+        config: &Config,
+        language: Option<&str>,
+    ) -> Context {
+        let mut ctx = self.index_context(documents, config);
+        self.insert_language(&mut ctx, config, language);
+        ctx.insert("title", config.language_title(language));
+        ctx
+    }
+
+    /// Add a language's `lang`, `lang_prefix`, and site title and
+    /// description to a context.
+    pub fn insert_language(&self, ctx: &mut Context, config: &Config, language: Option<&str>) {
+        ctx.insert("lang", config.language_code(language));
+        ctx.insert("lang_prefix", &language_prefix(language));
+        ctx.insert("site_title", config.language_title(language));
+        ctx.insert("site_description", config.language_description(language));
+    }
+
+    /// Build template context for a section page.
+    ///
+    /// Documents directly in the section are `pages`; `section`, `parent`
+    /// and `children` are as in [`TemplateEngine::document_context`].
+    pub fn section_context(
+        &self,
+        section: &Section,
+        documents: &[&Document],
+        collection: &Collection,
+        config: &Config,
+        html_content: &str,
+    ) -> Context {
+        let mut ctx = Context::new();
+
+        ctx.insert("site_title", &config.collection.title);
+        ctx.insert("site_description", &config.collection.description);
+        ctx.insert("site_author", &config.collection.author);
+        ctx.insert("base_url", &config.collection.base_url);
+
+        ctx.insert("title", &section.title());
+        ctx.insert("content", html_content);
+        ctx.insert("url", &section.url_path());
+        if let Some(ref desc) = section.frontmatter.description {
+            ctx.insert("description", desc);
+        }
+        for (key, value) in &section.frontmatter.extra {
+            ctx.insert(key, &toml_to_tera_value(value));
+        }
+
+        let pages = document_summaries(documents);
+        ctx.insert("pages", &pages);
+        ctx.insert("documents", &pages);
+        insert_section_vars(&mut ctx, section, collection);
+        self.insert_language(&mut ctx, config, section.language.as_deref());
+        ctx.insert("is_section", &true);
 
         ctx
     }
 
+    /// Add a document's related documents as `related`.
+    ///
+    /// Each has `title`, `url`, `slug`, and `score`, most similar first.
+    pub fn insert_related(&self, ctx: &mut Context, related: &[RelatedDocument]) {
+        ctx.insert("related", related);
+    }
+
+    /// Add a `paginator` for one page of a listing to a context.
+    ///
+    /// `documents` are the items on this page.
+    pub fn insert_paginator(&self, ctx: &mut Context, pager: &Pager, documents: &[&Document]) {
+        ctx.insert("paginator", &pager.to_paginator(document_summaries(documents)));
+    }
+
     /// Build template context for tag page.
     pub fn tag_context(
         &self,
@@ -158,10 +362,257 @@ impl TemplateEngine {
         ctx.insert("is_tag_page", &true);
         ctx
     }
+
+    /// Build template context for a taxonomy term page.
+    ///
+    /// Tag pages also get the variables of [`TemplateEngine::tag_context`].
+    pub fn term_context(
+        &self,
+        taxonomy: &str,
+        term: &str,
+        documents: &[&Document],
+        config: &Config,
+    ) -> Context {
+        let mut ctx = if taxonomy == TAGS {
+            self.tag_context(term, documents, config)
+        } else {
+            let mut ctx = self.index_context(documents, config);
+            ctx.insert("title", &format!("{}: {}", taxonomy_title(taxonomy), term));
+            ctx
+        };
+        ctx.insert("taxonomy", taxonomy);
+        ctx.insert("taxonomy_url", &taxonomy_url(taxonomy));
+        ctx.insert("term", term);
+        ctx.insert("url", &term_url(taxonomy, term));
+        ctx.insert("is_term_page", &true);
+        ctx
+    }
+
+    /// Build template context for a taxonomy's term index.
+    ///
+    /// `terms` pairs each term with its number of documents.
+    pub fn taxonomy_context(
+        &self,
+        taxonomy: &str,
+        terms: &[(String, usize)],
+        config: &Config,
+    ) -> Context {
+        let mut ctx = Context::new();
+
+        ctx.insert("site_title", &config.collection.title);
+        ctx.insert("site_description", &config.collection.description);
+        ctx.insert("site_author", &config.collection.author);
+        ctx.insert("base_url", &config.collection.base_url);
+
+        let terms: Vec<HashMap<&str, Value>> = terms
+            .iter()
+            .map(|(term, count)| {
+                HashMap::from([
+                    ("name", Value::String(term.clone())),
+                    ("url", Value::String(term_url(taxonomy, term))),
+                    ("count", Value::Number((*count).into())),
+                ])
+            })
+            .collect();
+
+        ctx.insert("title", &taxonomy_title(taxonomy));
+        ctx.insert("taxonomy", taxonomy);
+        ctx.insert("url", &taxonomy_url(taxonomy));
+        ctx.insert("terms", &terms);
+        ctx.insert("is_taxonomy", &true);
+
+        ctx
+    }
+}
+
+/// Insert `section`, `parent`, and `children` for a section.
+fn insert_section_vars(ctx: &mut Context, section: &Section, collection: &Collection) {
+    ctx.insert("section", &section_summary(section));
+    if let Some(parent) = collection.parent_section(section) {
+        ctx.insert("parent", &section_summary(parent));
+    }
+    let children: Vec<_> = collection
+        .child_sections(section)
+        .into_iter()
+        .map(section_summary)
+        .collect();
+    ctx.insert("children", &children);
+}
+
+/// Template value describing a section.
+fn section_summary(section: &Section) -> HashMap<&'static str, Value> {
+    let mut map = HashMap::new();
+    map.insert("title", Value::String(section.title()));
+    map.insert("name", Value::String(section.name().to_string()));
+    map.insert("path", Value::String(section.path.clone()));
+    map.insert("url", Value::String(section.url_path()));
+    map.insert("is_root", Value::Bool(section.is_root()));
+    if let Some(ref desc) = section.frontmatter.description {
+        map.insert("description", Value::String(desc.clone()));
+    }
+    map
+}
+
+/// Template values listing documents.
+fn document_summaries(documents: &[&Document]) -> Vec<HashMap<&'static str, Value>> {
+    documents
+        .iter()
+        .map(|doc| {
+            let mut map = HashMap::new();
+            map.insert("title", Value::String(doc.frontmatter.title.clone()));
+            map.insert("slug", Value::String(doc.slug()));
+            map.insert("url", Value::String(doc.url_path()));
+            map.insert("draft", Value::Bool(doc.frontmatter.draft));
+            map.insert(
+                "tags",
+                Value::Array(
+                    doc.frontmatter
+                        .tags
+                        .iter()
+                        .map(|t| Value::String(t.clone()))
+                        .collect(),
+                ),
+            );
+            if let Some(date) = doc.frontmatter.date {
+                map.insert("date", Value::String(date.to_string()));
+            }
+            map
+        })
+        .collect()
+}
+
+/// Template name for a user-defined shortcode.
+fn shortcode_template_name(name: &str) -> String {
+    format!("shortcodes/{}.html", name)
+}
+
+/// Trace a Tera error back to the line of the template it happened in.
+///
+/// Parse errors carry a line and column. Render errors only name the
+/// template, so the line is found by searching its tags for the variable,
+/// filter, or function the error is about. Errors that can't be traced,
+/// like those in built-in templates, are returned as they are.
+fn template_error(tera: Option<&Tera>, error: tera::Error) -> Error {
+    let mut chain: Vec<&(dyn std::error::Error + 'static)> = vec![&error];
+    while let Some(source) = chain[chain.len() - 1].source() {
+        chain.push(source);
+    }
+    let messages: Vec<String> = chain.iter().map(|e| e.to_string()).collect();
+
+    // Loading a directory reports every broken template in one message,
+    // each starting with `* `; the first is used.
+    let head = messages[0].trim_start().trim_start_matches("* ");
+    let diagnostic = if let Some(rest) = head.strip_prefix("Failed to parse ") {
+        let (name, detail) = rest.split_once('\n').unwrap_or((rest, ""));
+        let detail = match detail.split("\n* ").next().filter(|d| !d.trim().is_empty()) {
+            Some(detail) => detail,
+            None => messages.get(1).map(String::as_str).unwrap_or_default(),
+        };
+        let path = match quoted(name, '"') {
+            Some(path) => Some(path.to_string()),
+            None => quoted(name, '\'').and_then(|name| template_path(tera?, name)),
+        };
+        path.and_then(|path| parse_error_diagnostic(&path, detail))
+    } else if let Some(rest) = messages[0].strip_prefix("Failed to render ") {
+        // Errors in a parent template name it at the end.
+        let name = rest
+            .split_once("(error happened in ")
+            .and_then(|(_, parent)| quoted(parent, '\''))
+            .or_else(|| quoted(rest, '\''));
+        let path = name.and_then(|name| template_path(tera?, name));
+        let token = chain.iter().rev().find_map(|e| error_subject(*e));
+        path.zip(token).and_then(|(path, token)| {
+            let source = std::fs::read_to_string(&path).ok()?;
+            let offset = find_in_tags(&source, &token)?;
+            let message = messages[1..].join(": ");
+            Some(Diagnostic::at_offset(path, &source, offset, message))
+        })
+    } else {
+        None
+    };
+
+    match diagnostic {
+        Some(diagnostic) => Error::template_source(diagnostic),
+        None => Error::Template(error),
+    }
+}
+
+/// The text between the first pair of `quote`s.
+fn quoted(text: &str, quote: char) -> Option<&str> {
+    let start = text.find(quote)? + 1;
+    let end = start + text[start..].find(quote)?;
+    Some(&text[start..end])
+}
+
+/// The file a loaded template came from.
+fn template_path(tera: &Tera, name: &str) -> Option<String> {
+    tera.get_template(name).ok()?.path.clone()
+}
+
+/// Build a diagnostic from a Tera parse error, which reads like:
+///
+/// ```text
+///  --> 3:12
+///   |
+/// 3 | {{ title | }}
+///   |            ^---
+///   |
+///   = expected an identifier
+/// ```
+fn parse_error_diagnostic(path: &str, message: &str) -> Option<Diagnostic> {
+    let (line, column) = message
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("--> "))?
+        .split_once(':')?;
+    let detail = message
+        .lines()
+        .find_map(|l| l.trim().strip_prefix("= "))
+        .unwrap_or("syntax error");
+    let diagnostic = Diagnostic::new(path, line.parse().ok()?, column.parse().ok()?, detail);
+    Some(diagnostic.with_source_file())
+}
+
+/// The variable, filter, function, or test a render error is about.
+fn error_subject(error: &(dyn std::error::Error + 'static)) -> Option<String> {
+    use tera::ErrorKind;
+
+    let error = error.downcast_ref::<tera::Error>()?;
+    match &error.kind {
+        ErrorKind::FilterNotFound(name)
+        | ErrorKind::CallFilter(name)
+        | ErrorKind::FunctionNotFound(name)
+        | ErrorKind::CallFunction(name)
+        | ErrorKind::TestNotFound(name)
+        | ErrorKind::CallTest(name) => Some(name.clone()),
+        ErrorKind::Msg(message) => quoted(message, '`').map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Byte offset of the first `{{ }}` or `{% %}` tag in a template that
+/// mentions `token`.
+fn find_in_tags(source: &str, token: &str) -> Option<usize> {
+    let mut pos = 0;
+    while let Some(start) = source[pos..].find('{').map(|i| pos + i) {
+        let close = match source[start + 1..].chars().next() {
+            Some('{') => "}}",
+            Some('%') => "%}",
+            _ => {
+                pos = start + 1;
+                continue;
+            }
+        };
+        let end = source[start..].find(close).map(|i| start + i).unwrap_or(source.len());
+        if let Some(i) = source[start..end].find(token) {
+            return Some(start + i);
+        }
+        pos = end;
+    }
+    None
 }
 
 /// Convert TOML value to Tera value.
-fn toml_to_tera_value(value: &rustmax::toml::Value) -> Value {
+pub(crate) fn toml_to_tera_value(value: &rustmax::toml::Value) -> Value {
     match value {
         rustmax::toml::Value::String(s) => Value::String(s.clone()),
         rustmax::toml::Value::Integer(i) => Value::Number((*i).into()),
@@ -218,6 +669,15 @@ fn filter_word_count(value: &Value, _args: &HashMap<String, Value>) -> tera::Res
     Ok(Value::Number(count.into()))
 }
 
+/// Filter: URL segment of a taxonomy term, e.g. `/tags/{{ tag | term_slug }}/`.
+fn filter_term_slug(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let term = value
+        .as_str()
+        .ok_or_else(|| tera::Error::msg("term_slug expects a string"))?;
+
+    Ok(Value::String(term_slug(term)))
+}
+
 /// Filter: estimate reading time.
 fn filter_reading_time(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
     use rustmax::unicode_segmentation::UnicodeSegmentation;
@@ -255,11 +715,13 @@ fn filter_truncate_words(value: &Value, args: &HashMap<String, Value>) -> tera::
 }
 
 const BUILTIN_DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
-<html lang="en">
+<html lang="{{ lang | default(value="en") }}">
 <head>
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
     <title>{% if title %}{{ title }} - {% endif %}{{ site_title }}</title>
+    {% if translations %}{% for t in translations %}<link rel="alternate" hreflang="{{ t.lang }}" href="{{ t.url }}">
+    {% endfor %}{% endif %}
     <style>
         body {
             font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif;
@@ -298,7 +760,7 @@ const BUILTIN_DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
 </head>
 <body>
     <header>
-        <nav><a href="/">{{ site_title }}</a></nav>
+        <nav><a href="{{ lang_prefix | default(value="") }}/">{{ site_title }}</a></nav>
     </header>
     <main>
         {% if is_index %}
@@ -312,12 +774,20 @@ const BUILTIN_DEFAULT_TEMPLATE: &str = r#"<!DOCTYPE html>
             </li>
         {% endfor %}
         </ul>
+        {% elif is_taxonomy %}
+        <h1>{{ title }}</h1>
+        <ul class="document-list">
+        {% for term in terms %}
+            <li><a href="{{ term.url }}">{{ term.name }}</a> ({{ term.count }})</li>
+        {% endfor %}
+        </ul>
         {% else %}
         <article>
             <h1>{{ title }}</h1>
             {% if date %}<p class="meta">{{ date }}{% if reading_time %} &middot; {{ reading_time }} min read{% endif %}</p>{% endif %}
-            {% if tags %}<p class="tags">{% for tag in tags %}<a href="/tags/{{ tag }}/">{{ tag }}</a> {% endfor %}</p>{% endif %}
+            {% if tags %}<p class="tags">{% for tag in tags %}<a href="/tags/{{ tag | term_slug }}/">{{ tag }}</a> {% endfor %}</p>{% endif %}
             {{ content | safe }}
+            {% if related %}<aside class="related"><h2>See also</h2><ul>{% for doc in related %}<li><a href="{{ doc.url }}">{{ doc.title }}</a></li>{% endfor %}</ul></aside>{% endif %}
         </article>
         {% endif %}
     </main>
@@ -0,0 +1,296 @@
+//! External URL checking with a local cache and per-host rate limit.
+
+use rustmax::prelude::*;
+use rustmax::jiff::Timestamp;
+use rustmax::log::{debug, info, warn};
+use rustmax::reqwest::{Client, StatusCode};
+use rustmax::tokio;
+use rustmax::tokio::sync::Semaphore;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::{Error, Result};
+
+/// Cache of URLs that recently checked out, relative to the collection root.
+pub const LINK_CACHE_FILE: &str = ".anthology-cache/links.json";
+
+/// Options for checking external URLs.
+#[derive(Debug, Clone)]
+pub struct ExternalOptions {
+    /// Requests in flight at once.
+    pub concurrency: usize,
+    /// Requests per second to any one host.
+    pub rate_limit: f64,
+    /// Time allowed for each request.
+    pub timeout: Duration,
+    /// How long a working URL is trusted without rechecking.
+    pub cache_ttl: Duration,
+    /// Where to keep the cache, if anywhere.
+    pub cache_path: Option<PathBuf>,
+}
+
+impl Default for ExternalOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 8,
+            rate_limit: 2.0,
+            timeout: Duration::from_secs(10),
+            cache_ttl: Duration::from_secs(24 * 60 * 60),
+            cache_path: None,
+        }
+    }
+}
+
+/// URLs that worked, with when they were checked (Unix seconds).
+///
+/// Failures are not cached, so fixed or flaky links are rechecked on
+/// the next run.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LinkCache {
+    pub checked: BTreeMap<String, i64>,
+}
+
+impl LinkCache {
+    /// Load a cache, starting empty if it is missing or unreadable.
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|json| rustmax::serde_json::from_str(&json).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the cache.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, rustmax::serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Check whether a URL worked within `ttl` of `now`.
+    pub fn is_fresh(&self, url: &str, now: i64, ttl: Duration) -> bool {
+        self.checked
+            .get(url)
+            .is_some_and(|&at| now.saturating_sub(at) < ttl.as_secs() as i64)
+    }
+}
+
+/// Check external URLs, returning the broken ones with the reason.
+///
+/// URLs in the cache are skipped. The rest are requested concurrently,
+/// spacing requests to each host by the rate limit, with a `HEAD` request
+/// retried as `GET` for servers that reject `HEAD`.
+pub fn check_external(urls: &[String], options: &ExternalOptions) -> Result<BTreeMap<String, String>> {
+    let mut cache = options
+        .cache_path
+        .as_deref()
+        .map(LinkCache::load)
+        .unwrap_or_default();
+    let now = Timestamp::now().as_second();
+
+    let pending: Vec<String> = urls
+        .iter()
+        .filter(|url| !cache.is_fresh(url, now, options.cache_ttl))
+        .cloned()
+        .collect();
+    info!(
+        "Checking {} external links ({} cached)",
+        pending.len(),
+        urls.len() - pending.len()
+    );
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let results = runtime.block_on(check_all(pending, options))?;
+
+    let mut broken = BTreeMap::new();
+    for (url, result) in results {
+        match result {
+            Ok(()) => {
+                cache.checked.insert(url, now);
+            }
+            Err(reason) => {
+                cache.checked.remove(&url);
+                broken.insert(url, reason);
+            }
+        }
+    }
+
+    if let Some(path) = &options.cache_path
+        && let Err(e) = cache.save(path)
+    {
+        warn!("Failed to save link cache {}: {}", path.display(), e);
+    }
+
+    Ok(broken)
+}
+
+async fn check_all(urls: Vec<String>, options: &ExternalOptions) -> Result<Vec<(String, std::result::Result<(), String>)>> {
+    let client = Client::builder()
+        .timeout(options.timeout)
+        .user_agent(concat!("anthology/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| Error::Other(e.into()))?;
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let limiter = Arc::new(RateLimiter::new(options.rate_limit));
+
+    let tasks: Vec<_> = urls
+        .into_iter()
+        .map(|url| {
+            let client = client.clone();
+            let permits = permits.clone();
+            let limiter = limiter.clone();
+            tokio::spawn(async move {
+                let host = rustmax::url::Url::parse(&url)
+                    .ok()
+                    .and_then(|u| u.host_str().map(str::to_string))
+                    .unwrap_or_default();
+                limiter.wait(&host).await;
+                let _permit = permits.acquire_owned().await.expect("semaphore is never closed");
+                let result = check_url(&client, &url).await;
+                debug!("Checked {}: {:?}", url, result);
+                (url, result)
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(tasks.len());
+    for task in tasks {
+        results.push(task.await.map_err(|e| Error::Other(e.into()))?);
+    }
+    Ok(results)
+}
+
+/// Request a URL, trying `GET` if `HEAD` does not succeed.
+async fn check_url(client: &Client, url: &str) -> std::result::Result<(), String> {
+    let head = client.head(url).send().await.map(|r| r.status());
+    if head.as_ref().is_ok_and(StatusCode::is_success) {
+        return Ok(());
+    }
+
+    match client.get(url).send().await {
+        Ok(response) if response.status().is_success() => Ok(()),
+        Ok(response) => Err(format!("HTTP {}", response.status())),
+        Err(e) => {
+            // Include the causes, like a DNS or TLS failure.
+            let e = e.without_url();
+            let mut reason = e.to_string();
+            let mut source = std::error::Error::source(&e);
+            while let Some(cause) = source {
+                reason = format!("{}: {}", reason, cause);
+                source = cause.source();
+            }
+            Err(reason)
+        }
+    }
+}
+
+/// Spaces out requests to each host.
+struct RateLimiter {
+    interval: Duration,
+    /// When each host may next be requested.
+    next: Mutex<HashMap<String, Instant>>,
+}
+
+impl RateLimiter {
+    fn new(per_second: f64) -> Self {
+        let interval = if per_second > 0.0 {
+            Duration::from_secs_f64(1.0 / per_second)
+        } else {
+            Duration::ZERO
+        };
+        Self {
+            interval,
+            next: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Reserve the next slot for a host and wait for it.
+    async fn wait(&self, host: &str) {
+        let delay = {
+            let mut next = self.next.lock().expect("rate limiter lock poisoned");
+            let now = Instant::now();
+            let slot = next.entry(host.to_string()).or_insert(now);
+            let start = (*slot).max(now);
+            *slot = start + self.interval;
+            start - now
+        };
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustmax::tempfile::tempdir;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+
+    /// Serve `/ok` with 200, `/no-head` with 405 to `HEAD` and 200 to
+    /// `GET`, and anything else with 404.
+    fn serve() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = String::new();
+                BufReader::new(&stream).read_line(&mut request).unwrap();
+                let status = match request.split_whitespace().take(2).collect::<Vec<_>>()[..] {
+                    [_, "/ok"] | ["GET", "/no-head"] => "200 OK",
+                    ["HEAD", "/no-head"] => "405 Method Not Allowed",
+                    _ => "404 Not Found",
+                };
+                let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[test]
+    fn test_check_external() {
+        let base = serve();
+        let dir = tempdir().unwrap();
+        let options = ExternalOptions {
+            rate_limit: 100.0,
+            cache_path: Some(dir.path().join(LINK_CACHE_FILE)),
+            ..Default::default()
+        };
+
+        let urls = vec![
+            format!("{}/ok", base),
+            format!("{}/no-head", base),
+            format!("{}/gone", base),
+        ];
+        let broken = check_external(&urls, &options).unwrap();
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[&urls[2]], "HTTP 404 Not Found");
+
+        // Working URLs are cached; broken ones are not.
+        let cache = LinkCache::load(options.cache_path.as_deref().unwrap());
+        let now = Timestamp::now().as_second();
+        assert!(cache.is_fresh(&urls[0], now, options.cache_ttl));
+        assert!(cache.is_fresh(&urls[1], now, options.cache_ttl));
+        assert!(!cache.is_fresh(&urls[2], now, options.cache_ttl));
+        assert!(!cache.is_fresh(&urls[0], now + 2 * 24 * 60 * 60, options.cache_ttl));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(20.0);
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let start = Instant::now();
+        runtime.block_on(async {
+            for _ in 0..3 {
+                limiter.wait("example.com").await;
+            }
+            limiter.wait("example.org").await;
+        });
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(100), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(1000), "{:?}", elapsed);
+    }
+}
@@ -0,0 +1,448 @@
+//! Internal link and anchor checking.
+
+use rustmax::prelude::*;
+use rustmax::regex::Regex;
+use rustmax::tempfile::tempdir;
+use rustmax::walkdir::WalkDir;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use super::external::{check_external, ExternalOptions};
+use crate::build::{build, resolve_url};
+use crate::collection::{Collection, Config};
+use crate::features::BuildFeatures;
+use crate::Result;
+
+static HREF_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\shref\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("invalid regex")
+});
+
+static ID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\sid\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("invalid regex")
+});
+
+/// Options for [`check_links`].
+#[derive(Debug, Clone, Default)]
+pub struct LinkOptions {
+    /// Features to build the site with.
+    pub features: BuildFeatures,
+    /// Check external URLs too, with these options.
+    pub external: Option<ExternalOptions>,
+}
+
+/// A link that does not resolve.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokenLink {
+    /// The document, section index, or template the link is written in,
+    /// or the page URL if it could not be found in any of them.
+    pub source: PathBuf,
+    /// Line of the link in `source`, if found.
+    pub line: Option<usize>,
+    /// The link as written.
+    pub href: String,
+    /// Why the link is broken.
+    pub reason: String,
+    /// Number of pages the link appears on.
+    pub pages: usize,
+}
+
+impl fmt::Display for BrokenLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source.display())?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ": {} ({})", self.href, self.reason)?;
+        if self.pages > 1 {
+            write!(f, " on {} pages", self.pages)?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of [`check_links`].
+#[derive(Debug, Default)]
+pub struct LinkReport {
+    /// HTML pages scanned.
+    pub pages: usize,
+    /// Internal links checked, counted once per page.
+    pub internal: usize,
+    /// Distinct external URLs checked.
+    pub external: usize,
+    /// Broken links, by source location.
+    pub broken: Vec<BrokenLink>,
+}
+
+impl LinkReport {
+    /// Check whether every link resolved.
+    pub fn is_ok(&self) -> bool {
+        self.broken.is_empty()
+    }
+}
+
+/// Build a collection into a temporary directory and check its links.
+pub fn check_links(collection: &Collection, config: &Config, options: &LinkOptions) -> Result<LinkReport> {
+    let dir = tempdir()?;
+    build(collection, config, dir.path(), options.features)?;
+
+    let site = Site::scan(dir.path())?;
+    let mut sources = Sources::new(collection, config);
+    let mut report = LinkReport {
+        pages: site.pages.len(),
+        ..Default::default()
+    };
+
+    let mut broken: BTreeMap<(PathBuf, Option<usize>, String), (String, usize)> = BTreeMap::new();
+    let mut external: BTreeMap<String, Vec<(&str, &str)>> = BTreeMap::new();
+
+    for page in &site.pages {
+        for href in &page.links {
+            match classify(href) {
+                Link::Internal { path, fragment } => {
+                    report.internal += 1;
+                    if let Some(reason) = site.check(&page.url, path, fragment) {
+                        let (source, line) = sources.locate(&page.url, href);
+                        broken.entry((source, line, href.clone())).or_insert((reason, 0)).1 += 1;
+                    }
+                }
+                Link::External(url) => external.entry(url).or_default().push((&page.url, href)),
+                Link::Other => {}
+            }
+        }
+    }
+
+    if let Some(external_options) = &options.external {
+        let urls: Vec<String> = external.keys().cloned().collect();
+        report.external = urls.len();
+        for (url, reason) in check_external(&urls, external_options)? {
+            for &(page_url, href) in &external[&url] {
+                let (source, line) = sources.locate(page_url, href);
+                broken
+                    .entry((source, line, href.to_string()))
+                    .or_insert((reason.clone(), 0))
+                    .1 += 1;
+            }
+        }
+    }
+
+    report.broken = broken
+        .into_iter()
+        .map(|((source, line, href), (reason, pages))| BrokenLink {
+            source,
+            line,
+            href,
+            reason,
+            pages,
+        })
+        .collect();
+    Ok(report)
+}
+
+/// A generated HTML page.
+struct Page {
+    /// URL of the page, like `/posts/hello/`.
+    url: String,
+    /// Distinct `href` values on the page, unescaped.
+    links: Vec<String>,
+}
+
+/// The files of a built site.
+struct Site {
+    /// Site paths of all files, like `/posts/hello/index.html`.
+    files: HashSet<String>,
+    /// Element IDs in each HTML file, by site path.
+    ids: HashMap<String, HashSet<String>>,
+    pages: Vec<Page>,
+}
+
+impl Site {
+    fn scan(dir: &Path) -> Result<Self> {
+        let mut site = Site {
+            files: HashSet::new(),
+            ids: HashMap::new(),
+            pages: Vec::new(),
+        };
+
+        for entry in WalkDir::new(dir).sort_by_file_name() {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+            let path: String = relative
+                .components()
+                .map(|c| format!("/{}", c.as_os_str().to_string_lossy()))
+                .collect();
+
+            if relative.extension().is_some_and(|e| e == "html") {
+                let html = std::fs::read_to_string(entry.path())?;
+                site.ids.insert(path.clone(), attribute_values(&ID_RE, &html).collect());
+
+                let mut links: Vec<String> = attribute_values(&HREF_RE, &html).collect();
+                links.sort();
+                links.dedup();
+                let url = path.strip_suffix("index.html").unwrap_or(&path).to_string();
+                site.pages.push(Page { url, links });
+            }
+            site.files.insert(path);
+        }
+
+        Ok(site)
+    }
+
+    /// Check an internal link on a page, returning why it is broken.
+    fn check(&self, page_url: &str, path: &str, fragment: &str) -> Option<String> {
+        let target = if path.is_empty() {
+            page_url.to_string()
+        } else {
+            percent_decode(&resolve_url(page_url, path))
+        };
+
+        let file = if target.ends_with('/') {
+            format!("{}index.html", target)
+        } else if self.files.contains(&target) {
+            target.clone()
+        } else {
+            format!("{}/index.html", target)
+        };
+        if !self.files.contains(&file) {
+            return Some("page not found".to_string());
+        }
+
+        // Browsers scroll to the top for `#` and `#top` without an element.
+        let fragment = percent_decode(fragment);
+        match self.ids.get(&file) {
+            Some(ids) if !fragment.is_empty() && fragment != "top" && !ids.contains(&fragment) => {
+                Some(format!("no `#{}` on {}", fragment, target))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What kind of target a link has.
+#[derive(Debug, PartialEq, Eq)]
+enum Link<'a> {
+    /// A path on this site, possibly empty, and a fragment.
+    Internal { path: &'a str, fragment: &'a str },
+    /// An HTTP(S) URL, without its fragment.
+    External(String),
+    /// Another scheme, like `mailto:`, or an empty link.
+    Other,
+}
+
+fn classify(href: &str) -> Link<'_> {
+    if href.is_empty() {
+        return Link::Other;
+    }
+
+    let without_fragment = href.split('#').next().unwrap_or(href);
+    if let Some(rest) = without_fragment.strip_prefix("//") {
+        return Link::External(format!("https://{}", rest));
+    }
+
+    if let Some((scheme, _)) = href.split_once(':')
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+    {
+        return match scheme.to_ascii_lowercase().as_str() {
+            "http" | "https" => Link::External(without_fragment.to_string()),
+            _ => Link::Other,
+        };
+    }
+
+    let (path, fragment) = href.split_once('#').unwrap_or((href, ""));
+    Link::Internal { path, fragment }
+}
+
+/// Finds the files links were written in.
+struct Sources {
+    /// Source file of each document and section page, by URL.
+    pages: HashMap<String, PathBuf>,
+    /// Template files, the collection's then its themes', searched for
+    /// links not in a page's source.
+    templates: Vec<PathBuf>,
+    /// File contents, read on first use.
+    text: HashMap<PathBuf, Option<String>>,
+}
+
+impl Sources {
+    fn new(collection: &Collection, config: &Config) -> Self {
+        let mut pages: HashMap<String, PathBuf> = collection
+            .documents
+            .iter()
+            .map(|doc| (doc.url_path(), doc.source_path.clone()))
+            .collect();
+        for section in &collection.sections {
+            if let Some(path) = &section.source_path {
+                pages.insert(section.url_path(), path.clone());
+            }
+        }
+
+        let templates = crate::theme::template_dirs(&collection.root, &config.themes)
+            .into_iter()
+            .flat_map(|dir| WalkDir::new(dir).sort_by_file_name())
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| entry.into_path())
+            .collect();
+
+        Self {
+            pages,
+            templates,
+            text: HashMap::new(),
+        }
+    }
+
+    /// Find the file and line a link on a page was written at.
+    fn locate(&mut self, page_url: &str, href: &str) -> (PathBuf, Option<usize>) {
+        let source = self.pages.get(page_url).cloned();
+        let candidates: Vec<PathBuf> = source.iter().chain(&self.templates).cloned().collect();
+        for path in candidates {
+            if let Some(line) = self.find_line(&path, href) {
+                return (path, Some(line));
+            }
+        }
+        (source.unwrap_or_else(|| PathBuf::from(page_url)), None)
+    }
+
+    fn find_line(&mut self, path: &Path, needle: &str) -> Option<usize> {
+        let text = self
+            .text
+            .entry(path.to_path_buf())
+            .or_insert_with(|| std::fs::read_to_string(path).ok());
+        text.as_deref()?
+            .lines()
+            .position(|line| line.contains(needle))
+            .map(|i| i + 1)
+    }
+}
+
+/// Unescaped values of an attribute matched by `re`.
+fn attribute_values<'a>(re: &'a Regex, html: &'a str) -> impl Iterator<Item = String> + 'a {
+    re.captures_iter(html).filter_map(|caps| {
+        caps.get(1)
+            .or_else(|| caps.get(2))
+            .map(|m| unescape_attribute(m.as_str().trim()))
+    })
+}
+
+/// Decode the character references in an attribute value, like `&#x2F;`.
+fn unescape_attribute(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').and_then(|semi| {
+            let c = match &rest[1..semi] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                name => name
+                    .strip_prefix("#x")
+                    .or_else(|| name.strip_prefix("#X"))
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| name.strip_prefix('#').map(|dec| dec.parse()))
+                    .and_then(|n| n.ok())
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, semi))
+        });
+
+        match decoded {
+            Some((c, semi)) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Decode `%XX` escapes in a URL path or fragment.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| s.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("/docs/#intro"), Link::Internal { path: "/docs/", fragment: "intro" });
+        assert_eq!(classify("../other/"), Link::Internal { path: "../other/", fragment: "" });
+        assert_eq!(classify("#top"), Link::Internal { path: "", fragment: "top" });
+        assert_eq!(classify("https://example.com/a#b"), Link::External("https://example.com/a".to_string()));
+        assert_eq!(classify("//cdn.example.com/x.js"), Link::External("https://cdn.example.com/x.js".to_string()));
+        assert_eq!(classify("mailto:me@example.com"), Link::Other);
+        assert_eq!(classify(""), Link::Other);
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape_attribute("&#x2F;posts&#x2F;?a=1&amp;b=2"), "/posts/?a=1&b=2");
+        assert_eq!(unescape_attribute("&#47;x &unknown; & y"), "/x &unknown; & y");
+        assert_eq!(percent_decode("/caf%C3%A9/%zz"), "/café/%zz");
+    }
+
+    #[test]
+    fn test_site_check() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("guide")).unwrap();
+        std::fs::write(
+            root.join("guide/index.html"),
+            r##"<h2><a href="#setup" id="setup"></a>Setup</h2><a href='../notes.txt'>notes</a><a href="/missing/">x</a>"##,
+        )
+        .unwrap();
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+
+        let site = Site::scan(root).unwrap();
+        assert_eq!(site.pages.len(), 1);
+        assert_eq!(site.pages[0].url, "/guide/");
+        assert_eq!(site.pages[0].links, vec!["#setup", "../notes.txt", "/missing/"]);
+
+        assert_eq!(site.check("/guide/", "", "setup"), None);
+        assert_eq!(site.check("/other/", "/guide/", "setup"), None);
+        assert_eq!(site.check("/other/", "/guide", ""), None);
+        assert_eq!(site.check("/guide/", "../notes.txt", "line-2"), None);
+        assert_eq!(site.check("/guide/", "", "top"), None);
+        assert_eq!(site.check("/guide/", "/missing/", ""), Some("page not found".to_string()));
+        assert_eq!(
+            site.check("/other/", "/guide/", "install"),
+            Some("no `#install` on /guide/".to_string())
+        );
+    }
+}
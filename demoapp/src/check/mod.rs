@@ -0,0 +1,13 @@
+//! Site checks run by `anthology check`.
+//!
+//! Link checking builds the site into a temporary directory and verifies
+//! every `href` in the generated pages: internal links must name a
+//! generated file, and `#fragment`s an `id` on the target page. External
+//! URLs are checked over HTTP when asked for. Broken links are reported
+//! against the document or template they were written in.
+
+mod external;
+mod links;
+
+pub use external::{check_external, ExternalOptions, LinkCache, LINK_CACHE_FILE};
+pub use links::{check_links, BrokenLink, LinkOptions, LinkReport};
@@ -44,9 +44,22 @@ enum Command {
         #[arg(long)]
         drafts: bool,
 
-        /// Compress output files (create .gz versions).
+        /// Include documents whose publish_date is in the future.
         #[arg(long)]
-        compress: bool,
+        build_future: bool,
+
+        /// Precompress output files with these algorithms, comma-separated (e.g., "gzip,br,zstd").
+        ///
+        /// Overrides `[compress] algorithms` in anthology.toml. To precompress
+        /// with the configured algorithms, enable the `compress` feature instead.
+        #[arg(long, value_name = "ALGORITHMS")]
+        compress: Option<String>,
+
+        /// Build features to enable, comma-separated (e.g., "search-index,rss,sitemap").
+        ///
+        /// Overrides `[build] features` in anthology.toml.
+        #[arg(short, long)]
+        features: Option<String>,
 
         /// Use incremental build (skip unchanged documents).
         #[arg(short, long)]
@@ -70,6 +83,10 @@ enum Command {
         /// Include draft documents.
         #[arg(long)]
         drafts: bool,
+
+        /// Include documents whose publish_date is in the future.
+        #[arg(long)]
+        build_future: bool,
     },
 
     /// Validate documents in the collection.
@@ -77,6 +94,22 @@ enum Command {
         /// Collection directory (defaults to current directory).
         #[arg(default_value = ".")]
         path: PathBuf,
+
+        /// Build the site and check internal links and anchors.
+        #[arg(long)]
+        links: bool,
+
+        /// Also check external URLs (with --links).
+        #[arg(long, requires = "links")]
+        external: bool,
+
+        /// External requests in flight at once.
+        #[arg(long, default_value = "8", requires = "external")]
+        concurrency: usize,
+
+        /// External requests per second to each host.
+        #[arg(long, default_value = "2", requires = "external")]
+        rate_limit: f64,
     },
 
     /// Create a new document.
@@ -111,6 +144,37 @@ enum Command {
         output: Option<PathBuf>,
     },
 
+    /// Import a site made with another generator, or a WordPress export.
+    Import {
+        /// Site directory, or the export file with `--from wxr`.
+        source: PathBuf,
+
+        /// Generator the site was made with.
+        #[arg(long)]
+        from: ImportFormat,
+
+        /// Directory for the new collection (defaults to current directory).
+        #[arg(short, long, default_value = ".")]
+        output: PathBuf,
+    },
+
+    /// Rewrite YAML, JSON and `+++` frontmatter in the collection as `---` TOML.
+    ConvertFrontmatter {
+        /// Collection directory (defaults to current directory).
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Frontmatter format to write.
+        #[arg(long, default_value = "toml")]
+        to: FrontmatterSyntax,
+    },
+
+    /// Manage the collection's themes.
+    Theme {
+        #[command(subcommand)]
+        command: ThemeCommand,
+    },
+
     /// Start an interactive REPL for querying the collection.
     Repl {
         /// Collection directory (defaults to current directory).
@@ -144,6 +208,19 @@ enum Command {
     },
 }
 
+#[derive(Subcommand, Debug)]
+enum ThemeCommand {
+    /// Install a theme into themes/ from a directory or git URL.
+    Add {
+        /// Theme directory or git URL.
+        source: String,
+
+        /// Collection directory (defaults to current directory).
+        #[arg(short, long, default_value = ".")]
+        path: PathBuf,
+    },
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
 enum ExportFormat {
     Json,
@@ -152,6 +229,21 @@ enum ExportFormat {
     JsonFeed,
     Sitemap,
     Epub,
+    Pdf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ImportFormat {
+    Jekyll,
+    Hugo,
+    Zola,
+    Mdbook,
+    Wxr,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FrontmatterSyntax {
+    Toml,
 }
 
 impl Cli {
@@ -164,12 +256,23 @@ impl Cli {
 
         match self.command {
             Command::Init { path } => cmd_init(path),
-            Command::Build { path, output, drafts, compress, incremental, progress } => cmd_build(path, output, drafts, compress, incremental, progress),
-            Command::Serve { path, port, drafts } => cmd_serve(path, port, drafts),
-            Command::Check { path } => cmd_check(path),
+            Command::Build { path, output, drafts, build_future, compress, features, incremental, progress } => cmd_build(path, output, drafts, build_future, compress, features, incremental, progress),
+            Command::Serve { path, port, drafts, build_future } => cmd_serve(path, port, drafts, build_future),
+            Command::Check { path, links, external, concurrency, rate_limit } => {
+                let external = external.then(|| crate::check::ExternalOptions {
+                    concurrency,
+                    rate_limit,
+                    cache_path: Some(path.join(crate::check::LINK_CACHE_FILE)),
+                    ..Default::default()
+                });
+                cmd_check(path, links, external)
+            }
             Command::New { title, path } => cmd_new(title, path),
             Command::Index { path } => cmd_index(path),
             Command::Export { path, format, output } => cmd_export(path, format, output),
+            Command::Import { source, from, output } => cmd_import(source, from, output),
+            Command::ConvertFrontmatter { path, to } => cmd_convert_frontmatter(path, to),
+            Command::Theme { command: ThemeCommand::Add { source, path } } => cmd_theme_add(source, path),
             Command::Repl { path } => cmd_repl(path),
             Command::Files { path, pattern } => cmd_files(path, pattern),
             Command::Fetch { url, output, path } => cmd_fetch(url, output, path),
@@ -229,41 +332,58 @@ This is your first document. Edit or delete it and start writing!
     Ok(())
 }
 
-fn cmd_build(path: PathBuf, output: Option<PathBuf>, drafts: bool, compress: bool, incremental: bool, progress: bool) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+fn cmd_build(path: PathBuf, output: Option<PathBuf>, drafts: bool, build_future: bool, compress: Option<String>, features: Option<String>, incremental: bool, progress: bool) -> Result<()> {
+    use crate::features::BuildFeatures;
+
     if !progress {
         info!("Building collection at {}", path.display());
     }
 
-    let config = Config::load(&path)?;
+    let mut config = Config::load(&path)?;
     let output_dir = output.unwrap_or_else(|| path.join(&config.build.output_dir));
 
+    // Command-line algorithms replace configured ones.
+    if let Some(list) = &compress {
+        let algorithms = crate::build::CompressionAlgorithm::parse_list(list).map_err(Error::config)?;
+        if algorithms.is_empty() {
+            return Err(Error::config("--compress needs at least one algorithm (gzip, br, or zstd)"));
+        }
+        config.compress.algorithms = algorithms.iter().map(|a| a.name().to_string()).collect();
+    }
+
+    // Command-line features replace configured ones; flags add to either.
+    let mut features = match features {
+        Some(list) => BuildFeatures::try_from_str_list(&list).map_err(Error::config)?,
+        None => config.build.features()?,
+    };
+    if drafts {
+        features |= BuildFeatures::DRAFTS;
+    }
+    if build_future {
+        features |= BuildFeatures::FUTURE;
+    }
+    if compress.is_some() {
+        features |= BuildFeatures::COMPRESS;
+    }
+
     let collection = crate::collection::Collection::load(&path, &config)?;
     if !progress {
         info!("Found {} documents", collection.documents.len());
+        info!("Features: {}", features.to_names().join(", "));
     }
 
     if incremental {
         if progress {
-            crate::build::build_incremental_with_progress(&collection, &config, &output_dir, drafts)?;
+            crate::build::build_incremental_with_progress(&collection, &config, &output_dir, features)?;
         } else {
-            crate::build::build_incremental(&collection, &config, &output_dir, drafts)?;
+            crate::build::build_incremental(&collection, &config, &output_dir, features)?;
         }
     } else {
         if progress {
-            crate::build::build_with_progress(&collection, &config, &output_dir, drafts)?;
+            crate::build::build_with_progress(&collection, &config, &output_dir, features)?;
         } else {
-            crate::build::build(&collection, &config, &output_dir, drafts)?;
-        }
-    }
-
-    if compress {
-        if progress {
-            let compress_pb = crate::build::compress_spinner();
-            crate::build::compress_output(&output_dir)?;
-            crate::build::finish_with_check(&compress_pb, "Output compressed");
-        } else {
-            info!("Compressing output files...");
-            crate::build::compress_output(&output_dir)?;
+            crate::build::build(&collection, &config, &output_dir, features)?;
         }
     }
 
@@ -275,16 +395,16 @@ fn cmd_build(path: PathBuf, output: Option<PathBuf>, drafts: bool, compress: boo
     Ok(())
 }
 
-fn cmd_serve(path: PathBuf, port: u16, drafts: bool) -> Result<()> {
+fn cmd_serve(path: PathBuf, port: u16, drafts: bool, build_future: bool) -> Result<()> {
     info!("Starting server for collection at {}", path.display());
 
     let config = Config::load(&path)?;
     let collection = crate::collection::Collection::load(&path, &config)?;
 
-    crate::serve::serve(collection, config, port, drafts)
+    crate::serve::serve(collection, config, port, drafts, build_future)
 }
 
-fn cmd_check(path: PathBuf) -> Result<()> {
+fn cmd_check(path: PathBuf, links: bool, external: Option<crate::check::ExternalOptions>) -> Result<()> {
     use rustmax::termcolor::{ColorChoice, StandardStream, WriteColor, ColorSpec, Color};
     use std::io::Write;
 
@@ -306,6 +426,46 @@ fn cmd_check(path: PathBuf) -> Result<()> {
         }
     }
 
+    // Expired documents are left out of builds but still sit in content/.
+    let today = crate::time::today();
+    for doc in &collection.documents {
+        if doc.is_expired(today)
+            && let Some(expiry) = doc.frontmatter.expiry_date
+        {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+            write!(stdout, "Warning")?;
+            stdout.reset()?;
+            writeln!(stdout, " {}: expired on {}", doc.source_path.display(), expiry)?;
+        }
+    }
+
+    // Compile templates and expand shortcodes as a build would.
+    let features = config.build.features()?;
+    let mut render_errors = Vec::new();
+    match crate::build::TemplateEngine::for_collection(&path, &config) {
+        Ok(engine) => {
+            engine.set_data(crate::build::load_data(&path.join(crate::build::DATA_DIR))?);
+            for doc in &collection.documents {
+                if let Err(e) = crate::build::render_document(doc, &config, &engine, features) {
+                    render_errors.push(e);
+                }
+            }
+            for section in &collection.sections {
+                if let Err(e) = crate::build::render_section(section, &config, &engine, features) {
+                    render_errors.push(e);
+                }
+            }
+        }
+        Err(e) => render_errors.push(e),
+    }
+    for e in &render_errors {
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)))?;
+        write!(stdout, "Error")?;
+        stdout.reset()?;
+        writeln!(stdout, " {}", e)?;
+        errors += 1;
+    }
+
     if errors == 0 {
         stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
         write!(stdout, "OK")?;
@@ -315,6 +475,34 @@ fn cmd_check(path: PathBuf) -> Result<()> {
         return Err(Error::build(format!("{} validation errors", errors)));
     }
 
+    if links {
+        let options = crate::check::LinkOptions {
+            features: config.build.features()?,
+            external,
+        };
+        let report = crate::check::check_links(&collection, &config, &options)?;
+
+        for link in &report.broken {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)))?;
+            write!(stdout, "Broken")?;
+            stdout.reset()?;
+            writeln!(stdout, " {}", link)?;
+        }
+
+        if !report.is_ok() {
+            return Err(Error::build(format!("{} broken links", report.broken.len())));
+        }
+
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        write!(stdout, "OK")?;
+        stdout.reset()?;
+        write!(stdout, " {} internal links on {} pages", report.internal, report.pages)?;
+        if options.external.is_some() {
+            write!(stdout, ", {} external URLs", report.external)?;
+        }
+        writeln!(stdout, " checked")?;
+    }
+
     Ok(())
 }
 
@@ -390,7 +578,7 @@ fn cmd_export(path: PathBuf, format: ExportFormat, output: Option<PathBuf>) -> R
     let config = Config::load(&path)?;
     let collection = crate::collection::Collection::load(&path, &config)?;
 
-    // Handle EPUB separately since it's binary.
+    // Handle EPUB and PDF separately since they're binary.
     if format == ExportFormat::Epub {
         let epub_path = output.unwrap_or_else(|| path.join("output.epub"));
         let epub_config = crate::export::EpubConfig::default();
@@ -399,6 +587,14 @@ fn cmd_export(path: PathBuf, format: ExportFormat, output: Option<PathBuf>) -> R
         return Ok(());
     }
 
+    if format == ExportFormat::Pdf {
+        let pdf_path = output.unwrap_or_else(|| path.join("output.pdf"));
+        let pdf_config = crate::export::PdfConfig::default();
+        crate::export::generate_pdf(&collection, &config, &pdf_path, &pdf_config)?;
+        info!("Exported PDF to {}", pdf_path.display());
+        return Ok(());
+    }
+
     let content = match format {
         ExportFormat::Json => {
             rustmax::serde_json::to_string_pretty(&collection.to_export())?
@@ -415,7 +611,7 @@ fn cmd_export(path: PathBuf, format: ExportFormat, output: Option<PathBuf>) -> R
         ExportFormat::Sitemap => {
             crate::build::generate_sitemap(&collection, &config)?
         }
-        ExportFormat::Epub => unreachable!(),
+        ExportFormat::Epub | ExportFormat::Pdf => unreachable!(),
     };
 
     match output {
@@ -431,6 +627,100 @@ fn cmd_export(path: PathBuf, format: ExportFormat, output: Option<PathBuf>) -> R
     Ok(())
 }
 
+fn cmd_import(source: PathBuf, from: ImportFormat, output: PathBuf) -> Result<()> {
+    use crate::import::Source;
+    use rustmax::termcolor::{ColorChoice, StandardStream, WriteColor, ColorSpec, Color};
+    use std::io::Write;
+
+    let from = match from {
+        ImportFormat::Jekyll => Source::Jekyll,
+        ImportFormat::Hugo => Source::Hugo,
+        ImportFormat::Zola => Source::Zola,
+        ImportFormat::Mdbook => Source::MdBook,
+        ImportFormat::Wxr => Source::Wxr,
+    };
+    info!("Importing {} site from {}", from.name(), source.display());
+
+    let report = crate::import::import_site(from, &source, &output)?;
+
+    let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+    for note in &report.notes {
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+        write!(stdout, "Warning")?;
+        stdout.reset()?;
+        writeln!(stdout, " {}", note)?;
+    }
+
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+    write!(stdout, "Imported")?;
+    stdout.reset()?;
+    writeln!(
+        stdout,
+        " {} documents, {} sections and {} assets into {}",
+        report.documents,
+        report.sections,
+        report.assets,
+        output.display()
+    )?;
+
+    Ok(())
+}
+
+fn cmd_convert_frontmatter(path: PathBuf, to: FrontmatterSyntax) -> Result<()> {
+    use rustmax::termcolor::{ColorChoice, StandardStream, WriteColor, ColorSpec, Color};
+    use std::io::Write;
+
+    let FrontmatterSyntax::Toml = to;
+    let _config = Config::load(&path)?;
+    let converted = crate::collection::convert_frontmatter(&path)?;
+
+    let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+    for (file, null_keys) in &converted {
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+        write!(stdout, "Converted")?;
+        stdout.reset()?;
+        writeln!(stdout, " {}", file.display())?;
+        if !null_keys.is_empty() {
+            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+            write!(stdout, "Warning")?;
+            stdout.reset()?;
+            writeln!(stdout, " {}: dropped null keys: {}", file.display(), null_keys.join(", "))?;
+        }
+    }
+    writeln!(stdout, "{} files converted to TOML frontmatter", converted.len())?;
+
+    Ok(())
+}
+
+fn cmd_theme_add(source: String, path: PathBuf) -> Result<()> {
+    use rustmax::termcolor::{ColorChoice, StandardStream, WriteColor, ColorSpec, Color};
+    use std::io::Write;
+
+    if !path.join("anthology.toml").exists() {
+        return Err(Error::CollectionNotFound { path });
+    }
+    info!("Adding theme from {}", source);
+
+    let theme = crate::theme::add(&path, &source)?;
+
+    let mut stdout = StandardStream::stdout(ColorChoice::Auto);
+    stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
+    write!(stdout, "Added")?;
+    stdout.reset()?;
+    writeln!(stdout, " theme {} in {}", theme.name, theme.root.display())?;
+
+    // The themes it extends have to be added separately.
+    if let Err(e) = crate::theme::resolve(&path, &theme.name) {
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)))?;
+        write!(stdout, "Warning")?;
+        stdout.reset()?;
+        writeln!(stdout, " {}", e)?;
+    }
+    writeln!(stdout, "Set `theme = \"{}\"` under [build] in anthology.toml to use it", theme.name)?;
+
+    Ok(())
+}
+
 fn cmd_repl(path: PathBuf) -> Result<()> {
     let config = Config::load(&path)?;
     let collection = crate::collection::Collection::load(&path, &config)?;
@@ -516,3 +806,33 @@ fn cmd_fetch(url: String, output: Option<PathBuf>, path: PathBuf) -> Result<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build_args(args: &[&str]) -> (PathBuf, Option<String>) {
+        let cli = Cli::try_parse_from(args).unwrap();
+        match cli.command {
+            Command::Build { path, compress, .. } => (path, compress),
+            other => panic!("expected build, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_compress_flag() {
+        let (path, compress) = build_args(&["anthology", "build", "--compress", "gzip,br,zstd", "site"]);
+        assert_eq!(path, PathBuf::from("site"));
+        assert_eq!(compress.as_deref(), Some("gzip,br,zstd"));
+
+        let (path, compress) = build_args(&["anthology", "build", "--compress=br", "site"]);
+        assert_eq!(path, PathBuf::from("site"));
+        assert_eq!(compress.as_deref(), Some("br"));
+
+        let (_, compress) = build_args(&["anthology", "build"]);
+        assert_eq!(compress, None);
+
+        // The algorithms are required; the `compress` feature uses the configured ones.
+        assert!(Cli::try_parse_from(["anthology", "build", "--compress"]).is_err());
+    }
+}
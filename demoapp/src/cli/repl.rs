@@ -6,7 +6,7 @@ use rustmax::rustyline::{DefaultEditor, Config, EditMode};
 use rustmax::termcolor::{ColorChoice, StandardStream, WriteColor, ColorSpec, Color};
 use std::io::Write;
 
-use crate::collection::{Collection, Config as CollectionConfig};
+use crate::collection::{taxonomy_title, Collection, Config as CollectionConfig, TAGS};
 use crate::search::SearchIndex;
 use crate::Result;
 
@@ -42,7 +42,7 @@ pub fn run_repl(collection: &Collection, config: &CollectionConfig) -> Result<()
                 let arg = parts.get(1).copied().unwrap_or("");
 
                 match cmd {
-                    "help" | "?" => print_help(),
+                    "help" | "?" => print_help(config),
                     "quit" | "exit" | "q" => {
                         println!("Goodbye!");
                         break;
@@ -51,16 +51,22 @@ pub fn run_repl(collection: &Collection, config: &CollectionConfig) -> Result<()
                     "drafts" => cmd_drafts(collection, &mut stdout)?,
                     "show" => cmd_show(collection, arg, &mut stdout)?,
                     "search" | "find" => cmd_search(&index, arg, &mut stdout)?,
-                    "tags" => cmd_tags(collection, &mut stdout)?,
-                    "by-tag" | "tag" => cmd_by_tag(collection, arg, &mut stdout)?,
+                    "tags" => cmd_terms(collection, TAGS, &mut stdout)?,
+                    "by-tag" | "tag" => cmd_by_term(collection, TAGS, cmd, arg, &mut stdout)?,
                     "stats" => cmd_stats(collection, &mut stdout)?,
                     "recent" => cmd_recent(collection, &mut stdout)?,
                     "files" => cmd_files(collection, arg, &mut stdout)?,
                     _ => {
-                        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)))?;
-                        write!(stdout, "Unknown command")?;
-                        stdout.reset()?;
-                        writeln!(stdout, ": {}. Type 'help' for available commands.", cmd)?;
+                        if let Some(taxonomy) = cmd.strip_prefix("by-").and_then(|t| find_taxonomy(config, t)) {
+                            cmd_by_term(collection, taxonomy, cmd, arg, &mut stdout)?;
+                        } else if let Some(taxonomy) = find_taxonomy(config, cmd) {
+                            cmd_terms(collection, taxonomy, &mut stdout)?;
+                        } else {
+                            stdout.set_color(ColorSpec::new().set_fg(Some(Color::Red)))?;
+                            write!(stdout, "Unknown command")?;
+                            stdout.reset()?;
+                            writeln!(stdout, ": {}. Type 'help' for available commands.", cmd)?;
+                        }
                     }
                 }
             }
@@ -81,14 +87,29 @@ pub fn run_repl(collection: &Collection, config: &CollectionConfig) -> Result<()
     Ok(())
 }
 
-fn print_help() {
+/// Find a configured taxonomy by its name or the name without a trailing `s`.
+fn find_taxonomy<'a>(config: &'a CollectionConfig, name: &str) -> Option<&'a str> {
+    config
+        .content
+        .taxonomies
+        .iter()
+        .find(|t| *t == name || t.strip_suffix('s') == Some(name))
+        .map(String::as_str)
+}
+
+fn print_help(config: &CollectionConfig) {
     println!("Available commands:");
     println!("  list, ls           List all published documents");
     println!("  drafts             List draft documents");
     println!("  show <slug>        Show document details");
     println!("  search <query>     Search documents");
+    println!("                     (\"a phrase\", a AND b, a OR b, -word, tag:rust, title:word)");
     println!("  tags               List all tags");
     println!("  by-tag <tag>       List documents with tag");
+    for taxonomy in config.content.taxonomies.iter().filter(|t| *t != TAGS) {
+        println!("  {:19}List all {}", taxonomy, taxonomy);
+        println!("  {:19}List documents with term", format!("by-{} <term>", taxonomy));
+    }
     println!("  files [pattern]    List files matching glob pattern");
     println!("  stats              Show collection statistics");
     println!("  recent             Show 5 most recent documents");
@@ -217,49 +238,80 @@ fn cmd_search(index: &SearchIndex, query: &str, stdout: &mut StandardStream) ->
         stdout.reset()?;
 
         writeln!(stdout, " {}", result.title)?;
+        write_snippet(stdout, &result.snippet)?;
     }
 
     println!("\n{} results", results.len());
     Ok(())
 }
 
-fn cmd_tags(collection: &Collection, stdout: &mut StandardStream) -> Result<()> {
-    let tags = collection.tags();
+/// Print an HTML search snippet indented, with `<mark>` spans highlighted.
+fn write_snippet(stdout: &mut StandardStream, snippet: &str) -> Result<()> {
+    write!(stdout, "    ")?;
+    for (i, part) in snippet.split("<mark>").enumerate() {
+        let (marked, rest) = match part.split_once("</mark>") {
+            Some((marked, rest)) if i > 0 => (marked, rest),
+            _ => ("", part),
+        };
+        stdout.set_color(ColorSpec::new().set_fg(Some(Color::Yellow)).set_bold(true))?;
+        write!(stdout, "{}", unescape_html(marked))?;
+        stdout.reset()?;
+        write!(stdout, "{}", unescape_html(rest))?;
+    }
+    writeln!(stdout)?;
+    Ok(())
+}
+
+fn unescape_html(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+fn cmd_terms(collection: &Collection, taxonomy: &str, stdout: &mut StandardStream) -> Result<()> {
+    let terms = collection.terms(taxonomy);
 
-    if tags.is_empty() {
-        println!("No tags found.");
+    if terms.is_empty() {
+        println!("No {} found.", taxonomy);
         return Ok(());
     }
 
-    for tag in &tags {
-        let count = collection.by_tag(tag).len();
+    for term in &terms {
+        let count = collection.by_term(taxonomy, term).len();
 
         stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)))?;
-        write!(stdout, "{}", tag)?;
+        write!(stdout, "{}", term)?;
         stdout.reset()?;
 
         writeln!(stdout, " ({})", count)?;
     }
 
-    println!("\n{} tags", tags.len());
+    println!("\n{} {}", terms.len(), taxonomy);
     Ok(())
 }
 
-fn cmd_by_tag(collection: &Collection, tag: &str, stdout: &mut StandardStream) -> Result<()> {
-    if tag.is_empty() {
-        println!("Usage: by-tag <tag>");
+fn cmd_by_term(
+    collection: &Collection,
+    taxonomy: &str,
+    cmd: &str,
+    term: &str,
+    stdout: &mut StandardStream,
+) -> Result<()> {
+    if term.is_empty() {
+        println!("Usage: {} <term>", cmd);
         return Ok(());
     }
 
-    let docs = collection.by_tag(tag);
+    let docs = collection.by_term(taxonomy, term);
 
     if docs.is_empty() {
-        println!("No documents with tag '{}'", tag);
+        println!("No documents in {} '{}'", taxonomy, term);
         return Ok(());
     }
 
     stdout.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
-    writeln!(stdout, "Tag: {}", tag)?;
+    writeln!(stdout, "{}: {}", taxonomy_title(taxonomy), term)?;
     stdout.reset()?;
     println!();
 
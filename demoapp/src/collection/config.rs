@@ -2,8 +2,11 @@
 
 use rustmax::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::Path;
 
+use crate::features::BuildFeatures;
+use crate::theme::Theme;
 use crate::{Error, Result};
 
 /// Main configuration for a collection.
@@ -19,6 +22,19 @@ pub struct Config {
     pub server: ServerConfig,
     #[serde(default)]
     pub highlight: HighlightConfig,
+    #[serde(default)]
+    pub images: ImagesConfig,
+    #[serde(default)]
+    pub compress: CompressConfig,
+    #[serde(default)]
+    pub search: SearchConfig,
+    /// Languages by code, e.g. `[languages.de]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub languages: BTreeMap<String, LanguageConfig>,
+    /// Themes in use, `[build] theme` first and then the themes it extends.
+    /// Resolved by [`Config::load`].
+    #[serde(skip)]
+    pub themes: Vec<Theme>,
 }
 
 impl Default for Config {
@@ -29,6 +45,11 @@ impl Default for Config {
             content: ContentConfig::default(),
             server: ServerConfig::default(),
             highlight: HighlightConfig::default(),
+            images: ImagesConfig::default(),
+            compress: CompressConfig::default(),
+            search: SearchConfig::default(),
+            languages: BTreeMap::new(),
+            themes: Vec::new(),
         }
     }
 }
@@ -45,14 +66,61 @@ impl Config {
         }
 
         let content = std::fs::read_to_string(&config_path)?;
-        let config: Config =
-            rustmax::toml::from_str(&content).map_err(|e| Error::ConfigParse {
-                path: config_path,
-                source: e,
-            })?;
+        let parse_error = |e| Error::ConfigParse {
+            path: config_path.clone(),
+            source: e,
+        };
+        let table: rustmax::toml::Table = rustmax::toml::from_str(&content).map_err(parse_error)?;
+        let theme = table
+            .get("build")
+            .and_then(|build| build.get("theme"))
+            .and_then(|theme| theme.as_str())
+            .map_or_else(default_theme, str::to_string);
+        let themes = crate::theme::resolve(root, &theme)?;
+
+        // Without theme defaults, parse the file itself so errors keep their position.
+        let mut config: Config = if themes.iter().all(|t| t.manifest.config.is_empty()) {
+            rustmax::toml::from_str(&content).map_err(parse_error)?
+        } else {
+            rustmax::toml::Value::Table(crate::theme::merge_config(&themes, table))
+                .try_into()
+                .map_err(parse_error)?
+        };
+        config.build.theme = theme;
+        config.themes = themes;
 
         Ok(config)
     }
+
+    /// Codes of the languages other than the default `[collection] language`.
+    pub fn translation_languages(&self) -> Vec<String> {
+        self.languages
+            .keys()
+            .filter(|code| **code != self.collection.language)
+            .cloned()
+            .collect()
+    }
+
+    /// Code of a language, `None` being the default language.
+    pub fn language_code<'a>(&'a self, language: Option<&'a str>) -> &'a str {
+        language.unwrap_or(&self.collection.language)
+    }
+
+    /// Site title in a language, falling back to `[collection] title`.
+    pub fn language_title(&self, language: Option<&str>) -> &str {
+        language
+            .and_then(|code| self.languages.get(code))
+            .and_then(|l| l.title.as_deref())
+            .unwrap_or(&self.collection.title)
+    }
+
+    /// Site description in a language, falling back to `[collection] description`.
+    pub fn language_description(&self, language: Option<&str>) -> &str {
+        language
+            .and_then(|code| self.languages.get(code))
+            .and_then(|l| l.description.as_deref())
+            .unwrap_or(&self.collection.description)
+    }
 }
 
 /// Collection metadata.
@@ -95,13 +163,27 @@ fn default_language() -> String {
     "en".to_string()
 }
 
+/// Configuration of one language of a multilingual collection.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LanguageConfig {
+    /// Display name, e.g. "Deutsch".
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Site title in this language.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Site description in this language.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
 /// Build configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuildConfig {
     /// Output directory for built site.
     #[serde(default = "default_output_dir")]
     pub output_dir: String,
-    /// Theme directory name.
+    /// Theme to use, a directory of `themes/`.
     #[serde(default = "default_theme")]
     pub theme: String,
     /// Whether to include drafts in builds.
@@ -110,6 +192,9 @@ pub struct BuildConfig {
     /// Minify HTML output.
     #[serde(default)]
     pub minify: bool,
+    /// Build features to enable (e.g., "search-index", "rss", "sitemap").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub features: Option<Vec<String>>,
 }
 
 impl Default for BuildConfig {
@@ -119,7 +204,31 @@ impl Default for BuildConfig {
             theme: default_theme(),
             drafts: false,
             minify: false,
+            features: None,
+        }
+    }
+}
+
+impl BuildConfig {
+    /// Resolve the configured build features.
+    ///
+    /// Falls back to `BuildFeatures::default()` when `features` is unset.
+    /// The `drafts` and `minify` switches add their features on top.
+    pub fn features(&self) -> Result<BuildFeatures> {
+        let mut features = match &self.features {
+            Some(names) => BuildFeatures::try_from_names(names.iter().map(String::as_str))
+                .map_err(|e| Error::config(format!("[build] features: {}", e)))?,
+            None => BuildFeatures::default(),
+        };
+
+        if self.drafts {
+            features |= BuildFeatures::DRAFTS;
+        }
+        if self.minify {
+            features |= BuildFeatures::MINIFY;
         }
+
+        Ok(features)
     }
 }
 
@@ -128,7 +237,7 @@ fn default_output_dir() -> String {
 }
 
 fn default_theme() -> String {
-    "default".to_string()
+    crate::theme::DEFAULT_THEME.to_string()
 }
 
 /// Content configuration.
@@ -143,6 +252,15 @@ pub struct ContentConfig {
     /// Excerpt separator in content.
     #[serde(default = "default_excerpt_separator")]
     pub excerpt_separator: String,
+    /// Documents per listing page (0 = no pagination).
+    #[serde(default)]
+    pub paginate: usize,
+    /// Taxonomies to build term pages for, e.g. "tags" or "categories".
+    #[serde(default = "default_taxonomies")]
+    pub taxonomies: Vec<String>,
+    /// Related documents listed on each document page (0 = none).
+    #[serde(default = "default_related")]
+    pub related: usize,
 }
 
 impl Default for ContentConfig {
@@ -151,6 +269,9 @@ impl Default for ContentConfig {
             date_format: default_date_format(),
             default_template: default_template(),
             excerpt_separator: default_excerpt_separator(),
+            paginate: 0,
+            taxonomies: default_taxonomies(),
+            related: default_related(),
         }
     }
 }
@@ -167,6 +288,14 @@ fn default_excerpt_separator() -> String {
     "<!--more-->".to_string()
 }
 
+fn default_taxonomies() -> Vec<String> {
+    vec![super::TAGS.to_string()]
+}
+
+fn default_related() -> usize {
+    5
+}
+
 /// Development server configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerConfig {
@@ -245,3 +374,191 @@ impl HighlightConfig {
         }
     }
 }
+
+/// Image optimization configuration.
+///
+/// Applies when the `images` build feature is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImagesConfig {
+    /// Maximum width of optimized images (0 = no limit).
+    #[serde(default = "default_image_max_width")]
+    pub max_width: u32,
+    /// Maximum height of optimized images (0 = no limit).
+    #[serde(default = "default_image_max_height")]
+    pub max_height: u32,
+    /// JPEG quality (1-100).
+    #[serde(default = "default_image_quality")]
+    pub quality: u8,
+    /// Generate WebP versions and `<source>` elements.
+    #[serde(default = "default_image_webp")]
+    pub webp: bool,
+    /// Widths of resized copies listed in `srcset`.
+    #[serde(default = "default_image_widths")]
+    pub widths: Vec<u32>,
+    /// Value of the `sizes` attribute on rewritten images.
+    #[serde(default = "default_image_sizes")]
+    pub sizes: String,
+    /// Generate thumbnails alongside each image.
+    #[serde(default)]
+    pub thumbnails: bool,
+}
+
+impl Default for ImagesConfig {
+    fn default() -> Self {
+        Self {
+            max_width: default_image_max_width(),
+            max_height: default_image_max_height(),
+            quality: default_image_quality(),
+            webp: default_image_webp(),
+            widths: default_image_widths(),
+            sizes: default_image_sizes(),
+            thumbnails: false,
+        }
+    }
+}
+
+fn default_image_max_width() -> u32 {
+    1920
+}
+
+fn default_image_max_height() -> u32 {
+    1080
+}
+
+fn default_image_quality() -> u8 {
+    85
+}
+
+fn default_image_webp() -> bool {
+    true
+}
+
+fn default_image_widths() -> Vec<u32> {
+    vec![480, 960, 1440]
+}
+
+fn default_image_sizes() -> String {
+    "100vw".to_string()
+}
+
+impl ImagesConfig {
+    /// Convert to ImageConfig for the image optimizer.
+    pub fn to_image_config(&self) -> crate::build::ImageConfig {
+        crate::build::ImageConfig {
+            max_width: self.max_width,
+            max_height: self.max_height,
+            jpeg_quality: self.quality,
+            generate_webp: self.webp,
+            generate_thumbnails: self.thumbnails,
+            widths: self.widths.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Precompression configuration.
+///
+/// Applies when the `compress` build feature is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressConfig {
+    /// Algorithms to produce variants for ("gzip", "br", "zstd").
+    #[serde(default = "default_compress_algorithms")]
+    pub algorithms: Vec<String>,
+    /// Files smaller than this many bytes are not compressed.
+    #[serde(default = "default_compress_min_size")]
+    pub min_size: u64,
+    /// Extensions of files to compress.
+    #[serde(default = "default_compress_extensions")]
+    pub extensions: Vec<String>,
+}
+
+impl Default for CompressConfig {
+    fn default() -> Self {
+        Self {
+            algorithms: default_compress_algorithms(),
+            min_size: default_compress_min_size(),
+            extensions: default_compress_extensions(),
+        }
+    }
+}
+
+fn default_compress_algorithms() -> Vec<String> {
+    vec!["gzip".to_string()]
+}
+
+fn default_compress_min_size() -> u64 {
+    256
+}
+
+fn default_compress_extensions() -> Vec<String> {
+    crate::build::DEFAULT_COMPRESS_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+impl CompressConfig {
+    /// Convert to CompressOptions for the compressor.
+    pub fn to_options(&self) -> Result<crate::build::CompressOptions> {
+        let algorithms = crate::build::CompressionAlgorithm::parse_list(&self.algorithms.join(","))
+            .map_err(|e| Error::config(format!("[compress] algorithms: {}", e)))?;
+
+        Ok(crate::build::CompressOptions {
+            algorithms,
+            min_size: self.min_size,
+            extensions: self.extensions.iter().map(|e| e.trim_start_matches('.').to_string()).collect(),
+        })
+    }
+}
+
+/// Static search index configuration.
+///
+/// Applies when the `search-index` build feature is enabled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchConfig {
+    /// Index format: "json" for one file, "sharded" for binary shards
+    /// that `search.js` fetches as queries need them.
+    #[serde(default = "default_search_format")]
+    pub format: String,
+    /// Leading term characters that pick a shard.
+    #[serde(default = "default_search_prefix_len")]
+    pub prefix_len: usize,
+    /// Gzip the shards.
+    #[serde(default)]
+    pub gzip: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            format: default_search_format(),
+            prefix_len: default_search_prefix_len(),
+            gzip: false,
+        }
+    }
+}
+
+fn default_search_format() -> String {
+    "json".to_string()
+}
+
+fn default_search_prefix_len() -> usize {
+    crate::search::ShardOptions::default().prefix_len
+}
+
+impl SearchConfig {
+    /// Convert to the index format to write.
+    pub fn to_format(&self) -> Result<crate::search::IndexFormat> {
+        match self.format.trim().to_lowercase().as_str() {
+            "json" => Ok(crate::search::IndexFormat::Json),
+            "sharded" if self.prefix_len == 0 => {
+                Err(Error::config("[search] prefix_len must be at least 1"))
+            }
+            "sharded" => Ok(crate::search::IndexFormat::Sharded(crate::search::ShardOptions {
+                prefix_len: self.prefix_len,
+                gzip: self.gzip,
+            })),
+            other => Err(Error::config(format!(
+                "[search] format: unknown format `{}` (expected json or sharded)",
+                other
+            ))),
+        }
+    }
+}
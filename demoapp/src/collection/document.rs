@@ -6,6 +6,7 @@ use rustmax::jiff::civil::Date;
 use rustmax::blake3;
 use std::path::{Path, PathBuf};
 
+use crate::diagnostic::Diagnostic;
 use crate::{Error, Result};
 
 /// A document in the collection.
@@ -15,16 +16,43 @@ pub struct Document {
     pub source_path: PathBuf,
     /// Parsed frontmatter.
     pub frontmatter: Frontmatter,
+    /// Syntax the frontmatter was written in, `None` without frontmatter.
+    pub frontmatter_format: Option<FrontmatterFormat>,
+    /// YAML or JSON frontmatter keys left out for being null, e.g. `extra.a`.
+    pub null_keys: Vec<String>,
     /// Raw markdown content (after frontmatter).
     pub content: String,
+    /// Line in the source file where `content` begins (1-based).
+    pub body_line: usize,
     /// Content hash for caching.
     pub content_hash: String,
+    /// Directory of the page bundle, if this is a bundle's `index.md`.
+    pub bundle_dir: Option<PathBuf>,
+    /// Assets colocated with the document in its bundle.
+    pub assets: Vec<BundleAsset>,
+    /// Path of the containing section, `/`-separated ("" for the root).
+    pub section: String,
+    /// Language code of a translation, `None` for the default language.
+    pub language: Option<String>,
+}
+
+/// A file colocated with a document in a page bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleAsset {
+    /// Path relative to the bundle directory.
+    pub path: PathBuf,
+    /// Hash of the file contents.
+    pub hash: String,
 }
 
 impl Document {
     /// Parse a document from file contents.
     pub fn parse(source_path: PathBuf, raw: &str) -> Result<Self> {
-        let (frontmatter, content) = parse_frontmatter(&source_path, raw)?;
+        let (frontmatter, frontmatter_format, content, null_keys) = parse_frontmatter(&source_path, raw)?;
+
+        // Content is always a suffix of the raw file.
+        let body_offset = raw.len() - content.len();
+        let body_line = raw[..body_offset].matches('\n').count() + 1;
 
         // Compute content hash.
         let hash = blake3::hash(raw.as_bytes());
@@ -33,8 +61,15 @@ impl Document {
         Ok(Self {
             source_path,
             frontmatter,
+            frontmatter_format,
+            null_keys,
             content,
+            body_line,
             content_hash,
+            bundle_dir: None,
+            assets: Vec::new(),
+            section: String::new(),
+            language: None,
         })
     }
 
@@ -47,17 +82,113 @@ impl Document {
     /// Get the URL slug for this document.
     pub fn slug(&self) -> String {
         self.frontmatter.slug.clone().unwrap_or_else(|| {
-            self.source_path
-                .file_stem()
-                .and_then(|s| s.to_str())
-                .unwrap_or("untitled")
+            // Bundles are named after their directory, not `index.md`.
+            let name = match &self.bundle_dir {
+                Some(dir) => dir.file_name(),
+                None => self.source_path.file_stem(),
+            };
+            let name = name.and_then(|s| s.to_str()).unwrap_or("untitled");
+
+            // Translations share the slug of the original: `post.de.md` is `post`.
+            let suffix = self.language.as_deref().map(|code| format!(".{}", code));
+            suffix
+                .and_then(|suffix| name.strip_suffix(&suffix))
+                .unwrap_or(name)
                 .to_string()
         })
     }
 
+    /// Terms of a taxonomy this document is in.
+    ///
+    /// Tags come from `tags`; other taxonomies from an `extra` field of the
+    /// same name holding a string or an array of strings.
+    pub fn terms(&self, taxonomy: &str) -> Vec<&str> {
+        use rustmax::toml::Value;
+
+        if taxonomy == super::TAGS {
+            return self.frontmatter.tags.iter().map(String::as_str).collect();
+        }
+
+        match self.frontmatter.extra.get(taxonomy) {
+            Some(Value::String(term)) => vec![term.as_str()],
+            Some(Value::Array(terms)) => terms.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether this document has a term of a taxonomy with the given [`term_slug`](super::term_slug).
+    pub fn has_term(&self, taxonomy: &str, slug: &str) -> bool {
+        !slug.is_empty() && self.terms(taxonomy).into_iter().any(|term| super::term_slug(term) == slug)
+    }
+
+    /// Whether this document is the index of a page bundle.
+    pub fn is_bundle(&self) -> bool {
+        self.bundle_dir.is_some()
+    }
+
+    /// Hash of the document and its bundle assets, for caching.
+    pub fn cache_hash(&self) -> String {
+        if self.assets.is_empty() {
+            return self.content_hash.clone();
+        }
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(self.content_hash.as_bytes());
+        for asset in &self.assets {
+            hasher.update(asset.path.as_os_str().as_encoded_bytes());
+            hasher.update(asset.hash.as_bytes());
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
     /// Get the URL path for this document.
     pub fn url_path(&self) -> String {
-        format!("/{}/", self.slug())
+        let prefix = super::language_prefix(self.language.as_deref());
+        if self.section.is_empty() {
+            format!("{}/{}/", prefix, self.slug())
+        } else {
+            format!("{}/{}/{}/", prefix, self.section, self.slug())
+        }
+    }
+
+    /// Output directory for this document, relative to the output root.
+    pub fn output_path(&self) -> PathBuf {
+        let mut path: PathBuf = self
+            .language
+            .iter()
+            .map(String::as_str)
+            .chain(self.section.split('/'))
+            .filter(|p| !p.is_empty())
+            .collect();
+        path.push(self.slug());
+        path
+    }
+
+    /// Whether `publish_date` is after `today`.
+    pub fn is_scheduled(&self, today: Date) -> bool {
+        self.frontmatter.publish_date.is_some_and(|date| date > today)
+    }
+
+    /// Whether `expiry_date` is `today` or earlier.
+    pub fn is_expired(&self, today: Date) -> bool {
+        self.frontmatter.expiry_date.is_some_and(|date| date <= today)
+    }
+
+    /// Whether the document is shown today.
+    ///
+    /// Drafts and documents scheduled for later are shown only when asked
+    /// for; expired documents never are.
+    pub fn is_visible(&self, include_drafts: bool, include_future: bool) -> bool {
+        let today = crate::time::today();
+        (include_drafts || !self.frontmatter.draft)
+            && (include_future || !self.is_scheduled(today))
+            && !self.is_expired(today)
+    }
+
+    /// Whether the document is published: not a draft, not scheduled for
+    /// later, and not expired.
+    pub fn is_published(&self) -> bool {
+        self.is_visible(false, false)
     }
 
     /// Validate the document.
@@ -134,6 +265,12 @@ pub struct Frontmatter {
     /// Whether this is a draft.
     #[serde(default)]
     pub draft: bool,
+    /// Date the document is published; it is left out of the site before then.
+    #[serde(default, with = "option_date_format")]
+    pub publish_date: Option<Date>,
+    /// Date the document is taken down; it is left out of the site from then on.
+    #[serde(default, with = "option_date_format")]
+    pub expiry_date: Option<Date>,
     /// Custom URL slug.
     #[serde(default)]
     pub slug: Option<String>,
@@ -151,6 +288,52 @@ pub struct Frontmatter {
     pub extra: std::collections::HashMap<String, rustmax::toml::Value>,
 }
 
+impl Frontmatter {
+    /// Write the frontmatter as a TOML block between `---` lines.
+    ///
+    /// Known fields come first, in the order they are declared, followed
+    /// by extra fields sorted by name.
+    pub fn to_toml(&self) -> String {
+        use rustmax::toml;
+        use std::collections::BTreeMap;
+
+        let quote = |s: &str| toml::Value::String(s.to_string()).to_string();
+
+        let mut out = String::from("---\n");
+        out.push_str(&format!("title = {}\n", quote(&self.title)));
+        let dates = [("date", self.date), ("publish_date", self.publish_date), ("expiry_date", self.expiry_date)];
+        for (key, date) in dates {
+            if let Some(date) = date {
+                out.push_str(&format!("{} = \"{}\"\n", key, date));
+            }
+        }
+        if self.draft {
+            out.push_str("draft = true\n");
+        }
+        let strings = [
+            ("slug", &self.slug),
+            ("template", &self.template),
+            ("description", &self.description),
+            ("author", &self.author),
+        ];
+        for (key, value) in strings {
+            if let Some(value) = value {
+                out.push_str(&format!("{} = {}\n", key, quote(value)));
+            }
+        }
+        if !self.tags.is_empty() {
+            let tags: Vec<String> = self.tags.iter().map(|t| quote(t)).collect();
+            out.push_str(&format!("tags = [{}]\n", tags.join(", ")));
+        }
+        if !self.extra.is_empty() {
+            let extra: BTreeMap<&String, &toml::Value> = self.extra.iter().collect();
+            out.push_str(&toml::to_string(&extra).unwrap_or_default());
+        }
+        out.push_str("---\n");
+        out
+    }
+}
+
 /// Serializable document for export.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DocumentExport {
@@ -162,32 +345,199 @@ pub struct DocumentExport {
     pub content: String,
 }
 
+/// Syntax of a document's frontmatter block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrontmatterFormat {
+    /// TOML between `---` or `+++` lines.
+    Toml,
+    /// YAML between `---` lines.
+    Yaml,
+    /// A JSON object at the start of the file.
+    Json,
+}
+
+impl FrontmatterFormat {
+    /// Lowercase name of the format.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Toml => "toml",
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+        }
+    }
+}
+
 /// Parse frontmatter from document content.
 ///
-/// Supports TOML frontmatter delimited by `---`.
-fn parse_frontmatter(path: &Path, raw: &str) -> Result<(Frontmatter, String)> {
+/// The format is detected from the opening of the file: `+++` is TOML, a
+/// JSON object ending its line is JSON, and a `---` block is read as TOML if
+/// it is valid TOML and as YAML otherwise. Parse errors point at the line and
+/// column of the file where the frontmatter went wrong. Also returns the
+/// YAML or JSON keys dropped for being null.
+fn parse_frontmatter(path: &Path, raw: &str) -> Result<(Frontmatter, Option<FrontmatterFormat>, String, Vec<String>)> {
     let trimmed = raw.trim_start();
+    let open = raw.len() - trimmed.len();
 
-    if !trimmed.starts_with("---") {
-        // No frontmatter, use defaults.
-        return Ok((Frontmatter::default(), raw.to_string()));
+    if let Some((frontmatter, content, null_keys)) = parse_json(path, raw, open)? {
+        return Ok((frontmatter, Some(FrontmatterFormat::Json), content.trim_start().to_string(), null_keys));
     }
 
+    let Some(delimiter) = ["---", "+++"].into_iter().find(|d| trimmed.starts_with(d)) else {
+        // No frontmatter, use defaults.
+        return Ok((Frontmatter::default(), None, raw.to_string(), Vec::new()));
+    };
+
     // Find the closing delimiter.
     let rest = &trimmed[3..];
-    let end = rest.find("\n---").ok_or_else(|| {
-        Error::frontmatter(path, "unclosed frontmatter (missing closing ---)")
+    let end = rest.find(&format!("\n{}", delimiter)).ok_or_else(|| {
+        Error::frontmatter(Diagnostic::at_offset(
+            path,
+            raw,
+            open,
+            format!("unclosed frontmatter (missing closing {})", delimiter),
+        ))
+    })?;
+
+    let frontmatter_str = rest[..end].trim();
+    let frontmatter_start = open + 3 + (end - rest[..end].trim_start().len());
+    let content = &rest[end + 4..]; // Skip the newline and delimiter.
+
+    let toml_error = match rustmax::toml::from_str::<Frontmatter>(frontmatter_str) {
+        Ok(frontmatter) => {
+            return Ok((frontmatter, Some(FrontmatterFormat::Toml), content.trim_start().to_string(), Vec::new()));
+        }
+        Err(e) => e,
+    };
+    if delimiter == "+++" || looks_like_toml(frontmatter_str) {
+        let offset = frontmatter_start + toml_error.span().map(|span| span.start).unwrap_or(0);
+        let message = toml_error.message().trim().replace('\n', "; ");
+        return Err(Error::frontmatter(Diagnostic::at_offset(path, raw, offset, format!("invalid TOML: {}", message))));
+    }
+
+    // YAML lines are counted from the first line after the opening `---`.
+    let first_line = raw[..open].matches('\n').count() + 2;
+    let text = &rest[..end];
+    let text = text.split_once('\n').map_or("", |(_, text)| text);
+    let value = crate::yaml::parse(text).map_err(|e| {
+        let line = first_line + e.line - 1;
+        Error::frontmatter(Diagnostic::new(path, line, 1, format!("invalid YAML: {}", e.message)).with_source(raw))
     })?;
+    let (frontmatter, null_keys) = from_json(value)
+        .map_err(|message| Error::frontmatter(Diagnostic::at_offset(path, raw, open, message)))?;
+    Ok((frontmatter, Some(FrontmatterFormat::Yaml), content.trim_start().to_string(), null_keys))
+}
 
-    let frontmatter_str = &rest[..end].trim();
-    let content = &rest[end + 4..]; // Skip "\n---"
+/// Parse a JSON object starting at `open`, returning it and the rest of the file.
+///
+/// Only an object followed by a newline is frontmatter. Anything else, such
+/// as `{% raw %}` or a `{: .lead}` attribute list, is `None` and stays content.
+fn parse_json<'a>(path: &Path, raw: &'a str, open: usize) -> Result<Option<(Frontmatter, &'a str, Vec<String>)>> {
+    use rustmax::serde_json::{Deserializer, Value};
 
-    let frontmatter: Frontmatter =
-        rustmax::toml::from_str(frontmatter_str).map_err(|e| {
-            Error::frontmatter(path, format!("invalid TOML: {}", e))
-        })?;
+    if !raw[open..].starts_with('{') {
+        return Ok(None);
+    }
+    let mut stream = Deserializer::from_str(&raw[open..]).into_iter::<Value>();
+    let Some(Ok(value @ Value::Object(_))) = stream.next() else {
+        return Ok(None);
+    };
+    let content = &raw[open + stream.byte_offset()..];
+    let rest = content.trim_start_matches([' ', '\t']);
+    if !(rest.is_empty() || rest.starts_with('\n') || rest.starts_with("\r\n")) {
+        return Ok(None);
+    }
+    let (frontmatter, null_keys) = from_json(value)
+        .map_err(|message| Error::frontmatter(Diagnostic::at_offset(path, raw, open, message)))?;
+    Ok(Some((frontmatter, content, null_keys)))
+}
 
-    Ok((frontmatter, content.trim_start().to_string()))
+/// Whether a `---` block is meant as TOML: its first entry is `key = value`
+/// or a `[table]` header rather than YAML's `key: value`.
+fn looks_like_toml(text: &str) -> bool {
+    let Some(line) = text.lines().map(str::trim).find(|l| !l.is_empty() && !l.starts_with('#')) else {
+        return true;
+    };
+    if line.starts_with('[') {
+        return !line.starts_with("[[") || line.ends_with("]]");
+    }
+    match (line.find('='), line.find(':')) {
+        (Some(eq), Some(colon)) => eq < colon,
+        (Some(_), None) => true,
+        _ => false,
+    }
+}
+
+/// Read frontmatter from a YAML or JSON value.
+///
+/// Dates written with a time, as `2024-01-15 10:00:00`, keep just the date.
+/// Nulls, which TOML lacks, are dropped, so extra fields read the same as
+/// when written in TOML; the paths of the dropped keys are returned.
+fn from_json(value: rustmax::serde_json::Value) -> std::result::Result<(Frontmatter, Vec<String>), String> {
+    use rustmax::serde_json::Value;
+    use rustmax::toml;
+
+    let mut map = match value {
+        Value::Object(map) => map,
+        Value::Null => return Ok((Frontmatter::default(), Vec::new())),
+        _ => return Err("frontmatter is not a mapping".to_string()),
+    };
+    for key in ["date", "publish_date", "expiry_date"] {
+        if let Some(Value::String(date)) = map.get_mut(key)
+            && date.len() > 10
+            && date.is_char_boundary(10)
+            && date[..10].parse::<Date>().is_ok()
+            && date[10..].starts_with(['T', 't', ' '])
+        {
+            date.truncate(10);
+        }
+    }
+    let mut null_keys = Vec::new();
+    let table: toml::Table = map
+        .into_iter()
+        .filter_map(|(key, value)| Some((key.clone(), json_to_toml(value, &key, &mut null_keys)?)))
+        .collect();
+    let frontmatter = toml::Value::Table(table)
+        .try_into()
+        .map_err(|e: toml::de::Error| format!("invalid frontmatter: {}", e.message().trim()))?;
+    Ok((frontmatter, null_keys))
+}
+
+/// Convert a JSON value at `path` to TOML, dropping nulls and adding their paths to `null_keys`.
+fn json_to_toml(
+    value: rustmax::serde_json::Value,
+    path: &str,
+    null_keys: &mut Vec<String>,
+) -> Option<rustmax::toml::Value> {
+    use rustmax::serde_json::Value;
+    use rustmax::toml;
+
+    Some(match value {
+        Value::Null => {
+            null_keys.push(path.to_string());
+            return None;
+        }
+        Value::Bool(b) => toml::Value::Boolean(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64()?),
+        },
+        Value::String(s) => toml::Value::String(s),
+        Value::Array(items) => toml::Value::Array(
+            items
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, v)| json_to_toml(v, &format!("{}[{}]", path, i), null_keys))
+                .collect(),
+        ),
+        Value::Object(map) => toml::Value::Table(
+            map.into_iter()
+                .filter_map(|(k, v)| {
+                    let value = json_to_toml(v, &format!("{}.{}", path, k), null_keys)?;
+                    Some((k, value))
+                })
+                .collect(),
+        ),
+    })
 }
 
 mod option_date_format {
@@ -208,15 +558,26 @@ mod option_date_format {
     where
         D: Deserializer<'de>,
     {
-        let opt: Option<String> = Option::deserialize(deserializer)?;
-        match opt {
-            Some(s) => {
-                let date: Date = s.parse().map_err(serde::de::Error::custom)?;
-                Ok(Some(date))
-            }
+        match Option::<DateValue>::deserialize(deserializer)? {
+            Some(DateValue::String(s)) => s.parse().map(Some).map_err(serde::de::Error::custom),
+            Some(DateValue::Toml(datetime)) => match datetime.date {
+                Some(d) => Date::new(d.year as i16, d.month as i8, d.day as i8)
+                    .map(Some)
+                    .map_err(serde::de::Error::custom),
+                None => Err(serde::de::Error::custom(format!("{} is a time, not a date", datetime))),
+            },
             None => Ok(None),
         }
     }
+
+    /// A date written as a string, or as a bare TOML date or date-time,
+    /// whose time is dropped.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum DateValue {
+        String(String),
+        Toml(rustmax::toml::value::Datetime),
+    }
 }
 
 #[cfg(test)]
@@ -240,6 +601,7 @@ This is the content.
         assert_eq!(doc.frontmatter.tags, vec!["rust", "test"]);
         assert!(!doc.frontmatter.draft);
         assert!(doc.content.contains("This is the content"));
+        assert_eq!(doc.body_line, 8);
     }
 
     #[test]
@@ -250,6 +612,136 @@ This is the content.
         assert_eq!(doc.content, raw);
     }
 
+    #[test]
+    fn test_frontmatter_error_location() {
+        let raw = "\n---\ntitle = \"Test\"\ndate = 2024-01-15x\n---\n\nBody.\n";
+        let err = Document::parse(PathBuf::from("test.md"), raw).unwrap_err();
+        let diagnostic = err.diagnostic().unwrap();
+        assert_eq!((diagnostic.line, diagnostic.column), (4, 8));
+        assert_eq!(diagnostic.source_line.as_deref(), Some("date = 2024-01-15x"));
+        assert!(diagnostic.message.starts_with("invalid TOML"), "{}", diagnostic.message);
+
+        let err = Document::parse(PathBuf::from("test.md"), "---\ntitle = \"Test\"\n").unwrap_err();
+        assert_eq!(err.diagnostic().unwrap().line, 1);
+        assert!(err.to_string().contains("unclosed frontmatter"));
+    }
+
+    #[test]
+    fn test_frontmatter_formats() {
+        let yaml = "---\ntitle: \"Hello: World\"\ndate: 2024-01-15 10:00:00 +0000\ntags: [rust, web]\nweight: 3\nseries: null\n---\n\nYAML body.\n";
+        let doc = Document::parse(PathBuf::from("yaml.md"), yaml).unwrap();
+        assert_eq!(doc.frontmatter_format, Some(FrontmatterFormat::Yaml));
+        assert_eq!(doc.frontmatter.title, "Hello: World");
+        assert_eq!(doc.frontmatter.date, Some(Date::constant(2024, 1, 15)));
+        assert_eq!(doc.frontmatter.tags, vec!["rust", "web"]);
+        assert_eq!(doc.frontmatter.extra.get("weight"), Some(&rustmax::toml::Value::Integer(3)));
+        assert!(!doc.frontmatter.extra.contains_key("series"));
+        assert_eq!(doc.null_keys, ["series"]);
+        assert_eq!(doc.content, "YAML body.\n");
+        assert_eq!(doc.body_line, 9);
+
+        let json = "{\n  \"title\": \"JSON\",\n  \"draft\": true,\n  \"extra\": {\"a\": 1}\n}\n\nJSON body.\n";
+        let doc = Document::parse(PathBuf::from("json.md"), json).unwrap();
+        assert_eq!(doc.frontmatter_format, Some(FrontmatterFormat::Json));
+        assert_eq!(doc.frontmatter.title, "JSON");
+        assert!(doc.frontmatter.draft);
+        assert!(doc.frontmatter.extra.contains_key("extra"));
+        assert!(doc.null_keys.is_empty());
+        assert_eq!(doc.content, "JSON body.\n");
+        assert_eq!(doc.body_line, 7);
+
+        let toml = "+++\ntitle = \"TOML\"\n+++\nTOML body.\n";
+        let doc = Document::parse(PathBuf::from("toml.md"), toml).unwrap();
+        assert_eq!(doc.frontmatter_format, Some(FrontmatterFormat::Toml));
+        assert_eq!(doc.frontmatter.title, "TOML");
+        assert_eq!(doc.content, "TOML body.\n");
+
+        // Hugo and Zola write bare TOML dates, sometimes with a time.
+        let toml = "+++\ntitle = \"Dated\"\ndate = 2024-01-02\npublish_date = 2024-01-03T10:00:00Z\n+++\nBody.\n";
+        let doc = Document::parse(PathBuf::from("toml.md"), toml).unwrap();
+        assert_eq!(doc.frontmatter.date, Some(Date::constant(2024, 1, 2)));
+        assert_eq!(doc.frontmatter.publish_date, Some(Date::constant(2024, 1, 3)));
+        let err = Document::parse(PathBuf::from("toml.md"), "+++\ndate = 10:00:00\n+++\n").unwrap_err();
+        assert!(err.to_string().contains("not a date"), "{}", err);
+
+        // Only a JSON object followed by a newline is frontmatter.
+        let content = [
+            "{{< note >}}\n",
+            "{% raw %}{{ x }}{% endraw %}\n",
+            "{: .lead}\nIntro.\n",
+            "{\n  \"title\": \"Test\",\n  \"draft\": yes\n}\n",
+            "{\"a\": 1} is an object.\n",
+        ];
+        for text in content {
+            let doc = Document::parse(PathBuf::from("note.md"), text).unwrap();
+            assert_eq!(doc.frontmatter_format, None, "{}", text);
+            assert_eq!(doc.content, text);
+        }
+    }
+
+    #[test]
+    fn test_frontmatter_format_errors() {
+        let yaml = "---\ntitle: Test\ntags: [a, b\n---\n";
+        let err = Document::parse(PathBuf::from("test.md"), yaml).unwrap_err();
+        let diagnostic = err.diagnostic().unwrap();
+        assert_eq!(diagnostic.line, 3);
+        assert_eq!(diagnostic.source_line.as_deref(), Some("tags: [a, b"));
+        assert!(diagnostic.message.starts_with("invalid YAML"), "{}", diagnostic.message);
+
+        // Wrongly typed fields are reported once the syntax is read.
+        let err = Document::parse(PathBuf::from("test.md"), "---\ntitle: Test\ndraft: maybe\n---\n").unwrap_err();
+        assert!(err.to_string().contains("invalid frontmatter"), "{}", err);
+        let err = Document::parse(PathBuf::from("test.md"), "{\"title\": \"Test\", \"draft\": \"maybe\"}\n").unwrap_err();
+        assert!(err.to_string().contains("invalid frontmatter"), "{}", err);
+
+        assert!(looks_like_toml("# comment\ntitle = \"a: b\""));
+        assert!(looks_like_toml("[extra]\nkey = 1"));
+        assert!(!looks_like_toml("title: a = b"));
+    }
+
+    #[test]
+    fn test_frontmatter_null_keys() {
+        let json = "{\"title\": \"T\", \"a\": null, \"extra\": {\"b\": null, \"c\": [1, null]}}\n";
+        let doc = Document::parse(PathBuf::from("json.md"), json).unwrap();
+        assert_eq!(doc.null_keys, ["a", "extra.b", "extra.c[1]"]);
+
+        let yaml = "---\ntitle: T\nsubtitle: ~\n---\n";
+        let doc = Document::parse(PathBuf::from("yaml.md"), yaml).unwrap();
+        assert_eq!(doc.null_keys, ["subtitle"]);
+    }
+
+    #[test]
+    fn test_frontmatter_to_toml() {
+        let yaml = "---\ntitle: Round trip\ndate: 2024-01-15\ntags: [a]\nweight: 2\n---\nBody\n";
+        let doc = Document::parse(PathBuf::from("test.md"), yaml).unwrap();
+        let toml = doc.frontmatter.to_toml();
+        assert_eq!(toml, "---\ntitle = \"Round trip\"\ndate = \"2024-01-15\"\ntags = [\"a\"]\nweight = 2\n---\n");
+
+        let reparsed = Document::parse(PathBuf::from("test.md"), &format!("{}\n{}", toml, doc.content)).unwrap();
+        assert_eq!(reparsed.frontmatter_format, Some(FrontmatterFormat::Toml));
+        assert_eq!(reparsed.frontmatter.title, "Round trip");
+        assert_eq!(reparsed.content, "Body\n");
+    }
+
+    #[test]
+    fn test_publish_and_expiry_dates() {
+        let raw = "---\ntitle = \"Test\"\npublish_date = \"2024-03-01\"\nexpiry_date = \"2024-06-01\"\n---\n";
+        let doc = Document::parse(PathBuf::from("test.md"), raw).unwrap();
+        let date = |s: &str| s.parse::<Date>().unwrap();
+
+        assert!(doc.is_scheduled(date("2024-02-29")));
+        assert!(!doc.is_scheduled(date("2024-03-01")));
+        assert!(!doc.is_expired(date("2024-05-31")));
+        assert!(doc.is_expired(date("2024-06-01")));
+        assert!(!doc.is_published());
+        assert!(!doc.is_visible(true, true));
+
+        let raw = "---\ntitle = \"Test\"\npublish_date = \"2999-01-01\"\n---\n";
+        let doc = Document::parse(PathBuf::from("test.md"), raw).unwrap();
+        assert!(!doc.is_published());
+        assert!(doc.is_visible(false, true));
+    }
+
     #[test]
     fn test_word_count() {
         let raw = r#"---
@@ -261,6 +753,24 @@ One two three four five.
         let doc = Document::parse(PathBuf::from("test.md"), raw).unwrap();
         assert_eq!(doc.word_count(), 5);
     }
+
+    #[test]
+    fn test_taxonomy_terms() {
+        let raw = r#"---
+title = "Test"
+tags = ["rust"]
+categories = ["guides", "tools"]
+series = "intro"
+weight = 3
+---
+"#;
+        let doc = Document::parse(PathBuf::from("test.md"), raw).unwrap();
+        assert_eq!(doc.terms("tags"), vec!["rust"]);
+        assert_eq!(doc.terms("categories"), vec!["guides", "tools"]);
+        assert_eq!(doc.terms("series"), vec!["intro"]);
+        assert!(doc.terms("weight").is_empty());
+        assert!(doc.terms("authors").is_empty());
+    }
 }
 
 #[cfg(test)]
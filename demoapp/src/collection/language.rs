@@ -0,0 +1,39 @@
+//! Languages of multilingual collections.
+//!
+//! Documents in the default language live at the site root. A translation
+//! is marked by a language suffix (`post.de.md`) or a top-level language
+//! directory (`content/de/post.md`) and is published under `/de/`.
+
+use rustmax::prelude::*;
+
+/// URL prefix of a language: "" for the default language, else `/{code}`.
+pub fn language_prefix(language: Option<&str>) -> String {
+    language.map(|code| format!("/{}", code)).unwrap_or_default()
+}
+
+/// Split a language suffix such as `.de` off a file stem.
+///
+/// Only codes in `languages` are recognized, so `v1.2` stays intact.
+pub fn split_language_suffix<'a>(stem: &'a str, languages: &[String]) -> (&'a str, Option<&'a str>) {
+    if let Some((base, code)) = stem.rsplit_once('.')
+        && languages.iter().any(|l| l == code)
+    {
+        return (base, Some(code));
+    }
+    (stem, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_suffix() {
+        let languages = vec!["de".to_string(), "ja".to_string()];
+        assert_eq!(split_language_suffix("post.de", &languages), ("post", Some("de")));
+        assert_eq!(split_language_suffix("post", &languages), ("post", None));
+        assert_eq!(split_language_suffix("v1.2", &languages), ("v1.2", None));
+        assert_eq!(language_prefix(Some("ja")), "/ja");
+        assert_eq!(language_prefix(None), "");
+    }
+}
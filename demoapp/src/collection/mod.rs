@@ -2,11 +2,17 @@
 
 mod config;
 mod document;
+mod language;
 mod scanner;
+mod section;
+mod taxonomy;
 
-pub use config::{Config, HighlightConfig, CollectionConfig, BuildConfig, ContentConfig, ServerConfig};
-pub use document::{Document, Frontmatter};
+pub use config::{Config, HighlightConfig, CollectionConfig, BuildConfig, ContentConfig, ServerConfig, LanguageConfig, SearchConfig};
+pub use document::{Document, DocumentExport, Frontmatter, FrontmatterFormat, BundleAsset};
+pub use language::{language_prefix, split_language_suffix};
 pub use scanner::Scanner;
+pub use section::{Section, SECTION_INDEX};
+pub use taxonomy::{TAGS, taxonomy_url, term_url, term_slug, taxonomy_title, is_valid_taxonomy};
 
 use rustmax::prelude::*;
 use serde::{Deserialize, Serialize};
@@ -21,11 +27,13 @@ pub struct Collection {
     pub root: PathBuf,
     /// All documents in the collection.
     pub documents: Vec<Document>,
+    /// Sections of the content tree, sorted by path.
+    pub sections: Vec<Section>,
 }
 
 impl Collection {
     /// Load a collection from a directory.
-    pub fn load(root: &Path, _config: &Config) -> Result<Self> {
+    pub fn load(root: &Path, config: &Config) -> Result<Self> {
         let root = root.canonicalize()?;
         let content_dir = root.join("content");
 
@@ -33,18 +41,29 @@ impl Collection {
             return Err(Error::CollectionNotFound { path: root });
         }
 
-        let scanner = Scanner::new(&content_dir);
+        let scanner = Scanner::new(&content_dir).with_languages(config.translation_languages());
         let documents = scanner.scan()?;
+        let sections = scanner.scan_sections(&documents)?;
 
-        Ok(Self { root, documents })
+        Ok(Self { root, documents, sections })
     }
 
-    /// Get all non-draft documents, sorted by date descending.
+    /// Get all published documents, sorted by date descending.
+    ///
+    /// Drafts, documents scheduled for later, and expired documents are
+    /// left out.
     pub fn published(&self) -> Vec<&Document> {
+        self.visible(false, false)
+    }
+
+    /// Get the documents shown today, sorted by date descending.
+    ///
+    /// See [`Document::is_visible`].
+    pub fn visible(&self, include_drafts: bool, include_future: bool) -> Vec<&Document> {
         let mut docs: Vec<_> = self
             .documents
             .iter()
-            .filter(|d| !d.frontmatter.draft)
+            .filter(|d| d.is_visible(include_drafts, include_future))
             .collect();
         docs.sort_by(|a, b| b.frontmatter.date.cmp(&a.frontmatter.date));
         docs
@@ -57,26 +76,97 @@ impl Collection {
         docs
     }
 
-    /// Get documents by tag.
-    pub fn by_tag(&self, tag: &str) -> Vec<&Document> {
+    /// Get a section of the default language by path ("" for the root).
+    pub fn section(&self, path: &str) -> Option<&Section> {
+        self.section_in(None, path)
+    }
+
+    /// Get a section of a language by path.
+    pub fn section_in(&self, language: Option<&str>, path: &str) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|s| s.path == path && s.language.as_deref() == language)
+    }
+
+    /// Get the parent of a section.
+    pub fn parent_section(&self, section: &Section) -> Option<&Section> {
+        section
+            .parent_path()
+            .and_then(|p| self.section_in(section.language.as_deref(), p))
+    }
+
+    /// Get the direct subsections of a section.
+    pub fn child_sections(&self, section: &Section) -> Vec<&Section> {
+        self.sections
+            .iter()
+            .filter(|s| s.language == section.language && s.parent_path() == Some(section.path.as_str()))
+            .collect()
+    }
+
+    /// Get the visible documents directly in a section, sorted by date descending.
+    pub fn section_documents(&self, section: &Section, include_drafts: bool, include_future: bool) -> Vec<&Document> {
+        let mut docs: Vec<_> = self
+            .documents
+            .iter()
+            .filter(|d| d.section == section.path && d.language == section.language)
+            .filter(|d| d.is_visible(include_drafts, include_future))
+            .collect();
+        docs.sort_by_key(|d| std::cmp::Reverse(d.frontmatter.date));
+        docs
+    }
+
+    /// Get the translations of a document into other languages.
+    ///
+    /// Translations share the document's section path and slug.
+    pub fn translations(&self, doc: &Document) -> Vec<&Document> {
+        let slug = doc.slug();
         self.documents
             .iter()
-            .filter(|d| d.frontmatter.tags.iter().any(|t| t == tag))
+            .filter(|d| d.language != doc.language && d.section == doc.section && d.slug() == slug)
             .collect()
     }
 
+    /// Get documents by tag.
+    pub fn by_tag(&self, tag: &str) -> Vec<&Document> {
+        self.by_term(TAGS, tag)
+    }
+
     /// Get all unique tags.
     pub fn tags(&self) -> Vec<String> {
+        self.terms(TAGS)
+    }
+
+    /// Get documents with a term of a taxonomy, matched by [`term_slug`].
+    pub fn by_term(&self, taxonomy: &str, term: &str) -> Vec<&Document> {
+        let slug = term_slug(term);
+        self.documents
+            .iter()
+            .filter(|d| d.has_term(taxonomy, &slug))
+            .collect()
+    }
+
+    /// Get all unique terms of a taxonomy.
+    ///
+    /// Terms with the same [`term_slug`] are one term, named by the first in
+    /// sort order. Empty terms are left out.
+    pub fn terms(&self, taxonomy: &str) -> Vec<String> {
         use rustmax::itertools::Itertools;
 
         self.documents
             .iter()
-            .flat_map(|d| d.frontmatter.tags.iter().cloned())
+            .flat_map(|d| d.terms(taxonomy))
+            .filter(|term| !term.is_empty())
             .sorted()
-            .dedup()
+            .unique_by(|term| term_slug(term))
+            .map(str::to_string)
             .collect()
     }
 
+    /// Find the term of a taxonomy whose [`term_slug`] is `slug`.
+    pub fn term_by_slug(&self, taxonomy: &str, slug: &str) -> Option<String> {
+        self.terms(taxonomy).into_iter().find(|term| term_slug(term) == slug)
+    }
+
     /// Convert to exportable format.
     pub fn to_export(&self) -> CollectionExport {
         CollectionExport {
@@ -85,6 +175,40 @@ impl Collection {
     }
 }
 
+/// Rewrite YAML, JSON and `+++` frontmatter in a collection as `---` TOML.
+///
+/// Files already written with `---` TOML are left as they are, comments and
+/// all. Returns the files that were rewritten, each with the keys it lost
+/// for having null values, which TOML can't write. Every file is parsed
+/// before any is written, so a parse error leaves the collection untouched.
+pub fn convert_frontmatter(root: &Path) -> Result<Vec<(PathBuf, Vec<String>)>> {
+    let content_dir = root.join("content");
+    if !content_dir.exists() {
+        return Err(Error::CollectionNotFound { path: root.to_path_buf() });
+    }
+
+    let mut rewrites = Vec::new();
+    for path in Scanner::new(&content_dir).find_markdown_files()? {
+        let raw = std::fs::read_to_string(&path)?;
+        let doc = Document::parse(path.clone(), &raw)?;
+        let rewrite = match doc.frontmatter_format {
+            None => false,
+            Some(FrontmatterFormat::Toml) => !raw.trim_start().starts_with("---"),
+            Some(FrontmatterFormat::Yaml | FrontmatterFormat::Json) => true,
+        };
+        if rewrite {
+            rewrites.push((path, doc));
+        }
+    }
+
+    let mut converted = Vec::new();
+    for (path, doc) in rewrites {
+        std::fs::write(&path, format!("{}\n{}", doc.frontmatter.to_toml(), doc.content))?;
+        converted.push((path, doc.null_keys));
+    }
+    Ok(converted)
+}
+
 /// Serializable collection for export.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CollectionExport {
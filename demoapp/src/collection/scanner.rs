@@ -6,12 +6,14 @@ use rustmax::rayon::prelude::*;
 use rustmax::log::debug;
 use std::path::{Path, PathBuf};
 
-use super::Document;
+use super::section::{SECTION_INDEX, Section, ancestor_paths};
+use super::{BundleAsset, Document, split_language_suffix};
 use crate::Result;
 
 /// Scanner for finding documents in a content directory.
 pub struct Scanner {
     root: PathBuf,
+    languages: Vec<String>,
 }
 
 impl Scanner {
@@ -19,12 +21,28 @@ impl Scanner {
     pub fn new(root: &Path) -> Self {
         Self {
             root: root.to_path_buf(),
+            languages: Vec::new(),
         }
     }
 
+    /// Recognize translations into these languages.
+    ///
+    /// A file with a language suffix (`post.de.md`) or below a top-level
+    /// language directory (`de/post.md`) is a translation.
+    pub fn with_languages(mut self, languages: Vec<String>) -> Self {
+        self.languages = languages;
+        self
+    }
+
     /// Scan for all markdown documents.
+    ///
+    /// Section `_index.md` files are not documents; see [`Scanner::scan_sections`].
     pub fn scan(&self) -> Result<Vec<Document>> {
-        let paths = self.find_markdown_files()?;
+        let paths: Vec<_> = self
+            .find_markdown_files()?
+            .into_iter()
+            .filter(|p| !self.is_section_index(p))
+            .collect();
 
         debug!("Found {} markdown files", paths.len());
 
@@ -33,15 +51,143 @@ impl Scanner {
             .par_iter()
             .map(|path| {
                 debug!("Loading document: {}", path.display());
-                Document::load(path)
+                let mut doc = Document::load(path)?;
+                let (language, base) = self.language_of(path);
+                if let Some(bundle_dir) = self.bundle_dir(path, &base) {
+                    doc.assets = self.find_bundle_assets(&bundle_dir)?;
+                    doc.bundle_dir = Some(bundle_dir);
+                }
+
+                // A bundle belongs to the section containing its directory.
+                let dir = doc.bundle_dir.as_deref().unwrap_or(path).parent().unwrap_or(&base);
+                doc.section = section_path(&base, dir);
+                doc.language = language;
+                Ok(doc)
             })
             .collect();
 
         documents
     }
 
-    /// Find all markdown files in the content directory.
-    fn find_markdown_files(&self) -> Result<Vec<PathBuf>> {
+    /// Build the sections containing the given documents.
+    ///
+    /// Every directory holding a document or an `_index.md`, and each of its
+    /// ancestors, is a section. Each language has its own tree of sections,
+    /// translated by `_index.de.md` or `de/.../_index.md`. Sections are sorted
+    /// by language (default first), then path, root first.
+    pub fn scan_sections(&self, documents: &[Document]) -> Result<Vec<Section>> {
+        type SectionKey = (Option<String>, String);
+
+        let mut indexes: std::collections::HashMap<SectionKey, PathBuf> = std::collections::HashMap::new();
+        for path in self.find_markdown_files()?.into_iter().filter(|p| self.is_section_index(p)) {
+            let (language, base) = self.language_of(&path);
+            let section_path = section_path(&base, path.parent().unwrap_or(&base));
+            indexes.insert((language, section_path), path);
+        }
+
+        let mut keys: Vec<SectionKey> = documents
+            .iter()
+            .map(|d| (d.language.clone(), d.section.clone()))
+            .chain(indexes.keys().cloned())
+            .flat_map(|(language, path)| {
+                ancestor_paths(&path)
+                    .into_iter()
+                    .map(|p| (language.clone(), p.to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        keys.push((None, String::new()));
+        keys.sort();
+        keys.dedup();
+
+        keys.into_iter()
+            .map(|(language, path)| match indexes.get(&(language.clone(), path.clone())) {
+                Some(index) => {
+                    debug!("Loading section: {}", index.display());
+                    let mut index_doc = Document::load(index)?;
+                    index_doc.language = language;
+                    Ok(Section::from_index(&path, index_doc))
+                }
+                None => Ok(Section::new(&path).with_language(language)),
+            })
+            .collect()
+    }
+
+    /// Language of a file and the content root of that language.
+    ///
+    /// Files below a language directory are relative to that directory;
+    /// everything else is relative to the content root.
+    fn language_of(&self, path: &Path) -> (Option<String>, PathBuf) {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let mut components = relative.components();
+        if let Some(first) = components.next().and_then(|c| c.as_os_str().to_str())
+            && components.next().is_some()
+            && self.languages.iter().any(|l| l == first)
+        {
+            return (Some(first.to_string()), self.root.join(first));
+        }
+
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        let language = split_language_suffix(stem, &self.languages).1;
+        (language.map(str::to_string), self.root.clone())
+    }
+
+    /// Get the bundle directory for a markdown file, if it is a bundle index.
+    ///
+    /// An `index.md` (or a translation such as `index.de.md`) below the
+    /// content root `base` makes its directory a page bundle.
+    fn bundle_dir(&self, path: &Path, base: &Path) -> Option<PathBuf> {
+        let stem = path.file_stem().and_then(|s| s.to_str())?;
+        let is_index = split_language_suffix(stem, &self.languages).0 == "index" && is_markdown(path);
+        let dir = path.parent()?;
+        (is_index && dir != base).then(|| dir.to_path_buf())
+    }
+
+    /// Check if a path is a section's `_index.md` or a translation of one.
+    fn is_section_index(&self, path: &Path) -> bool {
+        let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+            return false;
+        };
+        let index_stem = SECTION_INDEX.trim_end_matches(".md");
+        is_markdown(path) && split_language_suffix(stem, &self.languages).0 == index_stem
+    }
+
+    /// Find the assets colocated in a page bundle.
+    ///
+    /// Markdown files and nested bundles are not part of the bundle.
+    fn find_bundle_assets(&self, bundle_dir: &Path) -> Result<Vec<BundleAsset>> {
+        let mut assets = Vec::new();
+
+        let walker = WalkBuilder::new(bundle_dir)
+            .hidden(true)
+            .git_ignore(true)
+            .git_global(false)
+            .git_exclude(false)
+            // Skip nested bundles; they own their assets.
+            .filter_entry(|entry| entry.depth() == 0 || !is_bundle_root(entry.path()))
+            .build();
+
+        for entry in walker {
+            let entry = entry?;
+            let path = entry.path();
+
+            if path.is_file() && !is_markdown(path) {
+                let data = std::fs::read(path)?;
+                assets.push(BundleAsset {
+                    path: path.strip_prefix(bundle_dir).unwrap().to_path_buf(),
+                    hash: rustmax::blake3::hash(&data).to_hex().to_string(),
+                });
+            }
+        }
+
+        // Sort for deterministic ordering.
+        assets.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Ok(assets)
+    }
+
+    /// Find all markdown files in the content directory, section indexes included.
+    pub fn find_markdown_files(&self) -> Result<Vec<PathBuf>> {
         let mut paths = Vec::new();
 
         for entry in WalkBuilder::new(&self.root)
@@ -54,12 +200,8 @@ impl Scanner {
             let entry = entry?;
             let path = entry.path();
 
-            if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "md" || ext == "markdown" {
-                        paths.push(path.to_path_buf());
-                    }
-                }
+            if path.is_file() && is_markdown(path) {
+                paths.push(path.to_path_buf());
             }
         }
 
@@ -70,6 +212,23 @@ impl Scanner {
     }
 }
 
+/// Check if a directory contains a bundle index.
+fn is_bundle_root(path: &Path) -> bool {
+    path.is_dir() && (path.join("index.md").exists() || path.join("index.markdown").exists())
+}
+
+/// Section path of a directory, relative to a language's content root.
+fn section_path(base: &Path, dir: &Path) -> String {
+    let relative = dir.strip_prefix(base).unwrap_or(Path::new(""));
+    let parts: Vec<_> = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+    parts.join("/")
+}
+
+/// Check if a path is a markdown file.
+fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md" || ext == "markdown")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -108,4 +267,98 @@ mod tests {
 
         assert_eq!(docs.len(), 3);
     }
+
+    #[test]
+    fn test_scanner_page_bundles() {
+        let dir = TempDir::new().unwrap();
+        let content_dir = dir.path().join("content");
+        let bundle = content_dir.join("my-post");
+        fs::create_dir_all(bundle.join("images")).unwrap();
+        fs::create_dir_all(bundle.join("child")).unwrap();
+
+        fs::write(content_dir.join("index.md"), "---\ntitle = \"Home\"\n---\n").unwrap();
+        fs::write(bundle.join("index.md"), "---\ntitle = \"Bundle\"\n---\n![](cat.png)").unwrap();
+        fs::write(bundle.join("cat.png"), "png").unwrap();
+        fs::write(bundle.join("images/dog.jpg"), "jpg").unwrap();
+        fs::write(bundle.join("notes.md"), "---\ntitle = \"Notes\"\n---\n").unwrap();
+        fs::write(bundle.join("child/index.md"), "---\ntitle = \"Child\"\n---\n").unwrap();
+        fs::write(bundle.join("child/data.csv"), "a,b").unwrap();
+
+        let docs = Scanner::new(&content_dir).scan().unwrap();
+        let find = |title: &str| docs.iter().find(|d| d.frontmatter.title == title).unwrap();
+
+        // The content root's index is not a bundle.
+        assert!(!find("Home").is_bundle());
+        assert!(!find("Notes").is_bundle());
+
+        let bundle_doc = find("Bundle");
+        assert_eq!(bundle_doc.slug(), "my-post");
+        let assets: Vec<_> = bundle_doc.assets.iter().map(|a| a.path.clone()).collect();
+        assert_eq!(assets, vec![PathBuf::from("cat.png"), PathBuf::from("images/dog.jpg")]);
+        assert_ne!(bundle_doc.cache_hash(), bundle_doc.content_hash);
+
+        let child = find("Child");
+        assert_eq!(child.slug(), "child");
+        assert_eq!(child.assets.len(), 1);
+    }
+
+    #[test]
+    fn test_scanner_sections() {
+        let dir = TempDir::new().unwrap();
+        let content_dir = dir.path().join("content");
+        fs::create_dir_all(content_dir.join("blog/2026/launch")).unwrap();
+        fs::create_dir_all(content_dir.join("guides")).unwrap();
+
+        fs::write(content_dir.join("about.md"), "---\ntitle = \"About\"\n---\n").unwrap();
+        fs::write(content_dir.join("guides/_index.md"), "---\ntitle = \"All Guides\"\n---\nIntro").unwrap();
+        fs::write(content_dir.join("blog/2026/new.md"), "---\ntitle = \"New\"\n---\n").unwrap();
+        fs::write(content_dir.join("blog/2026/launch/index.md"), "---\ntitle = \"Launch\"\n---\n").unwrap();
+
+        let scanner = Scanner::new(&content_dir);
+        let docs = scanner.scan().unwrap();
+        assert_eq!(docs.len(), 3, "_index.md is not a document");
+
+        let find = |title: &str| docs.iter().find(|d| d.frontmatter.title == title).unwrap();
+        assert_eq!(find("About").url_path(), "/about/");
+        assert_eq!(find("New").url_path(), "/blog/2026/new/");
+        assert_eq!(find("Launch").section, "blog/2026");
+        assert_eq!(find("Launch").output_path(), PathBuf::from("blog/2026/launch"));
+
+        let sections = scanner.scan_sections(&docs).unwrap();
+        let paths: Vec<_> = sections.iter().map(|s| s.path.as_str()).collect();
+        assert_eq!(paths, vec!["", "blog", "blog/2026", "guides"]);
+        assert_eq!(sections[3].title(), "All Guides");
+        assert_eq!(sections[3].content, "Intro");
+    }
+
+    #[test]
+    fn test_scanner_languages() {
+        let dir = TempDir::new().unwrap();
+        let content_dir = dir.path().join("content");
+        fs::create_dir_all(content_dir.join("blog")).unwrap();
+        fs::create_dir_all(content_dir.join("de/blog")).unwrap();
+
+        fs::write(content_dir.join("blog/post.md"), "---\ntitle = \"Post\"\n---\n").unwrap();
+        fs::write(content_dir.join("blog/post.ja.md"), "---\ntitle = \"Ja\"\n---\n").unwrap();
+        fs::write(content_dir.join("de/blog/post.md"), "---\ntitle = \"De\"\n---\n").unwrap();
+        fs::write(content_dir.join("de/blog/_index.md"), "---\ntitle = \"Das Blog\"\n---\n").unwrap();
+        fs::write(content_dir.join("blog/v1.2.md"), "---\ntitle = \"Version\"\n---\n").unwrap();
+
+        let scanner = Scanner::new(&content_dir).with_languages(vec!["de".to_string(), "ja".to_string()]);
+        let docs = scanner.scan().unwrap();
+        let find = |title: &str| docs.iter().find(|d| d.frontmatter.title == title).unwrap();
+
+        assert_eq!(find("Post").language, None);
+        assert_eq!(find("Ja").language.as_deref(), Some("ja"));
+        assert_eq!(find("Ja").url_path(), "/ja/blog/post/");
+        assert_eq!(find("De").language.as_deref(), Some("de"));
+        assert_eq!(find("De").section, "blog");
+        assert_eq!(find("De").output_path(), PathBuf::from("de/blog/post"));
+        assert_eq!(find("Version").slug(), "v1.2");
+
+        let sections = scanner.scan_sections(&docs).unwrap();
+        let keys: Vec<_> = sections.iter().map(|s| s.url_path()).collect();
+        assert_eq!(keys, vec!["/", "/blog/", "/de/", "/de/blog/", "/ja/", "/ja/blog/"]);
+        assert_eq!(sections[3].title(), "Das Blog");
+    }
 }
@@ -0,0 +1,152 @@
+//! Sections derived from the content directory tree.
+
+use rustmax::prelude::*;
+use std::path::PathBuf;
+
+use super::{Document, Frontmatter};
+
+/// File holding a section's frontmatter and introduction.
+pub const SECTION_INDEX: &str = "_index.md";
+
+/// A directory of documents in the content tree.
+#[derive(Debug, Clone)]
+pub struct Section {
+    /// Path relative to the content directory, `/`-separated ("" for the root).
+    pub path: String,
+    /// Frontmatter from `_index.md`, or defaults if there is none.
+    pub frontmatter: Frontmatter,
+    /// Markdown content of `_index.md`.
+    pub content: String,
+    /// Path to `_index.md`, if the section has one.
+    pub source_path: Option<PathBuf>,
+    /// Line in `_index.md` where `content` begins (1-based).
+    pub body_line: usize,
+    /// Language code of a translated section, `None` for the default language.
+    pub language: Option<String>,
+}
+
+impl Section {
+    /// Create a section without an `_index.md`.
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            frontmatter: Frontmatter::default(),
+            content: String::new(),
+            source_path: None,
+            body_line: 1,
+            language: None,
+        }
+    }
+
+    /// Create a section from its parsed `_index.md`.
+    pub fn from_index(path: &str, index: Document) -> Self {
+        Self {
+            path: path.to_string(),
+            frontmatter: index.frontmatter,
+            content: index.content,
+            source_path: Some(index.source_path),
+            body_line: index.body_line,
+            language: index.language,
+        }
+    }
+
+    /// Set the section's language.
+    pub fn with_language(mut self, language: Option<String>) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Whether this is the root of the content directory.
+    ///
+    /// Each language has its own root, published as that language's index.
+    pub fn is_root(&self) -> bool {
+        self.path.is_empty()
+    }
+
+    /// Last component of the section path.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or("")
+    }
+
+    /// Section title, defaulting to the directory name.
+    pub fn title(&self) -> String {
+        if !self.frontmatter.title.is_empty() {
+            return self.frontmatter.title.clone();
+        }
+        let mut chars = self.name().chars();
+        match chars.next() {
+            Some(first) => first.to_uppercase().chain(chars).collect(),
+            None => String::new(),
+        }
+    }
+
+    /// Path of the parent section, or `None` for the root.
+    pub fn parent_path(&self) -> Option<&str> {
+        if self.is_root() {
+            return None;
+        }
+        Some(self.path.rsplit_once('/').map_or("", |(parent, _)| parent))
+    }
+
+    /// Documents per page from `paginate` in `_index.md`, if set.
+    pub fn paginate(&self) -> Option<usize> {
+        let value = self.frontmatter.extra.get("paginate")?.as_integer()?;
+        usize::try_from(value).ok()
+    }
+
+    /// Get the URL path for this section.
+    pub fn url_path(&self) -> String {
+        let prefix = super::language_prefix(self.language.as_deref());
+        if self.is_root() {
+            format!("{}/", prefix)
+        } else {
+            format!("{}/{}/", prefix, self.path)
+        }
+    }
+}
+
+/// Section paths of a document's section and all its ancestors.
+pub fn ancestor_paths(path: &str) -> Vec<&str> {
+    let mut paths = vec![""];
+    for (i, c) in path.char_indices() {
+        if c == '/' {
+            paths.push(&path[..i]);
+        }
+    }
+    if !path.is_empty() {
+        paths.push(path);
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_section_paths() {
+        let root = Section::new("");
+        assert!(root.is_root());
+        assert_eq!(root.url_path(), "/");
+        assert_eq!(root.parent_path(), None);
+
+        let section = Section::new("blog/2026");
+        assert_eq!(section.name(), "2026");
+        assert_eq!(section.url_path(), "/blog/2026/");
+        assert_eq!(section.parent_path(), Some("blog"));
+        assert_eq!(Section::new("guides").parent_path(), Some(""));
+        assert_eq!(Section::new("guides").title(), "Guides");
+
+        let mut paged = Section::new("blog");
+        paged.frontmatter.extra.insert("paginate".to_string(), rustmax::toml::Value::Integer(5));
+        assert_eq!(paged.paginate(), Some(5));
+        assert_eq!(Section::new("blog").paginate(), None);
+
+        let german = Section::new("blog").with_language(Some("de".to_string()));
+        assert_eq!(german.url_path(), "/de/blog/");
+        assert_eq!(Section::new("").with_language(Some("de".to_string())).url_path(), "/de/");
+
+        assert_eq!(ancestor_paths("blog/2026"), vec!["", "blog", "blog/2026"]);
+        assert_eq!(ancestor_paths(""), vec![""]);
+    }
+}
@@ -0,0 +1,67 @@
+//! Taxonomies that group documents by frontmatter values.
+
+use rustmax::prelude::*;
+
+/// Taxonomy whose terms come from `Frontmatter::tags`.
+pub const TAGS: &str = "tags";
+
+/// URL of a taxonomy's term index.
+pub fn taxonomy_url(taxonomy: &str) -> String {
+    format!("/{}/", taxonomy)
+}
+
+/// URL of a term's listing page.
+pub fn term_url(taxonomy: &str, term: &str) -> String {
+    format!("/{}/{}/", taxonomy, term_slug(term))
+}
+
+/// URL segment and directory name of a term, e.g. "Jane Doe" becomes "jane-doe".
+///
+/// Terms are the same term when their slugs match. Terms without ASCII
+/// letters or digits are hex-encoded instead, so only the empty term has an
+/// empty slug.
+pub fn term_slug(term: &str) -> String {
+    let slug = crate::build::slugify(term);
+    if !slug.is_empty() {
+        return slug;
+    }
+    term.bytes().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Display title for a taxonomy, e.g. "Categories".
+pub fn taxonomy_title(taxonomy: &str) -> String {
+    let mut chars = taxonomy.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Check that a taxonomy name can be used as a URL segment.
+pub fn is_valid_taxonomy(taxonomy: &str) -> bool {
+    !taxonomy.is_empty()
+        && taxonomy
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taxonomy_urls() {
+        assert_eq!(taxonomy_url("series"), "/series/");
+        assert_eq!(term_url("tags", "rust"), "/tags/rust/");
+        assert_eq!(term_url("authors", "Jane Doe"), "/authors/jane-doe/");
+        assert_eq!(term_url("authors", "../../pwned"), "/authors/pwned/");
+        assert_eq!(term_slug("C++"), "c");
+        assert_eq!(term_slug("日本"), "e697a5e69cac");
+        assert_eq!(term_slug(".."), "2e2e");
+        assert_eq!(term_slug(""), "");
+        assert_eq!(taxonomy_title("categories"), "Categories");
+        assert!(is_valid_taxonomy("authors"));
+        assert!(!is_valid_taxonomy("a/b"));
+        assert!(!is_valid_taxonomy(""));
+    }
+}
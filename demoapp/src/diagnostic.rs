@@ -0,0 +1,160 @@
+//! Source-located diagnostics.
+//!
+//! A [`Diagnostic`] points at a line and column of a source file and
+//! renders like a compiler error, with the offending line and a caret:
+//!
+//! ```text
+//! content/post.md:3:9: invalid string
+//!   |
+//! 3 | title = "Hello
+//!   |         ^
+//! ```
+
+use std::fmt;
+use std::path::PathBuf;
+
+/// A message about a position in a source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The file the message is about.
+    pub path: PathBuf,
+    /// Line number (1-based).
+    pub line: usize,
+    /// Column in characters (1-based).
+    pub column: usize,
+    /// What went wrong.
+    pub message: String,
+    /// The text of the line, if the source is available.
+    pub source_line: Option<String>,
+}
+
+impl Diagnostic {
+    /// Create a diagnostic without a source excerpt.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        line: usize,
+        column: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            line: line.max(1),
+            column: column.max(1),
+            message: message.into(),
+            source_line: None,
+        }
+    }
+
+    /// Create a diagnostic at a byte offset into `source`, the file's contents.
+    pub fn at_offset(
+        path: impl Into<PathBuf>,
+        source: &str,
+        offset: usize,
+        message: impl Into<String>,
+    ) -> Self {
+        let (line, column) = position(source, offset);
+        Self::new(path, line, column, message).with_source(source)
+    }
+
+    /// Attach the excerpt for the diagnostic's line from the file's contents.
+    pub fn with_source(mut self, source: &str) -> Self {
+        self.source_line = source
+            .lines()
+            .nth(self.line - 1)
+            .map(|line| line.trim_end_matches('\r').to_string());
+        self
+    }
+
+    /// Attach the excerpt by reading the file, if it can be read.
+    pub fn with_source_file(self) -> Self {
+        match std::fs::read_to_string(&self.path) {
+            Ok(source) => self.with_source(&source),
+            Err(_) => self,
+        }
+    }
+
+    /// The `path:line:column` the diagnostic points at.
+    pub fn location(&self) -> String {
+        format!("{}:{}:{}", self.path.display(), self.line, self.column)
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location(), self.message)?;
+
+        if let Some(source_line) = &self.source_line {
+            let number = self.line.to_string();
+            let gutter = " ".repeat(number.len());
+            // Keep tabs so the caret lines up with the excerpt.
+            let indent: String = source_line
+                .chars()
+                .take(self.column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            write!(f, "\n{} |", gutter)?;
+            write!(f, "\n{} | {}", number, source_line)?;
+            write!(f, "\n{} | {}^", gutter, indent)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Get the 1-based line and character column of a byte offset.
+pub fn position(source: &str, offset: usize) -> (usize, usize) {
+    let offset = floor_char_boundary(source, offset);
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+/// Clamp an offset into `source` down to a character boundary.
+fn floor_char_boundary(source: &str, offset: usize) -> usize {
+    let mut offset = offset.min(source.len());
+    while !source.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_position() {
+        let source = "one\ntwo\nthré four";
+        assert_eq!(position(source, 0), (1, 1));
+        assert_eq!(position(source, 4), (2, 1));
+        assert_eq!(position(source, 6), (2, 3));
+        // Columns count characters, not bytes.
+        assert_eq!(position(source, source.find("four").unwrap()), (3, 6));
+        assert_eq!(position(source, 1000), (3, 10));
+    }
+
+    #[test]
+    fn test_display_with_excerpt() {
+        let source = "---\ntitle = \"Hello\ndate = 1\n---\n";
+        let offset = source.find('"').unwrap();
+        let diagnostic = Diagnostic::at_offset("post.md", source, offset, "invalid string");
+
+        assert_eq!(diagnostic.line, 2);
+        assert_eq!(diagnostic.column, 9);
+        assert_eq!(
+            diagnostic.to_string(),
+            "post.md:2:9: invalid string\n  |\n2 | title = \"Hello\n  |         ^"
+        );
+    }
+
+    #[test]
+    fn test_display_without_excerpt() {
+        let diagnostic = Diagnostic::new("page.html", 12, 0, "oops");
+        assert_eq!(diagnostic.to_string(), "page.html:12:1: oops");
+
+        let diagnostic = diagnostic.with_source("too short");
+        assert_eq!(diagnostic.source_line, None);
+    }
+}
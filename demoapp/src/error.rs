@@ -3,6 +3,8 @@
 use thiserror::Error;
 use std::path::PathBuf;
 
+use crate::diagnostic::Diagnostic;
+
 /// The result type for Anthology operations.
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -28,12 +30,22 @@ pub enum Error {
     #[error("Document error in {path}: {message}")]
     Document { path: PathBuf, message: String },
 
-    #[error("Frontmatter parse error in {path}: {message}")]
-    Frontmatter { path: PathBuf, message: String },
+    #[error("Frontmatter parse error in {0}")]
+    Frontmatter(Box<Diagnostic>),
+
+    #[error("Data file error in {path}: {message}")]
+    Data { path: PathBuf, message: String },
+
+    #[error("Shortcode error in {0}")]
+    Shortcode(Box<Diagnostic>),
 
     #[error("Template error: {0}")]
     Template(#[from] rustmax::tera::Error),
 
+    /// A template error traced back to a line of the template file.
+    #[error("Template error in {0}")]
+    TemplateSource(Box<Diagnostic>),
+
     #[error("JSON error: {0}")]
     Json(#[from] rustmax::serde_json::Error),
 
@@ -52,6 +64,12 @@ pub enum Error {
     #[error("Remote fetch error for {url}: {message}")]
     Remote { url: String, message: String },
 
+    #[error("Import error in {path}: {message}")]
+    Import { path: PathBuf, message: String },
+
+    #[error("Theme error in {name}: {message}")]
+    Theme { name: String, message: String },
+
     #[error("{0}")]
     Other(#[from] rustmax::anyhow::Error),
 }
@@ -70,13 +88,25 @@ impl Error {
         }
     }
 
-    pub fn frontmatter(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
-        Self::Frontmatter {
+    pub fn frontmatter(diagnostic: Diagnostic) -> Self {
+        Self::Frontmatter(Box::new(diagnostic))
+    }
+
+    pub fn data(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self::Data {
             path: path.into(),
             message: message.into(),
         }
     }
 
+    pub fn shortcode(diagnostic: Diagnostic) -> Self {
+        Self::Shortcode(Box::new(diagnostic))
+    }
+
+    pub fn template_source(diagnostic: Diagnostic) -> Self {
+        Self::TemplateSource(Box::new(diagnostic))
+    }
+
     pub fn build(message: impl Into<String>) -> Self {
         Self::Build {
             message: message.into(),
@@ -95,4 +125,26 @@ impl Error {
             message: message.into(),
         }
     }
+
+    pub fn import(path: impl Into<PathBuf>, message: impl Into<String>) -> Self {
+        Self::Import {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn theme(name: impl Into<String>, message: impl Into<String>) -> Self {
+        Self::Theme {
+            name: name.into(),
+            message: message.into(),
+        }
+    }
+
+    /// The source location the error points at, if it has one.
+    pub fn diagnostic(&self) -> Option<&Diagnostic> {
+        match self {
+            Self::Frontmatter(d) | Self::Shortcode(d) | Self::TemplateSource(d) => Some(d),
+            _ => None,
+        }
+    }
 }
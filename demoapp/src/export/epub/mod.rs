@@ -0,0 +1,1010 @@
+//! EPUB generation for collections.
+//!
+//! Creates EPUB3-compatible ebooks from document collections. Local images
+//! are packaged with the book, navigation is built from each document's
+//! headings (with an NCX for EPUB 2 readers), and the finished file is
+//! checked by [`validate_epub`] before it is written.
+
+mod validate;
+
+pub use validate::{EpubIssue, validate_epub};
+
+use rustmax::zip::{ZipWriter, write::SimpleFileOptions, CompressionMethod};
+use rustmax::jiff::Zoned;
+use rustmax::log::warn;
+use rustmax::regex::{Captures, Regex};
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Write, Seek};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::collection::{Collection, Config, Document};
+use crate::build::{Heading, TableOfContents, TocEntry, generate_id, render_markdown};
+use crate::export::resolve_image;
+use crate::remote::mime_from_extension;
+use crate::{Error, Result};
+
+/// Configuration for EPUB generation.
+#[derive(Debug, Clone)]
+pub struct EpubConfig {
+    /// Book title (defaults to collection title).
+    pub title: Option<String>,
+    /// Book author.
+    pub author: Option<String>,
+    /// Book language code (e.g., "en").
+    pub language: String,
+    /// Book description.
+    pub description: Option<String>,
+    /// Publisher name.
+    pub publisher: Option<String>,
+    /// Cover image path, relative to the collection root (optional).
+    pub cover_image: Option<String>,
+    /// Include draft documents.
+    pub include_drafts: bool,
+}
+
+impl Default for EpubConfig {
+    fn default() -> Self {
+        Self {
+            title: None,
+            author: None,
+            language: "en".to_string(),
+            description: None,
+            publisher: None,
+            cover_image: None,
+            include_drafts: false,
+        }
+    }
+}
+
+/// EPUB builder for constructing ebooks.
+pub struct EpubBuilder<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    config: EpubConfig,
+    root: PathBuf,
+    identifier: String,
+    spine_items: Vec<SpineItem>,
+    manifest_items: Vec<ManifestItem>,
+    chapters: Vec<Chapter>,
+    images: HashMap<PathBuf, Option<String>>,
+}
+
+#[derive(Debug)]
+struct SpineItem {
+    id: String,
+}
+
+#[derive(Debug)]
+struct ManifestItem {
+    id: String,
+    href: String,
+    media_type: String,
+    properties: Option<&'static str>,
+}
+
+/// A document's entry in the navigation.
+#[derive(Debug)]
+struct Chapter {
+    title: String,
+    href: String,
+    toc: TableOfContents,
+}
+
+impl<W: Write + Seek> EpubBuilder<W> {
+    /// Create a new EPUB builder.
+    pub fn new(writer: W, config: EpubConfig) -> Result<Self> {
+        let zip = ZipWriter::new(writer);
+        Ok(Self {
+            zip,
+            config,
+            root: PathBuf::new(),
+            identifier: format!("urn:uuid:{}", generate_uuid()),
+            spine_items: Vec::new(),
+            manifest_items: Vec::new(),
+            chapters: Vec::new(),
+            images: HashMap::new(),
+        })
+    }
+
+    /// Set the collection root that image paths are resolved against.
+    pub fn with_root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = root.into();
+        self
+    }
+
+    /// Write the mimetype file (must be first and uncompressed).
+    fn write_mimetype(&mut self) -> Result<()> {
+        let options = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored);
+        self.zip.start_file("mimetype", options)
+            .map_err(|e| Error::build(format!("Failed to create mimetype: {}", e)))?;
+        self.zip.write_all(b"application/epub+zip")
+            .map_err(|e| Error::build(format!("Failed to write mimetype: {}", e)))?;
+        Ok(())
+    }
+
+    /// Write the META-INF/container.xml file.
+    fn write_container(&mut self) -> Result<()> {
+        let options = SimpleFileOptions::default();
+        self.zip.start_file("META-INF/container.xml", options)
+            .map_err(|e| Error::build(format!("Failed to create container.xml: {}", e)))?;
+
+        let container = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>"#;
+
+        self.zip.write_all(container.as_bytes())
+            .map_err(|e| Error::build(format!("Failed to write container.xml: {}", e)))?;
+        Ok(())
+    }
+
+    /// Write the stylesheet.
+    fn write_stylesheet(&mut self) -> Result<()> {
+        let options = SimpleFileOptions::default();
+        self.zip.start_file("OEBPS/style.css", options)
+            .map_err(|e| Error::build(format!("Failed to create style.css: {}", e)))?;
+
+        let css = r#"body {
+    font-family: Georgia, serif;
+    line-height: 1.6;
+    margin: 2em;
+}
+h1, h2, h3, h4, h5, h6 {
+    font-family: Helvetica, Arial, sans-serif;
+    margin-top: 1.5em;
+    margin-bottom: 0.5em;
+}
+h1 { font-size: 2em; }
+h2 { font-size: 1.5em; }
+h3 { font-size: 1.25em; }
+p { margin: 1em 0; }
+code {
+    font-family: monospace;
+    background: #f4f4f4;
+    padding: 0.1em 0.3em;
+}
+pre {
+    background: #f4f4f4;
+    padding: 1em;
+    overflow-x: auto;
+}
+blockquote {
+    border-left: 3px solid #ccc;
+    margin-left: 0;
+    padding-left: 1em;
+    font-style: italic;
+}
+a { color: #0066cc; }
+img { max-width: 100%; }
+"#;
+
+        self.zip.write_all(css.as_bytes())
+            .map_err(|e| Error::build(format!("Failed to write style.css: {}", e)))?;
+
+        self.manifest_items.push(ManifestItem {
+            id: "style".to_string(),
+            href: "style.css".to_string(),
+            media_type: "text/css".to_string(),
+            properties: None,
+        });
+
+        Ok(())
+    }
+
+    /// Package an image, returning its path in the book.
+    ///
+    /// Images are stored once however often they are referenced. Files
+    /// that aren't images in a format reading systems must support are
+    /// left out.
+    fn add_image(&mut self, path: &Path, id: Option<&str>) -> Result<Option<String>> {
+        if let Some(href) = self.images.get(path) {
+            return Ok(href.clone());
+        }
+
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let media_type = mime_from_extension(&ext).to_string();
+        if !matches!(media_type.as_str(), "image/png" | "image/jpeg" | "image/gif" | "image/svg+xml" | "image/webp") {
+            warn!("Skipping image {}: unsupported format", path.display());
+            self.images.insert(path.to_path_buf(), None);
+            return Ok(None);
+        }
+
+        let data = std::fs::read(path)?;
+        let index = self.manifest_items.iter().filter(|item| item.href.starts_with("images/")).count();
+        let id = id.map(str::to_string).unwrap_or_else(|| format!("image{}", index));
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+        let href = format!("images/{}-{}.{}", index, generate_id(stem), ext);
+
+        self.zip.start_file(format!("OEBPS/{}", href), SimpleFileOptions::default())
+            .map_err(|e| Error::build(format!("Failed to create {}: {}", href, e)))?;
+        self.zip.write_all(&data)
+            .map_err(|e| Error::build(format!("Failed to write {}: {}", href, e)))?;
+
+        self.manifest_items.push(ManifestItem {
+            id: id.clone(),
+            href: href.clone(),
+            media_type,
+            properties: (id == "cover-image").then_some("cover-image"),
+        });
+        self.images.insert(path.to_path_buf(), Some(href.clone()));
+        Ok(Some(href))
+    }
+
+    /// Package the configured cover image.
+    fn write_cover_image(&mut self) -> Result<()> {
+        let Some(cover) = self.config.cover_image.clone() else {
+            return Ok(());
+        };
+        let path = self.root.join(&cover);
+        if !path.is_file() {
+            return Err(Error::build(format!("Cover image not found: {}", path.display())));
+        }
+        if self.add_image(&path, Some("cover-image"))?.is_none() {
+            return Err(Error::build(format!("Unsupported cover image format: {}", cover)));
+        }
+        Ok(())
+    }
+
+    /// Package the local images `html` refers to and point it at them.
+    ///
+    /// Images that can't be packaged are replaced by their alt text, so
+    /// the book never refers to files it doesn't contain.
+    fn embed_images(&mut self, html: &str, dir: &Path) -> Result<String> {
+        static IMG_RE: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r#"<img\b[^>]*?\ssrc="([^"]*)"[^>]*>"#).expect("invalid regex")
+        });
+        static ALT_RE: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r#"\salt="([^"]*)""#).expect("invalid regex")
+        });
+
+        let mut result = String::with_capacity(html.len());
+        let mut last = 0;
+        for caps in IMG_RE.captures_iter(html) {
+            let (Some(whole), Some(src)) = (caps.get(0), caps.get(1)) else {
+                continue;
+            };
+            let href = match resolve_image(&self.root, dir, &src.as_str().replace("&amp;", "&")) {
+                Some(path) => self.add_image(&path, None)?,
+                None => None,
+            };
+            match href {
+                Some(href) => {
+                    result.push_str(&html[last..src.start()]);
+                    result.push_str(&xml_escape(&href));
+                    last = src.end();
+                }
+                None => {
+                    result.push_str(&html[last..whole.start()]);
+                    if let Some(alt) = ALT_RE.captures(whole.as_str()) {
+                        result.push_str(&alt[1]);
+                    }
+                    last = whole.end();
+                }
+            }
+        }
+        result.push_str(&html[last..]);
+        Ok(result)
+    }
+
+    /// Add a document to the EPUB.
+    pub fn add_document(&mut self, doc: &Document, index: usize) -> Result<()> {
+        let id = format!("chapter{}", index);
+        let filename = format!("{}.xhtml", id);
+        let options = SimpleFileOptions::default();
+
+        let html_content = to_xhtml(&render_markdown(&doc.content));
+        let (html_content, headings) = anchor_headings(&html_content);
+        let dir = doc.source_path.parent().unwrap_or(&self.root).to_path_buf();
+        let html_content = unlink_local(&self.embed_images(&html_content, &dir)?);
+
+        self.zip.start_file(format!("OEBPS/{}", filename), options)
+            .map_err(|e| Error::build(format!("Failed to create {}: {}", filename, e)))?;
+
+        let xhtml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" lang="{}">
+<head>
+  <meta charset="utf-8"/>
+  <title>{}</title>
+  <link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+<h1>{}</h1>
+{}
+</body>
+</html>"#,
+            self.config.language,
+            xml_escape(&doc.frontmatter.title),
+            xml_escape(&doc.frontmatter.title),
+            html_content
+        );
+
+        self.zip.write_all(xhtml.as_bytes())
+            .map_err(|e| Error::build(format!("Failed to write {}: {}", filename, e)))?;
+
+        self.chapters.push(Chapter {
+            title: doc.frontmatter.title.clone(),
+            href: filename.clone(),
+            toc: TableOfContents::from_headings(&headings),
+        });
+
+        self.manifest_items.push(ManifestItem {
+            id: id.clone(),
+            href: filename,
+            media_type: "application/xhtml+xml".to_string(),
+            properties: None,
+        });
+
+        self.spine_items.push(SpineItem { id });
+
+        Ok(())
+    }
+
+    /// Write the navigation document (EPUB3 toc).
+    fn write_nav(&mut self) -> Result<()> {
+        let options = SimpleFileOptions::default();
+        self.zip.start_file("OEBPS/nav.xhtml", options)
+            .map_err(|e| Error::build(format!("Failed to create nav.xhtml: {}", e)))?;
+
+        let mut toc_items = String::new();
+        for chapter in &self.chapters {
+            toc_items.push_str(&format!(
+                "      <li><a href=\"{}\">{}</a>",
+                chapter.href,
+                xml_escape(&chapter.title)
+            ));
+            nav_list(&mut toc_items, &chapter.href, &chapter.toc.entries, 4);
+            toc_items.push_str("</li>\n");
+        }
+
+        let title = self.config.title.as_deref().unwrap_or("Table of Contents");
+
+        let nav = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" lang="{}">
+<head>
+  <meta charset="utf-8"/>
+  <title>Table of Contents</title>
+  <link rel="stylesheet" type="text/css" href="style.css"/>
+</head>
+<body>
+  <nav epub:type="toc" id="toc">
+    <h1>{}</h1>
+    <ol>
+{}    </ol>
+  </nav>
+</body>
+</html>"#,
+            self.config.language,
+            xml_escape(title),
+            toc_items
+        );
+
+        self.zip.write_all(nav.as_bytes())
+            .map_err(|e| Error::build(format!("Failed to write nav.xhtml: {}", e)))?;
+
+        self.manifest_items.push(ManifestItem {
+            id: "nav".to_string(),
+            href: "nav.xhtml".to_string(),
+            media_type: "application/xhtml+xml".to_string(),
+            properties: Some("nav"),
+        });
+
+        Ok(())
+    }
+
+    /// Write the NCX table of contents for EPUB 2 reading systems.
+    fn write_ncx(&mut self, config: &Config) -> Result<()> {
+        let options = SimpleFileOptions::default();
+        self.zip.start_file("OEBPS/toc.ncx", options)
+            .map_err(|e| Error::build(format!("Failed to create toc.ncx: {}", e)))?;
+
+        let mut nav_points = String::new();
+        let mut play_order = 0;
+        let mut depth = 0;
+        for chapter in &self.chapters {
+            play_order += 1;
+            nav_points.push_str(&format!(
+                "    <navPoint id=\"nav{}\" playOrder=\"{}\">\n      <navLabel><text>{}</text></navLabel>\n      <content src=\"{}\"/>\n",
+                play_order,
+                play_order,
+                xml_escape(&chapter.title),
+                chapter.href
+            ));
+            let entries = &chapter.toc.entries;
+            depth = depth.max(1 + toc_depth(entries));
+            ncx_points(&mut nav_points, &chapter.href, entries, 3, &mut play_order);
+            nav_points.push_str("    </navPoint>\n");
+        }
+
+        let title = self.config.title.as_ref().unwrap_or(&config.collection.title);
+
+        let ncx = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+  <head>
+    <meta name="dtb:uid" content="{}"/>
+    <meta name="dtb:depth" content="{}"/>
+    <meta name="dtb:totalPageCount" content="0"/>
+    <meta name="dtb:maxPageNumber" content="0"/>
+  </head>
+  <docTitle><text>{}</text></docTitle>
+  <navMap>
+{}  </navMap>
+</ncx>"#,
+            self.identifier,
+            depth.max(1),
+            xml_escape(title),
+            nav_points
+        );
+
+        self.zip.write_all(ncx.as_bytes())
+            .map_err(|e| Error::build(format!("Failed to write toc.ncx: {}", e)))?;
+
+        self.manifest_items.push(ManifestItem {
+            id: "ncx".to_string(),
+            href: "toc.ncx".to_string(),
+            media_type: "application/x-dtbncx+xml".to_string(),
+            properties: None,
+        });
+
+        Ok(())
+    }
+
+    /// Write the content.opf package file.
+    fn write_opf(&mut self, config: &Config) -> Result<()> {
+        let options = SimpleFileOptions::default();
+        self.zip.start_file("OEBPS/content.opf", options)
+            .map_err(|e| Error::build(format!("Failed to create content.opf: {}", e)))?;
+
+        let title = self.config.title.as_ref()
+            .unwrap_or(&config.collection.title);
+        let author = self.config.author.as_ref()
+            .unwrap_or(&config.collection.author);
+        let description = self.config.description.as_ref()
+            .unwrap_or(&config.collection.description);
+
+        let now = Zoned::now();
+        let modified = now.strftime("%Y-%m-%dT%H:%M:%SZ").to_string();
+
+        let mut manifest = String::new();
+        for item in &self.manifest_items {
+            let properties = item.properties
+                .map(|p| format!(" properties=\"{}\"", p))
+                .unwrap_or_default();
+            manifest.push_str(&format!(
+                "    <item id=\"{}\" href=\"{}\" media-type=\"{}\"{}/>\n",
+                item.id, item.href, item.media_type, properties
+            ));
+        }
+
+        // EPUB 2 readers find the cover through a meta element.
+        let cover = if self.manifest_items.iter().any(|item| item.id == "cover-image") {
+            "\n    <meta name=\"cover\" content=\"cover-image\"/>"
+        } else {
+            ""
+        };
+
+        let mut spine = String::new();
+        for item in &self.spine_items {
+            spine.push_str(&format!("    <itemref idref=\"{}\"/>\n", item.id));
+        }
+
+        let opf = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="BookId">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="BookId">{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:creator>{}</dc:creator>
+    <dc:language>{}</dc:language>
+    <dc:description>{}</dc:description>
+    <meta property="dcterms:modified">{}</meta>{}
+  </metadata>
+  <manifest>
+{}  </manifest>
+  <spine toc="ncx">
+{}  </spine>
+</package>"#,
+            self.identifier,
+            xml_escape(title),
+            xml_escape(author),
+            self.config.language,
+            xml_escape(description),
+            modified,
+            cover,
+            manifest,
+            spine
+        );
+
+        self.zip.write_all(opf.as_bytes())
+            .map_err(|e| Error::build(format!("Failed to write content.opf: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Finalize the EPUB file.
+    pub fn finish(mut self, documents: &[&Document], config: &Config) -> Result<W> {
+        self.write_mimetype()?;
+        self.write_container()?;
+        self.write_stylesheet()?;
+        self.write_cover_image()?;
+
+        for (i, doc) in documents.iter().enumerate() {
+            self.add_document(doc, i)?;
+        }
+
+        self.write_nav()?;
+        self.write_ncx(config)?;
+        self.write_opf(config)?;
+
+        self.zip.finish()
+            .map_err(|e| Error::build(format!("Failed to finalize EPUB: {}", e)))
+    }
+}
+
+/// Generate an EPUB file from a collection.
+pub fn generate_epub(
+    collection: &Collection,
+    config: &Config,
+    output_path: &Path,
+    epub_config: &EpubConfig,
+) -> Result<()> {
+    let builder = EpubBuilder::new(Cursor::new(Vec::new()), epub_config.clone())?
+        .with_root(&collection.root);
+
+    let documents: Vec<&Document> = if epub_config.include_drafts {
+        collection.all_sorted()
+    } else {
+        collection.published()
+    };
+
+    let epub = builder.finish(&documents, config)?.into_inner();
+
+    let issues = validate_epub(&epub)?;
+    if !issues.is_empty() {
+        let list: Vec<String> = issues.iter().map(|issue| format!("  {}", issue)).collect();
+        return Err(Error::build(format!("Generated EPUB is invalid:\n{}", list.join("\n"))));
+    }
+
+    std::fs::write(output_path, epub)
+        .map_err(|e| Error::build(format!("Failed to write EPUB file: {}", e)))?;
+
+    Ok(())
+}
+
+/// Append nested `<ol>` items linking to `entries` in `href`.
+fn nav_list(out: &mut String, href: &str, entries: &[TocEntry], indent: usize) {
+    if entries.is_empty() {
+        return;
+    }
+    let pad = "  ".repeat(indent);
+    out.push_str(&format!("\n{}<ol>\n", pad));
+    for entry in entries {
+        out.push_str(&format!(
+            "{}  <li><a href=\"{}#{}\">{}</a>",
+            pad,
+            href,
+            xml_escape(&entry.id),
+            xml_escape(&entry.text)
+        ));
+        nav_list(out, href, &entry.children, indent + 2);
+        out.push_str("</li>\n");
+    }
+    out.push_str(&format!("{}</ol>\n{}", pad, "  ".repeat(indent - 1)));
+}
+
+/// Append nested NCX nav points for `entries` in `href`.
+fn ncx_points(out: &mut String, href: &str, entries: &[TocEntry], indent: usize, play_order: &mut usize) {
+    let pad = "  ".repeat(indent);
+    for entry in entries {
+        *play_order += 1;
+        out.push_str(&format!(
+            "{}<navPoint id=\"nav{}\" playOrder=\"{}\">\n{}  <navLabel><text>{}</text></navLabel>\n{}  <content src=\"{}#{}\"/>\n",
+            pad,
+            play_order,
+            play_order,
+            pad,
+            xml_escape(&entry.text),
+            pad,
+            href,
+            xml_escape(&entry.id)
+        ));
+        ncx_points(out, href, &entry.children, indent + 1, play_order);
+        out.push_str(&format!("{}</navPoint>\n", pad));
+    }
+}
+
+/// Depth of the deepest entry in `entries`.
+fn toc_depth(entries: &[TocEntry]) -> usize {
+    entries.iter().map(|e| 1 + toc_depth(&e.children)).max().unwrap_or(0)
+}
+
+/// Make rendered HTML well-formed XHTML.
+///
+/// Comrak writes its own void elements self-closed, but raw HTML in
+/// documents and its footnote markers use HTML-only syntax.
+fn to_xhtml(html: &str) -> String {
+    static VOID_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"<(area|br|col|embed|hr|img|input|source|track|wbr)\b([^>]*?)\s*/?>").expect("invalid regex")
+    });
+    static BOOLEAN_ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"\s(data-footnotes|data-footnote-ref|data-footnote-backref)([\s>])").expect("invalid regex")
+    });
+
+    let html = VOID_RE.replace_all(html, "<$1$2 />");
+    BOOLEAN_ATTR_RE.replace_all(&html, " $1=\"\"$2").into_owned()
+}
+
+/// Replace links to pages of the site with their text.
+///
+/// Site pages aren't part of the book, so only links to other sites and
+/// to ids in the same chapter are kept.
+fn unlink_local(html: &str) -> String {
+    static LINK_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"(?s)<a\b[^>]*?\shref="([^"]*)"[^>]*>(.*?)</a>"#).expect("invalid regex")
+    });
+
+    LINK_RE
+        .replace_all(html, |caps: &Captures| {
+            let href = &caps[1];
+            if is_external(href) || href.starts_with('#') {
+                caps[0].to_string()
+            } else {
+                warn!("Dropping link to {}: not part of the book", href);
+                caps[2].to_string()
+            }
+        })
+        .into_owned()
+}
+
+/// Whether `href` has a URL scheme, like `https:` or `mailto:`.
+fn is_external(href: &str) -> bool {
+    href.split_once(':').is_some_and(|(scheme, _)| {
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// Move heading ids onto the headings and collect them.
+///
+/// Comrak puts each heading's id on an empty anchor inside it; the anchor
+/// is dropped so navigation links land on the heading itself. Headings
+/// without an id get one from their text.
+fn anchor_headings(html: &str) -> (String, Vec<Heading>) {
+    static HEADING_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"<h([1-6])([^>]*)>(.*?)</h[1-6]>"#).expect("invalid regex")
+    });
+    static ANCHOR_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r##"^<a href="#[^"]*" aria-hidden="true" class="anchor" id="([^"]*)"></a>"##).expect("invalid regex")
+    });
+    static ID_ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r#"\sid="([^"]*)""#).expect("invalid regex")
+    });
+    static TAG_RE: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"<[^>]+>").expect("invalid regex")
+    });
+
+    let mut headings = Vec::new();
+    let mut seen = HashSet::new();
+    let html = HEADING_RE.replace_all(html, |caps: &Captures| {
+        let level: u8 = caps[1].parse().unwrap_or(1);
+        let mut attrs = caps[2].to_string();
+        let mut content = &caps[3];
+
+        let existing = ID_ATTR_RE.captures(&attrs).map(|c| c[1].to_string());
+        let anchor = ANCHOR_RE.captures(content).map(|c| (c[0].len(), c[1].to_string()));
+        if let Some((len, _)) = &anchor {
+            content = &content[*len..];
+        }
+        let text = TAG_RE.replace_all(content, "")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&");
+
+        let id = match existing.clone().or(anchor.map(|(_, id)| id)) {
+            Some(id) => id,
+            None => {
+                let base = generate_id(&text);
+                let mut id = base.clone();
+                let mut n = 1;
+                while seen.contains(&id) {
+                    id = format!("{}-{}", base, n);
+                    n += 1;
+                }
+                id
+            }
+        };
+        seen.insert(id.clone());
+        if existing.is_none() {
+            attrs = format!(" id=\"{}\"{}", id, attrs);
+        }
+
+        headings.push(Heading::new(level, text, id));
+        format!("<h{}{}>{}</h{}>", level, attrs, content, level)
+    });
+
+    (html.into_owned(), headings)
+}
+
+/// Escape XML special characters.
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Generate a simple UUID v4.
+fn generate_uuid() -> String {
+    use rustmax::rand::Rng;
+    let mut rng = rustmax::rand::rng();
+
+    let mut bytes = [0u8; 16];
+    rng.fill(&mut bytes);
+
+    // Set version (4) and variant (RFC 4122).
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+
+    format!(
+        "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        bytes[0], bytes[1], bytes[2], bytes[3],
+        bytes[4], bytes[5],
+        bytes[6], bytes[7],
+        bytes[8], bytes[9],
+        bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustmax::tempfile::tempdir;
+    use std::fs::File;
+
+    #[test]
+    fn test_epub_config_default() {
+        let config = EpubConfig::default();
+        assert_eq!(config.language, "en");
+        assert!(!config.include_drafts);
+        assert!(config.title.is_none());
+    }
+
+    #[test]
+    fn test_xml_escape() {
+        assert_eq!(xml_escape("Hello & World"), "Hello &amp; World");
+        assert_eq!(xml_escape("<script>"), "&lt;script&gt;");
+        assert_eq!(xml_escape("\"quoted\""), "&quot;quoted&quot;");
+    }
+
+    #[test]
+    fn test_generate_uuid() {
+        let uuid = generate_uuid();
+        assert_eq!(uuid.len(), 36);
+        assert!(uuid.contains('-'));
+
+        // Verify format: xxxxxxxx-xxxx-4xxx-[89ab]xxx-xxxxxxxxxxxx
+        let parts: Vec<&str> = uuid.split('-').collect();
+        assert_eq!(parts.len(), 5);
+        assert_eq!(parts[0].len(), 8);
+        assert_eq!(parts[1].len(), 4);
+        assert_eq!(parts[2].len(), 4);
+        assert_eq!(parts[3].len(), 4);
+        assert_eq!(parts[4].len(), 12);
+
+        // Check version 4 marker.
+        assert!(parts[2].starts_with('4'));
+    }
+
+    #[test]
+    fn test_epub_builder_creates_valid_structure() {
+        let buffer = Cursor::new(Vec::new());
+        let config = EpubConfig::default();
+
+        let mut builder = EpubBuilder::new(buffer, config).unwrap();
+        builder.write_mimetype().unwrap();
+        builder.write_container().unwrap();
+        builder.write_stylesheet().unwrap();
+
+        let result = builder.zip.finish().unwrap();
+        let data = result.into_inner();
+
+        // Verify ZIP structure.
+        let reader = Cursor::new(data);
+        let mut archive = rustmax::zip::ZipArchive::new(reader).unwrap();
+
+        assert!(archive.by_name("mimetype").is_ok());
+        assert!(archive.by_name("META-INF/container.xml").is_ok());
+        assert!(archive.by_name("OEBPS/style.css").is_ok());
+    }
+
+    #[test]
+    fn test_generate_epub_creates_file() {
+        use crate::collection::{Collection, Config};
+
+        let dir = tempdir().unwrap();
+        let content_dir = dir.path().join("content");
+        std::fs::create_dir_all(&content_dir).unwrap();
+
+        // Create a test document.
+        let doc_content = r#"---
+title = "Test Document"
+date = "2024-01-01"
+---
+
+# Hello
+
+This is a test document.
+"#;
+        std::fs::write(content_dir.join("test.md"), doc_content).unwrap();
+
+        // Create config.
+        let config_content = r#"
+[collection]
+title = "Test Collection"
+base_url = "http://example.com"
+"#;
+        std::fs::write(dir.path().join("anthology.toml"), config_content).unwrap();
+
+        let config = Config::load(dir.path()).unwrap();
+        let collection = Collection::load(dir.path(), &config).unwrap();
+
+        let epub_path = dir.path().join("output.epub");
+        let epub_config = EpubConfig::default();
+
+        generate_epub(&collection, &config, &epub_path, &epub_config).unwrap();
+
+        assert!(epub_path.exists());
+
+        // Verify it's a valid ZIP.
+        let file = File::open(&epub_path).unwrap();
+        let mut archive = rustmax::zip::ZipArchive::new(file).unwrap();
+
+        assert!(archive.by_name("mimetype").is_ok());
+        assert!(archive.by_name("OEBPS/content.opf").is_ok());
+        assert!(archive.by_name("OEBPS/nav.xhtml").is_ok());
+        assert!(archive.by_name("OEBPS/toc.ncx").is_ok());
+        assert!(archive.by_name("OEBPS/chapter0.xhtml").is_ok());
+
+        let data = std::fs::read(&epub_path).unwrap();
+        assert_eq!(validate_epub(&data).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_to_xhtml() {
+        assert_eq!(to_xhtml("<p>a<br>b<img src=\"x.png\"></p>"), "<p>a<br />b<img src=\"x.png\" /></p>");
+        assert_eq!(to_xhtml("<hr />"), "<hr />");
+        assert_eq!(
+            to_xhtml("<section class=\"footnotes\" data-footnotes>\n<a data-footnote-backref data-footnote-backref-idx=\"1\">"),
+            "<section class=\"footnotes\" data-footnotes=\"\">\n<a data-footnote-backref=\"\" data-footnote-backref-idx=\"1\">"
+        );
+    }
+
+    #[test]
+    fn test_anchor_headings() {
+        let html = render_markdown("## Setup & Go\n\n### Step\n\n<h4>Raw</h4>\n\n<h4>Raw</h4>\n");
+        let (html, headings) = anchor_headings(&html);
+
+        assert!(html.contains("<h2 id=\"setup--go\">Setup &amp; Go</h2>"));
+        assert!(!html.contains("class=\"anchor\""));
+        let ids: Vec<&str> = headings.iter().map(|h| h.id.as_str()).collect();
+        assert_eq!(ids, ["setup--go", "step", "raw", "raw-1"]);
+        assert_eq!(headings[0].text, "Setup & Go");
+        assert_eq!(headings[1].level, 3);
+    }
+
+    #[test]
+    fn test_generate_epub_images_and_nested_nav() {
+        use crate::collection::{Collection, Config};
+        use rustmax::image::{ImageBuffer, Rgb, RgbImage};
+
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("content/guide")).unwrap();
+        std::fs::create_dir_all(root.join("static")).unwrap();
+        std::fs::write(
+            root.join("anthology.toml"),
+            "[collection]\ntitle = \"Test Collection\"\nbase_url = \"http://example.com\"\n",
+        )
+        .unwrap();
+
+        let img: RgbImage = ImageBuffer::from_fn(4, 4, |_, _| Rgb([200, 100, 50]));
+        img.save(root.join("static/logo.png")).unwrap();
+        img.save(root.join("content/guide/diagram.png")).unwrap();
+        img.save(root.join("cover.png")).unwrap();
+
+        std::fs::write(
+            root.join("content/guide/index.md"),
+            "---\ntitle = \"Guide\"\ndate = \"2024-01-01\"\n---\n\n## Install\n\n![Logo](/logo.png)\n\n### From source\n\n![Diagram](diagram.png) ![Again](/logo.png)\n\n## Use\n\nText ![Missing](nope.png) [About](/about/) [Web](https://example.com/)[^1].\n\n[^1]: Note.\n",
+        )
+        .unwrap();
+
+        let config = Config::load(root).unwrap();
+        let collection = Collection::load(root, &config).unwrap();
+        let epub_path = root.join("output.epub");
+        let epub_config = EpubConfig {
+            cover_image: Some("cover.png".to_string()),
+            ..Default::default()
+        };
+        generate_epub(&collection, &config, &epub_path, &epub_config).unwrap();
+
+        let file = File::open(&epub_path).unwrap();
+        let mut archive = rustmax::zip::ZipArchive::new(file).unwrap();
+        let mut read = |name: &str| {
+            let mut text = String::new();
+            std::io::Read::read_to_string(&mut archive.by_name(name).unwrap(), &mut text).unwrap();
+            text
+        };
+
+        let chapter = read("OEBPS/chapter0.xhtml");
+        assert!(chapter.contains("<img src=\"images/1-logo.png\" alt=\"Logo\" />"));
+        assert!(chapter.contains("<img src=\"images/2-diagram.png\" alt=\"Diagram\" />"));
+        assert!(chapter.contains("<img src=\"images/1-logo.png\" alt=\"Again\" />"));
+        // Images and pages that aren't in the book are left as text.
+        assert!(chapter.contains("Text Missing About <a href=\"https://example.com/\">Web</a>"));
+        assert!(!chapter.contains("nope.png") && !chapter.contains("/about/"));
+        assert!(chapter.contains("href=\"#fn-1\""));
+
+        let opf = read("OEBPS/content.opf");
+        assert!(opf.contains("<item id=\"cover-image\" href=\"images/0-cover.png\" media-type=\"image/png\" properties=\"cover-image\"/>"));
+        assert!(opf.contains("<item id=\"image1\" href=\"images/1-logo.png\" media-type=\"image/png\"/>"));
+        assert!(opf.contains("<meta name=\"cover\" content=\"cover-image\"/>"));
+        assert!(opf.contains("<spine toc=\"ncx\">"));
+
+        let nav = read("OEBPS/nav.xhtml");
+        assert!(nav.contains("<li><a href=\"chapter0.xhtml#install\">Install</a>\n"));
+        assert!(nav.contains("<li><a href=\"chapter0.xhtml#from-source\">From source</a></li>"));
+
+        let ncx = read("OEBPS/toc.ncx");
+        assert!(ncx.contains("<meta name=\"dtb:depth\" content=\"3\"/>"));
+        assert!(ncx.contains("<content src=\"chapter0.xhtml#use\"/>"));
+        assert_eq!(ncx.matches("<navPoint ").count(), 4);
+    }
+
+    #[test]
+    fn test_validate_epub_reports_problems() {
+        let mut zip = ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        zip.start_file("mimetype", stored).unwrap();
+        zip.write_all(b"application/epub+zip").unwrap();
+        zip.start_file("META-INF/container.xml", stored).unwrap();
+        zip.write_all(br#"<container><rootfiles><rootfile full-path="content.opf"/></rootfiles></container>"#).unwrap();
+        zip.start_file("content.opf", stored).unwrap();
+        zip.write_all(
+            br#"<package><manifest>
+<item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+<item id="a" href="a.xhtml" media-type="application/xhtml+xml"/>
+<item id="gone" href="gone.xhtml" media-type="application/xhtml+xml"/>
+</manifest><spine><itemref idref="a"/><itemref idref="missing"/></spine></package>"#,
+        )
+        .unwrap();
+        zip.start_file("nav.xhtml", stored).unwrap();
+        zip.write_all(br##"<html><body><a href="a.xhtml#nowhere">A</a><a href="b.xhtml">B</a><a href="#top">Top</a><a href="https://example.com/">Web</a><img src="nope.png"/></body></html>"##).unwrap();
+        zip.start_file("a.xhtml", stored).unwrap();
+        zip.write_all(b"<html><body>\n<p>Unclosed</body></html>").unwrap();
+        zip.start_file("extra.css", stored).unwrap();
+        let data = zip.finish().unwrap().into_inner();
+
+        let issues: Vec<String> = validate_epub(&data).unwrap().iter().map(|i| i.to_string()).collect();
+        assert_eq!(
+            issues,
+            [
+                "content.opf: manifest item gone refers to missing file gone.xhtml",
+                "extra.css: file is not listed in the manifest",
+                "content.opf: spine refers to unknown item missing",
+                "a.xhtml:2: expected </p>, found </body>",
+                "nav.xhtml: link to b.xhtml which is not a content document",
+                "nav.xhtml: link to #top which has no id top",
+                "nav.xhtml: reference to nope.png which is not in the manifest",
+            ]
+        );
+    }
+}
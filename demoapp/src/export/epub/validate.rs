@@ -0,0 +1,337 @@
+//! Structural validation of generated EPUB files.
+//!
+//! Checks the parts of the OCF and package rules that reading systems
+//! reject books for: the `mimetype` entry, the container pointing at the
+//! package document, manifest and spine consistency, well-formed XHTML,
+//! navigation links that lead somewhere, and files referred to by content
+//! documents being in the book.
+
+use rustmax::zip::{CompressionMethod, ZipArchive};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{Cursor, Read};
+
+use super::is_external;
+use crate::xml::{self, Element};
+use crate::{Error, Result};
+
+const XHTML: &str = "application/xhtml+xml";
+const NCX: &str = "application/x-dtbncx+xml";
+
+/// A problem found in an EPUB file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EpubIssue {
+    /// Path of the offending file inside the archive.
+    pub path: String,
+    /// Line in the file, for XML errors.
+    pub line: Option<usize>,
+    /// What is wrong.
+    pub message: String,
+}
+
+impl EpubIssue {
+    fn new(path: &str, message: impl Into<String>) -> Self {
+        Self {
+            path: path.to_string(),
+            line: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for EpubIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some(line) = self.line {
+            write!(f, ":{}", line)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// An item of the package manifest.
+struct Item {
+    id: String,
+    path: String,
+    media_type: String,
+    properties: String,
+}
+
+/// Validate an EPUB file, returning every problem found.
+///
+/// Fails only if `data` is not a readable ZIP archive.
+pub fn validate_epub(data: &[u8]) -> Result<Vec<EpubIssue>> {
+    let mut archive = ZipArchive::new(Cursor::new(data))
+        .map_err(|e| Error::build(format!("Failed to read EPUB: {}", e)))?;
+    let mut issues = Vec::new();
+
+    match archive.by_index(0) {
+        Ok(mut first) => {
+            if first.name() != "mimetype" {
+                issues.push(EpubIssue::new("mimetype", "must be the first file in the archive"));
+            } else {
+                if first.compression() != CompressionMethod::Stored {
+                    issues.push(EpubIssue::new("mimetype", "must be stored uncompressed"));
+                }
+                let mut content = Vec::new();
+                first.read_to_end(&mut content)?;
+                if content != b"application/epub+zip" {
+                    issues.push(EpubIssue::new("mimetype", "must contain exactly application/epub+zip"));
+                }
+            }
+        }
+        Err(_) => {
+            issues.push(EpubIssue::new("mimetype", "archive is empty"));
+            return Ok(issues);
+        }
+    }
+
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| Error::build(format!("Failed to read EPUB: {}", e)))?;
+        if file.is_dir() {
+            continue;
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        files.insert(file.name().to_string(), content);
+    }
+
+    // The container names the package document.
+    let container_path = "META-INF/container.xml";
+    let Some(container) = parse_file(&files, container_path, &mut issues) else {
+        return Ok(issues);
+    };
+    let Some(opf_path) = container
+        .descendants()
+        .into_iter()
+        .find(|e| e.name == "rootfile")
+        .and_then(|e| e.attr("full-path"))
+        .map(str::to_string)
+    else {
+        issues.push(EpubIssue::new(container_path, "no rootfile with a full-path"));
+        return Ok(issues);
+    };
+    let Some(package) = parse_file(&files, &opf_path, &mut issues) else {
+        return Ok(issues);
+    };
+
+    let items = check_manifest(&package, &opf_path, &files, &mut issues);
+    check_spine(&package, &opf_path, &items, &mut issues);
+
+    // Every content document must parse, navigation must resolve, and
+    // everything else a document refers to must be in the manifest.
+    // Documents that failed to parse are kept as `None` so links into them
+    // aren't reported twice.
+    let mut documents = HashMap::new();
+    for item in &items {
+        if (item.media_type == XHTML || item.media_type == NCX) && files.contains_key(&item.path) {
+            documents.insert(item.path.as_str(), parse_file(&files, &item.path, &mut issues));
+        }
+    }
+    for item in &items {
+        let navigation = if item.properties.split_whitespace().any(|p| p == "nav") {
+            Some(("a", "href"))
+        } else if item.media_type == NCX {
+            Some(("content", "src"))
+        } else {
+            None
+        };
+        let Some(Some(root)) = documents.get(item.path.as_str()) else {
+            continue;
+        };
+        for element in root.descendants() {
+            for attr in ["src", "href"] {
+                let Some(href) = element.attr(attr) else {
+                    continue;
+                };
+                let message = if navigation == Some((element.name.as_str(), attr)) {
+                    check_target(&item.path, href, &documents)
+                } else {
+                    check_reference(&item.path, href, &items)
+                };
+                if let Some(message) = message {
+                    issues.push(EpubIssue::new(&item.path, message));
+                }
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Read the manifest, checking ids are unique and files are listed.
+fn check_manifest(
+    package: &Element,
+    opf_path: &str,
+    files: &BTreeMap<String, Vec<u8>>,
+    issues: &mut Vec<EpubIssue>,
+) -> Vec<Item> {
+    let mut items = Vec::new();
+    let mut ids = HashSet::new();
+
+    for element in package.descendants().into_iter().filter(|e| e.name == "item") {
+        let (Some(id), Some(href), Some(media_type)) =
+            (element.attr("id"), element.attr("href"), element.attr("media-type"))
+        else {
+            issues.push(EpubIssue::new(opf_path, "manifest item needs an id, href and media-type"));
+            continue;
+        };
+        if !ids.insert(id) {
+            issues.push(EpubIssue::new(opf_path, format!("duplicate manifest id {}", id)));
+        }
+        let path = resolve_href(opf_path, href);
+        if !files.contains_key(&path) {
+            issues.push(EpubIssue::new(opf_path, format!("manifest item {} refers to missing file {}", id, href)));
+        }
+        items.push(Item {
+            id: id.to_string(),
+            path,
+            media_type: media_type.to_string(),
+            properties: element.attr("properties").unwrap_or("").to_string(),
+        });
+    }
+
+    for name in files.keys() {
+        let listed = items.iter().any(|item| &item.path == name);
+        if !listed && name != "mimetype" && !name.starts_with("META-INF/") && name != opf_path {
+            issues.push(EpubIssue::new(name, "file is not listed in the manifest"));
+        }
+    }
+
+    let navs = items
+        .iter()
+        .filter(|item| item.properties.split_whitespace().any(|p| p == "nav"))
+        .count();
+    if navs != 1 {
+        issues.push(EpubIssue::new(opf_path, format!("expected one nav document, found {}", navs)));
+    }
+
+    items
+}
+
+/// Check the spine only refers to manifest items that can be read.
+fn check_spine(package: &Element, opf_path: &str, items: &[Item], issues: &mut Vec<EpubIssue>) {
+    let find = |id: &str| items.iter().find(|item| item.id == id);
+
+    let elements = package.descendants();
+    match elements.iter().find(|e| e.name == "spine") {
+        Some(spine) => {
+            if let Some(toc) = spine.attr("toc")
+                && find(toc).is_none_or(|item| item.media_type != NCX)
+            {
+                issues.push(EpubIssue::new(opf_path, format!("spine toc {} is not an NCX manifest item", toc)));
+            }
+        }
+        None => issues.push(EpubIssue::new(opf_path, "package has no spine")),
+    }
+
+    let itemrefs: Vec<&&Element> = elements.iter().filter(|e| e.name == "itemref").collect();
+    if itemrefs.is_empty() {
+        issues.push(EpubIssue::new(opf_path, "spine is empty"));
+    }
+    for itemref in itemrefs {
+        let Some(idref) = itemref.attr("idref") else {
+            issues.push(EpubIssue::new(opf_path, "spine itemref needs an idref"));
+            continue;
+        };
+        match find(idref) {
+            Some(item) if item.media_type != XHTML => issues.push(EpubIssue::new(
+                opf_path,
+                format!("spine item {} is {}, not XHTML", idref, item.media_type),
+            )),
+            Some(_) => {}
+            None => issues.push(EpubIssue::new(opf_path, format!("spine refers to unknown item {}", idref))),
+        }
+    }
+}
+
+/// Check a `src` or `href` in a content document names a manifest item.
+fn check_reference(from: &str, href: &str, items: &[Item]) -> Option<String> {
+    let file = href.split(['#', '?']).next().unwrap_or(href);
+    if is_external(href) || file.is_empty() {
+        return None;
+    }
+    let path = resolve_href(from, file);
+    if items.iter().any(|item| item.path == path) {
+        None
+    } else {
+        Some(format!("reference to {} which is not in the manifest", href))
+    }
+}
+
+/// Check a navigation link names a content document and an id in it.
+fn check_target(from: &str, href: &str, documents: &HashMap<&str, Option<Element>>) -> Option<String> {
+    if is_external(href) {
+        return None;
+    }
+    let (file, fragment) = match href.split_once('#') {
+        Some((file, fragment)) => (file, Some(fragment)),
+        None => (href, None),
+    };
+    let path = if file.is_empty() { from.to_string() } else { resolve_href(from, file) };
+    let elements = match documents.get(path.as_str()) {
+        Some(Some(elements)) => elements,
+        Some(None) => return None,
+        None => return Some(format!("link to {} which is not a content document", href)),
+    };
+    match fragment {
+        Some(id) if !elements.descendants().iter().any(|e| e.attr("id") == Some(id)) => {
+            Some(format!("link to {} which has no id {}", href, id))
+        }
+        _ => None,
+    }
+}
+
+/// Resolve `href` relative to the file at `base`, both archive paths.
+fn resolve_href(base: &str, href: &str) -> String {
+    let mut segments: Vec<&str> = base.split('/').collect();
+    segments.pop();
+    for segment in href.split('/') {
+        match segment {
+            "." | "" => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Parse a file from the archive, recording why if it can't be.
+fn parse_file(files: &BTreeMap<String, Vec<u8>>, path: &str, issues: &mut Vec<EpubIssue>) -> Option<Element> {
+    let Some(content) = files.get(path) else {
+        issues.push(EpubIssue::new(path, "file is missing"));
+        return None;
+    };
+    let Ok(text) = std::str::from_utf8(content) else {
+        issues.push(EpubIssue::new(path, "file is not valid UTF-8"));
+        return None;
+    };
+    match xml::parse(text) {
+        Ok(root) => Some(root),
+        Err(error) => {
+            issues.push(EpubIssue {
+                path: path.to_string(),
+                line: Some(error.line),
+                message: error.message,
+            });
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_href() {
+        assert_eq!(resolve_href("OEBPS/content.opf", "chapter0.xhtml"), "OEBPS/chapter0.xhtml");
+        assert_eq!(resolve_href("OEBPS/text/a.xhtml", "../images/b.png"), "OEBPS/images/b.png");
+        assert_eq!(resolve_href("content.opf", "./a.xhtml"), "a.xhtml");
+    }
+}
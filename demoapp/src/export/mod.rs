@@ -1,7 +1,70 @@
 //! Export functionality for collections.
 //!
-//! Supports exporting to EPUB and other formats.
+//! Supports exporting to EPUB, PDF and other formats.
 
 mod epub;
+mod pdf;
 
-pub use epub::{EpubBuilder, EpubConfig, generate_epub};
+pub use epub::{EpubBuilder, EpubConfig, EpubIssue, generate_epub, validate_epub};
+pub use pdf::{PageSize, PdfConfig, generate_pdf, render_pdf};
+
+use rustmax::log::warn;
+use std::path::{Path, PathBuf};
+
+/// Find the local file an image `src` in a document refers to.
+///
+/// Site-absolute paths are looked up under the collection's `static/` and
+/// `content/` directories, relative ones next to the document in `dir`.
+/// Remote images are skipped.
+fn resolve_image(root: &Path, dir: &Path, src: &str) -> Option<PathBuf> {
+    if src.contains("://") || src.starts_with("data:") {
+        warn!("Skipping remote image {}", src);
+        return None;
+    }
+    let src = src.split(['?', '#']).next().unwrap_or(src);
+    let candidates = match src.strip_prefix('/') {
+        Some(path) => vec![
+            root.join("static").join(path),
+            root.join("content").join(path),
+        ],
+        None => vec![dir.join(src), root.join(src)],
+    };
+    let found = candidates.into_iter().find(|path| path.is_file());
+    if found.is_none() {
+        warn!("Image not found: {}", src);
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustmax::tempfile::tempdir;
+    use std::fs;
+
+    #[test]
+    fn test_resolve_image() {
+        let dir = tempdir().unwrap();
+        let root = dir.path().join("site");
+        fs::create_dir_all(root.join("static/img")).unwrap();
+        fs::create_dir_all(root.join("content/posts")).unwrap();
+        fs::write(root.join("static/img/a.png"), b"png").unwrap();
+        fs::write(root.join("content/posts/b.png"), b"png").unwrap();
+        let outside = dir.path().join("secret.png");
+        fs::write(&outside, b"png").unwrap();
+        let doc_dir = root.join("content/posts");
+
+        assert_eq!(
+            resolve_image(&root, &doc_dir, "/img/a.png?v=1"),
+            Some(root.join("static/img/a.png"))
+        );
+        assert_eq!(
+            resolve_image(&root, &doc_dir, "b.png"),
+            Some(doc_dir.join("b.png"))
+        );
+        // Site-absolute paths never fall back to the host filesystem.
+        let host = outside.to_str().unwrap();
+        assert_eq!(resolve_image(&root, &doc_dir, host), None);
+        assert_eq!(resolve_image(&root, &doc_dir, "https://x.test/a.png"), None);
+    }
+}
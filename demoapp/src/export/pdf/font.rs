@@ -0,0 +1,206 @@
+//! The standard fonts used in PDF export.
+//!
+//! Helvetica and Courier are among the base-14 fonts every PDF reader
+//! provides, so nothing is embedded. Text is encoded as WinAnsi and
+//! measured with the widths from the Adobe font metrics.
+
+/// A font face used in the exported PDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Font {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+    MonoBold,
+}
+
+impl Font {
+    /// Every font, in resource order.
+    pub const ALL: [Font; 6] = [
+        Font::Regular,
+        Font::Bold,
+        Font::Italic,
+        Font::BoldItalic,
+        Font::Mono,
+        Font::MonoBold,
+    ];
+
+    /// The font for a combination of styles.
+    pub fn styled(bold: bool, italic: bool, mono: bool) -> Font {
+        match (mono, bold, italic) {
+            (true, true, _) => Font::MonoBold,
+            (true, false, _) => Font::Mono,
+            (false, true, true) => Font::BoldItalic,
+            (false, true, false) => Font::Bold,
+            (false, false, true) => Font::Italic,
+            (false, false, false) => Font::Regular,
+        }
+    }
+
+    /// Name of the font in page resources.
+    pub fn resource_name(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Italic => "F3",
+            Font::BoldItalic => "F4",
+            Font::Mono => "F5",
+            Font::MonoBold => "F6",
+        }
+    }
+
+    /// PostScript name of the base-14 font.
+    pub fn base_font(self) -> &'static str {
+        match self {
+            Font::Regular => "Helvetica",
+            Font::Bold => "Helvetica-Bold",
+            Font::Italic => "Helvetica-Oblique",
+            Font::BoldItalic => "Helvetica-BoldOblique",
+            Font::Mono => "Courier",
+            Font::MonoBold => "Courier-Bold",
+        }
+    }
+
+    fn is_bold(self) -> bool {
+        matches!(self, Font::Bold | Font::BoldItalic)
+    }
+
+    /// Width of an encoded character in thousandths of the font size.
+    pub fn char_width(self, byte: u8) -> u16 {
+        if matches!(self, Font::Mono | Font::MonoBold) {
+            return 600;
+        }
+
+        let bold = self.is_bold();
+        let table = if bold { &HELVETICA_BOLD } else { &HELVETICA };
+        match byte {
+            32..=126 => table[(byte - 32) as usize],
+            // Accented letters are as wide as the letters they are built on.
+            0xC0..=0xFF => match LATIN1_BASE[(byte - 0xC0) as usize] {
+                b'*' => 584,
+                b'1' => 1000,
+                b'2' => 889,
+                b'3' => 611,
+                b'4' => 722,
+                b'5' => 611,
+                base => table[(base - 32) as usize],
+            },
+            0x82 | 0x91 | 0x92 => if bold { 278 } else { 222 },
+            0x84 | 0x93 | 0x94 => if bold { 500 } else { 333 },
+            0x85 | 0x89 | 0x8C | 0x97 | 0x99 => 1000,
+            0x88 | 0x8B | 0x98 | 0x9B => 333,
+            0x8A | 0x9F => 667,
+            0x8E => 611,
+            0x95 => 350,
+            0x9C => 944,
+            0x9A => if bold { 556 } else { 500 },
+            0x9E => 500,
+            0xA0 => 278,
+            _ => 556,
+        }
+    }
+
+    /// Width of `text` at `size` points.
+    pub fn text_width(self, text: &str, size: f32) -> f32 {
+        let units: u32 = text.chars().map(|c| self.char_width(encode_char(c)) as u32).sum();
+        units as f32 * size / 1000.0
+    }
+}
+
+/// Encode a character as WinAnsi, replacing what it can't represent.
+pub fn encode_char(c: char) -> u8 {
+    match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u8,
+        '\t' => b' ',
+        '€' => 0x80,
+        '‚' => 0x82,
+        'ƒ' => 0x83,
+        '„' => 0x84,
+        '…' => 0x85,
+        '†' => 0x86,
+        '‡' => 0x87,
+        'ˆ' => 0x88,
+        '‰' => 0x89,
+        'Š' => 0x8A,
+        '‹' => 0x8B,
+        'Œ' => 0x8C,
+        'Ž' => 0x8E,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '˜' => 0x98,
+        '™' => 0x99,
+        'š' => 0x9A,
+        '›' => 0x9B,
+        'œ' => 0x9C,
+        'ž' => 0x9E,
+        'Ÿ' => 0x9F,
+        _ => b'?',
+    }
+}
+
+/// Whether a character is shown as itself rather than as `?`.
+pub fn is_encodable(c: char) -> bool {
+    c == '?' || encode_char(c) != b'?'
+}
+
+/// Encode text as WinAnsi.
+pub fn encode(text: &str) -> Vec<u8> {
+    text.chars().map(encode_char).collect()
+}
+
+/// Helvetica widths for characters 32 to 126.
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // space to /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // 0 to ?
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // @ to O
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // P to _
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // ` to o
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // p to ~
+];
+
+/// Helvetica-Bold widths for characters 32 to 126.
+const HELVETICA_BOLD: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278, // space to /
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611, // 0 to ?
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778, // @ to O
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556, // P to _
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611, // ` to o
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584, // p to ~
+];
+
+/// The ASCII letter each of characters 0xC0 to 0xFF is built on.
+///
+/// Digits stand for characters with their own width: `1` for Æ, `2` for
+/// æ, `3` for ß, `4` for Ð and Þ, `5` for ð and þ; `*` for × and ÷.
+const LATIN1_BASE: &[u8; 64] = b"AAAAAA1CEEEEIIII4NOOOOO*OUUUUY43aaaaaa2ceeeeiiii5nooooo*ouuuuy5y";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("Hi!"), b"Hi!");
+        assert_eq!(encode("café"), b"caf\xe9");
+        assert_eq!(encode("“quoted” — yes…"), b"\x93quoted\x94 \x97 yes\x85");
+        assert_eq!(encode("λ"), b"?");
+        assert!(is_encodable('é') && is_encodable('?') && is_encodable('€'));
+        assert!(!is_encodable('λ') && !is_encodable('日'));
+    }
+
+    #[test]
+    fn test_text_width() {
+        // "Hi" is H (722) + i (222) in Helvetica.
+        assert!((Font::Regular.text_width("Hi", 10.0) - 9.44).abs() < 0.001);
+        assert_eq!(Font::Mono.text_width("abc", 10.0), 18.0);
+        assert!(Font::Bold.text_width("Hi", 10.0) > Font::Regular.text_width("Hi", 10.0));
+        assert_eq!(Font::Regular.text_width("é", 10.0), Font::Regular.text_width("e", 10.0));
+    }
+}
@@ -0,0 +1,1172 @@
+//! Page layout for PDF export.
+//!
+//! Walks the markdown AST of each document and places text, code blocks,
+//! tables and images on pages, breaking lines and pages as it goes. The
+//! result is a content stream per page plus the link rectangles and
+//! heading positions the writer turns into annotations and the outline.
+
+use rustmax::comrak::nodes::{AstNode, ListType, NodeValue};
+use rustmax::log::warn;
+use rustmax::regex::Regex;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use crate::build::{Heading, Theme, TokenType, generate_id, tokenize};
+use crate::export::resolve_image;
+use super::font::{Font, encode, is_encodable};
+use super::writer::literal_string;
+
+/// Body text size in points.
+const BODY_SIZE: f32 = 10.5;
+/// Line height as a multiple of the font size.
+const LEADING: f32 = 1.45;
+/// Space after paragraphs and other blocks.
+const BLOCK_SPACE: f32 = 7.0;
+/// Indentation of list items and block quotes.
+const INDENT: f32 = 18.0;
+/// Code block text size.
+const CODE_SIZE: f32 = 8.5;
+/// Padding inside code blocks and table cells.
+const PADDING: f32 = 6.0;
+/// Heading sizes for levels 1 to 6.
+const HEADING_SIZES: [f32; 6] = [20.0, 16.0, 13.5, 12.0, 11.0, 10.5];
+/// Points per image pixel, treating images as 96 DPI.
+const POINTS_PER_PIXEL: f32 = 0.75;
+
+static IMG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<img\b[^>]*>"#).expect("invalid regex")
+});
+static ATTR_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)\b(src|alt)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("invalid regex")
+});
+static SKIPPED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<(script|style|iframe)\b.*?</(?:script|style|iframe)>|<!--.*?-->"#).expect("invalid regex")
+});
+static BREAK_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)<br\s*/?>|</p>|</div>|</h[1-6]>|</li>"#).expect("invalid regex")
+});
+static TAG_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"<[^>]*>"#).expect("invalid regex"));
+
+/// An RGB color with components from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color(pub f32, pub f32, pub f32);
+
+impl Color {
+    pub const TEXT: Color = Color(0.1, 0.1, 0.1);
+    pub const MUTED: Color = Color(0.42, 0.42, 0.42);
+    pub const RULE: Color = Color(0.82, 0.82, 0.82);
+    pub const SHADE: Color = Color(0.95, 0.95, 0.95);
+    pub const LINK: Color = Color(0.0, 0.34, 0.72);
+
+    /// Parse a `#rrggbb` color, as used by highlight themes.
+    pub fn from_hex(hex: &str) -> Option<Color> {
+        let hex = hex.strip_prefix('#')?;
+        let channel = |i: usize| {
+            let value = u8::from_str_radix(hex.get(i..i + 2)?, 16).ok()?;
+            Some(value as f32 / 255.0)
+        };
+        if hex.len() != 6 {
+            return None;
+        }
+        Some(Color(channel(0)?, channel(2)?, channel(4)?))
+    }
+
+    fn components(self) -> String {
+        format!("{:.3} {:.3} {:.3}", self.0, self.1, self.2)
+    }
+}
+
+/// A laid-out page.
+#[derive(Debug, Default)]
+pub struct Page {
+    /// The page's content stream.
+    pub content: String,
+    /// Clickable areas on the page.
+    pub links: Vec<Link>,
+}
+
+/// A clickable area and where it leads.
+#[derive(Debug, Clone)]
+pub struct Link {
+    /// Lower-left and upper-right corners.
+    pub rect: [f32; 4],
+    /// The link's `href`.
+    pub href: String,
+    /// URL of the document the link is in, to resolve relative links.
+    pub from: String,
+}
+
+/// A position that outlines and links can jump to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Destination {
+    /// Index of the page.
+    pub page: usize,
+    /// Distance from the bottom of the page.
+    pub y: f32,
+}
+
+/// A decoded image ready to embed.
+#[derive(Debug)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+    /// 8-bit RGB samples.
+    pub rgb: Vec<u8>,
+    /// 8-bit alpha samples, if the image is not opaque.
+    pub alpha: Option<Vec<u8>>,
+}
+
+/// Text style of an inline run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Style {
+    pub bold: bool,
+    pub italic: bool,
+    pub mono: bool,
+    pub size: f32,
+    pub color: Color,
+    pub link: Option<String>,
+}
+
+impl Style {
+    /// Body text.
+    pub fn body() -> Self {
+        Self {
+            bold: false,
+            italic: false,
+            mono: false,
+            size: BODY_SIZE,
+            color: Color::TEXT,
+            link: None,
+        }
+    }
+
+    fn font(&self) -> Font {
+        Font::styled(self.bold, self.italic, self.mono)
+    }
+}
+
+/// Inline content of a paragraph.
+#[derive(Debug, Clone)]
+enum Inline {
+    Text(String, Style),
+    Break,
+    Image { src: String, alt: String },
+}
+
+/// A run of text in one style.
+#[derive(Debug, Clone)]
+struct Fragment {
+    text: String,
+    style: Style,
+    width: f32,
+}
+
+/// Text between spaces, possibly in several styles.
+#[derive(Debug, Default)]
+struct Word {
+    fragments: Vec<Fragment>,
+    width: f32,
+    /// Width of the space before the word.
+    space: f32,
+}
+
+/// A broken line of text.
+#[derive(Debug, Default)]
+struct Line {
+    /// Fragments and their offsets from the start of the line.
+    fragments: Vec<(f32, Fragment)>,
+    width: f32,
+    /// The largest font size on the line.
+    size: f32,
+}
+
+impl Line {
+    fn height(&self) -> f32 {
+        self.size * LEADING
+    }
+}
+
+/// How to place lines between the margins.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Align {
+    Left,
+    Center,
+}
+
+/// Lays out documents onto pages.
+pub struct Layout {
+    width: f32,
+    height: f32,
+    margin: f32,
+    /// The pages laid out so far.
+    pub pages: Vec<Page>,
+    /// Positions of documents (by URL) and headings (by `url#id`).
+    pub destinations: HashMap<String, Destination>,
+    /// Images, referenced from content streams as `/Im<index>`.
+    pub images: Vec<ImageData>,
+    /// Characters of the current document (or the cover) drawn as `?`,
+    /// because the standard fonts only cover WinAnsi.
+    pub lost_chars: BTreeSet<char>,
+    image_indexes: HashMap<PathBuf, Option<usize>>,
+    /// Top of the free space on the current page.
+    y: f32,
+    indent: f32,
+    /// List marker waiting for the first line of its item.
+    marker: Option<String>,
+    tight: bool,
+    quote_depth: usize,
+    theme: Option<Theme>,
+    root: PathBuf,
+    // The document being laid out.
+    url: String,
+    dir: PathBuf,
+    headings: Vec<Heading>,
+    heading_ids: HashSet<String>,
+    footnotes: usize,
+}
+
+impl Layout {
+    /// Start a layout for pages of the given size, in points.
+    ///
+    /// Code is colored with `theme` when there is one. Root-relative image
+    /// paths are looked up under `root`'s `static/` and `content/`.
+    pub fn new(width: f32, height: f32, margin: f32, theme: Option<Theme>, root: &Path) -> Self {
+        Self {
+            width,
+            height,
+            margin,
+            pages: Vec::new(),
+            destinations: HashMap::new(),
+            images: Vec::new(),
+            lost_chars: BTreeSet::new(),
+            image_indexes: HashMap::new(),
+            y: height - margin,
+            indent: 0.0,
+            marker: None,
+            tight: false,
+            quote_depth: 0,
+            theme,
+            root: root.to_path_buf(),
+            url: String::new(),
+            dir: root.to_path_buf(),
+            headings: Vec::new(),
+            heading_ids: HashSet::new(),
+            footnotes: 0,
+        }
+    }
+
+    /// Width and height of the pages.
+    pub fn page_size(&self) -> (f32, f32) {
+        (self.width, self.height)
+    }
+
+    fn top(&self) -> f32 {
+        self.height - self.margin
+    }
+
+    fn left(&self) -> f32 {
+        self.margin + self.indent
+    }
+
+    fn content_width(&self) -> f32 {
+        self.width - 2.0 * self.margin - self.indent
+    }
+
+    fn at_page_top(&self) -> bool {
+        self.y >= self.top()
+    }
+
+    /// Start a new page.
+    pub fn new_page(&mut self) {
+        self.pages.push(Page::default());
+        self.y = self.top();
+    }
+
+    /// Start a new page unless `height` still fits on this one.
+    fn ensure(&mut self, height: f32) {
+        if self.pages.is_empty() || (self.y - height < self.margin && !self.at_page_top()) {
+            self.new_page();
+        }
+    }
+
+    fn page(&mut self) -> &mut Page {
+        if self.pages.is_empty() {
+            self.new_page();
+        }
+        self.pages.last_mut().unwrap()
+    }
+
+    fn draw_text(&mut self, x: f32, y: f32, style: &Style, text: &str) {
+        self.lost_chars.extend(text.chars().filter(|&c| !is_encodable(c)));
+        let font = style.font();
+        let ops = format!(
+            "BT /{} {:.2} Tf {} rg {:.2} {:.2} Td {} Tj ET",
+            font.resource_name(),
+            style.size,
+            style.color.components(),
+            x,
+            y,
+            literal_string(&encode(text))
+        );
+        writeln!(self.page().content, "{}", ops).unwrap();
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        let ops = format!("{} rg {:.2} {:.2} {:.2} {:.2} re f", color.components(), x, y, width, height);
+        writeln!(self.page().content, "{}", ops).unwrap();
+    }
+
+    fn stroke_line(&mut self, page: usize, from: (f32, f32), to: (f32, f32), width: f32, color: Color) {
+        let ops = format!(
+            "{} RG {:.2} w {:.2} {:.2} m {:.2} {:.2} l S",
+            color.components(),
+            width,
+            from.0,
+            from.1,
+            to.0,
+            to.1
+        );
+        writeln!(self.pages[page].content, "{}", ops).unwrap();
+    }
+
+    fn rule(&mut self, color: Color) {
+        self.ensure(BLOCK_SPACE * 2.0);
+        let y = self.y - BLOCK_SPACE;
+        let page = self.pages.len() - 1;
+        let (left, right) = (self.left(), self.width - self.margin);
+        self.stroke_line(page, (left, y), (right, y), 0.75, color);
+        self.y -= BLOCK_SPACE * 2.0;
+    }
+
+    fn base_style(&self) -> Style {
+        let mut style = Style::body();
+        if self.quote_depth > 0 {
+            style.color = Color::MUTED;
+            style.italic = true;
+        }
+        style
+    }
+
+    /// Lay out the cover page.
+    pub fn cover(&mut self, cover: &Cover) {
+        self.new_page();
+        let area = self.top() - self.margin;
+
+        if let Some(index) = cover.image.as_deref().and_then(|path| self.load_image(path)) {
+            let (width, height) = self.fit_image(index, self.content_width(), area * 0.5);
+            let x = self.left() + (self.content_width() - width) / 2.0;
+            self.draw_image(index, x, self.y - height, width, height);
+            self.y -= height + 36.0;
+        } else {
+            self.y -= area * 0.3;
+        }
+
+        let title = Style { bold: true, size: 30.0, ..Style::body() };
+        self.text_block(vec![Inline::Text(cover.title.clone(), title)], Align::Center);
+        if !cover.description.is_empty() {
+            self.y -= 12.0;
+            let style = Style { italic: true, size: 13.0, color: Color::MUTED, ..Style::body() };
+            self.text_block(vec![Inline::Text(cover.description.clone(), style)], Align::Center);
+        }
+        if !cover.author.is_empty() {
+            self.y -= 28.0;
+            let style = Style { size: 15.0, ..Style::body() };
+            self.text_block(vec![Inline::Text(cover.author.clone(), style)], Align::Center);
+        }
+
+        // Publisher and date sit at the foot of the page.
+        let footer: Vec<&str> = [cover.publisher.as_str(), cover.date.as_str()]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect();
+        if !footer.is_empty() {
+            self.y = self.margin + 2.0 * BODY_SIZE * LEADING;
+            let style = Style { color: Color::MUTED, ..Style::body() };
+            self.text_block(vec![Inline::Text(footer.join(" · "), style)], Align::Center);
+        }
+    }
+
+    /// Start a document on a new page with its title.
+    ///
+    /// `url` names the document's destination and resolves its relative
+    /// links; relative images are looked up in `dir`.
+    pub fn begin_document(&mut self, url: &str, dir: &Path, title: &str, byline: &str) {
+        self.new_page();
+        self.url = url.to_string();
+        self.dir = dir.to_path_buf();
+        self.headings.clear();
+        self.heading_ids.clear();
+        self.footnotes = 0;
+        self.lost_chars.clear();
+        self.destinations.insert(url.to_string(), self.destination());
+
+        let style = Style { bold: true, size: 24.0, ..Style::body() };
+        self.text_block(vec![Inline::Text(title.to_string(), style)], Align::Left);
+        if !byline.is_empty() {
+            let style = Style { size: 9.5, color: Color::MUTED, ..Style::body() };
+            self.text_block(vec![Inline::Text(byline.to_string(), style)], Align::Left);
+        }
+        self.rule(Color::RULE);
+    }
+
+    /// Finish the current document, returning its headings in order.
+    pub fn end_document(&mut self) -> Vec<Heading> {
+        std::mem::take(&mut self.headings)
+    }
+
+    fn destination(&self) -> Destination {
+        Destination { page: self.pages.len() - 1, y: self.y }
+    }
+
+    /// Number every page from `first` on, starting at 1.
+    pub fn number_pages(&mut self, first: usize) {
+        let style = Style { size: 9.0, color: Color::MUTED, ..Style::body() };
+        for index in first..self.pages.len() {
+            let number = (index - first + 1).to_string();
+            let x = (self.width - style.font().text_width(&number, style.size)) / 2.0;
+            let ops = format!(
+                "BT /{} {:.2} Tf {} rg {:.2} {:.2} Td {} Tj ET",
+                style.font().resource_name(),
+                style.size,
+                style.color.components(),
+                x,
+                self.margin / 2.0,
+                literal_string(number.as_bytes())
+            );
+            writeln!(self.pages[index].content, "{}", ops).unwrap();
+        }
+    }
+
+    /// Lay out the children of a block node.
+    pub fn blocks<'a>(&mut self, node: &'a AstNode<'a>) {
+        for child in node.children() {
+            self.block(child);
+        }
+    }
+
+    fn block<'a>(&mut self, node: &'a AstNode<'a>) {
+        let data = node.data.borrow();
+        match &data.value {
+            NodeValue::Paragraph => {
+                let mut inlines = Vec::new();
+                self.inlines(node, &self.base_style(), &mut inlines);
+                self.paragraph(inlines);
+            }
+            NodeValue::Heading(heading) => self.heading(node, heading.level),
+            NodeValue::List(list) => {
+                let ordered = list.list_type == ListType::Ordered;
+                let (start, tight) = (list.start, list.tight);
+                let saved = std::mem::replace(&mut self.tight, tight);
+                self.indent += INDENT;
+                for (i, item) in node.children().enumerate() {
+                    let marker = match &item.data.borrow().value {
+                        NodeValue::TaskItem(Some(_)) => "[x]".to_string(),
+                        NodeValue::TaskItem(None) => "[ ]".to_string(),
+                        _ if ordered => format!("{}.", start + i),
+                        _ => "•".to_string(),
+                    };
+                    self.marker = Some(marker);
+                    self.blocks(item);
+                    self.marker = None;
+                }
+                self.indent -= INDENT;
+                self.tight = saved;
+                if !self.tight {
+                    self.y -= BLOCK_SPACE / 2.0;
+                }
+            }
+            NodeValue::BlockQuote | NodeValue::MultilineBlockQuote(_) | NodeValue::Alert(_) => {
+                self.quote(node);
+            }
+            NodeValue::CodeBlock(code) => self.code_block(&code.literal, &code.info),
+            NodeValue::HtmlBlock(html) => {
+                let inlines = html_inlines(&html.literal, &self.base_style());
+                self.paragraph(inlines);
+            }
+            NodeValue::ThematicBreak => self.rule(Color::RULE),
+            NodeValue::Table(_) => self.table(node),
+            NodeValue::FootnoteDefinition(_) => {
+                if self.footnotes == 0 {
+                    self.rule(Color::RULE);
+                }
+                self.footnotes += 1;
+                self.indent += INDENT;
+                self.marker = Some(format!("[{}]", self.footnotes));
+                self.blocks(node);
+                self.marker = None;
+                self.indent -= INDENT;
+            }
+            NodeValue::FrontMatter(_) => {}
+            _ => self.blocks(node),
+        }
+    }
+
+    fn heading<'a>(&mut self, node: &'a AstNode<'a>, level: u8) {
+        let size = HEADING_SIZES[(level.clamp(1, 6) - 1) as usize];
+        let style = Style { bold: true, size, ..self.base_style() };
+        let mut inlines = Vec::new();
+        self.inlines(node, &style, &mut inlines);
+
+        if !self.at_page_top() {
+            self.y -= size * 0.6;
+        }
+        // Keep the heading with the start of what follows it.
+        self.ensure(size * LEADING + 3.0 * BODY_SIZE * LEADING);
+
+        let text = plain_text(&inlines);
+        let id = generate_id(&text);
+        let mut unique = id.clone();
+        let mut counter = 1;
+        while !self.heading_ids.insert(unique.clone()) {
+            unique = format!("{}-{}", id, counter);
+            counter += 1;
+        }
+        self.destinations.insert(format!("{}#{}", self.url, unique), self.destination());
+        self.headings.push(Heading::new(level, text, unique));
+
+        self.text_block(inlines, Align::Left);
+        self.y -= size * 0.25;
+    }
+
+    fn quote<'a>(&mut self, node: &'a AstNode<'a>) {
+        if self.pages.is_empty() {
+            self.new_page();
+        }
+        let start = self.destination();
+        let x = self.left() + 4.0;
+        self.indent += INDENT;
+        self.quote_depth += 1;
+        self.blocks(node);
+        self.quote_depth -= 1;
+        self.indent -= INDENT;
+
+        // Draw the bar down the left, on every page the quote spans.
+        let end = self.destination();
+        for page in start.page..=end.page {
+            let top = if page == start.page { start.y } else { self.top() };
+            let bottom = if page == end.page { end.y + BLOCK_SPACE } else { self.margin };
+            if top > bottom {
+                self.stroke_line(page, (x, top), (x, bottom), 2.0, Color::RULE);
+            }
+        }
+    }
+
+    /// Lay out a paragraph, placing images between its lines of text.
+    ///
+    /// Images that can't be loaded are replaced by their alt text.
+    fn paragraph(&mut self, inlines: Vec<Inline>) {
+        let mut text = Vec::new();
+        for inline in inlines {
+            match inline {
+                Inline::Image { src, alt } => match self.load_image(&src) {
+                    Some(index) => {
+                        self.text_block(std::mem::take(&mut text), Align::Left);
+                        self.image(index);
+                    }
+                    None => {
+                        let style = Style { italic: true, color: Color::MUTED, ..self.base_style() };
+                        let alt = if alt.is_empty() { src } else { alt };
+                        text.push(Inline::Text(format!("[{}]", alt), style));
+                    }
+                },
+                other => text.push(other),
+            }
+        }
+        self.text_block(text, Align::Left);
+        self.y -= if self.tight { 2.0 } else { BLOCK_SPACE };
+    }
+
+    fn text_block(&mut self, inlines: Vec<Inline>, align: Align) {
+        let words = words(&inlines);
+        if words.iter().all(|w| w.as_ref().is_none_or(|w| w.fragments.is_empty())) {
+            return;
+        }
+        let width = self.content_width();
+        for line in break_lines(words, width) {
+            self.ensure(line.height());
+            let baseline = self.y - line.size * 1.1;
+            let x = match align {
+                Align::Left => self.left(),
+                Align::Center => self.left() + (width - line.width) / 2.0,
+            };
+            self.draw_marker(baseline);
+            self.draw_line(&line, x, baseline);
+            self.y -= line.height();
+        }
+    }
+
+    /// Draw the pending list marker, if any, left of the item at `baseline`.
+    fn draw_marker(&mut self, baseline: f32) {
+        if let Some(marker) = self.marker.take() {
+            let style = Style { color: Color::TEXT, italic: false, ..self.base_style() };
+            let width = style.font().text_width(&marker, style.size);
+            self.draw_text(self.left() - width - 5.0, baseline, &style, &marker);
+        }
+    }
+
+    fn draw_line(&mut self, line: &Line, x: f32, baseline: f32) {
+        for (offset, fragment) in &line.fragments {
+            let x = x + offset;
+            self.draw_text(x, baseline, &fragment.style, &fragment.text);
+            if let Some(href) = &fragment.style.link {
+                let size = fragment.style.size;
+                let link = Link {
+                    rect: [x, baseline - size * 0.25, x + fragment.width, baseline + size * 0.9],
+                    href: href.clone(),
+                    from: self.url.clone(),
+                };
+                self.page().links.push(link);
+            }
+        }
+    }
+
+    fn code_block(&mut self, code: &str, info: &str) {
+        let language = info.split_whitespace().next().unwrap_or("");
+        let (background, foreground) = match &self.theme {
+            Some(theme) => (
+                Color::from_hex(&theme.background).unwrap_or(Color::SHADE),
+                Color::from_hex(&theme.foreground).unwrap_or(Color::TEXT),
+            ),
+            None => (Color::SHADE, Color::TEXT),
+        };
+
+        // Split the tokens into lines of colored runs.
+        let tokens = match &self.theme {
+            Some(_) => tokenize(code, language),
+            None => tokenize(code, ""),
+        };
+        let mut lines: Vec<Vec<(String, Color)>> = vec![Vec::new()];
+        for token in tokens {
+            let color = match (&self.theme, token.token_type) {
+                (Some(theme), token_type) if token_type != TokenType::Plain => {
+                    Color::from_hex(theme.color_for(token_type)).unwrap_or(foreground)
+                }
+                _ => foreground,
+            };
+            for (i, part) in token.text.split('\n').enumerate() {
+                if i > 0 {
+                    lines.push(Vec::new());
+                }
+                let line = lines.last_mut().unwrap();
+                match line.last_mut() {
+                    Some((text, last)) if *last == color => text.push_str(&part.replace('\t', "    ")),
+                    _ if part.is_empty() => {}
+                    _ => line.push((part.replace('\t', "    "), color)),
+                }
+            }
+        }
+        if lines.len() > 1 && lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+
+        // Wrap lines too long for the page.
+        let char_width = Font::Mono.text_width(" ", CODE_SIZE);
+        let columns = (((self.content_width() - 2.0 * PADDING) / char_width) as usize).max(1);
+        let lines: Vec<Vec<(String, Color)>> = lines.into_iter().flat_map(|line| wrap_code_line(line, columns)).collect();
+
+        let line_height = CODE_SIZE * 1.35;
+        let mut next = 0;
+        while next < lines.len() {
+            if self.pages.is_empty() {
+                self.new_page();
+            }
+            let fits = ((self.y - self.margin - 2.0 * PADDING) / line_height).floor().max(0.0) as usize;
+            let remaining = lines.len() - next;
+            // Don't leave a lone line at the foot of a page.
+            if !self.at_page_top() && (fits == 0 || (fits < 2 && remaining > 1)) {
+                self.new_page();
+                continue;
+            }
+            let count = fits.clamp(1, remaining);
+            let height = count as f32 * line_height + 2.0 * PADDING;
+            let (left, width) = (self.left(), self.content_width());
+            self.fill_rect(left, self.y - height, width, height, background);
+
+            let style = Style { mono: true, size: CODE_SIZE, ..Style::body() };
+            for (row, line) in lines[next..next + count].iter().enumerate() {
+                let baseline = self.y - PADDING - row as f32 * line_height - CODE_SIZE;
+                if row == 0 {
+                    self.draw_marker(baseline);
+                }
+                let mut x = left + PADDING;
+                for (text, color) in line {
+                    self.draw_text(x, baseline, &Style { color: *color, ..style.clone() }, text);
+                    x += Font::Mono.text_width(text, CODE_SIZE);
+                }
+            }
+            self.y -= height;
+            next += count;
+            if next < lines.len() {
+                self.new_page();
+            }
+        }
+        self.y -= BLOCK_SPACE;
+    }
+
+    fn table<'a>(&mut self, node: &'a AstNode<'a>) {
+        let mut rows = Vec::new();
+        for row in node.children() {
+            let header = matches!(row.data.borrow().value, NodeValue::TableRow(true));
+            let style = Style { bold: header, size: BODY_SIZE - 1.0, ..self.base_style() };
+            let cells: Vec<Vec<Inline>> = row
+                .children()
+                .map(|cell| {
+                    let mut inlines = Vec::new();
+                    self.inlines(cell, &style, &mut inlines);
+                    inlines
+                })
+                .collect();
+            rows.push((header, cells));
+        }
+        let columns = rows.iter().map(|(_, cells)| cells.len()).max().unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+
+        let column_width = self.content_width() / columns as f32;
+        let cell_padding = PADDING / 1.5;
+        for (header, cells) in rows {
+            let cells: Vec<Vec<Line>> = cells
+                .iter()
+                .map(|cell| break_lines(words(cell), column_width - 2.0 * cell_padding))
+                .collect();
+            let height = cells
+                .iter()
+                .map(|lines| lines.iter().map(Line::height).sum::<f32>())
+                .fold(BODY_SIZE * LEADING, f32::max)
+                + 2.0 * cell_padding;
+            self.ensure(height);
+
+            let (left, width) = (self.left(), self.content_width());
+            if header {
+                self.fill_rect(left, self.y - height, width, height, Color::SHADE);
+            }
+            for (column, lines) in cells.iter().enumerate() {
+                let x = left + column as f32 * column_width + cell_padding;
+                let mut y = self.y - cell_padding;
+                for line in lines {
+                    self.draw_line(line, x, y - line.size * 1.1);
+                    y -= line.height();
+                }
+            }
+            self.y -= height;
+            let page = self.pages.len() - 1;
+            self.stroke_line(page, (left, self.y), (left + width, self.y), 0.5, Color::RULE);
+        }
+        self.y -= BLOCK_SPACE;
+    }
+
+    fn image(&mut self, index: usize) {
+        let max_height = (self.top() - self.margin) * 0.8;
+        let (width, height) = self.fit_image(index, self.content_width(), max_height);
+        self.ensure(height);
+        if self.pages.is_empty() {
+            self.new_page();
+        }
+        self.draw_marker(self.y - BODY_SIZE);
+        let x = self.left() + (self.content_width() - width) / 2.0;
+        self.draw_image(index, x, self.y - height, width, height);
+        self.y -= height + BLOCK_SPACE / 2.0;
+    }
+
+    /// Size of an image scaled down to fit a box, in points.
+    fn fit_image(&self, index: usize, max_width: f32, max_height: f32) -> (f32, f32) {
+        let image = &self.images[index];
+        let width = image.width as f32 * POINTS_PER_PIXEL;
+        let height = image.height as f32 * POINTS_PER_PIXEL;
+        let scale = (max_width / width).min(max_height / height).min(1.0);
+        (width * scale, height * scale)
+    }
+
+    fn draw_image(&mut self, index: usize, x: f32, y: f32, width: f32, height: f32) {
+        let ops = format!("q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q", width, height, x, y, index);
+        writeln!(self.page().content, "{}", ops).unwrap();
+    }
+
+    /// Decode an image once, returning its index.
+    fn load_image(&mut self, src: &str) -> Option<usize> {
+        let path = self.resolve_image(src)?;
+        if let Some(index) = self.image_indexes.get(&path) {
+            return *index;
+        }
+
+        let index = match rustmax::image::open(&path) {
+            Ok(image) => {
+                let rgba = image.to_rgba8();
+                let (width, height) = rgba.dimensions();
+                let mut rgb = Vec::with_capacity((width * height * 3) as usize);
+                let mut alpha = Vec::with_capacity((width * height) as usize);
+                for pixel in rgba.pixels() {
+                    rgb.extend_from_slice(&pixel.0[..3]);
+                    alpha.push(pixel.0[3]);
+                }
+                let alpha = alpha.iter().any(|&a| a < 255).then_some(alpha);
+                self.images.push(ImageData { width, height, rgb, alpha });
+                Some(self.images.len() - 1)
+            }
+            Err(e) => {
+                warn!("Skipping image {}: {}", path.display(), e);
+                None
+            }
+        };
+        self.image_indexes.insert(path, index);
+        index
+    }
+
+    /// Find the file an image `src` refers to.
+    fn resolve_image(&self, src: &str) -> Option<PathBuf> {
+        resolve_image(&self.root, &self.dir, src)
+    }
+
+    /// Collect the inline content of `node` in `style`.
+    fn inlines<'a>(&self, node: &'a AstNode<'a>, style: &Style, out: &mut Vec<Inline>) {
+        for child in node.children() {
+            let data = child.data.borrow();
+            match &data.value {
+                NodeValue::Text(text) => out.push(Inline::Text(text.clone(), style.clone())),
+                NodeValue::Code(code) => {
+                    let style = Style { mono: true, size: style.size * 0.92, ..style.clone() };
+                    out.push(Inline::Text(code.literal.clone(), style));
+                }
+                NodeValue::SoftBreak => out.push(Inline::Text(" ".to_string(), style.clone())),
+                NodeValue::LineBreak => out.push(Inline::Break),
+                NodeValue::Emph => self.inlines(child, &Style { italic: true, ..style.clone() }, out),
+                NodeValue::Strong => self.inlines(child, &Style { bold: true, ..style.clone() }, out),
+                NodeValue::Link(link) => {
+                    let style = Style { color: Color::LINK, link: Some(link.url.clone()), ..style.clone() };
+                    self.inlines(child, &style, out);
+                }
+                NodeValue::Image(link) => {
+                    let mut alt = Vec::new();
+                    self.inlines(child, style, &mut alt);
+                    out.push(Inline::Image { src: link.url.clone(), alt: plain_text(&alt) });
+                }
+                NodeValue::HtmlInline(html) => out.extend(html_inlines(html, style)),
+                NodeValue::FootnoteReference(footnote) => {
+                    out.push(Inline::Text(format!("[{}]", footnote.ix), style.clone()));
+                }
+                _ => self.inlines(child, style, out),
+            }
+        }
+    }
+}
+
+/// Metadata shown on the cover page.
+#[derive(Debug, Clone, Default)]
+pub struct Cover {
+    pub title: String,
+    pub description: String,
+    pub author: String,
+    pub publisher: String,
+    pub date: String,
+    /// Path of the cover image.
+    pub image: Option<String>,
+}
+
+/// Inline content for raw HTML: its images, line breaks and text.
+fn html_inlines(html: &str, style: &Style) -> Vec<Inline> {
+    let html = SKIPPED_RE.replace_all(html, "");
+    let mut inlines = Vec::new();
+    let text = |inlines: &mut Vec<Inline>, html: &str| {
+        for (i, part) in BREAK_RE.split(html).enumerate() {
+            if i > 0 {
+                inlines.push(Inline::Break);
+            }
+            let part = html_unescape(&TAG_RE.replace_all(part, ""));
+            if !part.trim().is_empty() {
+                inlines.push(Inline::Text(part, style.clone()));
+            }
+        }
+    };
+
+    let mut last = 0;
+    for image in IMG_RE.find_iter(&html) {
+        text(&mut inlines, &html[last..image.start()]);
+        let mut src = None;
+        let mut alt = String::new();
+        for caps in ATTR_RE.captures_iter(image.as_str()) {
+            let value = caps.get(2).or_else(|| caps.get(3)).map(|m| html_unescape(m.as_str())).unwrap_or_default();
+            match &caps[1].to_ascii_lowercase()[..] {
+                "src" => src = Some(value),
+                _ => alt = value,
+            }
+        }
+        if let Some(src) = src {
+            inlines.push(Inline::Image { src, alt });
+        }
+        last = image.end();
+    }
+    text(&mut inlines, &html[last..]);
+
+    // Trailing breaks from closing block tags add nothing.
+    while matches!(inlines.last(), Some(Inline::Break)) {
+        inlines.pop();
+    }
+    inlines
+}
+
+fn html_unescape(s: &str) -> String {
+    s.replace("&nbsp;", "\u{a0}")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The text of some inline content.
+fn plain_text(inlines: &[Inline]) -> String {
+    let text: String = inlines
+        .iter()
+        .map(|inline| match inline {
+            Inline::Text(text, _) => text.as_str(),
+            Inline::Break => " ",
+            Inline::Image { alt, .. } => alt.as_str(),
+        })
+        .collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split inline content into words; `None` marks a forced line break.
+fn words(inlines: &[Inline]) -> Vec<Option<Word>> {
+    let mut words = Vec::new();
+    let mut word = Word::default();
+    let mut space = 0.0;
+
+    for inline in inlines {
+        let (text, style) = match inline {
+            Inline::Text(text, style) => (text.as_str(), style),
+            Inline::Break => {
+                if !word.fragments.is_empty() {
+                    words.push(Some(std::mem::take(&mut word)));
+                }
+                words.push(None);
+                space = 0.0;
+                continue;
+            }
+            // Images that can't be placed as blocks, e.g. in tables.
+            Inline::Image { alt, .. } => {
+                if !word.fragments.is_empty() {
+                    words.push(Some(std::mem::take(&mut word)));
+                }
+                let style = Style { italic: true, ..Style::body() };
+                let text = format!("[{}]", alt);
+                let width = style.font().text_width(&text, style.size);
+                words.push(Some(Word { fragments: vec![Fragment { text, style, width }], width, space }));
+                space = Font::Regular.text_width(" ", BODY_SIZE);
+                continue;
+            }
+        };
+
+        for c in text.chars() {
+            // Non-breaking spaces stay inside words.
+            if c.is_whitespace() && c != '\u{a0}' {
+                if !word.fragments.is_empty() {
+                    words.push(Some(std::mem::take(&mut word)));
+                }
+                if !words.is_empty() && !matches!(words.last(), Some(None)) {
+                    space = style.font().text_width(" ", style.size);
+                }
+                continue;
+            }
+            if word.fragments.is_empty() {
+                word.space = space;
+            }
+            match word.fragments.last_mut() {
+                Some(fragment) if &fragment.style == style => fragment.text.push(c),
+                _ => word.fragments.push(Fragment { text: c.to_string(), style: style.clone(), width: 0.0 }),
+            }
+        }
+    }
+    if !word.fragments.is_empty() {
+        words.push(Some(word));
+    }
+
+    for word in words.iter_mut().flatten() {
+        for fragment in &mut word.fragments {
+            fragment.width = fragment.style.font().text_width(&fragment.text, fragment.style.size);
+        }
+        word.width = word.fragments.iter().map(|f| f.width).sum();
+    }
+    words
+}
+
+/// Fill lines of at most `width` points with words.
+fn break_lines(words: Vec<Option<Word>>, width: f32) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut line = Line::default();
+
+    for word in words {
+        let Some(word) = word else {
+            if line.size == 0.0 {
+                line.size = BODY_SIZE;
+            }
+            lines.push(std::mem::take(&mut line));
+            continue;
+        };
+
+        if !line.fragments.is_empty() && line.width + word.space + word.width > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        let mut x = if line.fragments.is_empty() { 0.0 } else { line.width + word.space };
+        let mut fragments = word.fragments.into_iter().peekable();
+
+        // Continue the previous run when the style doesn't change.
+        if let (Some((_, last)), Some(first)) = (line.fragments.last_mut(), fragments.peek())
+            && last.style == first.style
+            && first.width <= width
+        {
+            let first = fragments.next().unwrap();
+            last.text.push(' ');
+            last.text.push_str(&first.text);
+            last.width = x + first.width - (line.width - last.width);
+            x += first.width;
+        }
+
+        for fragment in fragments {
+            line.size = line.size.max(fragment.style.size);
+            // Split words longer than a whole line, such as URLs.
+            for piece in split_to_width(fragment, width) {
+                if x > 0.0 && x + piece.width > width {
+                    lines.push(std::mem::take(&mut line));
+                    line.size = piece.style.size;
+                    x = 0.0;
+                }
+                let next = x + piece.width;
+                line.fragments.push((x, piece));
+                x = next;
+            }
+        }
+        line.width = x;
+    }
+    if !line.fragments.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Split a fragment into pieces no wider than `width`.
+fn split_to_width(fragment: Fragment, width: f32) -> Vec<Fragment> {
+    if fragment.width <= width {
+        return vec![fragment];
+    }
+    let font = fragment.style.font();
+    let size = fragment.style.size;
+    let mut pieces = Vec::new();
+    let mut text = String::new();
+    for c in fragment.text.chars() {
+        text.push(c);
+        if font.text_width(&text, size) > width && text.chars().count() > 1 {
+            text.pop();
+            let width = font.text_width(&text, size);
+            pieces.push(Fragment { text: std::mem::take(&mut text), style: fragment.style.clone(), width });
+            text.push(c);
+        }
+    }
+    let width = font.text_width(&text, size);
+    pieces.push(Fragment { text, style: fragment.style, width });
+    pieces
+}
+
+/// Hard-wrap a line of code at `columns` characters.
+fn wrap_code_line(line: Vec<(String, Color)>, columns: usize) -> Vec<Vec<(String, Color)>> {
+    let mut lines = vec![Vec::new()];
+    let mut used = 0;
+    for (text, color) in line {
+        let mut rest = text.as_str();
+        while !rest.is_empty() {
+            if used == columns {
+                lines.push(Vec::new());
+                used = 0;
+            }
+            let take = rest.char_indices().nth(columns - used).map(|(i, _)| i).unwrap_or(rest.len());
+            lines.last_mut().unwrap().push((rest[..take].to_string(), color));
+            used += rest[..take].chars().count();
+            rest = &rest[take..];
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Inline {
+        Inline::Text(s.to_string(), Style::body())
+    }
+
+    #[test]
+    fn test_color_from_hex() {
+        assert_eq!(Color::from_hex("#ff0000"), Some(Color(1.0, 0.0, 0.0)));
+        assert_eq!(Color::from_hex("#fff"), None);
+        assert_eq!(Color::from_hex("red"), None);
+    }
+
+    #[test]
+    fn test_break_lines() {
+        let words = words(&[text("one two three four five six")]);
+        let width = Font::Regular.text_width("one two three", BODY_SIZE) + 1.0;
+        let lines = break_lines(words, width);
+        let texts: Vec<String> = lines
+            .iter()
+            .map(|line| line.fragments.iter().map(|(_, f)| f.text.as_str()).collect::<Vec<_>>().join(" "))
+            .collect();
+        assert_eq!(texts, vec!["one two three", "four five six"]);
+        assert!(lines.iter().all(|line| line.width <= width));
+    }
+
+    #[test]
+    fn test_words_keep_styles_together() {
+        let bold = Style { bold: true, ..Style::body() };
+        let words = words(&[text("a "), Inline::Text("bold".to_string(), bold), text(", c"), Inline::Break, text("d")]);
+        let texts: Vec<Option<Vec<&str>>> = words
+            .iter()
+            .map(|w| w.as_ref().map(|w| w.fragments.iter().map(|f| f.text.as_str()).collect()))
+            .collect();
+        assert_eq!(texts, vec![Some(vec!["a"]), Some(vec!["bold", ","]), Some(vec!["c"]), None, Some(vec!["d"])]);
+    }
+
+    #[test]
+    fn test_lost_chars() {
+        let mut layout = Layout::new(600.0, 800.0, 50.0, None, Path::new("."));
+        layout.begin_document("/a/", Path::new("."), "Café λ", "");
+        layout.text_block(vec![text("Greek αβ and 日本, but “quotes”?")], Align::Left);
+        assert_eq!(layout.lost_chars.iter().collect::<String>(), "αβλ日本");
+
+        layout.begin_document("/b/", Path::new("."), "Plain", "");
+        assert!(layout.lost_chars.is_empty());
+    }
+
+    #[test]
+    fn test_long_words_are_split() {
+        let long = "x".repeat(100);
+        let lines = break_lines(words(&[text(&long)]), 50.0);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.width <= 50.0));
+        let joined: String = lines.iter().flat_map(|l| l.fragments.iter().map(|(_, f)| f.text.clone())).collect();
+        assert_eq!(joined, long);
+    }
+
+    #[test]
+    fn test_html_inlines() {
+        let inlines = html_inlines(
+            "<figure><img src=\"/cat.png\" alt=\"A &amp; B\"><figcaption>Cat<br>here</figcaption></figure><!-- note --><script>x()</script>",
+            &Style::body(),
+        );
+        assert!(matches!(&inlines[0], Inline::Image { src, alt } if src == "/cat.png" && alt == "A & B"));
+        assert_eq!(plain_text(&inlines[1..]), "Cat here");
+        assert!(matches!(inlines[2], Inline::Break));
+    }
+
+    #[test]
+    fn test_wrap_code_line() {
+        let red = Color(1.0, 0.0, 0.0);
+        let lines = wrap_code_line(vec![("abcd".to_string(), red), ("efg".to_string(), Color::TEXT)], 3);
+        let texts: Vec<String> = lines.iter().map(|l| l.iter().map(|(t, _)| t.as_str()).collect()).collect();
+        assert_eq!(texts, vec!["abc", "def", "g"]);
+        assert_eq!(lines[1][0].1, red);
+    }
+}
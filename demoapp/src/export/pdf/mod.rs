@@ -0,0 +1,492 @@
+//! PDF generation for collections.
+//!
+//! Lays out the rendered collection as a paginated PDF with a cover page,
+//! a clickable outline of documents and their headings, highlighted code
+//! and embedded images. Everything is written directly, using the
+//! standard PDF fonts, so no browser or external tool is needed.
+
+mod font;
+mod layout;
+mod writer;
+
+use rustmax::comrak::{Arena, parse_document};
+use rustmax::jiff::Zoned;
+use rustmax::log::warn;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::build::{
+    DATA_DIR, TableOfContents, TemplateEngine, TocEntry, expand_content_shortcodes, load_data,
+    markdown_options, resolve_url, themes,
+};
+use crate::collection::{Collection, Config, Document};
+use crate::features::BuildFeatures;
+use crate::{Error, Result};
+use font::Font;
+use layout::{Cover, Destination, Layout, Link};
+use writer::{ObjectId, PdfWriter, literal_string, text_string};
+
+/// Page margin in points.
+const MARGIN: f32 = 56.0;
+
+/// Paper size of the exported PDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    /// Width and height in points.
+    pub fn dimensions(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+/// Configuration for PDF generation.
+#[derive(Debug, Clone)]
+pub struct PdfConfig {
+    /// Document title (defaults to collection title).
+    pub title: Option<String>,
+    /// Document author (defaults to collection author).
+    pub author: Option<String>,
+    /// Language code (e.g., "en").
+    pub language: String,
+    /// Description shown on the cover (defaults to collection description).
+    pub description: Option<String>,
+    /// Publisher name.
+    pub publisher: Option<String>,
+    /// Cover image path, relative to the collection root (optional).
+    pub cover_image: Option<String>,
+    /// Highlight theme for code (defaults to the `[highlight]` theme).
+    pub theme: Option<String>,
+    /// Paper size.
+    pub page_size: PageSize,
+    /// Include draft documents.
+    pub include_drafts: bool,
+}
+
+impl Default for PdfConfig {
+    fn default() -> Self {
+        Self {
+            title: None,
+            author: None,
+            language: "en".to_string(),
+            description: None,
+            publisher: None,
+            cover_image: None,
+            theme: None,
+            page_size: PageSize::default(),
+            include_drafts: false,
+        }
+    }
+}
+
+/// An entry in the PDF outline.
+#[derive(Debug)]
+struct OutlineItem {
+    title: String,
+    destination: Option<Destination>,
+    children: Vec<OutlineItem>,
+}
+
+impl OutlineItem {
+    fn from_toc(entry: &TocEntry, url: &str, destinations: &HashMap<String, Destination>) -> Self {
+        Self {
+            title: entry.text.clone(),
+            destination: destinations.get(&format!("{}#{}", url, entry.id)).copied(),
+            children: entry.children.iter().map(|child| Self::from_toc(child, url, destinations)).collect(),
+        }
+    }
+}
+
+/// Generate a PDF file from a collection.
+pub fn generate_pdf(
+    collection: &Collection,
+    config: &Config,
+    output_path: &Path,
+    pdf_config: &PdfConfig,
+) -> Result<()> {
+    let documents: Vec<&Document> = if pdf_config.include_drafts {
+        collection.all_sorted()
+    } else {
+        collection.published()
+    };
+
+    let pdf = render_pdf(collection, &documents, config, pdf_config)?;
+    std::fs::write(output_path, pdf)
+        .map_err(|e| Error::build(format!("Failed to write PDF file: {}", e)))
+}
+
+/// Render documents of a collection as a PDF, returning the file's bytes.
+///
+/// Shortcodes and highlighting follow the collection's build features, as
+/// they would for the HTML site.
+pub fn render_pdf(
+    collection: &Collection,
+    documents: &[&Document],
+    config: &Config,
+    pdf_config: &PdfConfig,
+) -> Result<Vec<u8>> {
+    let features = config.build.features()?;
+    let engine = TemplateEngine::for_collection(&collection.root, config)?;
+    engine.set_data(load_data(&collection.root.join(DATA_DIR))?);
+
+    let theme = (features.contains(BuildFeatures::SYNTAX_HIGHLIGHT) && config.highlight.enabled).then(|| {
+        let name = pdf_config.theme.as_deref().unwrap_or(&config.highlight.theme);
+        themes::by_name(name).unwrap_or_else(themes::github)
+    });
+
+    let title = pdf_config.title.clone().unwrap_or_else(|| config.collection.title.clone());
+    let author = pdf_config.author.clone().unwrap_or_else(|| config.collection.author.clone());
+    let description = pdf_config
+        .description
+        .clone()
+        .unwrap_or_else(|| config.collection.description.clone());
+    let now = Zoned::now();
+
+    let (width, height) = pdf_config.page_size.dimensions();
+    let mut layout = Layout::new(width, height, MARGIN, theme, &collection.root);
+    layout.cover(&Cover {
+        title: title.clone(),
+        description: description.clone(),
+        author: author.clone(),
+        publisher: pdf_config.publisher.clone().unwrap_or_default(),
+        date: now.strftime("%B %-d, %Y").to_string(),
+        image: pdf_config
+            .cover_image
+            .as_ref()
+            .map(|path| collection.root.join(path).to_string_lossy().into_owned()),
+    });
+    warn_lost_chars("PDF cover", &layout);
+
+    let options = markdown_options();
+    let mut contents = Vec::new();
+    for doc in documents {
+        let markdown = if features.contains(BuildFeatures::SHORTCODES) {
+            expand_content_shortcodes(&doc.content, &doc.source_path, doc.body_line, &engine)?
+        } else {
+            doc.content.clone()
+        };
+
+        let byline = [
+            doc.frontmatter.date.map(|d| d.to_string()),
+            doc.frontmatter.author.clone(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" · ");
+
+        let url = doc.url_path();
+        let dir = doc.source_path.parent().unwrap_or(&collection.root);
+        layout.begin_document(&url, dir, &doc.frontmatter.title, &byline);
+
+        let arena = Arena::new();
+        let root = parse_document(&arena, &markdown, &options);
+        layout.blocks(root);
+
+        warn_lost_chars(&doc.source_path.display().to_string(), &layout);
+        let toc = TableOfContents::from_headings(&layout.end_document());
+        contents.push((doc.frontmatter.title.clone(), url, toc));
+    }
+    layout.number_pages(1);
+
+    let outline = contents
+        .iter()
+        .map(|(title, url, toc)| OutlineItem {
+            title: title.clone(),
+            destination: layout.destinations.get(url).copied(),
+            children: toc
+                .entries
+                .iter()
+                .map(|entry| OutlineItem::from_toc(entry, url, &layout.destinations))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let info = format!(
+        "<< /Title {} /Author {} /Subject {} /Creator (anthology) /Producer (anthology) /CreationDate ({}) >>",
+        text_string(&title),
+        text_string(&author),
+        text_string(&description),
+        now.strftime("D:%Y%m%d%H%M%S")
+    );
+    Ok(write_pdf(&layout, &outline, &info, &config.collection.base_url, &pdf_config.language))
+}
+
+/// Warn about characters of `what` the standard fonts can't show.
+fn warn_lost_chars(what: &str, layout: &Layout) {
+    if !layout.lost_chars.is_empty() {
+        let chars: String = layout.lost_chars.iter().collect();
+        warn!("{}: characters outside Latin-1 are shown as `?` in the PDF: {}", what, chars);
+    }
+}
+
+/// Serialize laid-out pages, their resources and the outline.
+fn write_pdf(layout: &Layout, outline: &[OutlineItem], info: &str, base_url: &str, language: &str) -> Vec<u8> {
+    let (width, height) = layout.page_size();
+    let mut pdf = PdfWriter::new();
+    let catalog_id = pdf.reserve();
+    let info_id = pdf.reserve();
+    let pages_id = pdf.reserve();
+    let resources_id = pdf.reserve();
+    let outlines_id = pdf.reserve();
+    let page_ids: Vec<ObjectId> = layout.pages.iter().map(|_| pdf.reserve()).collect();
+
+    // Fonts and images are shared by every page.
+    let mut fonts = String::new();
+    for font in Font::ALL {
+        let id = pdf.reserve();
+        pdf.object(
+            id,
+            &format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font.base_font()
+            ),
+        );
+        fonts.push_str(&format!("/{} {} ", font.resource_name(), id.reference()));
+    }
+    let mut images = String::new();
+    for (index, image) in layout.images.iter().enumerate() {
+        let id = pdf.reserve();
+        let size = format!("/Width {} /Height {} /BitsPerComponent 8", image.width, image.height);
+        let mut dict = format!("/Type /XObject /Subtype /Image {} /ColorSpace /DeviceRGB", size);
+        if let Some(alpha) = &image.alpha {
+            let mask = pdf.reserve();
+            pdf.deflated_stream(mask, &format!("/Type /XObject /Subtype /Image {} /ColorSpace /DeviceGray", size), alpha);
+            dict.push_str(&format!(" /SMask {}", mask.reference()));
+        }
+        pdf.deflated_stream(id, &dict, &image.rgb);
+        images.push_str(&format!("/Im{} {} ", index, id.reference()));
+    }
+    pdf.object(resources_id, &format!("<< /Font << {}>> /XObject << {}>> >>", fonts, images));
+
+    let destination = |d: &Destination| format!("[{} /XYZ 0 {:.2} null]", page_ids[d.page].reference(), d.y);
+    for (page, &page_id) in layout.pages.iter().zip(&page_ids) {
+        let content_id = pdf.reserve();
+        pdf.deflated_stream(content_id, "", page.content.as_bytes());
+
+        let annotations: Vec<String> = page
+            .links
+            .iter()
+            .filter_map(|link| {
+                let action = match resolve_link(link, &layout.destinations, base_url)? {
+                    LinkTarget::Uri(uri) => format!("/A << /S /URI /URI {} >>", literal_string(uri.as_bytes())),
+                    LinkTarget::Page(d) => format!("/Dest {}", destination(&d)),
+                };
+                let [x1, y1, x2, y2] = link.rect;
+                Some(format!(
+                    "<< /Type /Annot /Subtype /Link /Rect [{:.2} {:.2} {:.2} {:.2}] /Border [0 0 0] {} >>",
+                    x1, y1, x2, y2, action
+                ))
+            })
+            .collect();
+
+        pdf.object(
+            page_id,
+            &format!(
+                "<< /Type /Page /Parent {} /MediaBox [0 0 {:.2} {:.2}] /Resources {} /Contents {} /Annots [{}] >>",
+                pages_id.reference(),
+                width,
+                height,
+                resources_id.reference(),
+                content_id.reference(),
+                annotations.join(" ")
+            ),
+        );
+    }
+
+    let kids: Vec<String> = page_ids.iter().map(|id| id.reference()).collect();
+    pdf.object(
+        pages_id,
+        &format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_ids.len()),
+    );
+
+    let (first, last) = write_outline(&mut pdf, outline, outlines_id, &destination);
+    match (first, last) {
+        (Some(first), Some(last)) => pdf.object(
+            outlines_id,
+            &format!(
+                "<< /Type /Outlines /First {} /Last {} /Count {} >>",
+                first.reference(),
+                last.reference(),
+                outline.len()
+            ),
+        ),
+        _ => pdf.object(outlines_id, "<< /Type /Outlines /Count 0 >>"),
+    }
+
+    pdf.object(
+        catalog_id,
+        &format!(
+            "<< /Type /Catalog /Pages {} /Outlines {} /PageMode /UseOutlines /Lang {} >>",
+            pages_id.reference(),
+            outlines_id.reference(),
+            literal_string(language.as_bytes())
+        ),
+    );
+    pdf.object(info_id, info);
+    pdf.finish(catalog_id, info_id)
+}
+
+/// Write outline items under `parent`, returning the first and last.
+///
+/// Items start closed, so only documents show until they are expanded.
+fn write_outline(
+    pdf: &mut PdfWriter,
+    items: &[OutlineItem],
+    parent: ObjectId,
+    destination: &dyn Fn(&Destination) -> String,
+) -> (Option<ObjectId>, Option<ObjectId>) {
+    let ids: Vec<ObjectId> = items.iter().map(|_| pdf.reserve()).collect();
+    for (i, item) in items.iter().enumerate() {
+        let mut dict = format!("<< /Title {} /Parent {}", text_string(&item.title), parent.reference());
+        if i > 0 {
+            dict.push_str(&format!(" /Prev {}", ids[i - 1].reference()));
+        }
+        if let Some(next) = ids.get(i + 1) {
+            dict.push_str(&format!(" /Next {}", next.reference()));
+        }
+        if let (Some(first), Some(last)) = write_outline(pdf, &item.children, ids[i], destination) {
+            dict.push_str(&format!(
+                " /First {} /Last {} /Count -{}",
+                first.reference(),
+                last.reference(),
+                item.children.len()
+            ));
+        }
+        if let Some(d) = &item.destination {
+            dict.push_str(&format!(" /Dest {}", destination(d)));
+        }
+        dict.push_str(" >>");
+        pdf.object(ids[i], &dict);
+    }
+    (ids.first().copied(), ids.last().copied())
+}
+
+/// Where a link annotation leads.
+#[derive(Debug, PartialEq)]
+enum LinkTarget {
+    Uri(String),
+    Page(Destination),
+}
+
+/// Resolve a link to a place in the PDF, or to its URL on the site.
+///
+/// Links to documents and headings in the PDF jump there; other links to
+/// the site go to `base_url`. Links that can't be resolved are dropped.
+fn resolve_link(link: &Link, destinations: &HashMap<String, Destination>, base_url: &str) -> Option<LinkTarget> {
+    let href = link.href.trim();
+    let is_external = href
+        .split_once(':')
+        .is_some_and(|(scheme, _)| !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c)));
+    let base = base_url.trim_end_matches('/');
+    let href = match href.strip_prefix(base) {
+        Some(rest) if !base.is_empty() && (rest.is_empty() || rest.starts_with('/')) => rest,
+        _ if is_external => return Some(LinkTarget::Uri(href.to_string())),
+        _ => href,
+    };
+
+    let (path, fragment) = match href.split_once('#') {
+        Some((path, fragment)) => (path, Some(fragment)),
+        None => (href, None),
+    };
+    let url = if path.is_empty() {
+        link.from.clone()
+    } else {
+        // Markdown links are relative to the source file, not its page.
+        let from = match path.ends_with(".md") {
+            true => link.from.trim_end_matches('/'),
+            false => &link.from,
+        };
+        let resolved = resolve_url(from, path);
+        let resolved = resolved.strip_suffix(".md").unwrap_or(&resolved);
+        let resolved = resolved.strip_suffix("/index").unwrap_or(resolved);
+        format!("{}/", resolved.trim_end_matches('/'))
+    };
+
+    let found = fragment
+        .and_then(|fragment| destinations.get(&format!("{}#{}", url, fragment)))
+        .or_else(|| destinations.get(&url));
+    match found {
+        Some(destination) => Some(LinkTarget::Page(*destination)),
+        None if !base.is_empty() && !path.is_empty() => {
+            let fragment = fragment.map(|f| format!("#{}", f)).unwrap_or_default();
+            Some(LinkTarget::Uri(format!("{}{}{}", base, url, fragment)))
+        }
+        None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::{Collection, Config};
+    use rustmax::tempfile::tempdir;
+
+    #[test]
+    fn test_pdf_config_default() {
+        let config = PdfConfig::default();
+        assert_eq!(config.language, "en");
+        assert_eq!(config.page_size, PageSize::A4);
+        assert!(!config.include_drafts);
+        assert!(config.theme.is_none());
+    }
+
+    #[test]
+    fn test_resolve_link() {
+        let destinations = HashMap::from([
+            ("/first/".to_string(), Destination { page: 1, y: 700.0 }),
+            ("/second/".to_string(), Destination { page: 3, y: 700.0 }),
+            ("/second/#setup".to_string(), Destination { page: 4, y: 320.0 }),
+        ]);
+        let link = |href: &str| Link { rect: [0.0; 4], href: href.to_string(), from: "/first/".to_string() };
+        let resolve = |href: &str| resolve_link(&link(href), &destinations, "https://example.com/");
+
+        assert_eq!(resolve("/second/#setup"), Some(LinkTarget::Page(Destination { page: 4, y: 320.0 })));
+        assert_eq!(resolve("../second/"), Some(LinkTarget::Page(Destination { page: 3, y: 700.0 })));
+        assert_eq!(resolve("second.md"), Some(LinkTarget::Page(Destination { page: 3, y: 700.0 })));
+        assert_eq!(resolve("https://example.com/second/"), Some(LinkTarget::Page(Destination { page: 3, y: 700.0 })));
+        // Unknown headings fall back to the page.
+        assert_eq!(resolve("/second/#nope"), Some(LinkTarget::Page(Destination { page: 3, y: 700.0 })));
+        assert_eq!(resolve("https://rust-lang.org"), Some(LinkTarget::Uri("https://rust-lang.org".to_string())));
+        assert_eq!(resolve("mailto:a@b.c"), Some(LinkTarget::Uri("mailto:a@b.c".to_string())));
+        assert_eq!(resolve("/tags/rust/"), Some(LinkTarget::Uri("https://example.com/tags/rust/".to_string())));
+        assert_eq!(resolve("#missing"), Some(LinkTarget::Page(Destination { page: 1, y: 700.0 })));
+    }
+
+    #[test]
+    fn test_render_pdf() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("content")).unwrap();
+        std::fs::write(
+            root.join("anthology.toml"),
+            "[collection]\ntitle = \"Test Collection\"\nbase_url = \"http://example.com\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("content/test.md"),
+            "---\ntitle = \"Test Document\"\ndate = \"2024-01-01\"\n---\n\n# Hello\n\nSome *text*.\n\n## Details\n\n```rust\nfn main() {}\n```\n",
+        )
+        .unwrap();
+
+        let config = Config::load(root).unwrap();
+        let collection = Collection::load(root, &config).unwrap();
+        let pdf = render_pdf(&collection, &collection.published(), &config, &PdfConfig::default()).unwrap();
+
+        assert!(pdf.starts_with(b"%PDF-1.7"));
+        assert!(pdf.ends_with(b"%%EOF\n"));
+        let text = String::from_utf8_lossy(&pdf);
+        // Cover and one page for the document.
+        assert!(text.contains("/Type /Pages /Kids [6 0 R 7 0 R] /Count 2"));
+        assert!(text.contains(&format!("/Title {}", text_string("Test Document"))));
+        assert!(text.contains(&format!("/Title {}", text_string("Details"))));
+        assert!(text.contains("/BaseFont /Courier"));
+    }
+}
@@ -0,0 +1,161 @@
+//! Low-level PDF serialization.
+//!
+//! Objects are numbered up front with [`PdfWriter::reserve`] so they can
+//! refer to each other before they are written, then written in any order.
+
+use rustmax::flate2::Compression;
+use rustmax::flate2::write::ZlibEncoder;
+use std::io::Write;
+
+/// Number of an indirect object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ObjectId(usize);
+
+impl ObjectId {
+    /// A reference to the object, e.g. `12 0 R`.
+    pub fn reference(self) -> String {
+        format!("{} 0 R", self.0)
+    }
+}
+
+/// Writes a PDF file object by object.
+pub struct PdfWriter {
+    buf: Vec<u8>,
+    offsets: Vec<Option<usize>>,
+}
+
+impl PdfWriter {
+    /// Start a PDF 1.7 file.
+    pub fn new() -> Self {
+        let mut buf = Vec::new();
+        // The binary comment marks the file as binary for transfer tools.
+        buf.extend_from_slice(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3\n");
+        Self { buf, offsets: Vec::new() }
+    }
+
+    /// Allocate an object number.
+    pub fn reserve(&mut self) -> ObjectId {
+        self.offsets.push(None);
+        ObjectId(self.offsets.len())
+    }
+
+    /// Write an object whose body is a dictionary or other value.
+    pub fn object(&mut self, id: ObjectId, body: &str) {
+        self.start(id);
+        self.buf.extend_from_slice(body.as_bytes());
+        self.buf.extend_from_slice(b"\nendobj\n");
+    }
+
+    /// Write a stream object; `dict` holds its entries besides `/Length`.
+    pub fn stream(&mut self, id: ObjectId, dict: &str, data: &[u8]) {
+        self.start(id);
+        write!(self.buf, "<< {} /Length {} >>\nstream\n", dict, data.len()).unwrap();
+        self.buf.extend_from_slice(data);
+        self.buf.extend_from_slice(b"\nendstream\nendobj\n");
+    }
+
+    /// Write a stream object compressed with `FlateDecode`.
+    pub fn deflated_stream(&mut self, id: ObjectId, dict: &str, data: &[u8]) {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        let compressed = encoder.finish().unwrap();
+        self.stream(id, &format!("{} /Filter /FlateDecode", dict), &compressed);
+    }
+
+    fn start(&mut self, id: ObjectId) {
+        self.offsets[id.0 - 1] = Some(self.buf.len());
+        writeln!(self.buf, "{} 0 obj", id.0).unwrap();
+    }
+
+    /// Write the cross-reference table and trailer and return the file.
+    ///
+    /// Objects that were reserved but never written are marked free.
+    pub fn finish(mut self, catalog: ObjectId, info: ObjectId) -> Vec<u8> {
+        let xref = self.buf.len();
+        writeln!(self.buf, "xref\n0 {}\n0000000000 65535 f ", self.offsets.len() + 1).unwrap();
+        for offset in &self.offsets {
+            match offset {
+                Some(offset) => writeln!(self.buf, "{:010} 00000 n ", offset).unwrap(),
+                None => self.buf.extend_from_slice(b"0000000000 00000 f \n"),
+            }
+        }
+        writeln!(
+            self.buf,
+            "trailer\n<< /Size {} /Root {} /Info {} >>\nstartxref\n{}\n%%EOF",
+            self.offsets.len() + 1,
+            catalog.reference(),
+            info.reference(),
+            xref
+        )
+        .unwrap();
+        self.buf
+    }
+}
+
+/// A text string for metadata and outlines, as UTF-16 with a byte order mark.
+pub fn text_string(text: &str) -> String {
+    let mut hex = String::from("<FEFF");
+    for unit in text.encode_utf16() {
+        hex.push_str(&format!("{:04X}", unit));
+    }
+    hex.push('>');
+    hex
+}
+
+/// A literal string of already encoded bytes, for content streams.
+pub fn literal_string(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('(');
+    for &byte in bytes {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(byte as char);
+            }
+            b' '..=b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("\\{:03o}", byte)),
+        }
+    }
+    out.push(')');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_string() {
+        assert_eq!(literal_string(b"a (b) \\c"), "(a \\(b\\) \\\\c)");
+        assert_eq!(literal_string(b"caf\xe9"), "(caf\\351)");
+    }
+
+    #[test]
+    fn test_text_string() {
+        assert_eq!(text_string("Hé"), "<FEFF004800E9>");
+    }
+
+    #[test]
+    fn test_xref_offsets() {
+        let mut writer = PdfWriter::new();
+        let catalog = writer.reserve();
+        let info = writer.reserve();
+        let unused = writer.reserve();
+        writer.object(info, "<< >>");
+        writer.object(catalog, "<< /Type /Catalog >>");
+        assert_eq!(unused.reference(), "3 0 R");
+
+        let pdf = writer.finish(catalog, info);
+        let find = |needle: &[u8]| pdf.windows(needle.len()).position(|w| w == needle).unwrap();
+        let xref = find(b"xref\n");
+        let tail = String::from_utf8(pdf[xref..].to_vec()).unwrap();
+        let startxref: usize = tail.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert_eq!(startxref, xref);
+
+        // Each entry points at its object.
+        let entries: Vec<&str> = tail.lines().skip(3).take(3).collect();
+        let catalog_offset: usize = entries[0][..10].parse().unwrap();
+        assert!(pdf[catalog_offset..].starts_with(b"1 0 obj"));
+        assert!(entries[2].ends_with("f "));
+    }
+}
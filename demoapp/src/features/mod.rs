@@ -29,13 +29,19 @@ bitflags! {
         const TOC = 1 << 8;
         /// Enable syntax highlighting.
         const SYNTAX_HIGHLIGHT = 1 << 9;
+        /// Optimize images from the static directory.
+        const IMAGES = 1 << 10;
+        /// Include documents whose `publish_date` has not come yet.
+        const FUTURE = 1 << 11;
 
         /// Default features for production builds.
         const PRODUCTION = Self::SEARCH_INDEX.bits()
             | Self::RSS.bits()
             | Self::SITEMAP.bits()
             | Self::SHORTCODES.bits()
-            | Self::COMPRESS.bits();
+            | Self::COMPRESS.bits()
+            | Self::TOC.bits()
+            | Self::SYNTAX_HIGHLIGHT.bits();
 
         /// Default features for development.
         const DEVELOPMENT = Self::DRAFTS.bits()
@@ -52,36 +58,61 @@ bitflags! {
             | Self::MINIFY.bits()
             | Self::INLINE_IMAGES.bits()
             | Self::TOC.bits()
-            | Self::SYNTAX_HIGHLIGHT.bits();
+            | Self::SYNTAX_HIGHLIGHT.bits()
+            | Self::IMAGES.bits()
+            | Self::FUTURE.bits();
     }
 }
 
 impl BuildFeatures {
     /// Parse features from a comma-separated string.
+    ///
+    /// Unknown names are ignored.
     pub fn from_str_list(s: &str) -> Self {
-        let mut features = Self::empty();
+        s.split(',')
+            .filter_map(Self::parse_name)
+            .fold(Self::empty(), |acc, f| acc | f)
+    }
+
+    /// Parse features from a comma-separated string, rejecting unknown names.
+    pub fn try_from_str_list(s: &str) -> Result<Self, String> {
+        Self::try_from_names(s.split(',').filter(|part| !part.trim().is_empty()))
+    }
 
-        for part in s.split(',') {
-            let part = part.trim().to_lowercase();
-            match part.as_str() {
-                "drafts" => features |= Self::DRAFTS,
-                "compress" => features |= Self::COMPRESS,
-                "search" | "search_index" => features |= Self::SEARCH_INDEX,
-                "rss" => features |= Self::RSS,
-                "sitemap" => features |= Self::SITEMAP,
-                "shortcodes" => features |= Self::SHORTCODES,
-                "minify" => features |= Self::MINIFY,
-                "inline_images" => features |= Self::INLINE_IMAGES,
-                "toc" => features |= Self::TOC,
-                "syntax" | "syntax_highlight" => features |= Self::SYNTAX_HIGHLIGHT,
-                "production" => features |= Self::PRODUCTION,
-                "development" | "dev" => features |= Self::DEVELOPMENT,
-                "all" => features |= Self::ALL,
-                _ => {}
-            }
+    /// Parse features from a list of names, rejecting unknown names.
+    pub fn try_from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Result<Self, String> {
+        let mut features = Self::empty();
+        for name in names {
+            features |= Self::parse_name(name)
+                .ok_or_else(|| format!("unknown build feature `{}`", name.trim()))?;
         }
+        Ok(features)
+    }
 
-        features
+    /// Parse a single feature or preset name.
+    ///
+    /// Names are case-insensitive and accept `-` in place of `_`.
+    pub fn parse_name(name: &str) -> Option<Self> {
+        let name = name.trim().to_lowercase().replace('-', "_");
+        let features = match name.as_str() {
+            "drafts" => Self::DRAFTS,
+            "compress" => Self::COMPRESS,
+            "search" | "search_index" => Self::SEARCH_INDEX,
+            "rss" | "feeds" => Self::RSS,
+            "sitemap" => Self::SITEMAP,
+            "shortcodes" => Self::SHORTCODES,
+            "minify" => Self::MINIFY,
+            "inline_images" => Self::INLINE_IMAGES,
+            "toc" => Self::TOC,
+            "syntax" | "syntax_highlight" => Self::SYNTAX_HIGHLIGHT,
+            "images" => Self::IMAGES,
+            "future" => Self::FUTURE,
+            "production" => Self::PRODUCTION,
+            "development" | "dev" => Self::DEVELOPMENT,
+            "all" => Self::ALL,
+            _ => return None,
+        };
+        Some(features)
     }
 
     /// Convert to a list of feature names.
@@ -97,13 +128,16 @@ impl BuildFeatures {
         if self.contains(Self::INLINE_IMAGES) { names.push("inline_images"); }
         if self.contains(Self::TOC) { names.push("toc"); }
         if self.contains(Self::SYNTAX_HIGHLIGHT) { names.push("syntax_highlight"); }
+        if self.contains(Self::IMAGES) { names.push("images"); }
+        if self.contains(Self::FUTURE) { names.push("future"); }
         names
     }
 }
 
 impl Default for BuildFeatures {
+    /// Features used when neither the config nor the command line selects any.
     fn default() -> Self {
-        Self::SHORTCODES | Self::SYNTAX_HIGHLIGHT
+        Self::SHORTCODES | Self::SYNTAX_HIGHLIGHT | Self::SEARCH_INDEX | Self::TOC
     }
 }
 
@@ -205,6 +239,22 @@ mod tests {
         assert!(!features.contains(BuildFeatures::SITEMAP));
     }
 
+    #[test]
+    fn test_build_features_hyphenated_names() {
+        let features = BuildFeatures::from_str_list("search-index, inline-images");
+        assert!(features.contains(BuildFeatures::SEARCH_INDEX));
+        assert!(features.contains(BuildFeatures::INLINE_IMAGES));
+    }
+
+    #[test]
+    fn test_build_features_try_from_str() {
+        let features = BuildFeatures::try_from_str_list("rss,sitemap").unwrap();
+        assert_eq!(features, BuildFeatures::RSS | BuildFeatures::SITEMAP);
+
+        let err = BuildFeatures::try_from_str_list("rss,sitemaps").unwrap_err();
+        assert!(err.contains("sitemaps"));
+    }
+
     #[test]
     fn test_build_features_to_names() {
         let features = BuildFeatures::DRAFTS | BuildFeatures::RSS;
@@ -222,6 +222,11 @@ mod tests {
             content: Default::default(),
             server: Default::default(),
             highlight: Default::default(),
+            images: Default::default(),
+            compress: Default::default(),
+            search: Default::default(),
+            languages: Default::default(),
+            themes: Default::default(),
         }
     }
 
@@ -235,6 +240,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let config = make_config();
@@ -261,6 +267,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let config = make_config();
@@ -279,6 +286,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let config = make_config();
@@ -297,6 +305,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let config = make_config();
@@ -318,6 +327,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let config = make_config();
@@ -342,6 +352,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let config = make_config();
@@ -361,6 +372,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: vec![],
+            sections: Vec::new(),
         };
 
         let config = make_config();
@@ -0,0 +1,359 @@
+//! Frontmatter in the formats other generators write.
+//!
+//! Pages are read as YAML between `---` lines, TOML between `+++` lines,
+//! or a leading JSON object, into JSON values, then mapped onto
+//! [`Frontmatter`]. Fields Anthology has no use for are kept in `extra`
+//! where templates can still reach them.
+
+use rustmax::jiff::civil::Date;
+use rustmax::serde_json::{Map, Value};
+use rustmax::toml;
+use std::path::Path;
+
+use super::Notes;
+use crate::collection::Frontmatter;
+
+/// Fields that decide where a page goes, read by the importers themselves.
+const URL_FIELDS: &[&str] = &["slug", "permalink", "url", "path"];
+
+/// Fields that name templates or redirects, which don't carry over.
+const DROPPED_FIELDS: &[&str] = &["layout", "template", "type", "aliases", "redirect_from", "redirect_to"];
+
+/// Split a page into its frontmatter fields and body.
+///
+/// A page without frontmatter has no fields.
+pub(super) fn parse(raw: &str) -> std::result::Result<(Map<String, Value>, &str), String> {
+    let raw = raw.strip_prefix('\u{feff}').unwrap_or(raw);
+
+    if raw.starts_with('{') {
+        let mut stream = rustmax::serde_json::Deserializer::from_str(raw).into_iter::<Value>();
+        let value = stream
+            .next()
+            .ok_or("empty JSON frontmatter")?
+            .map_err(|e| format!("invalid JSON frontmatter: {}", e))?;
+        let body = &raw[stream.byte_offset()..];
+        return Ok((into_map(value)?, body.trim_start_matches(['\r', '\n'])));
+    }
+
+    let Some((delimiter, text, body)) = split(raw) else {
+        return Ok((Map::new(), raw));
+    };
+    let value = match delimiter {
+        "+++" => {
+            let table: toml::Table = toml::from_str(text)
+                .map_err(|e| format!("invalid TOML frontmatter: {}", e.message().trim()))?;
+            toml_to_json(toml::Value::Table(table))
+        }
+        _ => crate::yaml::parse(text).map_err(|e| format!("invalid YAML frontmatter: {}", e))?,
+    };
+    Ok((into_map(value)?, body))
+}
+
+/// Whether a file starts with a frontmatter delimiter line.
+pub(super) fn has_frontmatter(raw: &[u8]) -> bool {
+    let raw = raw.strip_prefix("\u{feff}".as_bytes()).unwrap_or(raw);
+    [b"---", b"+++"].iter().any(|d| {
+        raw.starts_with(*d) && matches!(raw.get(3), Some(b'\n' | b'\r'))
+    })
+}
+
+/// Split off frontmatter between `---` or `+++` lines.
+fn split(raw: &str) -> Option<(&str, &str, &str)> {
+    let delimiter = ["---", "+++"].into_iter().find(|d| raw.starts_with(d))?;
+    let first = raw.find('\n')?;
+    if raw[3..first].trim() != "" {
+        return None;
+    }
+
+    let mut offset = first + 1;
+    for line in raw[first + 1..].split_inclusive('\n') {
+        if line.trim_end() == delimiter {
+            let text = &raw[first + 1..offset];
+            let body = &raw[offset + line.len()..];
+            return Some((delimiter, text, body.trim_start_matches(['\r', '\n'])));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn into_map(value: Value) -> std::result::Result<Map<String, Value>, String> {
+    match value {
+        Value::Object(map) => Ok(map),
+        Value::Null => Ok(Map::new()),
+        _ => Err("frontmatter is not a mapping".to_string()),
+    }
+}
+
+/// Convert a TOML value to JSON, writing dates as strings.
+pub(super) fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(dt) => Value::String(dt.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => {
+            Value::Object(table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect())
+        }
+    }
+}
+
+/// Convert a JSON value to TOML; nulls, which TOML lacks, are dropped.
+fn json_to_toml(value: &Value) -> Option<toml::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => toml::Value::Boolean(*b),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64()?),
+        },
+        Value::String(s) => toml::Value::String(s.clone()),
+        Value::Array(items) => toml::Value::Array(items.iter().filter_map(json_to_toml).collect()),
+        Value::Object(map) => toml::Value::Table(
+            map.iter().filter_map(|(k, v)| Some((k.clone(), json_to_toml(v)?))).collect(),
+        ),
+    })
+}
+
+/// Read a date written as `2024-01-15`, with or without a time after it.
+pub(super) fn parse_date(text: &str) -> Option<Date> {
+    let mut parts = text.trim().splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let rest = parts.next()?;
+    let day_len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    Date::new(year, month, rest[..day_len].parse().ok()?).ok()
+}
+
+/// A string field, also accepting numbers.
+pub(super) fn string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Terms written as an array or a single string.
+///
+/// Strings are split on commas if they have any and on whitespace
+/// otherwise, as Jekyll does.
+pub(super) fn terms(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().filter_map(string).collect(),
+        Value::String(s) if s.contains(',') => {
+            s.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect()
+        }
+        Value::String(s) => s.split_whitespace().map(String::from).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Convert frontmatter fields to Anthology's.
+///
+/// Keys are matched case-insensitively, as Hugo does. Terms of the
+/// `taxonomies` other than tags go to `extra`, where
+/// [`Document::terms`](crate::collection::Document::terms) finds them.
+/// URL fields are skipped; the importer places the page by them.
+pub(super) fn convert(
+    fields: &Map<String, Value>,
+    taxonomies: &[String],
+    source: &Path,
+    notes: &mut Notes,
+) -> Frontmatter {
+    let mut fm = Frontmatter::default();
+    let date_field = |key: &str, value: &Value, notes: &mut Notes| {
+        let date = string(value).as_deref().and_then(parse_date);
+        if date.is_none() {
+            notes.add(source, format!("could not read {} {}", key, value));
+        }
+        date
+    };
+
+    for (key, value) in fields {
+        let lower = key.to_lowercase();
+        match lower.as_str() {
+            "title" => fm.title = string(value).unwrap_or_default(),
+            "date" => fm.date = date_field(key, value, notes),
+            "publishdate" | "publish_date" => fm.publish_date = date_field(key, value, notes),
+            "expirydate" | "expiry_date" => fm.expiry_date = date_field(key, value, notes),
+            "draft" => fm.draft = value.as_bool().unwrap_or(value.as_str() == Some("true")),
+            "published" => fm.draft |= value.as_bool() == Some(false),
+            "description" => fm.description = string(value),
+            "summary" | "excerpt" if fm.description.is_none() => fm.description = string(value),
+            "author" | "authors" => {
+                let authors: Vec<String> = match value {
+                    Value::Object(author) => author.get("name").and_then(string).into_iter().collect(),
+                    _ => terms_list(value),
+                };
+                if authors.len() > 1 {
+                    notes.add(source, format!("only the first of {} authors kept", authors.len()));
+                }
+                fm.author = authors.into_iter().next();
+            }
+            "tags" | "tag" => fm.tags.extend(terms(value)),
+            "taxonomies" => {
+                for (name, value) in value.as_object().into_iter().flatten() {
+                    match name.as_str() {
+                        "tags" => fm.tags.extend(terms(value)),
+                        _ => add_terms(&mut fm, name, terms(value)),
+                    }
+                }
+            }
+            "extra" => {
+                for (name, value) in value.as_object().into_iter().flatten() {
+                    if let Some(value) = json_to_toml(value) {
+                        fm.extra.entry(name.clone()).or_insert(value);
+                    }
+                }
+            }
+            "lastmod" | "updated" | "last_modified_at" | "modified" => {
+                if let Some(date) = string(value) {
+                    fm.extra.insert("updated".to_string(), toml::Value::String(date));
+                }
+            }
+            _ if URL_FIELDS.contains(&lower.as_str()) => {}
+            _ if DROPPED_FIELDS.contains(&lower.as_str()) => notes.dropped(&lower),
+            _ => match taxonomies.iter().find(|t| **t == lower || **t == plural(&lower)) {
+                Some(taxonomy) => add_terms(&mut fm, taxonomy, terms(value)),
+                None => {
+                    if let Some(value) = json_to_toml(value) {
+                        fm.extra.insert(key.clone(), value);
+                    }
+                }
+            },
+        }
+    }
+
+    fm.tags.dedup();
+    fm
+}
+
+/// Names listed as an array or a single string, without splitting.
+pub(super) fn terms_list(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(items) => items.iter().filter_map(string).collect(),
+        _ => string(value).into_iter().collect(),
+    }
+}
+
+/// Add terms of a taxonomy to `extra`.
+fn add_terms(fm: &mut Frontmatter, taxonomy: &str, terms: Vec<String>) {
+    if terms.is_empty() {
+        return;
+    }
+    let entry = fm
+        .extra
+        .entry(taxonomy.to_string())
+        .or_insert_with(|| toml::Value::Array(Vec::new()));
+    if let toml::Value::Array(existing) = entry {
+        for term in terms {
+            let term = toml::Value::String(term);
+            if !existing.contains(&term) {
+                existing.push(term);
+            }
+        }
+    }
+}
+
+/// Plural of a taxonomy name, for Jekyll's `category` and `categories`.
+fn plural(name: &str) -> String {
+    match name.strip_suffix('y') {
+        Some(stem) => format!("{}ies", stem),
+        None => format!("{}s", name),
+    }
+}
+
+/// Write a page as Anthology reads it: TOML frontmatter between `---` lines.
+pub(super) fn render(fm: &Frontmatter, body: &str) -> String {
+    format!("{}\n{}\n", fm.to_toml(), body.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Document;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_parse_formats() {
+        let (fields, body) = parse("---\ntitle: Hello\ntags: [a, b]\n---\n\nBody\n").unwrap();
+        assert_eq!(fields["title"], "Hello");
+        assert_eq!(body, "Body\n");
+
+        let (fields, body) = parse("+++\ntitle = \"Hello\"\ndate = 2024-01-15T10:00:00Z\n+++\nBody\n").unwrap();
+        assert_eq!(fields["date"], "2024-01-15T10:00:00Z");
+        assert_eq!(body, "Body\n");
+
+        let (fields, body) = parse("{\n  \"title\": \"Hello\"\n}\n\nBody\n").unwrap();
+        assert_eq!(fields["title"], "Hello");
+        assert_eq!(body, "Body\n");
+
+        let (fields, body) = parse("No frontmatter.\n").unwrap();
+        assert!(fields.is_empty());
+        assert_eq!(body, "No frontmatter.\n");
+
+        assert!(parse("---\n- a list\n---\n").unwrap_err().contains("not a mapping"));
+        assert!(has_frontmatter(b"---\r\nx: 1\r\n---\r\n"));
+        assert!(!has_frontmatter(b"----\n"));
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = |s: &str| parse_date(s).map(|d| d.to_string());
+        assert_eq!(date("2024-01-15").as_deref(), Some("2024-01-15"));
+        assert_eq!(date("2024-01-15 10:00:00 +0000").as_deref(), Some("2024-01-15"));
+        assert_eq!(date("2024-1-5T10:00:00Z").as_deref(), Some("2024-01-05"));
+        assert_eq!(date("January 5"), None);
+    }
+
+    #[test]
+    fn test_convert_and_render() {
+        let (fields, body) = parse(
+            "---\ntitle: \"Hello: World\"\ndate: 2024-01-15 10:00:00 +0000\nlayout: post\ncategory: Rust\ntags: web rust\npublished: false\nauthors: [Ada, Grace]\nweight: 3\nseries: null\nlastmod: 2024-02-01\n---\nBody\n",
+        )
+        .unwrap();
+        let mut notes = Notes::default();
+        let taxonomies = vec!["categories".to_string()];
+        let fm = convert(&fields, &taxonomies, Path::new("post.md"), &mut notes);
+
+        assert_eq!(fm.title, "Hello: World");
+        assert_eq!(fm.date.unwrap().to_string(), "2024-01-15");
+        assert_eq!(fm.tags, ["web", "rust"]);
+        assert!(fm.draft);
+        assert_eq!(fm.author.as_deref(), Some("Ada"));
+        assert_eq!(fm.extra["categories"], toml::Value::Array(vec![toml::Value::String("Rust".into())]));
+        assert_eq!(fm.extra["weight"], toml::Value::Integer(3));
+        assert!(!fm.extra.contains_key("series"));
+        assert!(!fm.extra.contains_key("layout"));
+
+        let notes = notes.into_notes();
+        let messages: Vec<String> = notes.iter().map(|n| n.to_string()).collect();
+        assert_eq!(messages, [
+            "post.md: only the first of 2 authors kept",
+            "frontmatter field `layout` dropped from 1 page",
+        ]);
+
+        let text = render(&fm, body);
+        let doc = Document::parse(PathBuf::from("post.md"), &text).unwrap();
+        assert_eq!(doc.frontmatter.title, "Hello: World");
+        assert_eq!(doc.terms("categories"), ["Rust"]);
+        assert_eq!(doc.frontmatter.extra["updated"].as_str(), Some("2024-02-01"));
+        assert_eq!(doc.content, "Body\n");
+    }
+
+    #[test]
+    fn test_convert_zola_tables() {
+        let (fields, _) = parse(
+            "+++\ntitle = \"Post\"\n[taxonomies]\ntags = [\"a\"]\nseries = [\"intro\"]\n[extra]\ncover = \"x.png\"\n+++\n",
+        )
+        .unwrap();
+        let mut notes = Notes::default();
+        let fm = convert(&fields, &[], Path::new("post.md"), &mut notes);
+        assert_eq!(fm.tags, ["a"]);
+        assert_eq!(fm.extra["series"].as_array().unwrap().len(), 1);
+        assert_eq!(fm.extra["cover"].as_str(), Some("x.png"));
+    }
+}
@@ -0,0 +1,389 @@
+//! Hugo sites: configuration, content sections, page bundles and shortcodes.
+//!
+//! Hugo lays out content as Anthology does, with `_index.md` sections and
+//! `index.md` bundles, so pages keep their paths unless the config's
+//! `permalinks` or a page's `url` moves them. `ref` and `relref` are
+//! resolved and `highlight` becomes a fenced code block; other shortcodes
+//! are kept in Anthology's syntax and noted unless Anthology has them.
+
+use rustmax::regex::Regex;
+use rustmax::serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use super::{
+    Notes, Page, PermalinkVars, Site, expand_permalink, fields, files, is_markdown, language_code,
+    note_shortcode, read_page, relative_url, set_taxonomies, title_from_slug,
+};
+use crate::Result;
+
+/// Config file names, in the order Hugo looks for them.
+const CONFIG_FILES: &[&str] = &[
+    "hugo.toml", "hugo.yaml", "hugo.yml", "hugo.json",
+    "config.toml", "config.yaml", "config.yml", "config.json",
+];
+
+/// Site directories with no Anthology counterpart, and why.
+const UNSUPPORTED_DIRS: &[(&str, &str)] = &[
+    ("layouts", "templates need rewriting for Tera"),
+    ("themes", "templates need rewriting for Tera"),
+    ("assets", "Hugo Pipes has no Anthology counterpart"),
+    ("archetypes", "`anthology new` has no archetypes"),
+    ("i18n", "translation strings have no Anthology counterpart"),
+];
+
+/// A shortcode tag: `{{< name args >}}`, `{{% name args %}}` or a closing `{{< /name >}}`.
+static SHORTCODE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\{\{([<%])\s*(/?)\s*([\w-]+)\s*(.*?)\s*[>%]\}\}").expect("invalid regex")
+});
+
+/// Read a Hugo site.
+pub(super) fn read(root: &Path) -> Result<Site> {
+    let mut site = Site::default();
+
+    let path = ["", "config/_default"]
+        .iter()
+        .flat_map(|dir| CONFIG_FILES.iter().map(move |name| root.join(dir).join(name)))
+        .find(|path| path.is_file());
+    let config: Map<String, Value> = match &path {
+        Some(path) => super::read_config(path)?.into_iter().map(|(k, v)| (k.to_lowercase(), v)).collect(),
+        None => {
+            site.notes.site("no hugo.toml or config.toml found; using defaults");
+            Map::new()
+        }
+    };
+    let settings = read_config(&config, &mut site);
+
+    let site_dir = |key: &str, default: &str| {
+        root.join(config.get(key).and_then(fields::string).unwrap_or_else(|| default.to_string()))
+    };
+    let content_dir = site_dir("contentdir", "content");
+    let mut refs = HashMap::new();
+    if content_dir.is_dir() {
+        read_content(&content_dir, &settings, &mut site, &mut refs)?;
+    }
+    for page in &mut site.pages {
+        page.body = convert_shortcodes(&page.body, &refs, &page.source, &mut site.notes);
+    }
+
+    let copied = [
+        (site_dir("staticdir", "static"), "static"),
+        (site_dir("datadir", "data"), crate::build::DATA_DIR),
+    ];
+    for (dir, dest) in copied {
+        for file in files(&dir)? {
+            let to = Path::new(dest).join(file.strip_prefix(&dir).unwrap_or(&file));
+            site.assets.push((file, to));
+        }
+    }
+    for (name, reason) in UNSUPPORTED_DIRS {
+        if root.join(name).is_dir() {
+            site.notes.site(format!("{}/ not imported; {}", name, reason));
+        }
+    }
+    Ok(site)
+}
+
+/// Settings from the config that decide where pages go.
+#[derive(Default)]
+struct Settings {
+    taxonomies: Vec<String>,
+    /// Permalink patterns by top-level section.
+    permalinks: HashMap<String, String>,
+    /// Codes of languages other than the default, whose pages are skipped.
+    translations: HashSet<String>,
+}
+
+/// Read the site config into the site's [`Config`](crate::collection::Config).
+fn read_config(config: &Map<String, Value>, site: &mut Site) -> Settings {
+    let get = |key: &str| config.get(key).and_then(fields::string);
+    let params = config.get("params").and_then(Value::as_object);
+    let param = |key: &str| params.and_then(|p| p.get(key));
+
+    let collection = &mut site.config.collection;
+    if let Some(title) = get("title") {
+        collection.title = title;
+    }
+    collection.base_url = get("baseurl").unwrap_or_default().trim_end_matches('/').to_string();
+    collection.description = param("description").and_then(fields::string).unwrap_or_default();
+    let author = param("author").or_else(|| config.get("author"));
+    collection.author = match author {
+        Some(Value::Object(author)) => author.get("name").and_then(fields::string),
+        Some(author) => fields::terms_list(author).into_iter().next(),
+        None => None,
+    }
+    .unwrap_or_default();
+    let default_language = get("defaultcontentlanguage");
+    if let Some(code) = get("languagecode").or(default_language.clone()) {
+        collection.language = language_code(&code);
+    }
+
+    let paginate = config
+        .get("pagination")
+        .and_then(|p| p.get("pagerSize").or_else(|| p.get("pagersize")))
+        .or_else(|| config.get("paginate"));
+    if let Some(paginate) = paginate.and_then(Value::as_u64) {
+        site.config.content.paginate = paginate as usize;
+    }
+    if let Some(theme) = config.get("theme") {
+        let theme = fields::terms_list(theme).join(", ");
+        site.notes.site(format!("theme `{}` not imported; templates need rewriting for Tera", theme));
+    }
+
+    let taxonomies: Vec<String> = match config.get("taxonomies").and_then(Value::as_object) {
+        Some(taxonomies) => taxonomies.values().filter_map(fields::string).collect(),
+        None => vec!["tags".to_string(), "categories".to_string()],
+    };
+    set_taxonomies(&mut site.config, taxonomies.clone(), &mut site.notes);
+    let mut settings = Settings { taxonomies, ..Default::default() };
+
+    if let Some(permalinks) = config.get("permalinks").and_then(Value::as_object) {
+        // Newer configs key patterns by page kind first.
+        let patterns = permalinks.get("page").and_then(Value::as_object).unwrap_or(permalinks);
+        for (section, pattern) in patterns {
+            if let Some(pattern) = fields::string(pattern) {
+                settings.permalinks.insert(section.clone(), pattern);
+            }
+        }
+    }
+
+    if let Some(languages) = config.get("languages").and_then(Value::as_object) {
+        let default = default_language.unwrap_or_else(|| "en".to_string());
+        settings.translations = languages.keys().filter(|code| **code != default).cloned().collect();
+        if !settings.translations.is_empty() {
+            site.notes.site(format!("only pages in the default language `{}` are imported", default));
+        }
+    }
+    settings
+}
+
+/// Read the content directory into pages and assets.
+///
+/// `refs` gets each page's URL under the names `ref` can give it.
+fn read_content(
+    content_dir: &Path,
+    settings: &Settings,
+    site: &mut Site,
+    refs: &mut HashMap<String, String>,
+) -> Result<()> {
+    let all = files(content_dir)?;
+    let bundles: HashSet<PathBuf> = all
+        .iter()
+        .filter(|path| is_markdown(path) && path.file_stem().is_some_and(|s| s == "index"))
+        .filter_map(|path| path.parent().map(Path::to_path_buf))
+        .collect();
+
+    for path in &all {
+        let rel = relative_url(content_dir, path);
+        let parent = path.parent().unwrap_or(content_dir);
+        let bundle = path.ancestors().skip(1).find(|dir| bundles.contains(*dir));
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+
+        if !is_markdown(path) {
+            match bundle {
+                // Resources of a leaf bundle stay with its page.
+                Some(dir) if dir == parent => {}
+                Some(_) => site.notes.add(path, "skipped: nested bundle resources aren't imported"),
+                None if path.extension().is_some_and(|e| e == "html") => {
+                    site.notes.add(path, "skipped: only markdown pages are imported");
+                }
+                None => site.assets.push((path.clone(), Path::new("static").join(&rel))),
+            }
+            continue;
+        }
+        if bundle.is_some_and(|dir| dir != parent || stem != "index") {
+            site.notes.add(path, "skipped: markdown resources of a bundle aren't imported");
+            continue;
+        }
+        if stem.rsplit_once('.').is_some_and(|(_, code)| settings.translations.contains(code)) {
+            continue;
+        }
+
+        let Some((fields, body)) = read_page(path, &mut site.notes) else { continue };
+        let mut fm = fields::convert(&fields, &settings.taxonomies, path, &mut site.notes);
+
+        let dir = relative_url(content_dir, parent);
+        let is_index = stem == "_index" || stem == "index";
+        let name = match is_index {
+            true => dir.rsplit('/').next().unwrap_or_default(),
+            false => stem,
+        };
+        if fm.title.is_empty() {
+            fm.title = match dir.as_str() {
+                "" if is_index => site.config.collection.title.clone(),
+                _ => title_from_slug(name),
+            };
+        }
+
+        let slug = fields.get("slug").and_then(fields::string);
+        let title = crate::build::slugify(&fm.title);
+        let section = dir.split('/').next().unwrap_or_default();
+        let pattern = settings.permalinks.get(section).filter(|_| stem != "_index");
+        let url = match (fields.get("url").and_then(fields::string), pattern) {
+            (Some(url), _) => expand_permalink(&url, &PermalinkVars::default()).unwrap_or(url),
+            (None, Some(pattern)) => {
+                let vars = PermalinkVars {
+                    date: fm.date,
+                    title: &title,
+                    slug: slug.as_deref(),
+                    filename: name,
+                    section: &dir,
+                    ..Default::default()
+                };
+                expand_permalink(pattern, &vars).unwrap_or_else(|e| {
+                    let url = default_url(&dir, is_index, slug.as_deref().unwrap_or(name));
+                    site.notes.add(path, format!("permalink `{}`: cannot fill {}; placed at {}", pattern, e, url));
+                    url
+                })
+            }
+            (None, None) => default_url(&dir, is_index, slug.as_deref().unwrap_or(name)),
+        };
+
+        let without_ext = rel.rsplit_once('.').map_or(rel.as_str(), |(base, _)| base);
+        refs.insert(rel.clone(), url.clone());
+        refs.insert(without_ext.to_string(), url.clone());
+        if is_index {
+            refs.insert(dir.clone(), url.clone());
+        }
+        refs.entry(format!("{}.md", name)).or_insert_with(|| url.clone());
+        refs.entry(name.to_string()).or_insert_with(|| url.clone());
+
+        let assets = match stem {
+            "index" => all.iter().filter(|p| p.parent() == Some(parent) && !is_markdown(p)).cloned().collect(),
+            _ => Vec::new(),
+        };
+        site.pages.push(Page { source: path.clone(), url, frontmatter: fm, body, assets });
+    }
+    Ok(())
+}
+
+/// URL of a page where Hugo puts it by default.
+fn default_url(dir: &str, is_index: bool, slug: &str) -> String {
+    match (is_index, dir) {
+        (true, "") => "/".to_string(),
+        (true, _) => format!("/{}/", dir),
+        (false, "") => format!("/{}/", slug),
+        (false, _) => format!("/{}/{}/", dir, slug),
+    }
+}
+
+/// Rewrite Hugo shortcodes as Anthology reads them.
+///
+/// Shortcodes with a closing tag become `{{% name %}}` blocks, the others
+/// `{{< name >}}`; Anthology doesn't tell markdown and HTML content apart.
+fn convert_shortcodes(body: &str, refs: &HashMap<String, String>, source: &Path, notes: &mut Notes) -> String {
+    let closed: HashSet<&str> = SHORTCODE_RE
+        .captures_iter(body)
+        .filter(|caps| &caps[2] == "/")
+        .map(|caps| caps.get(3).map_or("", |m| m.as_str()))
+        .collect();
+
+    SHORTCODE_RE
+        .replace_all(body, |caps: &rustmax::regex::Captures| {
+            let (closing, name, args) = (&caps[2] == "/", &caps[3], &caps[4]);
+            let spaced_args = if args.is_empty() { String::new() } else { format!(" {}", args) };
+            match name {
+                "ref" | "relref" => {
+                    let target = args.split_whitespace().next().unwrap_or_default().trim_matches(['"', '\'']);
+                    let (path, anchor) = target.split_once('#').map_or((target, ""), |(p, a)| (p, a));
+                    let path = path.trim_start_matches('/').trim_end_matches('/');
+                    match refs.get(path) {
+                        Some(url) if anchor.is_empty() => url.clone(),
+                        Some(url) => format!("{}#{}", url, anchor),
+                        None => {
+                            notes.add(source, format!("{} target `{}` not found", name, target));
+                            target.to_string()
+                        }
+                    }
+                }
+                "highlight" if closing => "```".to_string(),
+                "highlight" => format!("```{}", args.split_whitespace().next().unwrap_or_default().trim_matches('"')),
+                _ => {
+                    note_shortcode(name, notes);
+                    match (closing, closed.contains(name)) {
+                        (true, _) => format!("{{{{% /{} %}}}}", name),
+                        (false, true) => format!("{{{{% {}{} %}}}}", name, spaced_args),
+                        (false, false) => format!("{{{{< {}{} >}}}}", name, spaced_args),
+                    }
+                }
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_shortcodes() {
+        let refs = HashMap::from([("blog/hello.md".to_string(), "/posts/2024/hello/".to_string())]);
+        let body = "\
+[Hello]({{< ref \"/blog/hello.md#intro\" >}}) and [gone]({{< relref \"gone\" >}})
+{{< youtube abc >}}
+{{% notice info %}}Careful.{{% /notice %}}
+{{< highlight go >}}
+fmt.Println()
+{{< /highlight >}}
+";
+        let mut notes = Notes::default();
+        let out = convert_shortcodes(body, &refs, Path::new("post.md"), &mut notes);
+        assert_eq!(out, "\
+[Hello](/posts/2024/hello/#intro) and [gone](gone)
+{{< youtube abc >}}
+{{% notice info %}}Careful.{{% /notice %}}
+```go
+fmt.Println()
+```
+");
+        let notes: Vec<String> = notes.into_notes().iter().map(|n| n.to_string()).collect();
+        assert_eq!(notes, [
+            "post.md: relref target `gone` not found",
+            "shortcode `notice` is not built in; add templates/shortcodes/notice.html",
+        ]);
+    }
+
+    #[test]
+    fn test_read_site() {
+        let dir = rustmax::tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "hugo.toml",
+            "baseURL = 'https://example.com/'\ntitle = 'Site'\n[params]\ndescription = 'About things'\n[taxonomies]\ntag = 'tags'\nseries = 'series'\n[permalinks]\nposts = '/:year/:month/:slug/'\n",
+        );
+        write("content/_index.md", "+++\ntitle = 'Home'\n+++\n");
+        write("content/posts/_index.md", "---\ntitle: Posts\n---\n");
+        write("content/posts/first.md", "---\ntitle: First Post\ndate: 2024-03-01\nseries: [intro]\n---\nSee {{< ref \"trip\" >}}.\n");
+        write("content/posts/trip/index.md", "---\ntitle: Trip\ndate: 2024-04-02\nslug: the-trip\n---\n");
+        write("content/posts/trip/photo.jpg", "jpg");
+        write("content/about.md", "---\ntitle: About\nurl: /about-us/\n---\n");
+        write("static/favicon.ico", "ico");
+        write("layouts/_default/single.html", "");
+
+        let site = read(root).unwrap();
+        assert_eq!(site.config.collection.base_url, "https://example.com");
+        assert_eq!(site.config.collection.description, "About things");
+        assert_eq!(site.config.content.taxonomies, ["tags", "series"]);
+
+        let urls: HashMap<&str, &Page> = site.pages.iter().map(|p| (p.url.as_str(), p)).collect();
+        assert_eq!(urls.len(), 5);
+        assert_eq!(urls["/"].frontmatter.title, "Home");
+        assert!(urls.contains_key("/posts/"));
+        assert!(urls.contains_key("/about-us/"));
+        let first = urls["/2024/03/first-post/"];
+        assert_eq!(first.body, "See /2024/04/the-trip/.\n");
+        assert_eq!(first.frontmatter.extra["series"].as_array().unwrap().len(), 1);
+        assert_eq!(urls["/2024/04/the-trip/"].assets, [root.join("content/posts/trip/photo.jpg")]);
+
+        let assets: Vec<String> = site.assets.iter().map(|(_, to)| to.display().to_string()).collect();
+        assert_eq!(assets, ["static/favicon.ico"]);
+        let notes: Vec<String> = site.notes.into_notes().iter().map(|n| n.to_string()).collect();
+        assert_eq!(notes, ["layouts/ not imported; templates need rewriting for Tera"]);
+    }
+}
@@ -0,0 +1,534 @@
+//! Jekyll sites: `_config.yml`, posts, collections and pages.
+//!
+//! Posts are placed by the site's permalink style. Liquid with a markdown
+//! equivalent (`highlight` blocks, `post_url`, `link`, `site.baseurl`) is
+//! converted; other tags are left in place and noted.
+
+use rustmax::regex::Regex;
+use rustmax::serde_json::{Map, Value};
+use rustmax::toml;
+use rustmax::walkdir::WalkDir;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use super::{
+    Page, PermalinkVars, Site, expand_permalink, fields, files, is_markdown, language_code, read_page,
+    relative_url, set_taxonomies, split_date_prefix, title_from_slug,
+};
+use crate::Result;
+
+/// Entries Jekyll never publishes.
+const SKIPPED: &[&str] = &["_site", "node_modules", "vendor", "Gemfile", "Gemfile.lock"];
+
+/// Directories of templates and styles, which need rewriting for Tera.
+const TEMPLATE_DIRS: &[&str] = &["_layouts", "_includes", "_sass"];
+
+/// The taxonomy Jekyll has besides tags.
+const CATEGORIES: &str = "categories";
+
+/// Liquid tags, `{% tag args %}`, and output, `{{ expression }}`.
+static LIQUID_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\{%-?\s*(\w+)\s*(.*?)\s*-?%\}|\{\{-?\s*(.*?)\s*-?\}\}").expect("invalid regex")
+});
+
+/// A quoted path passed through a URL filter, `{{ "/about/" | relative_url }}`.
+static URL_FILTER_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"^["']([^"']*)["']\s*\|\s*(relative_url|absolute_url|prepend:\s*site\.(baseurl|url))$"#)
+        .expect("invalid regex")
+});
+
+/// Settings from `_config.yml` that decide where pages go.
+struct JekyllConfig {
+    permalink: String,
+    /// Collections by name, with their permalink pattern if Jekyll outputs them.
+    collections: HashMap<String, Option<String>>,
+    exclude: Vec<String>,
+}
+
+/// State while reading a site, kept until all URLs are known.
+struct Reader<'a> {
+    root: &'a Path,
+    config: JekyllConfig,
+    site: Site,
+    /// Post URLs by file name without extension, for `{% post_url %}`.
+    post_urls: HashMap<String, String>,
+    /// Page URLs by path from the site root, for `{% link %}`.
+    link_urls: HashMap<String, String>,
+}
+
+/// Read a Jekyll site.
+pub(super) fn read(root: &Path) -> Result<Site> {
+    let mut site = Site::default();
+    let config = read_config(root, &mut site)?;
+    let mut reader = Reader {
+        root,
+        config,
+        site,
+        post_urls: HashMap::new(),
+        link_urls: HashMap::new(),
+    };
+    reader.walk()?;
+
+    let Reader { mut site, post_urls, link_urls, .. } = reader;
+    for page in &mut site.pages {
+        page.body = convert_liquid(&page.body, &post_urls, &link_urls, &page.source, &mut site.notes);
+    }
+    if site.pages.iter().any(|page| page.frontmatter.extra.contains_key(CATEGORIES)) {
+        set_taxonomies(&mut site.config, [CATEGORIES.to_string()], &mut site.notes);
+    }
+    Ok(site)
+}
+
+/// Read `_config.yml` into the site's config.
+fn read_config(root: &Path, site: &mut Site) -> Result<JekyllConfig> {
+    let path = ["_config.yml", "_config.yaml"]
+        .into_iter()
+        .map(|name| root.join(name))
+        .find(|path| path.is_file());
+    let config = match &path {
+        Some(path) => super::read_config(path)?,
+        None => {
+            site.notes.site("no _config.yml found; using defaults");
+            Map::new()
+        }
+    };
+    let get = |key: &str| config.get(key).and_then(fields::string);
+
+    let collection = &mut site.config.collection;
+    if let Some(title) = get("title") {
+        collection.title = title;
+    }
+    collection.description = get("description").unwrap_or_default();
+    collection.author = match config.get("author") {
+        Some(Value::Object(author)) => author.get("name").and_then(fields::string),
+        Some(author) => fields::string(author),
+        None => None,
+    }
+    .unwrap_or_default();
+    if let Some(lang) = get("lang").or_else(|| get("locale")) {
+        collection.language = language_code(&lang);
+    }
+    let base_url = format!("{}{}", get("url").unwrap_or_default(), get("baseurl").unwrap_or_default());
+    collection.base_url = base_url.trim_end_matches('/').to_string();
+
+    if let Some(paginate) = config.get("paginate").and_then(Value::as_u64) {
+        site.config.content.paginate = paginate as usize;
+    }
+    if let Some(separator) = get("excerpt_separator") {
+        site.config.content.excerpt_separator = separator;
+    }
+    let plugins: Vec<String> = ["plugins", "gems"]
+        .iter()
+        .filter_map(|key| config.get(*key))
+        .flat_map(fields::terms)
+        .collect();
+    if !plugins.is_empty() {
+        site.notes.site(format!("plugins not carried over: {}", plugins.join(", ")));
+    }
+
+    let mut permalink = match get("permalink").as_deref() {
+        None | Some("date") => "/:categories/:year/:month/:day/:title:output_ext".to_string(),
+        Some("pretty") => "/:categories/:year/:month/:day/:title/".to_string(),
+        Some("ordinal") => "/:categories/:year/:y_day/:title:output_ext".to_string(),
+        Some("none") => "/:categories/:title:output_ext".to_string(),
+        Some(pattern) => pattern.to_string(),
+    };
+
+    let mut collections = HashMap::new();
+    let declared: Vec<(String, Option<&Value>)> = match config.get("collections") {
+        Some(Value::Object(map)) => map.iter().map(|(name, value)| (name.clone(), Some(value))).collect(),
+        Some(value) => fields::terms(value).into_iter().map(|name| (name, None)).collect(),
+        None => Vec::new(),
+    };
+    for (name, settings) in declared {
+        let setting = |key: &str| settings.and_then(|s| s.get(key));
+        let pattern = setting("permalink").and_then(fields::string);
+        if name == "posts" {
+            permalink = pattern.unwrap_or(permalink);
+        } else if setting("output").and_then(Value::as_bool) == Some(true) {
+            collections.insert(name, Some(pattern.unwrap_or_else(|| "/:collection/:path/".to_string())));
+        } else {
+            site.notes.site(format!("collection `{}` is not output by Jekyll; skipped", name));
+            collections.insert(name, None);
+        }
+    }
+
+    Ok(JekyllConfig {
+        permalink,
+        collections,
+        exclude: config.get("exclude").map(fields::terms_list).unwrap_or_default(),
+    })
+}
+
+impl Reader<'_> {
+    /// Read everything under the site root.
+    fn walk(&mut self) -> Result<()> {
+        let root = self.root;
+        let mut entries = WalkDir::new(root).min_depth(1).sort_by_file_name().into_iter();
+        while let Some(entry) = entries.next() {
+            let entry = entry?;
+            let path = entry.path();
+            let rel = relative_url(root, path);
+            let name = entry.file_name().to_string_lossy();
+            let is_dir = entry.file_type().is_dir();
+
+            let skipped = name.starts_with('.')
+                || SKIPPED.contains(&name.as_ref())
+                || name.ends_with(".gemspec")
+                || self.config.exclude.iter().any(|e| e.trim_matches('/') == rel);
+            if skipped || (is_dir && name.starts_with('_')) {
+                if is_dir {
+                    entries.skip_current_dir();
+                }
+                if !skipped {
+                    self.read_special_dir(path, &rel, &name)?;
+                }
+                continue;
+            }
+            if is_dir || name.starts_with('_') {
+                continue;
+            }
+
+            if !fields::has_frontmatter(&std::fs::read(path)?) {
+                self.site.assets.push((path.to_path_buf(), Path::new("static").join(&rel)));
+            } else if !is_markdown(path) {
+                self.site.notes.add(path, "skipped: only markdown pages are imported");
+            } else {
+                self.read_page(path, &rel);
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a directory whose name starts with `_`.
+    fn read_special_dir(&mut self, path: &Path, rel: &str, name: &str) -> Result<()> {
+        match name {
+            "_posts" | "_drafts" => {
+                // Directories above `_posts` are categories of its posts.
+                let categories: Vec<String> = match rel.rsplit_once('/') {
+                    Some((dir, _)) => dir.split('/').map(String::from).collect(),
+                    None => Vec::new(),
+                };
+                for file in files(path)? {
+                    self.read_post(path, &file, &categories, name == "_drafts");
+                }
+            }
+            "_data" if rel == name => {
+                for file in files(path)? {
+                    let dest = Path::new(crate::build::DATA_DIR).join(file.strip_prefix(path).unwrap_or(&file));
+                    self.site.assets.push((file, dest));
+                }
+            }
+            _ if TEMPLATE_DIRS.contains(&name) => {
+                self.site.notes.site(format!("{}/ not imported; templates need rewriting for Tera", rel));
+            }
+            _ => match self.config.collections.get(&name[1..]).cloned() {
+                Some(Some(pattern)) if rel == name => {
+                    for file in files(path)? {
+                        self.read_document(path, &file, &name[1..], &pattern)?;
+                    }
+                }
+                Some(None) => {}
+                _ => self.site.notes.site(format!("{}/ skipped", rel)),
+            },
+        }
+        Ok(())
+    }
+
+    /// Read a post from `_posts` or `_drafts`.
+    fn read_post(&mut self, dir: &Path, path: &Path, categories: &[String], draft: bool) {
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let (date, slug) = split_date_prefix(stem);
+        if !is_markdown(path) {
+            self.site.notes.add(path, "skipped: only markdown posts are imported");
+            return;
+        }
+        if date.is_none() && !draft {
+            self.site.notes.add(path, "skipped: post file names start with a date, as in 2024-01-15-title.md");
+            return;
+        }
+        let Some((fields, body)) = read_page(path, &mut self.site.notes) else { return };
+
+        let mut fm = fields::convert(&fields, &[CATEGORIES.to_string()], path, &mut self.site.notes);
+        fm.date = fm.date.or(date);
+        fm.draft |= draft;
+        let slug = fields.get("slug").and_then(fields::string).unwrap_or_else(|| slug.to_string());
+        if fm.title.is_empty() {
+            fm.title = title_from_slug(&slug);
+        }
+
+        let mut all_categories = categories.to_vec();
+        for term in terms(&fm.extra, CATEGORIES) {
+            if !all_categories.contains(&term) {
+                all_categories.push(term);
+            }
+        }
+        if !all_categories.is_empty() {
+            let terms = all_categories.iter().cloned().map(toml::Value::String).collect();
+            fm.extra.insert(CATEGORIES.to_string(), toml::Value::Array(terms));
+        }
+
+        let vars = PermalinkVars {
+            date: fm.date,
+            title: &slug,
+            filename: stem,
+            categories: &all_categories,
+            ..Default::default()
+        };
+        let url = match fields.get("permalink").and_then(fields::string) {
+            Some(pattern) => self.expand(&pattern, &vars, path),
+            // Jekyll dates drafts when it builds them; undated drafts have no place yet.
+            None if fm.date.is_none() => format!("/{}/", slug),
+            None => {
+                let pattern = self.config.permalink.clone();
+                self.expand(&pattern, &vars, path)
+            }
+        };
+
+        let key = path.strip_prefix(dir).unwrap_or(path).with_extension("");
+        self.post_urls.insert(key.to_string_lossy().replace('\\', "/"), url.clone());
+        self.add_page(path, url, fm, body);
+    }
+
+    /// Read a page outside of posts and collections.
+    fn read_page(&mut self, path: &Path, rel: &str) {
+        let Some((fields, body)) = read_page(path, &mut self.site.notes) else { return };
+        let mut fm = fields::convert(&fields, &[CATEGORIES.to_string()], path, &mut self.site.notes);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+
+        let without_ext = rel.rsplit_once('.').map_or(rel, |(base, _)| base);
+        let default_url = match without_ext.strip_suffix("index") {
+            Some(dir) if dir.is_empty() || dir.ends_with('/') => format!("/{}", dir),
+            _ => format!("/{}/", without_ext),
+        };
+        let url = match fields.get("permalink").and_then(fields::string) {
+            Some(pattern) => {
+                let vars = PermalinkVars { date: fm.date, title: stem, filename: stem, ..Default::default() };
+                self.expand(&pattern, &vars, path)
+            }
+            None => default_url,
+        };
+        if fm.title.is_empty() {
+            fm.title = match url.as_str() {
+                "/" => self.site.config.collection.title.clone(),
+                _ => title_from_slug(stem),
+            };
+        }
+        self.add_page(path, url, fm, body);
+    }
+
+    /// Read a document of a published collection.
+    fn read_document(&mut self, dir: &Path, path: &Path, collection: &str, pattern: &str) -> Result<()> {
+        let rel = path.strip_prefix(dir).unwrap_or(path);
+        if !fields::has_frontmatter(&std::fs::read(path)?) || !is_markdown(path) {
+            let dest = Path::new("static").join(collection).join(rel);
+            self.site.assets.push((path.to_path_buf(), dest));
+            return Ok(());
+        }
+        let Some((fields, body)) = read_page(path, &mut self.site.notes) else { return Ok(()) };
+        let mut fm = fields::convert(&fields, &[CATEGORIES.to_string()], path, &mut self.site.notes);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        if fm.title.is_empty() {
+            fm.title = title_from_slug(stem);
+        }
+
+        let rel_path = rel.with_extension("").to_string_lossy().replace('\\', "/");
+        let pattern = fields.get("permalink").and_then(fields::string).unwrap_or_else(|| pattern.to_string());
+        let pattern = pattern.replace(":collection", collection).replace(":path", &rel_path);
+        let vars = PermalinkVars { date: fm.date, title: stem, filename: stem, ..Default::default() };
+        let url = self.expand(&pattern, &vars, path);
+        self.add_page(path, url, fm, body);
+        Ok(())
+    }
+
+    /// Expand a permalink, placing the page by its slug if that fails.
+    fn expand(&mut self, pattern: &str, vars: &PermalinkVars, source: &Path) -> String {
+        expand_permalink(pattern, vars).unwrap_or_else(|e| {
+            let url = format!("/{}/", vars.title);
+            self.site.notes.add(source, format!("permalink `{}`: cannot fill {}; placed at {}", pattern, e, url));
+            url
+        })
+    }
+
+    fn add_page(&mut self, path: &Path, url: String, frontmatter: crate::collection::Frontmatter, body: String) {
+        self.link_urls.insert(relative_url(self.root, path), url.clone());
+        self.site.pages.push(Page {
+            source: path.to_path_buf(),
+            url,
+            frontmatter,
+            body,
+            assets: Vec::new(),
+        });
+    }
+}
+
+/// Terms of a taxonomy stored in `extra`.
+fn terms(extra: &HashMap<String, toml::Value>, taxonomy: &str) -> Vec<String> {
+    match extra.get(taxonomy) {
+        Some(toml::Value::Array(terms)) => terms.iter().filter_map(|t| t.as_str().map(String::from)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Convert the Liquid in a page that has a markdown equivalent.
+fn convert_liquid(
+    body: &str,
+    post_urls: &HashMap<String, String>,
+    link_urls: &HashMap<String, String>,
+    source: &Path,
+    notes: &mut super::Notes,
+) -> String {
+    let mut out = String::with_capacity(body.len());
+    let mut last = 0;
+    let mut raw = false;
+    let mut left: Vec<&str> = Vec::new();
+
+    for caps in LIQUID_RE.captures_iter(body) {
+        let whole = caps.get(0).expect("group 0 always matches");
+        let tag = caps.get(1).map(|t| t.as_str());
+        // Text inside `{% raw %}` is copied as it is when the block ends.
+        if raw && tag != Some("endraw") {
+            continue;
+        }
+        out.push_str(&body[last..whole.start()]);
+        last = whole.end();
+
+        let args = caps.get(2).map_or("", |a| a.as_str()).trim();
+        let mut resolve = |urls: &HashMap<String, String>, key: &str| match urls.get(key) {
+            Some(url) => out.push_str(url),
+            None => {
+                notes.add(source, format!("{} target `{}` not found", tag.unwrap_or_default(), key));
+                out.push_str(whole.as_str());
+            }
+        };
+        match tag {
+            Some("raw") => raw = true,
+            Some("endraw") => raw = false,
+            Some("post_url") => resolve(post_urls, args),
+            Some("link") => resolve(link_urls, args.trim_start_matches('/')),
+            Some("highlight") => {
+                out.push_str("```");
+                out.push_str(args.split_whitespace().next().unwrap_or_default());
+            }
+            Some("endhighlight") => {
+                if !out.ends_with('\n') {
+                    out.push('\n');
+                }
+                out.push_str("```");
+            }
+            Some(_) => {
+                left.push(whole.as_str());
+                out.push_str(whole.as_str());
+            }
+            None => {
+                let expression = caps[3].trim();
+                if matches!(expression, "site.baseurl" | "site.url") {
+                    continue;
+                }
+                match URL_FILTER_RE.captures(expression) {
+                    Some(filtered) => out.push_str(&filtered[1]),
+                    None => {
+                        left.push(whole.as_str());
+                        out.push_str(whole.as_str());
+                    }
+                }
+            }
+        }
+    }
+    out.push_str(&body[last..]);
+
+    if let Some(first) = left.first() {
+        let count = match left.len() {
+            1 => "Liquid left as is:".to_string(),
+            n => format!("{} pieces of Liquid left as is, first", n),
+        };
+        notes.add(source, format!("{} `{}`", count, first));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::import::Notes;
+
+    #[test]
+    fn test_convert_liquid() {
+        let post_urls = HashMap::from([("2024-01-15-hello".to_string(), "/2024/01/15/hello/".to_string())]);
+        let link_urls = HashMap::from([("about.md".to_string(), "/about/".to_string())]);
+        let body = "\
+See [hello]({% post_url 2024-01-15-hello %}) and [about]({{ site.baseurl }}{% link about.md %}).
+![x]({{ '/img/x.png' | relative_url }})
+
+{% highlight ruby linenos %}
+puts 1
+{% endhighlight %}
+
+{% raw %}{{ kept }}{% endraw %}
+{% include note.html %}
+";
+        let mut notes = Notes::default();
+        let out = convert_liquid(body, &post_urls, &link_urls, Path::new("post.md"), &mut notes);
+        assert_eq!(out, "\
+See [hello](/2024/01/15/hello/) and [about](/about/).
+![x](/img/x.png)
+
+```ruby
+puts 1
+```
+
+{{ kept }}
+{% include note.html %}
+");
+        let notes = notes.into_notes();
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].to_string(), "post.md: Liquid left as is: `{% include note.html %}`");
+    }
+
+    #[test]
+    fn test_read_site() {
+        let dir = rustmax::tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("_config.yml", "title: My Blog\nlang: en-US\nurl: https://example.com\nbaseurl: /blog\npermalink: pretty\nplugins:\n  - jekyll-feed\n");
+        write("_posts/2024-01-15-hello-world.md", "---\nlayout: post\ncategories: rust\n---\nHello.\n");
+        write("news/_posts/2024-02-01-update.md", "---\ntitle: Update\n---\nSee {% post_url 2024-01-15-hello-world %}.\n");
+        write("_posts/notes.md", "---\n---\nUndated.\n");
+        write("_drafts/idea.md", "---\ntitle: Idea\n---\nLater.\n");
+        write("about.md", "---\ntitle: About\n---\nAbout.\n");
+        write("index.html", "---\nlayout: home\n---\n");
+        write("_layouts/post.html", "{{ content }}");
+        write("assets/style.css", "body {}");
+        write("_data/nav.yml", "- home\n");
+        write("Gemfile", "source 'https://rubygems.org'\n");
+
+        let site = read(root).unwrap();
+        assert_eq!(site.config.collection.title, "My Blog");
+        assert_eq!(site.config.collection.base_url, "https://example.com/blog");
+        assert_eq!(site.config.content.taxonomies, ["tags", "categories"]);
+
+        let urls: HashMap<&str, &Page> = site.pages.iter().map(|p| (p.url.as_str(), p)).collect();
+        let hello = urls["/rust/2024/01/15/hello-world/"];
+        assert_eq!(hello.frontmatter.title, "Hello World");
+        let update = urls["/news/2024/02/01/update/"];
+        assert_eq!(update.body, "See /rust/2024/01/15/hello-world/.\n");
+        assert!(urls["/idea/"].frontmatter.draft);
+        assert!(urls.contains_key("/about/"));
+        assert_eq!(site.pages.len(), 4);
+
+        let assets: Vec<String> = site.assets.iter().map(|(_, to)| to.display().to_string()).collect();
+        assert_eq!(assets, ["data/nav.yml", "static/assets/style.css"]);
+
+        let notes: Vec<String> = site.notes.into_notes().iter().map(|n| n.to_string()).collect();
+        assert!(notes.contains(&"plugins not carried over: jekyll-feed".to_string()));
+        assert!(notes.iter().any(|n| n.ends_with("notes.md: skipped: post file names start with a date, as in 2024-01-15-title.md")));
+        assert!(notes.iter().any(|n| n.ends_with("index.html: skipped: only markdown pages are imported")));
+        assert!(notes.contains(&"_layouts/ not imported; templates need rewriting for Tera".to_string()));
+        assert!(notes.contains(&"frontmatter field `layout` dropped from 1 page".to_string()));
+    }
+}
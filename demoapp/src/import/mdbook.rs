@@ -0,0 +1,272 @@
+//! mdBook books: `book.toml` and the chapters listed in `SUMMARY.md`.
+//!
+//! Chapters keep their paths, with `README.md` as its directory's index,
+//! and their place in the summary as `weight`. Links between chapters are
+//! rewritten to the new URLs, and links to other files made site-absolute
+//! since each chapter now has a directory URL.
+
+use rustmax::regex::Regex;
+use rustmax::serde_json::{Map, Value};
+use rustmax::toml;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use super::{
+    Notes, Page, Site, fields, files, is_markdown, is_relative_link, language_code, read_page, relative_url,
+    rewrite_links,
+};
+use crate::{Error, Result};
+
+/// A chapter link in `SUMMARY.md`: `- [Title](path.md)`.
+static SUMMARY_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:[-*+]\s+)?\[((?:[^\]\\]|\\.)*)\]\(\s*<?([^)>]*?)>?\s*\)").expect("invalid regex")
+});
+
+/// Preprocessor directives such as `{{#include file.rs}}`.
+static DIRECTIVE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*#(\w+)[^}]*\}\}").expect("invalid regex")
+});
+
+/// A chapter listed in the summary.
+struct Chapter {
+    title: String,
+    /// Path relative to the source directory, `/`-separated.
+    path: String,
+}
+
+/// Read an mdBook book.
+pub(super) fn read(root: &Path) -> Result<Site> {
+    let mut site = Site::default();
+
+    let book_toml = root.join("book.toml");
+    let config = match book_toml.is_file() {
+        true => super::read_config(&book_toml)?,
+        false => {
+            site.notes.site("no book.toml found; using defaults");
+            Map::new()
+        }
+    };
+    let src = read_config(&config, &mut site);
+    let src_dir = root.join(src);
+    let summary_path = src_dir.join("SUMMARY.md");
+    let summary = std::fs::read_to_string(&summary_path)
+        .map_err(|e| Error::import(&summary_path, e.to_string()))?;
+    let chapters = parse_summary(&summary, &summary_path, &mut site.notes);
+
+    let mut refs = HashMap::new();
+    for (index, chapter) in chapters.iter().enumerate() {
+        let path = src_dir.join(&chapter.path);
+        if !path.is_file() {
+            site.notes.add(&summary_path, format!("chapter file {} not found", chapter.path));
+            continue;
+        }
+        let Some((fields, body)) = read_page(&path, &mut site.notes) else { continue };
+        let mut fm = fields::convert(&fields, &[], &path, &mut site.notes);
+        if fm.title.is_empty() {
+            fm.title = chapter.title.clone();
+        }
+        fm.extra.entry("weight".to_string()).or_insert(toml::Value::Integer(index as i64 + 1));
+
+        let url = chapter_url(&chapter.path);
+        refs.insert(chapter.path.clone(), url.clone());
+        site.pages.push(Page { source: path, url, frontmatter: fm, body, assets: Vec::new() });
+    }
+    if !site.pages.is_empty() {
+        site.notes.site("chapter order is kept as `weight`; templates must sort by it to list chapters in order");
+    }
+
+    for page in &mut site.pages {
+        let rel = relative_url(&src_dir, &page.source);
+        page.body = convert_body(&page.body, &rel, &refs, &page.source, &mut site.notes);
+    }
+
+    let listed: HashSet<PathBuf> = chapters.iter().map(|c| src_dir.join(&c.path)).collect();
+    for file in files(&src_dir)? {
+        if file == summary_path {
+            continue;
+        }
+        if is_markdown(&file) {
+            if !listed.contains(&file) {
+                site.notes.add(&file, "skipped: not listed in SUMMARY.md");
+            }
+            continue;
+        }
+        let to = Path::new("static").join(file.strip_prefix(&src_dir).unwrap_or(&file));
+        site.assets.push((file, to));
+    }
+    if root.join("theme").is_dir() {
+        site.notes.site("theme/ not imported; templates need rewriting for Tera");
+    }
+    Ok(site)
+}
+
+/// Read `book.toml` into the site's config, returning the source directory.
+fn read_config(config: &Map<String, Value>, site: &mut Site) -> String {
+    let book = config.get("book");
+    let get = |key: &str| book.and_then(|b| b.get(key)).and_then(fields::string);
+
+    let collection = &mut site.config.collection;
+    if let Some(title) = get("title") {
+        collection.title = title;
+    }
+    collection.description = get("description").unwrap_or_default();
+    let authors = book.and_then(|b| b.get("authors")).map(fields::terms_list).unwrap_or_default();
+    collection.author = authors.join(", ");
+    if let Some(language) = get("language") {
+        collection.language = language_code(&language);
+    }
+
+    let preprocessors: Vec<&String> = config
+        .get("preprocessor")
+        .and_then(Value::as_object)
+        .into_iter()
+        .flat_map(|p| p.keys())
+        .collect();
+    for name in preprocessors {
+        site.notes.site(format!("preprocessor `{}` not carried over", name));
+    }
+    get("src").unwrap_or_else(|| "src".to_string())
+}
+
+/// Chapters in the order `SUMMARY.md` lists them.
+fn parse_summary(summary: &str, path: &Path, notes: &mut Notes) -> Vec<Chapter> {
+    let mut chapters = Vec::new();
+    for line in summary.lines() {
+        let Some(caps) = SUMMARY_RE.captures(line) else { continue };
+        let title = caps[1].replace("\\", "");
+        let target = caps[2].trim();
+        if target.is_empty() {
+            notes.add(path, format!("draft chapter `{}` has no file; skipped", title));
+            continue;
+        }
+        let target = target.trim_start_matches("./").to_string();
+        if !chapters.iter().any(|c: &Chapter| c.path == target) {
+            chapters.push(Chapter { title, path: target });
+        }
+    }
+    chapters
+}
+
+/// URL of a chapter: `guide/intro.md` is `/guide/intro/`, `guide/README.md` is `/guide/`.
+fn chapter_url(path: &str) -> String {
+    let without_ext = path.rsplit_once('.').map_or(path, |(base, _)| base);
+    let (dir, stem) = without_ext.rsplit_once('/').unwrap_or(("", without_ext));
+    match (stem, dir) {
+        ("README" | "index", "") => "/".to_string(),
+        ("README" | "index", _) => format!("/{}/", dir),
+        _ => format!("/{}/", without_ext),
+    }
+}
+
+/// Resolve `target` against the directory of the page at `rel`.
+fn resolve(rel: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = rel.split('/').collect();
+    segments.pop();
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Rewrite relative links and note preprocessor directives.
+fn convert_body(body: &str, rel: &str, refs: &HashMap<String, String>, source: &Path, notes: &mut Notes) -> String {
+    let body = rewrite_links(body, |target| {
+        if !is_relative_link(target) {
+            return None;
+        }
+        let (path, anchor) = target.split_once('#').map_or((target, ""), |(p, a)| (p, a));
+        let resolved = resolve(rel, path);
+        // mdBook writes chapters as `.html`, and `README.md` as `index.html`.
+        let as_markdown = match resolved.strip_suffix(".html") {
+            Some(base) => format!("{}.md", base),
+            None => resolved.clone(),
+        };
+        let readme = as_markdown.strip_suffix("index.md").map(|dir| format!("{}README.md", dir));
+        let url = match refs.get(&as_markdown).or_else(|| refs.get(readme.as_ref()?)) {
+            Some(url) => url.clone(),
+            None if path.ends_with(".md") || path.ends_with(".html") => {
+                notes.add(source, format!("link target `{}` not found", target));
+                return None;
+            }
+            None => format!("/{}", resolved),
+        };
+        Some(if anchor.is_empty() { url } else { format!("{}#{}", url, anchor) })
+    });
+
+    let mut directives: Vec<&str> = DIRECTIVE_RE.captures_iter(&body).map(|c| c.get(1).map_or("", |m| m.as_str())).collect();
+    directives.dedup();
+    for directive in directives {
+        notes.add(source, format!("`{{{{#{}}}}}` not expanded", directive));
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_summary() {
+        let summary = "\
+# Summary
+
+[Introduction](README.md)
+
+# Guide
+
+- [Getting \\[Started\\]](guide/start.md)
+    - [Install](./guide/install.md)
+- [Later]()
+---
+[Appendix](appendix.md)
+";
+        let mut notes = Notes::default();
+        let chapters = parse_summary(summary, Path::new("SUMMARY.md"), &mut notes);
+        let titles: Vec<(&str, &str)> = chapters.iter().map(|c| (c.title.as_str(), c.path.as_str())).collect();
+        assert_eq!(titles, [
+            ("Introduction", "README.md"),
+            ("Getting [Started]", "guide/start.md"),
+            ("Install", "guide/install.md"),
+            ("Appendix", "appendix.md"),
+        ]);
+        assert_eq!(notes.into_notes()[0].to_string(), "SUMMARY.md: draft chapter `Later` has no file; skipped");
+
+        assert_eq!(chapter_url("README.md"), "/");
+        assert_eq!(chapter_url("guide/README.md"), "/guide/");
+        assert_eq!(chapter_url("guide/start.md"), "/guide/start/");
+    }
+
+    #[test]
+    fn test_convert_body() {
+        let refs = HashMap::from([
+            ("README.md".to_string(), "/".to_string()),
+            ("guide/install.md".to_string(), "/guide/install/".to_string()),
+        ]);
+        let body = "\
+[Install](install.md#linux), [home](../index.html), [gone](missing.md)
+![Diagram](../images/flow.png) [Docs](https://example.com)
+
+{{#include ../code/main.rs}}
+";
+        let mut notes = Notes::default();
+        let out = convert_body(body, "guide/start.md", &refs, Path::new("start.md"), &mut notes);
+        assert_eq!(out, "\
+[Install](/guide/install/#linux), [home](/), [gone](missing.md)
+![Diagram](/images/flow.png) [Docs](https://example.com)
+
+{{#include ../code/main.rs}}
+");
+        let notes: Vec<String> = notes.into_notes().iter().map(|n| n.to_string()).collect();
+        assert_eq!(notes, [
+            "start.md: link target `missing.md` not found",
+            "start.md: `{{#include}}` not expanded",
+        ]);
+    }
+}
@@ -0,0 +1,595 @@
+//! Importing sites from other static site generators.
+//!
+//! Each importer reads a site into pages, assets and a [`Config`]; this
+//! module writes them out as a collection. Pages are placed so that the
+//! scanner gives them the URLs they had before: a page that lived at
+//! `/2024/01/hello/` becomes `content/2024/01/hello.md`, a page with
+//! children becomes its directory's `_index.md`, and a page with its own
+//! assets becomes a bundle. Anything that has no counterpart in Anthology
+//! is listed in the [`ImportReport`].
+
+mod fields;
+mod hugo;
+mod jekyll;
+mod mdbook;
+mod wxr;
+mod zola;
+
+use rustmax::jiff::civil::Date;
+use rustmax::regex::{Captures, Regex};
+use rustmax::serde_json::{Map, Value};
+use rustmax::walkdir::WalkDir;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::LazyLock;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::collection::{Config, Document, Frontmatter, SECTION_INDEX, is_valid_taxonomy};
+use crate::{Error, Result};
+
+/// A site generator or blog export to import from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Jekyll,
+    Hugo,
+    Zola,
+    MdBook,
+    /// A WordPress eXtended RSS export file.
+    Wxr,
+}
+
+impl Source {
+    /// Name of the source as written on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Jekyll => "jekyll",
+            Source::Hugo => "hugo",
+            Source::Zola => "zola",
+            Source::MdBook => "mdbook",
+            Source::Wxr => "wxr",
+        }
+    }
+}
+
+/// Something from the source site that was not carried over as it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportNote {
+    /// The file it concerns, or `None` for the site as a whole.
+    pub source: Option<PathBuf>,
+    pub message: String,
+}
+
+impl fmt::Display for ImportNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}: {}", source.display(), self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// What an import wrote, and what it couldn't translate.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Documents written to `content/`.
+    pub documents: usize,
+    /// Section `_index.md` files written.
+    pub sections: usize,
+    /// Files copied as they were.
+    pub assets: usize,
+    pub notes: Vec<ImportNote>,
+}
+
+/// A converted page waiting to be written.
+#[derive(Debug)]
+struct Page {
+    /// File it was read from, for notes.
+    source: PathBuf,
+    /// URL path the page had on the old site, e.g. `/blog/hello/`.
+    url: String,
+    frontmatter: Frontmatter,
+    body: String,
+    /// Files to keep next to the page, making it a bundle.
+    assets: Vec<PathBuf>,
+}
+
+/// Everything read from a site.
+#[derive(Debug, Default)]
+struct Site {
+    config: Config,
+    pages: Vec<Page>,
+    /// Files copied as they are, with their destination in the collection.
+    assets: Vec<(PathBuf, PathBuf)>,
+    notes: Notes,
+}
+
+/// Collects notes, counting frontmatter fields dropped from many pages.
+#[derive(Debug, Default)]
+struct Notes {
+    notes: Vec<ImportNote>,
+    once: HashSet<String>,
+    dropped: BTreeMap<String, usize>,
+}
+
+impl Notes {
+    /// Note a problem with a file.
+    fn add(&mut self, source: &Path, message: impl Into<String>) {
+        self.notes.push(ImportNote {
+            source: Some(source.to_path_buf()),
+            message: message.into(),
+        });
+    }
+
+    /// Note something about the whole site, once.
+    fn site(&mut self, message: impl Into<String>) {
+        let message = message.into();
+        if self.once.insert(message.clone()) {
+            self.notes.push(ImportNote { source: None, message });
+        }
+    }
+
+    /// Count a frontmatter field that has no Anthology equivalent.
+    fn dropped(&mut self, field: &str) {
+        *self.dropped.entry(field.to_string()).or_default() += 1;
+    }
+
+    fn into_notes(mut self) -> Vec<ImportNote> {
+        for (field, count) in self.dropped {
+            let pages = if count == 1 { "page" } else { "pages" };
+            self.notes.push(ImportNote {
+                source: None,
+                message: format!("frontmatter field `{}` dropped from {} {}", field, count, pages),
+            });
+        }
+        self.notes
+    }
+}
+
+/// Import a site at `from` into a new collection at `to`.
+///
+/// For [`Source::Wxr`], `from` is the export file. Fails if `to` already
+/// holds a collection.
+pub fn import_site(source: Source, from: &Path, to: &Path) -> Result<ImportReport> {
+    if to.join("anthology.toml").exists() {
+        return Err(Error::config(format!("Collection already exists at {}", to.display())));
+    }
+    if !from.exists() {
+        return Err(Error::import(from, "not found"));
+    }
+
+    let site = match source {
+        Source::Jekyll => jekyll::read(from)?,
+        Source::Hugo => hugo::read(from)?,
+        Source::Zola => zola::read(from)?,
+        Source::MdBook => mdbook::read(from)?,
+        Source::Wxr => wxr::read(from)?,
+    };
+    write_site(site, to)
+}
+
+/// Write an imported site as a collection.
+fn write_site(site: Site, root: &Path) -> Result<ImportReport> {
+    let Site { config, pages, assets, mut notes } = site;
+    let content_dir = root.join("content");
+    std::fs::create_dir_all(&content_dir)?;
+    std::fs::create_dir_all(root.join("templates"))?;
+    std::fs::create_dir_all(root.join("static"))?;
+
+    let config_toml = rustmax::toml::to_string_pretty(&config).map_err(|e| Error::config(e.to_string()))?;
+    std::fs::write(root.join("anthology.toml"), config_toml)?;
+    std::fs::write(root.join("templates/default.html"), include_str!("../../templates/default.html"))?;
+
+    let mut report = ImportReport::default();
+
+    // A page whose URL has pages below it is its section's index.
+    let dirs: BTreeSet<String> = pages
+        .iter()
+        .flat_map(|page| {
+            let segments = url_segments(&page.url);
+            (1..segments.len()).map(move |n| segments[..n].join("/"))
+        })
+        .collect();
+
+    let mut written = HashSet::new();
+    for page in pages {
+        if page.url.split('/').any(is_unsafe_segment) {
+            notes.add(&page.source, format!("`.`, `..` and `\\` segments dropped from the URL {}", page.url));
+        }
+        let segments = url_segments(&page.url);
+        let joined = segments.join("/");
+        let mut path: PathBuf = segments.iter().collect();
+        let is_section = segments.is_empty() || dirs.contains(&joined);
+        if is_section {
+            path.push(SECTION_INDEX);
+        } else if !page.assets.is_empty() {
+            path.push("index.md");
+        } else {
+            path.set_file_name(format!("{}.md", segments[segments.len() - 1]));
+        }
+
+        if !written.insert(path.clone()) {
+            notes.add(&page.source, format!("skipped: another page already has the URL {}", page.url));
+            continue;
+        }
+
+        let text = fields::render(&page.frontmatter, &page.body);
+        let dest = content_dir.join(&path);
+        // Make sure the page reads back as Anthology writes it.
+        Document::parse(dest.clone(), &text)?;
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&dest, text)?;
+
+        // Sections aren't bundles, so their files are served from `static/`.
+        let dir = match is_section {
+            true => root.join("static").join(&joined),
+            false => dest.parent().unwrap_or(&content_dir).to_path_buf(),
+        };
+        if !page.assets.is_empty() {
+            std::fs::create_dir_all(&dir)?;
+        }
+        for asset in &page.assets {
+            let Some(name) = asset.file_name() else { continue };
+            std::fs::copy(asset, dir.join(name))?;
+            report.assets += 1;
+        }
+        if is_section {
+            report.sections += 1;
+        } else {
+            report.documents += 1;
+        }
+    }
+
+    for (from, to) in assets {
+        let dest = root.join(&to);
+        if dest.exists() {
+            notes.add(&from, format!("skipped: {} already exists", to.display()));
+            continue;
+        }
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::copy(&from, &dest)?;
+        report.assets += 1;
+    }
+
+    report.notes = notes.into_notes();
+    Ok(report)
+}
+
+/// Read a page's frontmatter fields and body.
+///
+/// Pages that can't be read are noted and skipped.
+fn read_page(path: &Path, notes: &mut Notes) -> Option<(Map<String, Value>, String)> {
+    let raw = match std::fs::read_to_string(path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            notes.add(path, format!("skipped: {}", e));
+            return None;
+        }
+    };
+    match fields::parse(&raw) {
+        Ok((fields, body)) => Some((fields, body.to_string())),
+        Err(e) => {
+            notes.add(path, format!("skipped: {}", e));
+            None
+        }
+    }
+}
+
+/// Read a configuration file as TOML, YAML or JSON by its extension.
+fn read_config(path: &Path) -> Result<Map<String, Value>> {
+    let text = std::fs::read_to_string(path)?;
+    let value = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => rustmax::toml::from_str::<rustmax::toml::Table>(&text)
+            .map(|table| fields::toml_to_json(rustmax::toml::Value::Table(table)))
+            .map_err(|e| e.message().trim().to_string()),
+        Some("json") => rustmax::serde_json::from_str(&text).map_err(|e| e.to_string()),
+        _ => crate::yaml::parse(&text).map_err(|e| e.to_string()),
+    };
+    match value.map_err(|e| Error::import(path, e))? {
+        Value::Object(map) => Ok(map),
+        Value::Null => Ok(Map::new()),
+        _ => Err(Error::import(path, "configuration is not a mapping")),
+    }
+}
+
+/// Files under a directory, in name order, skipping hidden ones.
+///
+/// A missing directory has no files.
+fn files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+    let entries = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_name().to_string_lossy().starts_with('.'));
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// Use the given taxonomies, noting names Anthology can't build pages for.
+fn set_taxonomies(config: &mut Config, names: impl IntoIterator<Item = String>, notes: &mut Notes) {
+    let mut taxonomies = vec![crate::collection::TAGS.to_string()];
+    for name in names {
+        if !is_valid_taxonomy(&name) {
+            notes.site(format!("taxonomy `{}` skipped: names may only use letters, digits, `-` and `_`", name));
+        } else if !taxonomies.contains(&name) {
+            taxonomies.push(name);
+        }
+    }
+    config.content.taxonomies = taxonomies;
+}
+
+/// Split a `2024-01-15-` date prefix off a file name, as blogs name posts.
+fn split_date_prefix(stem: &str) -> (Option<Date>, &str) {
+    let date = stem.get(..10).and_then(fields::parse_date);
+    match stem.get(10..11) {
+        Some("-" | "_") if date.is_some() => (date, &stem[11..]),
+        _ => (None, stem),
+    }
+}
+
+/// A title from a slug: `hello-world` becomes `Hello World`.
+fn title_from_slug(slug: &str) -> String {
+    slug.split(['-', '_'])
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Shortcodes Anthology has built in.
+const BUILTIN_SHORTCODES: &[&str] = &["youtube", "figure", "note", "warning", "tip", "code", "toc"];
+
+/// Note a converted shortcode that needs a template to render.
+fn note_shortcode(name: &str, notes: &mut Notes) {
+    if !BUILTIN_SHORTCODES.contains(&name) {
+        notes.site(format!("shortcode `{}` is not built in; add templates/shortcodes/{}.html", name, name));
+    }
+}
+
+/// Markdown link and image targets, inline or in reference definitions.
+static LINK_TARGET_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?m)(\]\(\s*<?|^\s{0,3}\[[^\]]+\]:\s*)([^\s)>]+)"#).expect("invalid regex")
+});
+
+/// Rewrite the targets of markdown links and images.
+///
+/// `rewrite` returns the new target, or `None` to keep a target as it is.
+fn rewrite_links(body: &str, mut rewrite: impl FnMut(&str) -> Option<String>) -> String {
+    LINK_TARGET_RE
+        .replace_all(body, |caps: &Captures| {
+            let target = rewrite(&caps[2]).unwrap_or_else(|| caps[2].to_string());
+            format!("{}{}", &caps[1], target)
+        })
+        .into_owned()
+}
+
+/// Whether a link target is relative to the page it's on.
+fn is_relative_link(target: &str) -> bool {
+    !(target.starts_with(['/', '#', '@']) || target.contains("://") || target.starts_with("mailto:"))
+}
+
+/// Segments of a URL path that are safe to use as file names.
+///
+/// Empty segments are skipped, and so are [unsafe ones](is_unsafe_segment).
+fn url_segments(url: &str) -> Vec<&str> {
+    url.split('/').filter(|s| !s.is_empty() && !is_unsafe_segment(s)).collect()
+}
+
+/// Whether a URL segment would leave its directory if used as a file name.
+fn is_unsafe_segment(segment: &str) -> bool {
+    matches!(segment, "." | "..") || segment.contains('\\')
+}
+
+/// A URL path in `/a/b/` form.
+///
+/// Unsafe segments are kept, so [`write_site`] can note them when it drops them.
+fn normalize_url(url: &str) -> String {
+    let segments: Vec<&str> = url.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.is_empty() { "/".to_string() } else { format!("/{}/", segments.join("/")) }
+}
+
+/// Values for the placeholders of a permalink pattern.
+#[derive(Debug, Default)]
+struct PermalinkVars<'a> {
+    date: Option<Date>,
+    /// Slug from the title or file name.
+    title: &'a str,
+    /// Explicit slug, falling back to `title`.
+    slug: Option<&'a str>,
+    /// Source file name without its extension.
+    filename: &'a str,
+    /// Section path, `/`-separated.
+    section: &'a str,
+    categories: &'a [String],
+}
+
+/// Expand a permalink pattern such as `/:year/:month/:title/`.
+///
+/// Jekyll and Hugo placeholders are understood. URLs ending in `.html`
+/// become directory URLs, since that is what Anthology writes. Fails with
+/// the placeholder that could not be filled.
+fn expand_permalink(pattern: &str, vars: &PermalinkVars) -> std::result::Result<String, String> {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(i) = rest.find(':') {
+        out.push_str(&rest[..i]);
+        let name_len = rest[i + 1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len() - i - 1);
+        let name = &rest[i + 1..i + 1 + name_len];
+        let date = || vars.date.ok_or_else(|| format!(":{} needs a date", name));
+        let value = match name {
+            "year" => date()?.year().to_string(),
+            "short_year" => format!("{:02}", date()?.year() % 100),
+            "month" => format!("{:02}", date()?.month()),
+            "i_month" => date()?.month().to_string(),
+            "day" => format!("{:02}", date()?.day()),
+            "i_day" => date()?.day().to_string(),
+            "title" | "slugified_title" => vars.title.to_string(),
+            "slug" => vars.slug.unwrap_or(vars.title).to_string(),
+            "name" | "filename" | "contentbasename" => vars.filename.to_string(),
+            "section" => vars.section.split('/').next().unwrap_or("").to_string(),
+            "sections" => vars.section.to_string(),
+            "categories" => vars
+                .categories
+                .iter()
+                .map(|c| crate::build::slugify(c))
+                .collect::<Vec<_>>()
+                .join("/"),
+            "output_ext" => String::new(),
+            _ => return Err(format!(":{}", name)),
+        };
+        out.push_str(&value);
+        rest = &rest[i + 1 + name_len..];
+    }
+    out.push_str(rest);
+
+    Ok(normalize_url(out.strip_suffix(".html").unwrap_or(&out)))
+}
+
+/// Language code from a locale such as `en-US` or `de_DE`.
+fn language_code(locale: &str) -> String {
+    locale.split(['-', '_']).next().unwrap_or(locale).to_lowercase()
+}
+
+/// Whether a file is markdown by its extension.
+fn is_markdown(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("md" | "markdown" | "mkd" | "mdown")
+    )
+}
+
+/// Path relative to `root` with `/` separators.
+fn relative_url(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_permalink() {
+        let categories = vec!["Rust Tips".to_string()];
+        let vars = PermalinkVars {
+            date: Some("2024-03-05".parse().unwrap()),
+            title: "hello-world",
+            filename: "2024-03-05-hello-world",
+            section: "blog/archive",
+            categories: &categories,
+            ..Default::default()
+        };
+        let expand = |pattern: &str| expand_permalink(pattern, &vars);
+
+        assert_eq!(expand("/:categories/:year/:month/:day/:title:output_ext").unwrap(), "/rust-tips/2024/03/05/hello-world/");
+        assert_eq!(expand("/:year/:i_month/:title.html").unwrap(), "/2024/3/hello-world/");
+        assert_eq!(expand("/:section/:slug/").unwrap(), "/blog/hello-world/");
+        assert_eq!(expand("/:sections/:filename").unwrap(), "/blog/archive/2024-03-05-hello-world/");
+        assert_eq!(expand("/:weekday/").unwrap_err(), ":weekday");
+
+        let undated = PermalinkVars { title: "about", ..Default::default() };
+        assert_eq!(expand_permalink("/:year/:title/", &undated).unwrap_err(), ":year needs a date");
+        assert_eq!(expand_permalink("/", &undated).unwrap(), "/");
+    }
+
+    #[test]
+    fn test_write_site_places_pages() {
+        let dir = rustmax::tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let page = |url: &str, title: &str| Page {
+            source: PathBuf::from(format!("{}.md", title)),
+            url: url.to_string(),
+            frontmatter: Frontmatter { title: title.to_string(), ..Default::default() },
+            body: "Body.\n".to_string(),
+            assets: Vec::new(),
+        };
+
+        std::fs::write(root.join("photo.png"), b"png").unwrap();
+        let mut bundle = page("/gallery/", "Gallery");
+        bundle.assets.push(root.join("photo.png"));
+
+        let site = Site {
+            pages: vec![
+                page("/", "Home"),
+                page("/blog/", "Blog"),
+                page("/blog/2024/hello/", "Hello"),
+                page("/about/", "About"),
+                page("/about/", "Duplicate"),
+                bundle,
+            ],
+            ..Default::default()
+        };
+        let out = root.join("site");
+        let report = write_site(site, &out).unwrap();
+
+        assert!(out.join("anthology.toml").exists());
+        assert!(out.join("content/_index.md").exists());
+        assert!(out.join("content/blog/_index.md").exists());
+        assert!(out.join("content/blog/2024/hello.md").exists());
+        assert!(out.join("content/about.md").exists());
+        assert!(out.join("content/gallery/index.md").exists());
+        assert!(out.join("content/gallery/photo.png").exists());
+        assert_eq!((report.documents, report.sections, report.assets), (3, 2, 1));
+        assert_eq!(report.notes.len(), 1);
+        assert!(report.notes[0].to_string().starts_with("Duplicate.md: skipped"));
+
+        let config = Config::load(&out).unwrap();
+        let collection = crate::collection::Collection::load(&out, &config).unwrap();
+        let urls: BTreeSet<String> = collection.documents.iter().map(|d| d.url_path()).collect();
+        assert!(urls.contains("/blog/2024/hello/"));
+        assert!(urls.contains("/gallery/"));
+    }
+
+    #[test]
+    fn test_write_site_drops_traversal_segments() {
+        let dir = rustmax::tempfile::tempdir().unwrap();
+        let out = dir.path().join("out/site");
+        let vars = PermalinkVars { title: "post", ..Default::default() };
+        let url = expand_permalink("/../../escaped/:title/", &vars).unwrap();
+        assert_eq!(url, "/../../escaped/post/");
+
+        let page = |source: &str, url: &str| Page {
+            source: PathBuf::from(source),
+            url: url.to_string(),
+            frontmatter: Frontmatter { title: source.to_string(), ..Default::default() },
+            body: "Body.\n".to_string(),
+            assets: Vec::new(),
+        };
+        let site = Site {
+            pages: vec![page("escape.md", &url), page("windows.md", "/a/..\\..\\b/./c/")],
+            ..Default::default()
+        };
+        let report = write_site(site, &out).unwrap();
+
+        assert!(!dir.path().join("escaped").exists());
+        assert!(!dir.path().join("out/escaped").exists());
+        assert!(out.join("content/escaped/post.md").exists());
+        assert!(out.join("content/a/c.md").exists());
+        assert_eq!(report.documents, 2);
+        let notes: Vec<String> = report.notes.iter().map(|n| n.to_string()).collect();
+        assert_eq!(notes.len(), 2, "{:?}", notes);
+        assert!(notes[0].starts_with("escape.md: "), "{}", notes[0]);
+        assert!(notes[0].contains("/../../escaped/post/"), "{}", notes[0]);
+        assert!(notes[1].starts_with("windows.md: "), "{}", notes[1]);
+    }
+}
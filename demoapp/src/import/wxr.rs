@@ -0,0 +1,282 @@
+//! WordPress eXtended RSS (WXR) exports.
+//!
+//! Posts and pages are read from the export's items and placed at the
+//! paths of their old permalinks. Their HTML content is kept as it is,
+//! which markdown passes through, with the block editor's comments
+//! removed. Attachments stay on the old site and are only counted.
+
+use rustmax::regex::Regex;
+use rustmax::toml;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use super::{Page, Site, fields, language_code, set_taxonomies};
+use crate::collection::Frontmatter;
+use crate::xml::Element;
+use crate::{Error, Result};
+
+/// The block editor's `<!-- wp:paragraph -->` comments.
+static BLOCK_COMMENT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"<!--\s*/?wp:[^>]*-->\n?").expect("invalid regex")
+});
+
+/// WordPress shortcodes such as `[gallery ids="1,2"]`, which have no
+/// markdown meaning.
+static SHORTCODE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[/?(audio|caption|embed|gallery|playlist|video|wp_caption)\b[^\]]*\]").expect("invalid regex")
+});
+
+/// Categories WordPress files posts under when none is chosen.
+const UNCATEGORIZED: &str = "uncategorized";
+
+/// Read a WordPress export file.
+pub(super) fn read(path: &Path) -> Result<Site> {
+    let text = std::fs::read_to_string(path)?;
+    let rss = crate::xml::parse(&text).map_err(|e| Error::import(path, e.to_string()))?;
+    let channel = rss
+        .child("channel")
+        .ok_or_else(|| Error::import(path, "not an RSS file: no <channel>"))?;
+    if channel.child("wp:wxr_version").is_none() {
+        return Err(Error::import(path, "not a WordPress export: no <wp:wxr_version>"));
+    }
+
+    let mut site = Site::default();
+    let collection = &mut site.config.collection;
+    if let Some(title) = channel.child_text("title").filter(|t| !t.trim().is_empty()) {
+        collection.title = title.trim().to_string();
+    }
+    collection.base_url = channel.child_text("link").unwrap_or_default().trim().trim_end_matches('/').to_string();
+    collection.description = channel.child_text("description").unwrap_or_default().trim().to_string();
+    if let Some(language) = channel.child_text("language").filter(|l| !l.trim().is_empty()) {
+        collection.language = language_code(language.trim());
+    }
+
+    let authors: HashMap<String, String> = channel
+        .children_named("wp:author")
+        .filter_map(|author| {
+            let login = author.child_text("wp:author_login")?;
+            let name = author.child_text("wp:author_display_name").filter(|n| !n.is_empty())?;
+            Some((login, name))
+        })
+        .collect();
+    let site_author = authors.values().next().filter(|_| authors.len() == 1);
+    site.config.collection.author = site_author.cloned().unwrap_or_default();
+
+    // Item types and statuses left out, with how many of each.
+    let mut skipped: BTreeMap<String, usize> = BTreeMap::new();
+    let mut comments = 0;
+    for item in channel.children_named("item") {
+        let kind = item.child_text("wp:post_type").unwrap_or_else(|| "post".to_string());
+        let status = item.child_text("wp:status").unwrap_or_default();
+        if !matches!(kind.as_str(), "post" | "page") {
+            *skipped.entry(format!("{} items", kind)).or_default() += 1;
+            continue;
+        }
+        if matches!(status.as_str(), "trash" | "auto-draft" | "inherit") {
+            *skipped.entry(format!("{} items with status `{}`", kind, status)).or_default() += 1;
+            continue;
+        }
+        comments += item.children_named("wp:comment").count();
+        if let Some(page) = read_item(item, &status, &authors, path, &mut site) {
+            site.pages.push(page);
+        }
+    }
+
+    for (what, count) in skipped {
+        site.notes.site(format!("{} not imported: {}", what, count));
+    }
+    if comments > 0 {
+        site.notes.site(format!("comments not imported: {}", comments));
+    }
+    if site.pages.iter().any(|page| page.frontmatter.extra.contains_key("categories")) {
+        set_taxonomies(&mut site.config, ["categories".to_string()], &mut site.notes);
+    }
+    Ok(site)
+}
+
+/// Convert a post or page.
+fn read_item(
+    item: &Element,
+    status: &str,
+    authors: &HashMap<String, String>,
+    path: &Path,
+    site: &mut Site,
+) -> Option<Page> {
+    let text = |name: &str| item.child_text(name).map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    let id = text("wp:post_id").unwrap_or_default();
+    // Notes name the item, since the export is one file.
+    let source = PathBuf::from(format!("{}#{}", path.display(), id));
+
+    let mut fm = Frontmatter {
+        title: text("title").unwrap_or_default(),
+        description: text("excerpt:encoded"),
+        ..Default::default()
+    };
+    let date = text("wp:post_date").and_then(|d| fields::parse_date(&d));
+    match status {
+        "future" => fm.publish_date = date,
+        "draft" | "pending" | "private" => fm.draft = true,
+        _ => {}
+    }
+    fm.date = date;
+    fm.author = text("dc:creator").map(|login| authors.get(&login).cloned().unwrap_or(login));
+    if fm.author.as_deref() == Some(site.config.collection.author.as_str()) {
+        fm.author = None;
+    }
+
+    let mut categories = Vec::new();
+    for category in item.children_named("category") {
+        let term = category.text().trim().to_string();
+        match category.attr("domain") {
+            Some("post_tag") => fm.tags.push(term),
+            Some("category") if category.attr("nicename") != Some(UNCATEGORIZED) => categories.push(term),
+            Some("category") => {}
+            Some(domain) => site.notes.site(format!("terms of taxonomy `{}` not imported", domain)),
+            None => {}
+        }
+    }
+    if !categories.is_empty() {
+        let terms = categories.into_iter().map(toml::Value::String).collect();
+        fm.extra.insert("categories".to_string(), toml::Value::Array(terms));
+    }
+
+    let slug = text("wp:post_name").unwrap_or_else(|| crate::build::slugify(&fm.title));
+    if fm.title.is_empty() {
+        fm.title = super::title_from_slug(&slug);
+    }
+    let url = match text("link").as_deref().and_then(link_path) {
+        Some(url) => url,
+        None if slug.is_empty() => {
+            site.notes.add(&source, "skipped: no permalink or slug");
+            return None;
+        }
+        None => format!("/{}/", slug),
+    };
+
+    let content = item.child_text("content:encoded").unwrap_or_default();
+    let body = BLOCK_COMMENT_RE.replace_all(&content, "").trim().to_string();
+    let mut shortcodes: Vec<&str> = SHORTCODE_RE
+        .captures_iter(&body)
+        .map(|c| c.get(1).map_or("", |m| m.as_str()))
+        .collect();
+    shortcodes.sort_unstable();
+    shortcodes.dedup();
+    for name in shortcodes {
+        site.notes.add(&source, format!("WordPress shortcode `[{}]` left as is", name));
+    }
+
+    Some(Page { source, url, frontmatter: fm, body, assets: Vec::new() })
+}
+
+/// Path of a pretty permalink, or `None` for `?p=123` links.
+fn link_path(link: &str) -> Option<String> {
+    let after_scheme = link.split_once("://").map_or(link, |(_, rest)| rest);
+    let path = after_scheme.find('/').map_or("/", |i| &after_scheme[i..]);
+    if path.contains('?') {
+        return None;
+    }
+    Some(super::normalize_url(path.split('#').next().unwrap_or(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPORT: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/"
+    xmlns:excerpt="http://wordpress.org/export/1.2/excerpt/"
+    xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:wp="http://wordpress.org/export/1.2/">
+<channel>
+  <title>Old Blog</title>
+  <link>https://old.example.com</link>
+  <description>Thoughts</description>
+  <language>en-GB</language>
+  <wp:wxr_version>1.2</wp:wxr_version>
+  <wp:author><wp:author_login><![CDATA[ada]]></wp:author_login><wp:author_display_name><![CDATA[Ada L]]></wp:author_display_name></wp:author>
+  <item>
+    <title>Hello &amp; Welcome</title>
+    <link>https://old.example.com/2024/01/hello-welcome/</link>
+    <dc:creator><![CDATA[ada]]></dc:creator>
+    <content:encoded><![CDATA[<!-- wp:paragraph -->
+<p>First post.</p>
+<!-- /wp:paragraph -->
+
+[gallery ids="1,2"]]]></content:encoded>
+    <excerpt:encoded><![CDATA[]]></excerpt:encoded>
+    <wp:post_id>1</wp:post_id>
+    <wp:post_date><![CDATA[2024-01-15 10:00:00]]></wp:post_date>
+    <wp:post_name><![CDATA[hello-welcome]]></wp:post_name>
+    <wp:status><![CDATA[publish]]></wp:status>
+    <wp:post_type><![CDATA[post]]></wp:post_type>
+    <category domain="category" nicename="news"><![CDATA[News]]></category>
+    <category domain="category" nicename="uncategorized"><![CDATA[Uncategorized]]></category>
+    <category domain="post_tag" nicename="intro"><![CDATA[intro]]></category>
+    <wp:comment><wp:comment_id>5</wp:comment_id></wp:comment>
+  </item>
+  <item>
+    <title>Coming Soon</title>
+    <link>https://old.example.com/?p=2</link>
+    <wp:post_id>2</wp:post_id>
+    <wp:post_date><![CDATA[2030-01-01 00:00:00]]></wp:post_date>
+    <wp:post_name><![CDATA[coming-soon]]></wp:post_name>
+    <wp:status><![CDATA[future]]></wp:status>
+    <wp:post_type><![CDATA[post]]></wp:post_type>
+  </item>
+  <item>
+    <title>About</title>
+    <link>https://old.example.com/about/</link>
+    <content:encoded><![CDATA[About me.]]></content:encoded>
+    <wp:post_id>3</wp:post_id>
+    <wp:status><![CDATA[publish]]></wp:status>
+    <wp:post_type><![CDATA[page]]></wp:post_type>
+  </item>
+  <item>
+    <title>photo</title>
+    <wp:post_id>4</wp:post_id>
+    <wp:post_type><![CDATA[attachment]]></wp:post_type>
+  </item>
+</channel>
+</rss>
+"#;
+
+    #[test]
+    fn test_read_export() {
+        let dir = rustmax::tempfile::tempdir().unwrap();
+        let path = dir.path().join("export.xml");
+        std::fs::write(&path, EXPORT).unwrap();
+        let site = read(&path).unwrap();
+
+        let collection = &site.config.collection;
+        assert_eq!(collection.title, "Old Blog");
+        assert_eq!(collection.language, "en");
+        assert_eq!(collection.author, "Ada L");
+        assert_eq!(site.config.content.taxonomies, ["tags", "categories"]);
+
+        let urls: HashMap<&str, &Page> = site.pages.iter().map(|p| (p.url.as_str(), p)).collect();
+        assert_eq!(urls.len(), 3);
+        let hello = urls["/2024/01/hello-welcome/"];
+        assert_eq!(hello.frontmatter.title, "Hello & Welcome");
+        assert_eq!(hello.frontmatter.date.unwrap().to_string(), "2024-01-15");
+        assert_eq!(hello.frontmatter.tags, ["intro"]);
+        assert_eq!(hello.frontmatter.author, None);
+        assert_eq!(hello.frontmatter.description, None);
+        assert_eq!(hello.body, "<p>First post.</p>\n\n[gallery ids=\"1,2\"]");
+        let soon = urls["/coming-soon/"];
+        assert_eq!(soon.frontmatter.publish_date.unwrap().to_string(), "2030-01-01");
+        assert!(urls.contains_key("/about/"));
+
+        let notes: Vec<String> = site.notes.into_notes().iter().map(|n| n.to_string()).collect();
+        assert_eq!(notes.len(), 3);
+        assert!(notes[0].ends_with("export.xml#1: WordPress shortcode `[gallery]` left as is"));
+        assert_eq!(notes[1..], ["attachment items not imported: 1", "comments not imported: 1"]);
+    }
+
+    #[test]
+    fn test_read_rejects_other_feeds() {
+        let dir = rustmax::tempfile::tempdir().unwrap();
+        let path = dir.path().join("feed.xml");
+        std::fs::write(&path, "<rss><channel><title>x</title></channel></rss>").unwrap();
+        assert!(read(&path).unwrap_err().to_string().contains("not a WordPress export"));
+    }
+}
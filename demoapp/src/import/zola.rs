@@ -0,0 +1,319 @@
+//! Zola sites: `config.toml`, content sections and `@/` links.
+//!
+//! Zola's content tree is Anthology's: `_index.md` sections and `index.md`
+//! pages with colocated assets. Dates and slugs come from `2024-01-15-`
+//! file name prefixes as Zola reads them, and shortcode calls are rewritten
+//! in Anthology's syntax.
+
+use rustmax::regex::{Captures, Regex};
+use rustmax::serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use super::{
+    Notes, Page, Site, fields, files, is_markdown, language_code, note_shortcode, read_page, relative_url,
+    rewrite_links, set_taxonomies, split_date_prefix, title_from_slug,
+};
+use crate::Result;
+
+/// Section fields that only steer Zola's rendering.
+const SECTION_FIELDS: &[&str] = &[
+    "sort_by", "page_template", "insert_anchor_links", "transparent", "render",
+    "in_search_index", "generate_feed", "generate_feeds",
+];
+
+/// Site directories with no Anthology counterpart, and why.
+const UNSUPPORTED_DIRS: &[(&str, &str)] = &[
+    ("templates", "templates need rewriting for Tera"),
+    ("themes", "templates need rewriting for Tera"),
+    ("sass", "Sass is not compiled"),
+];
+
+/// Shortcode calls: `{{ name(args) }}`, `{% name(args) %}` and its `{% end %}`.
+static SHORTCODE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)\{\{\s*(\w+)\((.*?)\)\s*\}\}|\{%\s*(\w+)\((.*?)\)\s*%\}|\{%\s*end\s*%\}").expect("invalid regex")
+});
+
+/// One `key=value` argument of a shortcode call.
+static ARG_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(\w+)\s*=\s*("(?:[^"\\]|\\.)*"|'[^']*'|`[^`]*`|[^,\s]+)"#).expect("invalid regex")
+});
+
+/// Read a Zola site.
+pub(super) fn read(root: &Path) -> Result<Site> {
+    let mut site = Site::default();
+
+    let path = ["zola.toml", "config.toml"].into_iter().map(|name| root.join(name)).find(|p| p.is_file());
+    let config = match &path {
+        Some(path) => super::read_config(path)?,
+        None => {
+            site.notes.site("no config.toml found; using defaults");
+            Map::new()
+        }
+    };
+    let taxonomies = read_config(&config, &mut site);
+
+    let content_dir = root.join("content");
+    let mut refs = HashMap::new();
+    read_content(&content_dir, &taxonomies, &mut site, &mut refs)?;
+    for page in &mut site.pages {
+        page.body = convert_body(&page.body, &refs, &page.source, &mut site.notes);
+    }
+
+    for file in files(&root.join("static"))? {
+        let to = Path::new("static").join(file.strip_prefix(root.join("static")).unwrap_or(&file));
+        site.assets.push((file, to));
+    }
+    for (name, reason) in UNSUPPORTED_DIRS {
+        if root.join(name).is_dir() {
+            site.notes.site(format!("{}/ not imported; {}", name, reason));
+        }
+    }
+    Ok(site)
+}
+
+/// Read `config.toml` into the site's config, returning the taxonomies.
+fn read_config(config: &Map<String, Value>, site: &mut Site) -> Vec<String> {
+    let get = |key: &str| config.get(key).and_then(fields::string);
+    let collection = &mut site.config.collection;
+    if let Some(title) = get("title") {
+        collection.title = title;
+    }
+    collection.base_url = get("base_url").unwrap_or_default().trim_end_matches('/').to_string();
+    collection.description = get("description").unwrap_or_default();
+    collection.author = get("author").unwrap_or_default();
+    if let Some(code) = get("default_language") {
+        collection.language = language_code(&code);
+    }
+
+    if config.get("languages").and_then(Value::as_object).is_some_and(|l| !l.is_empty()) {
+        site.notes.site("only pages in the default language are imported");
+    }
+    if config.get("extra").and_then(Value::as_object).is_some_and(|e| !e.is_empty()) {
+        site.notes.site("config [extra] not imported; templates reading `config.extra` need updating");
+    }
+
+    let taxonomies: Vec<String> = config
+        .get("taxonomies")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|t| t.get("name").and_then(fields::string))
+        .collect();
+    set_taxonomies(&mut site.config, taxonomies.clone(), &mut site.notes);
+    taxonomies
+}
+
+/// Read the content directory into pages and assets.
+///
+/// `refs` gets each page's URL by its path in `content/`, for `@/` links.
+fn read_content(
+    content_dir: &Path,
+    taxonomies: &[String],
+    site: &mut Site,
+    refs: &mut HashMap<String, String>,
+) -> Result<()> {
+    let all = files(content_dir)?;
+    for path in &all {
+        let rel = relative_url(content_dir, path);
+        let parent = path.parent().unwrap_or(content_dir);
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let bundled = parent.join("index.md").is_file() && parent != content_dir;
+
+        if !is_markdown(path) {
+            if !bundled {
+                site.assets.push((path.clone(), Path::new("static").join(&rel)));
+            }
+            continue;
+        }
+        if bundled && stem != "index" {
+            site.notes.add(path, "skipped: markdown next to a page's index.md isn't imported");
+            continue;
+        }
+        if stem.contains('.') {
+            site.notes.add(path, "skipped: translations aren't imported");
+            continue;
+        }
+
+        let Some((mut fields, body)) = read_page(path, &mut site.notes) else { continue };
+        let dir = relative_url(content_dir, parent);
+        let is_section = stem == "_index";
+        if is_section {
+            if let Some(paginate) = fields.remove("paginate_by") {
+                fields.insert("paginate".to_string(), paginate);
+            }
+            for field in SECTION_FIELDS {
+                if fields.remove(*field).is_some() {
+                    site.notes.dropped(field);
+                }
+            }
+        }
+        let mut fm = fields::convert(&fields, taxonomies, path, &mut site.notes);
+
+        // Pages named after their directory take its date prefix.
+        let name = match stem {
+            "_index" | "index" => dir.rsplit('/').next().unwrap_or_default(),
+            _ => stem,
+        };
+        let (date, name) = split_date_prefix(name);
+        if !is_section {
+            fm.date = fm.date.or(date);
+        }
+        if fm.title.is_empty() {
+            fm.title = match dir.as_str() {
+                "" if is_section => site.config.collection.title.clone(),
+                _ => title_from_slug(name),
+            };
+        }
+
+        let section_dir = match stem {
+            "index" => dir.rsplit_once('/').map_or("", |(section, _)| section),
+            _ => dir.as_str(),
+        };
+        let slug = fields
+            .get("slug")
+            .and_then(fields::string)
+            .unwrap_or_else(|| crate::build::slugify(name));
+        let url = match fields.get("path").and_then(fields::string) {
+            Some(path) => format!("/{}/", path.trim_matches('/')).replace("//", "/"),
+            None if is_section && dir.is_empty() => "/".to_string(),
+            None if is_section => format!("/{}/", dir),
+            None if section_dir.is_empty() => format!("/{}/", slug),
+            None => format!("/{}/{}/", section_dir, slug),
+        };
+        refs.insert(rel.clone(), url.clone());
+
+        let assets = match stem {
+            "index" => all.iter().filter(|p| p.parent() == Some(parent) && !is_markdown(p)).cloned().collect(),
+            _ => Vec::new(),
+        };
+        site.pages.push(Page { source: path.clone(), url, frontmatter: fm, body, assets });
+    }
+    Ok(())
+}
+
+/// Resolve `@/` links and rewrite shortcode calls.
+fn convert_body(body: &str, refs: &HashMap<String, String>, source: &Path, notes: &mut Notes) -> String {
+    let body = rewrite_links(body, |target| {
+        let path = target.strip_prefix("@/")?;
+        let (path, anchor) = path.split_once('#').map_or((path, None), |(p, a)| (p, Some(a)));
+        match (refs.get(path), anchor) {
+            (Some(url), Some(anchor)) => Some(format!("{}#{}", url, anchor)),
+            (Some(url), None) => Some(url.clone()),
+            (None, _) => {
+                notes.add(source, format!("link target `{}` not found", target));
+                None
+            }
+        }
+    });
+
+    let mut open: Vec<String> = Vec::new();
+    SHORTCODE_RE
+        .replace_all(&body, |caps: &Captures| {
+            let (name, args, block) = match (caps.get(1), caps.get(3)) {
+                (Some(name), _) => (name.as_str(), &caps[2], false),
+                (None, Some(name)) => (name.as_str(), &caps[4], true),
+                (None, None) => {
+                    return match open.pop() {
+                        Some(name) => format!("{{{{% /{} %}}}}", name),
+                        None => caps[0].to_string(),
+                    };
+                }
+            };
+            note_shortcode(name, notes);
+            let args: String = ARG_RE
+                .captures_iter(args)
+                .map(|arg| {
+                    let value = &arg[2];
+                    let value = match value.strip_prefix('`').and_then(|v| v.strip_suffix('`')) {
+                        Some(raw) => format!("\"{}\"", raw),
+                        None => value.to_string(),
+                    };
+                    format!(" {}={}", &arg[1], value)
+                })
+                .collect();
+            if block {
+                open.push(name.to_string());
+                format!("{{{{% {}{} %}}}}", name, args)
+            } else {
+                format!("{{{{< {}{} >}}}}", name, args)
+            }
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustmax::toml;
+
+    #[test]
+    fn test_convert_body() {
+        let refs = HashMap::from([("blog/post.md".to_string(), "/blog/post/".to_string())]);
+        let body = "\
+[Post](@/blog/post.md#end) and [gone](@/gone.md)
+{{ youtube(id=\"abc\", autoplay=true) }}
+{% quote(author=`Ada`) %}
+Hi.
+{% end %}
+";
+        let mut notes = Notes::default();
+        let out = convert_body(body, &refs, Path::new("page.md"), &mut notes);
+        assert_eq!(out, "\
+[Post](/blog/post/#end) and [gone](@/gone.md)
+{{< youtube id=\"abc\" autoplay=true >}}
+{{% quote author=\"Ada\" %}}
+Hi.
+{{% /quote %}}
+");
+        let notes: Vec<String> = notes.into_notes().iter().map(|n| n.to_string()).collect();
+        assert_eq!(notes, [
+            "page.md: link target `@/gone.md` not found",
+            "shortcode `quote` is not built in; add templates/shortcodes/quote.html",
+        ]);
+    }
+
+    #[test]
+    fn test_read_site() {
+        let dir = rustmax::tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "config.toml",
+            "base_url = \"https://example.com\"\ntitle = \"Notes\"\ntaxonomies = [{ name = \"tags\" }, { name = \"series\" }]\n",
+        );
+        write("content/blog/_index.md", "+++\ntitle = \"Blog\"\nsort_by = \"date\"\npaginate_by = 5\n+++\n");
+        write("content/blog/2024-01-15-first.md", "+++\ntitle = \"First\"\n[taxonomies]\nseries = [\"intro\"]\n+++\nSee [trip](@/blog/trip/index.md).\n");
+        write("content/blog/trip/index.md", "+++\ntitle = \"Trip\"\ndate = 2024-02-01T10:00:00Z\n+++\n");
+        write("content/blog/trip/map.png", "png");
+        write("content/about.md", "+++\ntitle = \"About\"\npath = \"about-us\"\n+++\n");
+        write("static/robots.txt", "");
+        write("templates/index.html", "");
+
+        let site = read(root).unwrap();
+        assert_eq!(site.config.collection.title, "Notes");
+        assert_eq!(site.config.content.taxonomies, ["tags", "series"]);
+
+        let urls: HashMap<&str, &Page> = site.pages.iter().map(|p| (p.url.as_str(), p)).collect();
+        assert_eq!(urls.len(), 4);
+        assert_eq!(urls["/blog/"].frontmatter.extra["paginate"], toml::Value::Integer(5));
+        let first = urls["/blog/first/"];
+        assert_eq!(first.frontmatter.date.unwrap().to_string(), "2024-01-15");
+        assert_eq!(first.body, "See [trip](/blog/trip/).\n");
+        let trip = urls["/blog/trip/"];
+        assert_eq!(trip.frontmatter.date.unwrap().to_string(), "2024-02-01");
+        assert_eq!(trip.assets, [root.join("content/blog/trip/map.png")]);
+        assert!(urls.contains_key("/about-us/"));
+
+        let notes: Vec<String> = site.notes.into_notes().iter().map(|n| n.to_string()).collect();
+        assert_eq!(notes, [
+            "templates/ not imported; templates need rewriting for Tera",
+            "frontmatter field `sort_by` dropped from 1 page",
+        ]);
+    }
+}
@@ -7,18 +7,24 @@
 pub mod cli;
 pub mod collection;
 pub mod build;
+pub mod check;
 pub mod serve;
 pub mod search;
 pub mod remote;
 pub mod util;
 pub mod crypto;
 pub mod text;
+pub mod yaml;
+pub mod xml;
 pub mod time;
 pub mod shortcode;
 pub mod features;
 pub mod concurrency;
 pub mod feeds;
 pub mod export;
+pub mod import;
+pub mod theme;
+pub mod diagnostic;
 pub mod error;
 
 pub use error::{Error, Result};
@@ -1,13 +1,21 @@
 //! Full-text search indexing with stemming and stop words.
 
+mod query;
+mod shard;
+mod snippet;
+
+pub use query::{Clause, Filter, Query};
+pub use shard::{write_index, ClientIndex, IndexFormat, ShardOptions, INDEX_FILE, SHARD_DIR};
+pub use snippet::{plain_excerpt, snippet};
+
 use rustmax::prelude::*;
 use serde::{Deserialize, Serialize};
 use rustmax::unicode_segmentation::UnicodeSegmentation;
 use rustmax::log::info;
-use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use crate::collection::Collection;
+use crate::collection::{Collection, Document};
 use crate::Result;
 
 /// Common English stop words to exclude from indexing.
@@ -25,6 +33,22 @@ const STOP_WORDS: &[&str] = &[
     "until", "while", "out", "up", "down", "off", "above", "below",
 ];
 
+/// Position gap between the title, tags, and content, so phrases do not
+/// match across them.
+const FIELD_GAP: u32 = 16;
+
+/// Term frequency weight of a prefix match, relative to an exact match.
+const PREFIX_WEIGHT: f64 = 0.5;
+
+/// Term frequency weight of a one-typo match; two typos get half of it.
+const TYPO_WEIGHT: f64 = 0.3;
+
+/// Similarity added to related documents for each tag they share.
+const RELATED_TAG_BOOST: f64 = 0.1;
+
+/// Words of each document's text kept in the index for client-side snippets.
+const EXCERPT_WORDS: usize = 100;
+
 /// Porter stemmer suffix rules.
 struct PorterStemmer;
 
@@ -239,13 +263,17 @@ impl PorterStemmer {
     }
 }
 
+/// A document's occurrences of a term: document index, term frequency
+/// (with title words counted three times), and word positions.
+pub type Posting = (usize, u16, Vec<u32>);
+
 /// Search index for a collection.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchIndex {
     /// Document entries.
     pub documents: Vec<IndexEntry>,
-    /// Inverted index: stemmed word -> document indices with term frequency.
-    pub word_index: HashMap<String, Vec<(usize, u16)>>,
+    /// Inverted index: stemmed word -> postings.
+    pub word_index: HashMap<String, Vec<Posting>>,
     /// Total word count per document.
     pub doc_lengths: Vec<usize>,
 }
@@ -254,60 +282,72 @@ pub struct SearchIndex {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct IndexEntry {
     pub slug: String,
+    #[serde(default)]
+    pub url: String,
     pub title: String,
     pub content_preview: String,
+    /// Source text, for query-dependent snippets on the server.
+    #[serde(skip)]
+    pub text: String,
+    /// Opening words of the text without markup, for snippets in `search.js`.
+    #[serde(default)]
+    pub excerpt: String,
     pub tags: Vec<String>,
     pub word_count: usize,
 }
 
 impl SearchIndex {
-    /// Build an index from a collection.
+    /// Build an index of a collection's published documents.
+    ///
+    /// Drafts, future and expired documents are left out, as they are from
+    /// the built site. See [`Collection::published`].
     pub fn build(collection: &Collection) -> Self {
-        let stop_words: HashSet<&str> = STOP_WORDS.iter().copied().collect();
+        Self::from_documents(&collection.published())
+    }
+
+    /// Build an index of some documents, e.g. those in one language.
+    pub fn from_documents(docs: &[&Document]) -> Self {
         let mut documents = Vec::new();
-        let mut word_index: HashMap<String, Vec<(usize, u16)>> = HashMap::new();
+        let mut word_index: HashMap<String, Vec<Posting>> = HashMap::new();
         let mut doc_lengths = Vec::new();
 
-        for (idx, doc) in collection.documents.iter().enumerate() {
+        for (idx, doc) in docs.iter().enumerate() {
             // Create entry.
             let entry = IndexEntry {
                 slug: doc.slug(),
+                url: doc.url_path(),
                 title: doc.frontmatter.title.clone(),
                 content_preview: doc.excerpt("<!--more-->", 200),
+                text: doc.content.clone(),
+                excerpt: plain_excerpt(&doc.content, EXCERPT_WORDS),
                 tags: doc.frontmatter.tags.clone(),
                 word_count: doc.word_count(),
             };
 
-            // Index words from title (boosted) and content.
-            let title_text = &doc.frontmatter.title;
-            let content_text = format!("{} {}", doc.frontmatter.tags.join(" "), doc.content);
+            // Index words from title (boosted), tags, and content, with
+            // positions counted across all three.
+            let tags = doc.frontmatter.tags.join(" ");
+            let fields = [(doc.frontmatter.title.as_str(), 3), (tags.as_str(), 1), (doc.content.as_str(), 1)];
 
-            let mut term_counts: HashMap<String, u16> = HashMap::new();
+            let mut terms: HashMap<String, (u16, Vec<u32>)> = HashMap::new();
             let mut total_words = 0;
-
-            // Title words get extra weight.
-            for word in title_text.unicode_words() {
-                let normalized = word.to_lowercase();
-                if normalized.len() >= 2 && !stop_words.contains(normalized.as_str()) {
-                    let stemmed = PorterStemmer::stem(&normalized);
-                    *term_counts.entry(stemmed).or_default() += 3; // Title boost.
-                    total_words += 1;
-                }
-            }
-
-            // Content words.
-            for word in content_text.unicode_words() {
-                let normalized = word.to_lowercase();
-                if normalized.len() >= 2 && !stop_words.contains(normalized.as_str()) {
-                    let stemmed = PorterStemmer::stem(&normalized);
-                    *term_counts.entry(stemmed).or_default() += 1;
+            let mut offset = 0;
+
+            for (text, weight) in fields {
+                let mut end = offset;
+                for (pos, stemmed) in analyze(text) {
+                    let (count, positions) = terms.entry(stemmed).or_default();
+                    *count = count.saturating_add(weight);
+                    positions.push(offset + pos);
+                    end = offset + pos + 1;
                     total_words += 1;
                 }
+                offset = end + FIELD_GAP;
             }
 
             // Add to inverted index.
-            for (term, count) in term_counts {
-                word_index.entry(term).or_default().push((idx, count));
+            for (term, (count, positions)) in terms {
+                word_index.entry(term).or_default().push((idx, count, positions));
             }
 
             documents.push(entry);
@@ -322,81 +362,190 @@ impl SearchIndex {
     }
 
     /// Search for documents matching a query.
+    ///
+    /// See [`Query`] for the syntax.
     pub fn search(&self, query: &str) -> Vec<SearchResult> {
-        let stop_words: HashSet<&str> = STOP_WORDS.iter().copied().collect();
-
-        let query_terms: Vec<String> = query
-            .unicode_words()
-            .map(|w| w.to_lowercase())
-            .filter(|w| w.len() >= 2 && !stop_words.contains(w.as_str()))
-            .map(|w| PorterStemmer::stem(&w))
-            .collect();
+        self.search_query(&Query::parse(query))
+    }
 
-        if query_terms.is_empty() {
+    /// Search for documents matching a parsed query.
+    pub fn search_query(&self, query: &Query) -> Vec<SearchResult> {
+        if query.is_empty() {
             return Vec::new();
         }
 
-        // Calculate BM25-like scores.
-        let avg_doc_len = if self.doc_lengths.is_empty() {
-            1.0
-        } else {
-            self.doc_lengths.iter().sum::<usize>() as f64 / self.doc_lengths.len() as f64
-        };
-
-        let k1 = 1.2;
-        let b = 0.75;
-        let num_docs = self.documents.len() as f64;
-
+        let bm25 = Bm25::new(self);
         let mut doc_scores: HashMap<usize, f64> = HashMap::new();
 
-        for term in &query_terms {
-            // Exact stem match.
-            if let Some(postings) = self.word_index.get(term) {
-                let df = postings.len() as f64;
-                let idf = ((num_docs - df + 0.5) / (df + 0.5) + 1.0).ln();
-
-                for &(doc_idx, tf) in postings {
-                    let doc_len = self.doc_lengths.get(doc_idx).copied().unwrap_or(1) as f64;
-                    let tf = tf as f64;
-                    let score = idf * (tf * (k1 + 1.0)) / (tf + k1 * (1.0 - b + b * doc_len / avg_doc_len));
-                    *doc_scores.entry(doc_idx).or_default() += score;
-                }
-            }
+        // Filters alone list every document that passes them.
+        if query.groups.is_empty() {
+            doc_scores.extend((0..self.documents.len()).map(|idx| (idx, 0.0)));
+        }
 
-            // Prefix matching for partial terms.
-            for (indexed_term, postings) in &self.word_index {
-                if indexed_term.starts_with(term) && indexed_term != term {
-                    let df = postings.len() as f64;
-                    let idf = ((num_docs - df + 0.5) / (df + 0.5) + 1.0).ln();
-
-                    for &(doc_idx, tf) in postings {
-                        let doc_len = self.doc_lengths.get(doc_idx).copied().unwrap_or(1) as f64;
-                        let tf = tf as f64 * 0.5; // Reduced weight for prefix match.
-                        let score = idf * (tf * (k1 + 1.0)) / (tf + k1 * (1.0 - b + b * doc_len / avg_doc_len));
-                        *doc_scores.entry(doc_idx).or_default() += score;
-                    }
-                }
+        // A document scores for each group whose clauses it all matches.
+        for group in &query.groups {
+            let mut matched: Option<HashMap<usize, f64>> = None;
+            for clause in group {
+                let scores = self.clause_scores(clause, &bm25);
+                matched = Some(match matched {
+                    None => scores,
+                    Some(prev) => prev
+                        .into_iter()
+                        .filter_map(|(idx, score)| scores.get(&idx).map(|s| (idx, score + s)))
+                        .collect(),
+                });
+            }
+            for (idx, score) in matched.unwrap_or_default() {
+                *doc_scores.entry(idx).or_default() += score;
             }
         }
 
-        use rustmax::itertools::Itertools;
+        for filter in &query.required {
+            let docs = self.filter_docs(filter);
+            doc_scores.retain(|idx, _| docs.contains(idx));
+        }
+        for filter in &query.excluded {
+            let docs = self.filter_docs(filter);
+            doc_scores.retain(|idx, _| !docs.contains(idx));
+        }
 
         // Sort by score descending.
-        doc_scores
+        let mut ranked: Vec<(usize, f64)> = doc_scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+
+        ranked
             .into_iter()
-            .sorted_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal))
             .take(20)
             .filter_map(|(idx, score)| {
                 self.documents.get(idx).map(|entry| SearchResult {
                     slug: entry.slug.clone(),
+                    url: entry.url.clone(),
                     title: entry.title.clone(),
                     preview: entry.content_preview.clone(),
+                    snippet: snippet(&entry.text, query),
                     score: (score * 100.0) as usize,
                 })
             })
             .collect()
     }
 
+    /// Score the documents matching one clause.
+    fn clause_scores(&self, clause: &Clause, bm25: &Bm25) -> HashMap<usize, f64> {
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+
+        // Add BM25 scores for one matching term, with its tf scaled by `weight`.
+        let mut add_scores = |postings: &[Posting], weight: f64| {
+            let idf = bm25.idf(postings.len());
+            for (doc_idx, tf, _) in postings {
+                let score = bm25.score(idf, *tf as f64 * weight, self.doc_lengths.get(*doc_idx).copied().unwrap_or(1));
+                *scores.entry(*doc_idx).or_default() += score;
+            }
+        };
+
+        match clause {
+            Clause::Term(term) => {
+                // Exact stem match.
+                if let Some(postings) = self.word_index.get(term) {
+                    add_scores(postings, 1.0);
+                }
+
+                // Prefix matching for partial terms, then typo matching.
+                let max_typos = max_typos(term);
+                for (indexed_term, postings) in &self.word_index {
+                    if indexed_term == term {
+                        continue;
+                    }
+                    if indexed_term.starts_with(term.as_str()) {
+                        add_scores(postings, PREFIX_WEIGHT);
+                    } else if let Some(distance) = edit_distance(term, indexed_term, max_typos) {
+                        add_scores(postings, TYPO_WEIGHT / distance as f64);
+                    }
+                }
+            }
+            Clause::Phrase(words) => {
+                // Each word scores as if it occurred once per phrase match.
+                let matches = self.phrase_matches(words);
+                for (word, _) in words {
+                    let idf = bm25.idf(self.word_index.get(word).map_or(0, Vec::len));
+                    for (&doc_idx, &count) in &matches {
+                        let score = bm25.score(idf, count as f64, self.doc_lengths.get(doc_idx).copied().unwrap_or(1));
+                        *scores.entry(doc_idx).or_default() += score;
+                    }
+                }
+            }
+        }
+
+        scores
+    }
+
+    /// Count each document's occurrences of a phrase.
+    fn phrase_matches(&self, words: &[(String, u32)]) -> HashMap<usize, usize> {
+        let mut matches = HashMap::new();
+        let Some(((first, _), rest)) = words.split_first() else {
+            return matches;
+        };
+        let Some(first_postings) = self.word_index.get(first) else {
+            return matches;
+        };
+
+        // Positions of the other words, by document.
+        let mut others = Vec::new();
+        for (word, offset) in rest {
+            let Some(postings) = self.word_index.get(word) else {
+                return matches;
+            };
+            let by_doc: HashMap<usize, &[u32]> = postings
+                .iter()
+                .map(|(idx, _, positions)| (*idx, positions.as_slice()))
+                .collect();
+            others.push((by_doc, *offset));
+        }
+
+        for (doc_idx, _, positions) in first_postings {
+            let count = positions
+                .iter()
+                .filter(|&&pos| {
+                    others.iter().all(|(by_doc, offset)| {
+                        by_doc
+                            .get(doc_idx)
+                            .is_some_and(|p| p.binary_search(&(pos + offset)).is_ok())
+                    })
+                })
+                .count();
+            if count > 0 {
+                matches.insert(*doc_idx, count);
+            }
+        }
+
+        matches
+    }
+
+    /// Indices of the documents passing a filter.
+    fn filter_docs(&self, filter: &Filter) -> HashSet<usize> {
+        match filter {
+            Filter::Tag(tag) => self
+                .documents
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)))
+                .map(|(idx, _)| idx)
+                .collect(),
+            Filter::Title(clause) => self
+                .documents
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| title_matches(&entry.title, clause))
+                .map(|(idx, _)| idx)
+                .collect(),
+            Filter::Text(Clause::Term(term)) => self
+                .word_index
+                .get(term)
+                .map(|postings| postings.iter().map(|(idx, _, _)| *idx).collect())
+                .unwrap_or_default(),
+            Filter::Text(Clause::Phrase(words)) => self.phrase_matches(words).into_keys().collect(),
+        }
+    }
+
     /// Get suggestions for a partial query (autocomplete).
     pub fn suggest(&self, prefix: &str) -> Vec<String> {
         let prefix = PorterStemmer::stem(&prefix.to_lowercase());
@@ -411,25 +560,292 @@ impl SearchIndex {
             .cloned()
             .collect()
     }
+
+    /// Find the documents most similar to the document at `doc_idx`.
+    ///
+    /// Similarity is the cosine of the documents' TF-IDF term vectors,
+    /// plus a boost for each tag they share.
+    pub fn related(&self, doc_idx: usize, limit: usize) -> Vec<RelatedDocument> {
+        let vectors = TermVectors::new(self);
+        self.related_with(&vectors, doc_idx, limit)
+    }
+
+    /// Related documents for every document, in index order.
+    pub fn all_related(&self, limit: usize) -> Vec<Vec<RelatedDocument>> {
+        let vectors = TermVectors::new(self);
+        (0..self.documents.len())
+            .map(|idx| self.related_with(&vectors, idx, limit))
+            .collect()
+    }
+
+    fn related_with(&self, vectors: &TermVectors<'_>, doc_idx: usize, limit: usize) -> Vec<RelatedDocument> {
+        let Some(entry) = self.documents.get(doc_idx) else {
+            return Vec::new();
+        };
+
+        // Dot products with every document sharing a term.
+        let mut dots: HashMap<usize, f64> = HashMap::new();
+        for &(term, weight) in &vectors.weights[doc_idx] {
+            let idf = vectors.idf[term];
+            for &(other, tf, _) in &self.word_index[term] {
+                if other != doc_idx {
+                    *dots.entry(other).or_default() += weight * tf as f64 * idf;
+                }
+            }
+        }
+
+        let tags: HashSet<&str> = entry.tags.iter().map(String::as_str).collect();
+        let mut scored: Vec<(usize, f64)> = self
+            .documents
+            .iter()
+            .enumerate()
+            .filter(|&(other, _)| other != doc_idx)
+            .map(|(other, other_entry)| {
+                let norms = vectors.norms[doc_idx] * vectors.norms[other];
+                let cosine = match dots.get(&other) {
+                    Some(dot) if norms > 0.0 => dot / norms,
+                    _ => 0.0,
+                };
+                let shared = other_entry.tags.iter().filter(|t| tags.contains(t.as_str())).count();
+                (other, cosine + RELATED_TAG_BOOST * shared as f64)
+            })
+            .filter(|&(_, score)| score > 0.0)
+            .collect();
+
+        scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+
+        scored
+            .into_iter()
+            .take(limit)
+            .map(|(idx, score)| {
+                let entry = &self.documents[idx];
+                RelatedDocument {
+                    slug: entry.slug.clone(),
+                    url: entry.url.clone(),
+                    title: entry.title.clone(),
+                    score,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Split text into stemmed terms with their word positions.
+///
+/// Positions count every word, including stop words, so phrase offsets
+/// line up between queries and documents.
+fn analyze(text: &str) -> impl Iterator<Item = (u32, String)> + '_ {
+    text.unicode_words().enumerate().filter_map(|(pos, word)| {
+        let normalized = word.to_lowercase();
+        if normalized.len() >= 2 && !STOP_WORDS.contains(&normalized.as_str()) {
+            Some((pos as u32, PorterStemmer::stem(&normalized)))
+        } else {
+            None
+        }
+    })
+}
+
+/// Check whether a title contains a term (or a longer word starting with
+/// it) or a phrase.
+fn title_matches(title: &str, clause: &Clause) -> bool {
+    let words: Vec<(u32, String)> = analyze(title).collect();
+    match clause {
+        Clause::Term(term) => words.iter().any(|(_, w)| w.starts_with(term.as_str())),
+        Clause::Phrase(phrase) => words.iter().any(|(pos, _)| {
+            phrase.iter().all(|(word, offset)| {
+                words.iter().any(|(p, w)| *p == pos + offset && w == word)
+            })
+        }),
+    }
+}
+
+/// BM25 scoring parameters for an index.
+struct Bm25 {
+    num_docs: f64,
+    avg_doc_len: f64,
+}
+
+impl Bm25 {
+    const K1: f64 = 1.2;
+    const B: f64 = 0.75;
+
+    fn new(index: &SearchIndex) -> Self {
+        let avg_doc_len = if index.doc_lengths.is_empty() {
+            1.0
+        } else {
+            index.doc_lengths.iter().sum::<usize>() as f64 / index.doc_lengths.len() as f64
+        };
+        Self {
+            num_docs: index.documents.len() as f64,
+            avg_doc_len,
+        }
+    }
+
+    /// Inverse document frequency of a term in `df` documents.
+    fn idf(&self, df: usize) -> f64 {
+        let df = df as f64;
+        ((self.num_docs - df + 0.5) / (df + 0.5) + 1.0).ln()
+    }
+
+    fn score(&self, idf: f64, tf: f64, doc_len: usize) -> f64 {
+        let norm = 1.0 - Self::B + Self::B * doc_len as f64 / self.avg_doc_len;
+        idf * (tf * (Self::K1 + 1.0)) / (tf + Self::K1 * norm)
+    }
+}
+
+/// Number of typos tolerated in a query term.
+///
+/// Short terms must match exactly, since one edit turns them into
+/// unrelated words.
+fn max_typos(term: &str) -> usize {
+    match term.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// Edit distance between two terms, if it is at most `max`.
+///
+/// Counts insertions, deletions, substitutions, and transpositions of
+/// adjacent characters, so "tokoi" is one edit from "tokio". Rows of the
+/// table are abandoned as soon as every entry exceeds `max`.
+fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    if max == 0 {
+        return None;
+    }
+
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    let mut prev_prev: Vec<usize> = Vec::new();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+
+    for i in 1..=a.len() {
+        let mut row = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            row[j] = (prev[j] + 1).min(row[j - 1] + 1).min(prev[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(prev_prev[j - 2] + 1);
+            }
+        }
+        if row.iter().all(|&d| d > max) {
+            return None;
+        }
+        prev_prev = std::mem::replace(&mut prev, row);
+    }
+
+    Some(prev[b.len()]).filter(|&d| d <= max)
+}
+
+/// TF-IDF weights of each document's terms, for similarity scoring.
+struct TermVectors<'a> {
+    /// Inverse document frequency per term.
+    idf: HashMap<&'a str, f64>,
+    /// Weighted terms per document.
+    weights: Vec<Vec<(&'a str, f64)>>,
+    /// Euclidean norm of each document's vector.
+    norms: Vec<f64>,
+}
+
+impl<'a> TermVectors<'a> {
+    fn new(index: &'a SearchIndex) -> Self {
+        let num_docs = index.documents.len() as f64;
+        let mut idf = HashMap::new();
+        let mut weights = vec![Vec::new(); index.documents.len()];
+
+        for (term, postings) in &index.word_index {
+            // Terms in every document carry almost no weight.
+            let term_idf = (1.0 + num_docs / postings.len() as f64).ln();
+            idf.insert(term.as_str(), term_idf);
+            for &(doc_idx, tf, _) in postings {
+                if let Some(terms) = weights.get_mut(doc_idx) {
+                    terms.push((term.as_str(), tf as f64 * term_idf));
+                }
+            }
+        }
+
+        let norms = weights
+            .iter()
+            .map(|terms| terms.iter().map(|(_, w)| w * w).sum::<f64>().sqrt())
+            .collect();
+
+        Self { idf, weights, norms }
+    }
+}
+
+/// Related documents for each document in a collection.
+#[derive(Debug, Default)]
+pub struct RelatedIndex {
+    related: HashMap<PathBuf, Vec<RelatedDocument>>,
+}
+
+impl RelatedIndex {
+    /// Find the `limit` most related documents for each document.
+    ///
+    /// Documents are only related to others in the same language.
+    pub fn build(docs: &[&Document], limit: usize) -> Self {
+        let mut related = HashMap::new();
+        if limit == 0 {
+            return Self { related };
+        }
+
+        let languages: BTreeSet<Option<&str>> = docs.iter().map(|d| d.language.as_deref()).collect();
+        for language in languages {
+            let group: Vec<&Document> = docs
+                .iter()
+                .copied()
+                .filter(|d| d.language.as_deref() == language)
+                .collect();
+            let index = SearchIndex::from_documents(&group);
+            for (doc, list) in group.iter().zip(index.all_related(limit)) {
+                related.insert(doc.source_path.clone(), list);
+            }
+        }
+
+        Self { related }
+    }
+
+    /// Related documents for a document, most similar first.
+    pub fn get(&self, doc: &Document) -> &[RelatedDocument] {
+        self.related.get(&doc.source_path).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// A document related to another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelatedDocument {
+    pub slug: String,
+    pub url: String,
+    pub title: String,
+    pub score: f64,
 }
 
 /// A search result.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SearchResult {
     pub slug: String,
+    #[serde(default)]
+    pub url: String,
     pub title: String,
     pub preview: String,
+    /// HTML excerpt around the matches, with matched words in `<mark>`.
+    #[serde(default)]
+    pub snippet: String,
     pub score: usize,
 }
 
 /// Build and save search index for a collection.
 pub fn build_index(collection: &Collection, root: &Path) -> Result<()> {
-    info!("Building search index for {} documents", collection.documents.len());
-
     let index = SearchIndex::build(collection);
+    info!("Building search index for {} documents", index.documents.len());
     let index_path = root.join("search-index.json");
 
-    let json = rustmax::serde_json::to_string_pretty(&index)?;
+    let json = rustmax::serde_json::to_string_pretty(&ClientIndex::new(&index))?;
     std::fs::write(&index_path, json)?;
 
     info!("Search index saved to {}", index_path.display());
@@ -461,6 +877,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let index = SearchIndex::build(&collection);
@@ -473,6 +890,33 @@ mod tests {
         let results = index.search("python");
         assert_eq!(results.len(), 1);
         assert!(results[0].title.contains("Python"));
+
+        // The source text stays on the server; clients get the excerpt.
+        let entry = rustmax::serde_json::to_value(&index.documents[0]).unwrap();
+        assert!(entry.get("text").is_none());
+        assert_eq!(entry["excerpt"], "Learn about Rust and memory safety");
+    }
+
+    #[test]
+    fn test_search_index_published_only() {
+        let parse = |name: &str, frontmatter: &str| {
+            let raw = format!("---\ntitle = \"{}\"\n{}\n---\nRust notes.\n", name, frontmatter);
+            Document::parse(PathBuf::from(format!("{}.md", name)), &raw).unwrap()
+        };
+        let collection = Collection {
+            root: PathBuf::from("."),
+            documents: vec![
+                parse("Published", ""),
+                parse("Draft", "draft = true"),
+                parse("Future", "publish_date = \"2999-01-01\""),
+                parse("Expired", "expiry_date = \"2000-01-01\""),
+            ],
+            sections: Vec::new(),
+        };
+
+        let index = SearchIndex::build(&collection);
+        let titles: Vec<_> = index.search("rust").iter().map(|r| r.title.clone()).collect();
+        assert_eq!(titles, ["Published"]);
     }
 
     #[test]
@@ -508,6 +952,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let index = SearchIndex::build(&collection);
@@ -532,6 +977,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let index = SearchIndex::build(&collection);
@@ -556,6 +1002,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let index = SearchIndex::build(&collection);
@@ -563,6 +1010,109 @@ mod tests {
         assert!(suggestions.len() >= 1);
     }
 
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("asynch", "async", 1), Some(1));
+        assert_eq!(edit_distance("tokoi", "tokio", 1), Some(1));
+        assert_eq!(edit_distance("kitten", "sitting", 2), None);
+        assert_eq!(edit_distance("kitten", "sitting", 3), Some(3));
+        assert_eq!(edit_distance("rust", "rust", 0), None);
+        assert_eq!(max_typos("web"), 0);
+        assert_eq!(max_typos("tokio"), 1);
+        assert_eq!(max_typos("programm"), 2);
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let docs = [
+            make_doc("Async Runtimes", "The tokio runtime schedules async tasks."),
+            make_doc("Resting", "Rest is important."),
+            make_doc("Rust", "Rust is a language."),
+        ];
+        let refs: Vec<&Document> = docs.iter().collect();
+        let index = SearchIndex::from_documents(&refs);
+
+        assert_eq!(index.search("asynch")[0].title, "Async Runtimes");
+        assert_eq!(index.search("tokoi")[0].title, "Async Runtimes");
+
+        // A typo match ranks below an exact one.
+        let results = index.search("rust");
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].title, "Rust");
+        assert!(results[0].score > results[1].score);
+    }
+
+    #[test]
+    fn test_query_syntax() {
+        let doc = |title: &str, tags: &str, content: &str| {
+            let raw = format!("---\ntitle = \"{}\"\ntags = [{}]\n---\n{}", title, tags, content);
+            Document::parse(PathBuf::from("test.md"), &raw).unwrap()
+        };
+        let docs = [
+            doc("Error Handling", "\"rust\"", "Handling errors with Result."),
+            doc("Logging", "\"rust\"", "Log every error, then continue handling requests."),
+            doc("Panics", "\"c\"", "Error handling in C uses return codes."),
+        ];
+        let refs: Vec<&Document> = docs.iter().collect();
+        let index = SearchIndex::from_documents(&refs);
+        let titles = |query: &str| -> Vec<String> {
+            index.search(query).into_iter().map(|r| r.title).collect()
+        };
+
+        // Phrases need adjacent words; across fields they do not match.
+        assert_eq!(titles("\"error handling\""), ["Error Handling", "Panics"]);
+        assert_eq!(titles("\"handling rust\""), Vec::<String>::new());
+
+        assert_eq!(titles("result AND codes"), Vec::<String>::new());
+        assert_eq!(titles("result OR codes").len(), 2);
+        assert_eq!(titles("handling -\"return codes\""), ["Error Handling", "Logging"]);
+
+        assert_eq!(titles("error tag:rust"), ["Error Handling", "Logging"]);
+        assert_eq!(titles("tag:RUST -title:log"), ["Error Handling"]);
+        assert_eq!(titles("title:\"error handling\""), ["Error Handling"]);
+    }
+
+    #[test]
+    fn test_related() {
+        let docs = [
+            make_doc("Ownership in Rust", "Borrowing and ownership rules for references."),
+            make_doc("Borrow Checker", "The borrow checker enforces ownership of references."),
+            make_doc("Baking Bread", "Flour, water, yeast, and patience."),
+        ];
+        let refs: Vec<&Document> = docs.iter().collect();
+        let index = SearchIndex::from_documents(&refs);
+
+        let related = index.related(0, 5);
+        assert_eq!(related.len(), 1);
+        assert_eq!(related[0].title, "Borrow Checker");
+        assert!(index.related(2, 5).is_empty());
+        assert_eq!(index.all_related(5).len(), 3);
+    }
+
+    #[test]
+    fn test_related_tag_boost_and_languages() {
+        let doc = |path: &str, title: &str, tags: &str, language: Option<&str>| {
+            let raw = format!("---\ntitle = \"{}\"\ntags = [{}]\n---\nText.", title, tags);
+            let mut doc = Document::parse(PathBuf::from(path), &raw).unwrap();
+            doc.language = language.map(String::from);
+            doc
+        };
+        let docs = [
+            doc("a.md", "Alpha", "\"rust\", \"web\"", None),
+            doc("b.md", "Beta", "\"rust\"", None),
+            doc("c.md", "Gamma", "\"rust\", \"web\"", None),
+            doc("d.md", "Delta", "\"rust\", \"web\"", Some("de")),
+        ];
+        let refs: Vec<&Document> = docs.iter().collect();
+        let related = RelatedIndex::build(&refs, 5);
+
+        // Shared tags rank Gamma above Beta; Delta is in another language.
+        let titles: Vec<&str> = related.get(&docs[0]).iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, ["Gamma", "Beta"]);
+        assert!(related.get(&docs[3]).is_empty());
+        assert!(RelatedIndex::build(&refs, 0).get(&docs[0]).is_empty());
+    }
+
     #[test]
     fn test_title_boost() {
         let docs = vec![
@@ -573,6 +1123,7 @@ mod tests {
         let collection = Collection {
             root: PathBuf::from("."),
             documents: docs,
+            sections: Vec::new(),
         };
 
         let index = SearchIndex::build(&collection);
@@ -631,6 +1182,7 @@ mod proptest_tests {
             let collection = Collection {
                 root: std::path::PathBuf::from("."),
                 documents: docs,
+                sections: Vec::new(),
             };
 
             let index = SearchIndex::build(&collection);
@@ -661,6 +1213,7 @@ mod proptest_tests {
             let collection = Collection {
                 root: std::path::PathBuf::from("."),
                 documents: docs,
+                sections: Vec::new(),
             };
 
             let index = SearchIndex::build(&collection);
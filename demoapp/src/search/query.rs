@@ -0,0 +1,254 @@
+//! Search query syntax.
+//!
+//! - `word` matches the word's stem, longer terms it is a prefix of, and
+//!   near typos.
+//! - `"two words"` matches the words next to each other, in order.
+//! - Clauses are alternatives; `a AND b` requires both, and `OR` may be
+//!   written for clarity.
+//! - `-word` and `-"a phrase"` exclude documents containing them.
+//! - `tag:rust` keeps documents with a tag, and `title:word` or
+//!   `title:"a phrase"` those whose title matches. Both can be negated,
+//!   as in `-tag:draft`.
+
+use rustmax::prelude::*;
+
+use super::analyze;
+
+/// A scored part of a query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Clause {
+    /// A stemmed term.
+    Term(String),
+    /// Stemmed terms with their word offsets from the first.
+    Phrase(Vec<(String, u32)>),
+}
+
+/// A condition on documents, independent of scoring.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// The document has the tag (case-insensitive).
+    Tag(String),
+    /// The document's title contains the term or phrase.
+    Title(Clause),
+    /// The document's text contains the exact term or phrase.
+    Text(Clause),
+}
+
+/// A parsed search query.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    /// Alternatives, each a list of clauses a document must all match.
+    pub groups: Vec<Vec<Clause>>,
+    /// Conditions every result must meet.
+    pub required: Vec<Filter>,
+    /// Conditions no result may meet.
+    pub excluded: Vec<Filter>,
+}
+
+/// A lexical piece of a query.
+enum Token {
+    And,
+    Or,
+    Item {
+        negated: bool,
+        field: Option<String>,
+        text: String,
+        quoted: bool,
+    },
+}
+
+impl Query {
+    /// Parse a query string.
+    ///
+    /// Parsing never fails: unbalanced quotes run to the end of the
+    /// query, unknown fields are searched as text, and words that are
+    /// all stop words are dropped.
+    pub fn parse(input: &str) -> Self {
+        let mut query = Query::default();
+        let mut join_next = false;
+
+        for token in tokenize(input) {
+            let (negated, field, text, quoted) = match token {
+                Token::And => {
+                    join_next = !query.groups.is_empty();
+                    continue;
+                }
+                Token::Or => {
+                    join_next = false;
+                    continue;
+                }
+                Token::Item { negated, field, text, quoted } => (negated, field, text, quoted),
+            };
+
+            let filter = match field.as_deref() {
+                Some("tag") => Some(Filter::Tag(text.clone())),
+                Some("title") => clause(&text, quoted).map(Filter::Title),
+                _ if negated => clause(&text, quoted).map(Filter::Text),
+                _ => None,
+            };
+
+            if let Some(filter) = filter {
+                if negated {
+                    query.excluded.push(filter);
+                } else {
+                    query.required.push(filter);
+                }
+                continue;
+            }
+
+            let Some(clause) = clause(&text, quoted) else {
+                continue;
+            };
+            match query.groups.last_mut() {
+                Some(group) if join_next => group.push(clause),
+                _ => query.groups.push(vec![clause]),
+            }
+            join_next = false;
+        }
+
+        query
+    }
+
+    /// Check whether the query has nothing to search for or filter by.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty() && self.required.is_empty()
+    }
+}
+
+/// Analyze text into a clause.
+///
+/// Quoted text and unquoted text with several words (like `error-handling`)
+/// become phrases; a single unquoted word is a term.
+fn clause(text: &str, quoted: bool) -> Option<Clause> {
+    let mut words: Vec<(String, u32)> = analyze(text).map(|(pos, stem)| (stem, pos)).collect();
+    let first = words.first()?.1;
+    for (_, pos) in &mut words {
+        *pos -= first;
+    }
+
+    if words.len() == 1 && !quoted {
+        Some(Clause::Term(words.remove(0).0))
+    } else {
+        Some(Clause::Phrase(words))
+    }
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        if chars.peek().is_none() {
+            return tokens;
+        }
+
+        let negated = chars.next_if_eq(&'-').is_some();
+
+        // A bare word, possibly `field:value`.
+        let mut word = String::new();
+        if chars.peek() != Some(&'"') {
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+                if c == ':' && chars.peek() == Some(&'"') {
+                    break;
+                }
+            }
+        }
+
+        let mut field = None;
+        if let Some((name, value)) = word.split_once(':') {
+            let name = name.to_lowercase();
+            if name == "tag" || name == "title" {
+                field = Some(name);
+                word = value.to_string();
+            }
+        }
+
+        // A quoted value, either alone or after a field.
+        let quoted = word.is_empty() && chars.next_if_eq(&'"').is_some();
+        if quoted {
+            for c in chars.by_ref() {
+                if c == '"' {
+                    break;
+                }
+                word.push(c);
+            }
+        }
+
+        let token = match word.as_str() {
+            "AND" | "&&" if !negated && !quoted && field.is_none() => Token::And,
+            "OR" | "||" if !negated && !quoted && field.is_none() => Token::Or,
+            _ => Token::Item {
+                negated,
+                field,
+                text: word,
+                quoted,
+            },
+        };
+        tokens.push(token);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(s: &str) -> Clause {
+        Clause::Term(s.to_string())
+    }
+
+    #[test]
+    fn test_parse_terms_and_operators() {
+        let query = Query::parse("rust async AND tokio OR web");
+        assert_eq!(
+            query.groups,
+            vec![vec![term("rust")], vec![term("async"), term("tokio")], vec![term("web")]]
+        );
+        assert!(query.required.is_empty());
+        assert!(query.excluded.is_empty());
+    }
+
+    #[test]
+    fn test_parse_phrases() {
+        let query = Query::parse("\"state of the art\" error-handling \"unclosed");
+        assert_eq!(
+            query.groups,
+            vec![
+                vec![Clause::Phrase(vec![("state".to_string(), 0), ("art".to_string(), 3)])],
+                vec![Clause::Phrase(vec![("error".to_string(), 0), ("handl".to_string(), 1)])],
+                vec![Clause::Phrase(vec![("unclos".to_string(), 0)])],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_fields_and_exclusions() {
+        let query = Query::parse("parser tag:Rust -tag:draft title:\"error handling\" -legacy -\"old api\"");
+        assert_eq!(query.groups, vec![vec![term("parser")]]);
+        assert_eq!(
+            query.required,
+            vec![
+                Filter::Tag("Rust".to_string()),
+                Filter::Title(Clause::Phrase(vec![("error".to_string(), 0), ("handl".to_string(), 1)])),
+            ]
+        );
+        assert_eq!(
+            query.excluded,
+            vec![
+                Filter::Tag("draft".to_string()),
+                Filter::Text(term("legaci")),
+                Filter::Text(Clause::Phrase(vec![("old".to_string(), 0), ("api".to_string(), 1)])),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_degenerate() {
+        assert!(Query::parse("").is_empty());
+        assert!(Query::parse("the AND of").is_empty());
+        assert!(!Query::parse("tag:rust").is_empty());
+        // Unknown fields are plain text.
+        assert_eq!(Query::parse("foo:bar").groups.len(), 1);
+    }
+}
@@ -0,0 +1,399 @@
+//! Sharded binary search index for static sites.
+//!
+//! Instead of one JSON file, the index is split so `search.js` only
+//! downloads what a query needs:
+//!
+//! - `search-index.json` is a small manifest listing the other files.
+//! - `search-index/terms-<key>.bin` holds the terms starting with `key`
+//!   (their first `prefix_len` characters) and their postings.
+//! - `search-index/lengths.bin` holds each document's length, for BM25.
+//! - `search-index/docs-<n>.json` holds document entries in chunks, so
+//!   only the chunks with results are fetched.
+//!
+//! Binary files are sequences of unsigned LEB128 varints. A shard is a
+//! term count followed by, per term in sorted order, the number of bytes
+//! shared with the previous term, the remaining bytes (length, then
+//! UTF-8), the posting count, and per posting the document index as a
+//! delta from the previous posting and the term frequency. Positions are
+//! left out, since the client does not match phrases. With `gzip`, every
+//! file but the manifest is gzipped and named with a `.gz` suffix.
+
+use rustmax::prelude::*;
+use rustmax::flate2::write::GzEncoder;
+use rustmax::flate2::Compression;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use super::{IndexEntry, Posting, SearchIndex};
+use crate::Result;
+
+/// File name of the JSON index, or of the manifest of a sharded index.
+pub const INDEX_FILE: &str = "search-index.json";
+
+/// Directory holding the files of a sharded index, next to the manifest.
+pub const SHARD_DIR: &str = "search-index";
+
+/// Version of the sharded format, recorded in the manifest.
+const SHARD_FORMAT_VERSION: u32 = 1;
+
+/// Documents per `docs-<n>.json` chunk.
+const DOCS_PER_CHUNK: usize = 256;
+
+/// How a static site's search index is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexFormat {
+    /// One `search-index.json` with everything.
+    Json,
+    /// A manifest and binary shards fetched on demand.
+    Sharded(ShardOptions),
+}
+
+/// Options for a sharded index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardOptions {
+    /// Leading characters of a term that pick its shard.
+    pub prefix_len: usize,
+    /// Gzip the shards and document chunks.
+    pub gzip: bool,
+}
+
+impl Default for ShardOptions {
+    fn default() -> Self {
+        Self {
+            prefix_len: 2,
+            gzip: false,
+        }
+    }
+}
+
+/// Manifest of a sharded index.
+#[derive(Debug, Serialize)]
+struct Manifest<'a> {
+    format: &'static str,
+    version: u32,
+    /// Directory of the other files, relative to the manifest.
+    base: &'static str,
+    documents: usize,
+    prefix_len: usize,
+    gzip: bool,
+    lengths: String,
+    docs_per_chunk: usize,
+    chunks: Vec<String>,
+    /// Shard file by term prefix.
+    shards: BTreeMap<&'a str, String>,
+}
+
+/// A search index as `search.js` reads it from one JSON file.
+///
+/// Postings are `[document, frequency]` pairs: positions are left out, as in
+/// the shards, since the client does not match phrases.
+#[derive(Debug, Serialize)]
+pub struct ClientIndex<'a> {
+    documents: &'a [IndexEntry],
+    word_index: BTreeMap<&'a str, Vec<(usize, u16)>>,
+    doc_lengths: &'a [usize],
+}
+
+impl<'a> ClientIndex<'a> {
+    pub fn new(index: &'a SearchIndex) -> Self {
+        let word_index = index
+            .word_index
+            .iter()
+            .map(|(term, postings)| (term.as_str(), postings.iter().map(|(idx, tf, _)| (*idx, *tf)).collect()))
+            .collect();
+        Self {
+            documents: &index.documents,
+            word_index,
+            doc_lengths: &index.doc_lengths,
+        }
+    }
+}
+
+/// Write a search index into `dir` in the given format.
+///
+/// Files left over from a previous build in the other format are removed.
+pub fn write_index(index: &SearchIndex, dir: &Path, format: IndexFormat) -> Result<()> {
+    let shard_dir = dir.join(SHARD_DIR);
+    if shard_dir.exists() {
+        std::fs::remove_dir_all(&shard_dir)?;
+    }
+
+    match format {
+        IndexFormat::Json => {
+            std::fs::write(dir.join(INDEX_FILE), rustmax::serde_json::to_string(&ClientIndex::new(index))?)?;
+        }
+        IndexFormat::Sharded(options) => write_sharded(index, dir, options)?,
+    }
+    Ok(())
+}
+
+fn write_sharded(index: &SearchIndex, dir: &Path, options: ShardOptions) -> Result<()> {
+    let shard_dir = dir.join(SHARD_DIR);
+    std::fs::create_dir_all(&shard_dir)?;
+
+    let write = |name: String, data: Vec<u8>| -> Result<String> {
+        let (name, data) = if options.gzip {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+            encoder.write_all(&data)?;
+            (format!("{}.gz", name), encoder.finish()?)
+        } else {
+            (name, data)
+        };
+        std::fs::write(shard_dir.join(&name), data)?;
+        Ok(name)
+    };
+
+    let mut grouped: BTreeMap<&str, Vec<(&str, &[Posting])>> = BTreeMap::new();
+    for (term, postings) in &index.word_index {
+        grouped
+            .entry(shard_key(term, options.prefix_len))
+            .or_default()
+            .push((term, postings));
+    }
+
+    let mut shards = BTreeMap::new();
+    for (key, mut terms) in grouped {
+        terms.sort_by_key(|(term, _)| *term);
+        let name = write(format!("terms-{}.bin", file_stem(key)), encode_shard(&terms))?;
+        shards.insert(key, name);
+    }
+
+    let mut lengths = Vec::new();
+    write_varint(&mut lengths, index.doc_lengths.len() as u64);
+    for &len in &index.doc_lengths {
+        write_varint(&mut lengths, len as u64);
+    }
+    let lengths = write("lengths.bin".to_string(), lengths)?;
+
+    let chunks = index
+        .documents
+        .chunks(DOCS_PER_CHUNK)
+        .enumerate()
+        .map(|(n, docs)| write(format!("docs-{}.json", n), rustmax::serde_json::to_vec(docs)?))
+        .collect::<Result<Vec<_>>>()?;
+
+    let manifest = Manifest {
+        format: "sharded",
+        version: SHARD_FORMAT_VERSION,
+        base: "search-index/",
+        documents: index.documents.len(),
+        prefix_len: options.prefix_len,
+        gzip: options.gzip,
+        lengths,
+        docs_per_chunk: DOCS_PER_CHUNK,
+        chunks,
+        shards,
+    };
+    std::fs::write(dir.join(INDEX_FILE), rustmax::serde_json::to_string(&manifest)?)?;
+    Ok(())
+}
+
+/// The shard a term belongs to: its first `prefix_len` characters.
+fn shard_key(term: &str, prefix_len: usize) -> &str {
+    let end = term
+        .char_indices()
+        .nth(prefix_len)
+        .map_or(term.len(), |(i, _)| i);
+    &term[..end]
+}
+
+/// A file-name-safe form of a shard key: the key itself if it is ASCII
+/// alphanumeric, otherwise `_` and its UTF-8 bytes in hex.
+fn file_stem(key: &str) -> String {
+    if key.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        key.to_string()
+    } else {
+        std::iter::once("_".to_string())
+            .chain(key.bytes().map(|b| format!("{:02x}", b)))
+            .collect()
+    }
+}
+
+/// Encode sorted terms and their postings as a shard.
+fn encode_shard(terms: &[(&str, &[Posting])]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(&mut out, terms.len() as u64);
+
+    let mut prev: &[u8] = &[];
+    for (term, postings) in terms {
+        let bytes = term.as_bytes();
+        let shared = prev.iter().zip(bytes).take_while(|(a, b)| a == b).count();
+        write_varint(&mut out, shared as u64);
+        write_varint(&mut out, (bytes.len() - shared) as u64);
+        out.extend_from_slice(&bytes[shared..]);
+        prev = bytes;
+
+        let mut docs: Vec<(usize, u16)> = postings.iter().map(|(idx, tf, _)| (*idx, *tf)).collect();
+        docs.sort_unstable();
+        write_varint(&mut out, docs.len() as u64);
+        let mut last = 0;
+        for (idx, tf) in docs {
+            write_varint(&mut out, (idx - last) as u64);
+            write_varint(&mut out, tf as u64);
+            last = idx;
+        }
+    }
+    out
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::Document;
+    use rustmax::flate2::read::GzDecoder;
+    use rustmax::serde_json::Value;
+    use rustmax::tempfile::tempdir;
+    use std::io::Read;
+    use std::path::PathBuf;
+
+    fn read_varint(data: &[u8], pos: &mut usize) -> u64 {
+        let mut n = 0;
+        let mut shift = 0;
+        loop {
+            let byte = data[*pos];
+            *pos += 1;
+            n |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return n;
+            }
+            shift += 7;
+        }
+    }
+
+    fn decode_shard(data: &[u8]) -> Vec<(String, Vec<(usize, u16)>)> {
+        let mut pos = 0;
+        let mut terms = Vec::new();
+        let mut prev: Vec<u8> = Vec::new();
+        for _ in 0..read_varint(data, &mut pos) {
+            let shared = read_varint(data, &mut pos) as usize;
+            let len = read_varint(data, &mut pos) as usize;
+            prev.truncate(shared);
+            prev.extend_from_slice(&data[pos..pos + len]);
+            pos += len;
+
+            let mut postings = Vec::new();
+            let mut idx = 0;
+            for _ in 0..read_varint(data, &mut pos) {
+                idx += read_varint(data, &mut pos) as usize;
+                postings.push((idx, read_varint(data, &mut pos) as u16));
+            }
+            terms.push((String::from_utf8(prev.clone()).unwrap(), postings));
+        }
+        assert_eq!(pos, data.len());
+        terms
+    }
+
+    fn test_index() -> SearchIndex {
+        let docs: Vec<Document> = [
+            ("a.md", "Parsing", "Parser combinators parse text."),
+            ("b.md", "Networking", "Async sockets and parsers."),
+            ("c.md", "Élan", "Écrire du texte."),
+        ]
+        .iter()
+        .map(|(path, title, body)| {
+            Document::parse(
+                PathBuf::from(path),
+                &format!("---\ntitle = \"{}\"\n---\n\n{}", title, body),
+            )
+            .unwrap()
+        })
+        .collect();
+        SearchIndex::from_documents(&docs.iter().collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn test_varint() {
+        let mut out = Vec::new();
+        for n in [0, 127, 128, 300, u32::MAX as u64] {
+            write_varint(&mut out, n);
+        }
+        assert_eq!(out.len(), 1 + 1 + 2 + 2 + 5);
+        let mut pos = 0;
+        for n in [0, 127, 128, 300, u32::MAX as u64] {
+            assert_eq!(read_varint(&out, &mut pos), n);
+        }
+    }
+
+    #[test]
+    fn test_shard_keys() {
+        assert_eq!(shard_key("parser", 2), "pa");
+        assert_eq!(shard_key("a", 2), "a");
+        assert_eq!(shard_key("écrir", 2), "éc");
+        assert_eq!(file_stem("pa"), "pa");
+        assert_eq!(file_stem("éc"), "_c3a963");
+    }
+
+    #[test]
+    fn test_write_sharded() {
+        let index = test_index();
+        let dir = tempdir().unwrap();
+        write_index(&index, dir.path(), IndexFormat::Sharded(ShardOptions::default())).unwrap();
+
+        let manifest: Value =
+            rustmax::serde_json::from_str(&std::fs::read_to_string(dir.path().join(INDEX_FILE)).unwrap()).unwrap();
+        assert_eq!(manifest["format"], "sharded");
+        assert_eq!(manifest["documents"], 3);
+        assert_eq!(manifest["shards"]["pa"], "terms-pa.bin");
+        assert_eq!(manifest["chunks"][0], "docs-0.json");
+
+        // Every term lands in its shard with the same postings.
+        let shard_dir = dir.path().join(SHARD_DIR);
+        let mut seen = 0;
+        for (key, file) in manifest["shards"].as_object().unwrap() {
+            let terms = decode_shard(&std::fs::read(shard_dir.join(file.as_str().unwrap())).unwrap());
+            for (term, postings) in terms {
+                assert!(term.starts_with(key.as_str()));
+                let expected: Vec<(usize, u16)> =
+                    index.word_index[&term].iter().map(|(idx, tf, _)| (*idx, *tf)).collect();
+                assert_eq!(postings, expected);
+                seen += 1;
+            }
+        }
+        assert_eq!(seen, index.word_index.len());
+
+        let docs: Value =
+            rustmax::serde_json::from_str(&std::fs::read_to_string(shard_dir.join("docs-0.json")).unwrap()).unwrap();
+        assert_eq!(docs[1]["title"], "Networking");
+
+        // Switching back to JSON removes the shards.
+        write_index(&index, dir.path(), IndexFormat::Json).unwrap();
+        assert!(!shard_dir.exists());
+
+        // The JSON index has the same postings, without positions.
+        let json: Value =
+            rustmax::serde_json::from_str(&std::fs::read_to_string(dir.path().join(INDEX_FILE)).unwrap()).unwrap();
+        let (idx, tf, _) = &index.word_index["pars"][0];
+        assert_eq!(json["word_index"]["pars"][0], rustmax::serde_json::json!([idx, tf]));
+        assert_eq!(json["documents"][1]["title"], "Networking");
+    }
+
+    #[test]
+    fn test_write_sharded_gzip() {
+        let index = test_index();
+        let dir = tempdir().unwrap();
+        let options = ShardOptions { prefix_len: 1, gzip: true };
+        write_index(&index, dir.path(), IndexFormat::Sharded(options)).unwrap();
+
+        let shard = dir.path().join(SHARD_DIR).join("terms-p.bin.gz");
+        let mut data = Vec::new();
+        GzDecoder::new(std::fs::File::open(shard).unwrap()).read_to_end(&mut data).unwrap();
+        let terms: Vec<String> = decode_shard(&data).into_iter().map(|(term, _)| term).collect();
+        assert!(terms.contains(&"pars".to_string()));
+        assert!(terms.windows(2).all(|w| w[0] < w[1]));
+        assert!(dir.path().join(SHARD_DIR).join("lengths.bin.gz").exists());
+    }
+}
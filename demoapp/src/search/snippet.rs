@@ -0,0 +1,201 @@
+//! Query-dependent result snippets.
+
+use rustmax::prelude::*;
+use rustmax::regex::Regex;
+use rustmax::unicode_segmentation::UnicodeSegmentation;
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use super::{analyze, edit_distance, max_typos, Clause, Query};
+
+/// Number of words in a snippet.
+const SNIPPET_WORDS: usize = 30;
+
+/// Words of context shown before the first match.
+const SNIPPET_CONTEXT: usize = 5;
+
+/// HTML tags, shortcodes, and link targets, which excerpts leave out.
+static MARKUP_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<[^>]*>|\{\{.*?\}\}|\{%.*?%\}|\]\([^)]*\)").expect("invalid regex")
+});
+
+/// A query word to highlight, and whether near matches count.
+struct Highlight<'a> {
+    stem: &'a str,
+    loose: bool,
+}
+
+impl Highlight<'_> {
+    /// Check a stemmed word against this query word, using the same
+    /// prefix and typo rules as scoring for loose terms.
+    fn matches(&self, stem: &str) -> bool {
+        stem == self.stem
+            || (self.loose
+                && (stem.starts_with(self.stem)
+                    || edit_distance(self.stem, stem, max_typos(self.stem)).is_some()))
+    }
+}
+
+/// Build an HTML snippet of `text` for a query.
+///
+/// The snippet is the window of text with the most distinct query words
+/// (then the most matches), with matched words wrapped in `<mark>`.
+/// Text without matches yields its opening words. The text is
+/// HTML-escaped, with whitespace collapsed and Markdown markup dropped.
+pub fn snippet(text: &str, query: &Query) -> String {
+    let highlights: Vec<Highlight<'_>> = query
+        .groups
+        .iter()
+        .flatten()
+        .flat_map(|clause| match clause {
+            Clause::Term(term) => vec![Highlight { stem: term, loose: true }],
+            Clause::Phrase(words) => words
+                .iter()
+                .map(|(word, _)| Highlight { stem: word, loose: false })
+                .collect(),
+        })
+        .collect();
+
+    // Each word's byte range and the query word it matches, if any.
+    let words: Vec<(usize, &str, Option<usize>)> = text
+        .unicode_word_indices()
+        .map(|(start, word)| {
+            let matched = analyze(word)
+                .next()
+                .and_then(|(_, stem)| highlights.iter().position(|h| h.matches(&stem)));
+            (start, word, matched)
+        })
+        .collect();
+
+    let mut best: Option<(usize, usize, usize)> = None;
+    for (i, _) in words.iter().enumerate().filter(|(_, w)| w.2.is_some()) {
+        let start = i.saturating_sub(SNIPPET_CONTEXT);
+        let window = &words[start..words.len().min(start + SNIPPET_WORDS)];
+        let distinct: HashSet<usize> = window.iter().filter_map(|w| w.2).collect();
+        let total = window.iter().filter(|w| w.2.is_some()).count();
+        if best.is_none_or(|(_, d, t)| (distinct.len(), total) > (d, t)) {
+            best = Some((start, distinct.len(), total));
+        }
+    }
+
+    let start = best.map_or(0, |(start, _, _)| start);
+    let end = words.len().min(start + SNIPPET_WORDS);
+    let mut out = String::new();
+    if start > 0 {
+        out.push_str("… ");
+    }
+
+    for (n, &(offset, word, matched)) in words[start..end].iter().enumerate() {
+        if n > 0 {
+            let (prev_offset, prev_word, _) = words[start + n - 1];
+            let gap = &text[prev_offset + prev_word.len()..offset];
+            out.push_str(&html_escape(&clean_gap(gap)));
+        }
+        if matched.is_some() {
+            out.push_str("<mark>");
+            out.push_str(&html_escape(word));
+            out.push_str("</mark>");
+        } else {
+            out.push_str(&html_escape(word));
+        }
+    }
+
+    if end < words.len() {
+        out.push_str(" …");
+    } else if let Some(&(offset, word, _)) = words.last() {
+        out.push_str(&html_escape(clean_gap(&text[offset + word.len()..]).trim_end()));
+    }
+    out
+}
+
+/// The first `max_words` words of `text` as plain text.
+///
+/// HTML tags, shortcodes, link targets and Markdown markup are dropped and
+/// whitespace is collapsed. The result is not HTML-escaped.
+pub fn plain_excerpt(text: &str, max_words: usize) -> String {
+    let text = MARKUP_RE.replace_all(text, " ");
+    let words: Vec<(usize, &str)> = text.unicode_word_indices().take(max_words).collect();
+
+    let mut out = String::new();
+    for (n, &(offset, word)) in words.iter().enumerate() {
+        if n > 0 {
+            let (prev_offset, prev_word) = words[n - 1];
+            out.push_str(&clean_gap(&text[prev_offset + prev_word.len()..offset]));
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+/// Collapse whitespace and drop Markdown markup between two words.
+fn clean_gap(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut in_space = false;
+    for c in s.chars() {
+        if matches!(c, '#' | '*' | '_' | '`' | '~' | '|' | '[' | ']') {
+            continue;
+        }
+        if c.is_whitespace() {
+            if !in_space {
+                out.push(' ');
+            }
+            in_space = true;
+        } else {
+            out.push(c);
+            in_space = false;
+        }
+    }
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_marks_best_window() {
+        let filler = "lorem ipsum ".repeat(40);
+        let text = format!("Errors once. {}Handle <errors> with **Result**.\n\n## Then recover.", filler);
+
+        let result = snippet(&text, &Query::parse("error result"));
+        assert!(result.starts_with("… "));
+        assert!(result.contains("Handle &lt;<mark>errors</mark>&gt; with <mark>Result</mark>. Then recover."));
+        assert!(!result.contains("Errors once"));
+    }
+
+    #[test]
+    fn test_snippet_typos_and_phrases() {
+        let text = "The tokio runtime drives async tasks.";
+        assert!(snippet(text, &Query::parse("tokoi")).contains("<mark>tokio</mark>"));
+
+        // Phrase words match exactly.
+        let result = snippet(text, &Query::parse("\"async taks\""));
+        assert!(!result.contains("<mark>tasks</mark>"));
+        assert!(result.contains("<mark>async</mark>"));
+    }
+
+    #[test]
+    fn test_plain_excerpt() {
+        let text = "## Intro\n\nSee [the **docs**](https://example.com/docs) <em>now</em>.\n\n{{< note >}}\n\nMore words here.";
+        assert_eq!(plain_excerpt(text, 100), "Intro See the docs now . More words here");
+        assert_eq!(plain_excerpt(text, 3), "Intro See the");
+    }
+
+    #[test]
+    fn test_snippet_without_matches() {
+        let text = "one two three four five six seven eight nine ten eleven twelve thirteen \
+            fourteen fifteen sixteen seventeen eighteen nineteen twenty twenty-one twenty-two \
+            twenty-three twenty-four twenty-five twenty-six";
+        let result = snippet(text, &Query::parse("missing"));
+        assert!(result.starts_with("one two"));
+        assert!(result.ends_with(" …"));
+        assert!(!result.contains("<mark>"));
+    }
+}
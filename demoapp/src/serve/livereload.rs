@@ -1,7 +1,8 @@
 //! Live reload functionality via WebSocket.
 //!
 //! Watches for file changes and broadcasts reload messages to connected clients.
-//! Uses the notify crate for native filesystem event notifications.
+//! Uses the notify crate for native filesystem event notifications. Build
+//! errors are shown over the page in an overlay until they are fixed.
 
 use rustmax::prelude::*;
 use rustmax::axum::{
@@ -14,8 +15,8 @@ use rustmax::axum::{
 use rustmax::tokio::sync::broadcast;
 use rustmax::notify::{self, Watcher, RecursiveMode, EventKind, event::{CreateKind, ModifyKind, RemoveKind}};
 use rustmax::log::{debug, info, warn};
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 
 /// Type of change detected.
 #[derive(Debug, Clone, Copy)]
@@ -42,13 +43,31 @@ impl ChangeType {
 /// State for live reload.
 pub struct LiveReloadState {
     sender: broadcast::Sender<ChangeType>,
+    /// The error to show over the page, if any.
+    error: Mutex<Option<String>>,
 }
 
 impl LiveReloadState {
     /// Create a new live reload state.
     pub fn new() -> Self {
         let (sender, _) = broadcast::channel(16);
-        Self { sender }
+        Self {
+            sender,
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Set or clear the error shown to clients.
+    ///
+    /// Clients get it when they connect, so it appears on the page that
+    /// reloads after the change that caused it.
+    pub fn set_error(&self, error: Option<String>) {
+        *self.error.lock().unwrap() = error;
+    }
+
+    /// The error shown to clients.
+    pub fn error(&self) -> Option<String> {
+        self.error.lock().unwrap().clone()
     }
 
     /// Get a receiver for reload events.
@@ -87,6 +106,11 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<LiveReloadState>) {
     if socket.send(Message::Text("connected".into())).await.is_err() {
         return;
     }
+    if let Some(error) = state.error()
+        && socket.send(Message::Text(format!("error:{}", error).into())).await.is_err()
+    {
+        return;
+    }
 
     loop {
         rustmax::tokio::select! {
@@ -127,15 +151,31 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<LiveReloadState>) {
     debug!("Live reload client disconnected");
 }
 
+/// Callback run with each changed path before clients are notified.
+type ChangeHook = Box<dyn Fn(&Path) + Send + Sync>;
+
 /// File watcher using native filesystem events via notify crate.
 pub struct FileWatcher {
     paths: Vec<PathBuf>,
+    on_change: Option<ChangeHook>,
 }
 
 impl FileWatcher {
     /// Create a new file watcher.
     pub fn new(paths: Vec<PathBuf>) -> Self {
-        Self { paths }
+        Self {
+            paths,
+            on_change: None,
+        }
+    }
+
+    /// Run a callback for each change before triggering a reload.
+    ///
+    /// Lets the server refresh state it caches, such as data files,
+    /// so the reloaded page sees the change.
+    pub fn on_change(mut self, hook: impl Fn(&Path) + Send + Sync + 'static) -> Self {
+        self.on_change = Some(Box::new(hook));
+        self
     }
 
     /// Determine change type from a file path.
@@ -147,7 +187,7 @@ impl FileWatcher {
 
         match ext {
             "css" => ChangeType::Css,
-            "md" | "html" => ChangeType::Content,
+            "md" | "html" | "toml" | "json" | "yaml" | "yml" => ChangeType::Content,
             _ => ChangeType::Asset,
         }
     }
@@ -214,18 +254,44 @@ impl FileWatcher {
         while let Some(path) = rx.recv().await {
             let change_type = Self::classify_path(&path);
             debug!("File changed ({:?}): {}", change_type, path.display());
+            if let Some(hook) = &self.on_change {
+                hook(&path);
+            }
             info!("Detected {:?} change, triggering reload", change_type);
             state.trigger(change_type);
         }
     }
 }
 
+/// Id of the element showing a build error over the page.
+const OVERLAY_ID: &str = "anthology-error-overlay";
+
+/// Style of the error overlay, shared by the script and [`error_page`].
+const OVERLAY_STYLE: &str = "position:fixed;inset:0;z-index:2147483647;overflow:auto;margin:0;\
+padding:2em;background:rgba(20,20,20,0.92);color:#f3f3f3;\
+font:14px/1.5 ui-monospace,SFMono-Regular,Menlo,Consolas,monospace;white-space:pre-wrap;";
+
 /// JavaScript snippet to inject into pages for live reload via WebSocket.
+///
+/// An `error:` message shows the rest of the message in an overlay;
+/// clicking the overlay dismisses it.
 pub fn live_reload_script(_port: u16) -> String {
     r#"<script>
 (function() {
     var reconnectDelay = 1000;
 
+    function showError(text) {
+        var overlay = document.getElementById('__OVERLAY_ID__');
+        if (!overlay) {
+            overlay = document.createElement('pre');
+            overlay.id = '__OVERLAY_ID__';
+            overlay.setAttribute('style', '__OVERLAY_STYLE__');
+            overlay.onclick = function() { overlay.remove(); };
+            document.body.appendChild(overlay);
+        }
+        overlay.textContent = text;
+    }
+
     function connect() {
         var protocol = location.protocol === 'https:' ? 'wss:' : 'ws:';
         var ws = new WebSocket(protocol + '//' + location.host + '/livereload');
@@ -246,6 +312,9 @@ pub fn live_reload_script(_port: u16) -> String {
                     var href = link.href.split('?')[0];
                     link.href = href + '?v=' + Date.now();
                 });
+            } else if (event.data.indexOf('error:') === 0) {
+                console.log('[live-reload] Build error');
+                showError(event.data.slice('error:'.length));
             } else if (event.data === 'connected') {
                 console.log('[live-reload] Ready');
             }
@@ -264,7 +333,30 @@ pub fn live_reload_script(_port: u16) -> String {
 
     connect();
 })();
-</script>"#.to_string()
+</script>"#
+        .replace("__OVERLAY_ID__", OVERLAY_ID)
+        .replace("__OVERLAY_STYLE__", OVERLAY_STYLE)
+}
+
+/// A page showing an error in the overlay, with live reload so it is
+/// replaced once the error is fixed.
+pub fn error_page(error: &str, port: u16) -> String {
+    let html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Build error</title></head>\n\
+         <body><pre id=\"{}\" style=\"{}\">{}</pre></body>\n</html>\n",
+        OVERLAY_ID,
+        OVERLAY_STYLE,
+        html_escape(error),
+    );
+    inject_script(&html, port)
+}
+
+/// Escape text for HTML.
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
 /// Inject live reload script into HTML content.
@@ -308,6 +400,24 @@ mod tests {
         assert!(script.contains("location.reload()"));
     }
 
+    #[test]
+    fn test_error_overlay() {
+        let state = LiveReloadState::new();
+        assert_eq!(state.error(), None);
+        state.set_error(Some("post.md:3:9: invalid TOML".into()));
+        assert_eq!(state.error().as_deref(), Some("post.md:3:9: invalid TOML"));
+
+        let script = live_reload_script(3000);
+        assert!(script.contains("'error:'"));
+        assert!(script.contains(OVERLAY_ID));
+        assert!(!script.contains("__OVERLAY"));
+
+        let page = error_page("page.html:2:4: Variable `x` <missing>", 3000);
+        assert!(page.contains(r#"<pre id="anthology-error-overlay""#));
+        assert!(page.contains("Variable `x` &lt;missing&gt;"));
+        assert!(page.contains("</script></body>"));
+    }
+
     #[test]
     fn test_inject_script_body() {
         let html = "<html><body><p>Hello</p></body></html>";
@@ -348,6 +458,12 @@ mod tests {
         assert!(matches!(FileWatcher::classify_path(&path), ChangeType::Content));
     }
 
+    #[test]
+    fn test_classify_path_content_data() {
+        let path = PathBuf::from("data/nav.yaml");
+        assert!(matches!(FileWatcher::classify_path(&path), ChangeType::Content));
+    }
+
     #[test]
     fn test_classify_path_asset() {
         let path = PathBuf::from("image.png");
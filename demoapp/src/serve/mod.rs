@@ -2,26 +2,29 @@
 
 mod livereload;
 
-pub use livereload::{LiveReloadState, ChangeType, FileWatcher, live_reload_script, inject_script};
+pub use livereload::{LiveReloadState, ChangeType, FileWatcher, error_page, live_reload_script, inject_script};
 
 use rustmax::prelude::*;
 use rustmax::axum::{
     Router,
     routing::get,
     response::{Html, IntoResponse, Response},
-    extract::{State, Path as AxumPath, Query},
+    extract::{State, Path as AxumPath, Query, Request},
     http::StatusCode,
 };
 use tower_http::services::ServeDir;
 use rustmax::tokio::net::TcpListener;
+use rustmax::tower::ServiceExt;
 use rustmax::tokio::sync::oneshot;
-use rustmax::log::info;
-use serde::Deserialize;
+use rustmax::log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use crate::collection::{Collection, Config, Document};
-use crate::build::{render_markdown, TemplateEngine};
-use crate::search::SearchIndex;
+use crate::collection::{language_prefix, term_url, Collection, Config, Document, DocumentExport, Section};
+use crate::build::{guess_mime_type, load_data, paginate, render_document, render_section, split_page_url, Pager, TemplateEngine, DATA_DIR};
+use crate::features::BuildFeatures;
+use crate::search::{RelatedDocument, RelatedIndex, SearchIndex};
 use crate::{Error, Result};
 
 /// Shared server state.
@@ -30,8 +33,65 @@ struct AppState {
     config: Config,
     engine: TemplateEngine,
     search_index: SearchIndex,
+    related: RelatedIndex,
     include_drafts: bool,
+    include_future: bool,
     port: u16,
+    output_dir: PathBuf,
+}
+
+/// Reload data files into the template engine after a change.
+///
+/// A file that fails to parse keeps the previous data in place.
+fn reload_data(engine: &TemplateEngine, data_dir: &Path) {
+    match load_data(data_dir) {
+        Ok(data) => {
+            engine.set_data(data);
+            info!("Reloaded data files");
+        }
+        Err(e) => warn!("Failed to reload data files: {}", e),
+    }
+}
+
+/// Check a changed document or template for errors to show over the page.
+///
+/// Documents are parsed and their shortcodes expanded; templates are all
+/// recompiled. A clean check clears the overlay.
+fn check_change(state: &AppState, live_reload: &LiveReloadState, template_dirs: &[PathBuf], path: &Path) {
+    let root = &state.collection.root;
+    let content_dir = root.join("content");
+    let extension = path.extension().and_then(|e| e.to_str());
+    let result = match extension {
+        Some("html") if template_dirs.iter().any(|dir| path.starts_with(dir)) => {
+            TemplateEngine::for_collection(root, &state.config).map(drop)
+        }
+        Some("md") if path.starts_with(&content_dir) && path.exists() => Document::load(path)
+            .and_then(|doc| render_document(&doc, &state.config, &state.engine, BuildFeatures::DEVELOPMENT))
+            .map(drop),
+        _ => return,
+    };
+
+    match result {
+        Ok(()) => live_reload.set_error(None),
+        Err(e) => {
+            warn!("{}", e);
+            live_reload.set_error(Some(e.to_string()));
+        }
+    }
+}
+
+/// Respond to a request whose page failed to render.
+fn error_response(state: &AppState, error: &Error) -> Response {
+    warn!("{}", error);
+    (StatusCode::INTERNAL_SERVER_ERROR, Html(error_page(&error.to_string(), state.port))).into_response()
+}
+
+/// A document in the API, with its related documents.
+#[derive(Serialize)]
+struct DocumentResponse<'a> {
+    #[serde(flatten)]
+    document: DocumentExport,
+    related: &'a [RelatedDocument],
 }
 
 /// Query parameters for search endpoint.
@@ -52,19 +112,26 @@ pub fn serve(
     config: Config,
     port: u16,
     include_drafts: bool,
+    include_future: bool,
 ) -> Result<()> {
-    let templates_dir = collection.root.join("templates");
-    let engine = TemplateEngine::new(&templates_dir)?;
+    let template_dirs = crate::theme::template_dirs(&collection.root, &config.themes);
+    let engine = TemplateEngine::for_collection(&collection.root, &config)?;
     let static_dir = collection.root.join("static");
     let content_dir = collection.root.join("content");
+    let data_dir = collection.root.join(DATA_DIR);
+    engine.set_data(load_data(&data_dir)?);
+    let output_dir = collection.root.join(&config.build.output_dir);
 
-    // Build search index.
-    let search_index = SearchIndex::build(&collection);
+    // Index and relate the documents the server shows.
+    let visible = collection.visible(include_drafts, include_future);
+    let search_index = SearchIndex::from_documents(&visible);
     info!("Search index built with {} documents", search_index.documents.len());
+    let related = RelatedIndex::build(&visible, config.content.related);
 
     // Set up live reload.
     let live_reload = Arc::new(LiveReloadState::new());
     let live_reload_for_watcher = Arc::clone(&live_reload);
+    let live_reload_for_checks = Arc::clone(&live_reload);
     let live_reload_for_ws = Arc::clone(&live_reload);
 
     let state = Arc::new(AppState {
@@ -72,17 +139,28 @@ pub fn serve(
         config,
         engine,
         search_index,
+        related,
         include_drafts,
+        include_future,
         port,
+        output_dir,
     });
+    let state_for_watcher = Arc::clone(&state);
     drop(live_reload); // Ownership transferred to routes.
 
     let rt = rustmax::tokio::runtime::Runtime::new()?;
     rt.block_on(async move {
         // Start file watcher in background.
-        let watch_paths = vec![content_dir, templates_dir, static_dir.clone()];
+        let mut watch_paths = vec![content_dir, static_dir.clone(), data_dir.clone()];
+        watch_paths.extend(template_dirs.iter().cloned());
+        watch_paths.extend(state_for_watcher.config.themes.iter().map(|theme| theme.static_dir()));
         rustmax::tokio::spawn(async move {
-            let watcher = FileWatcher::new(watch_paths);
+            let watcher = FileWatcher::new(watch_paths).on_change(move |path| {
+                if path.starts_with(&data_dir) {
+                    reload_data(&state_for_watcher.engine, &data_dir);
+                }
+                check_change(&state_for_watcher, &live_reload_for_checks, &template_dirs, path);
+            });
             watcher.watch(live_reload_for_watcher).await;
         });
 
@@ -93,18 +171,18 @@ pub fn serve(
 
         let mut app = Router::new()
             .route("/", get(handle_index))
-            .route("/{slug}/", get(handle_document))
-            .route("/tags/{tag}/", get(handle_tag))
+            .route("/page/{number}/", get(handle_index_page))
             .route("/api/documents", get(api_documents))
             .route("/api/documents/{slug}", get(api_document))
             .route("/api/search", get(api_search))
             .route("/api/search/suggest", get(api_suggest))
+            .fallback(handle_page)
             .with_state(state)
             .merge(reload_routes);
 
         // Serve static files if directory exists.
         if static_dir.exists() {
-            app = app.nest_service("/static", ServeDir::new(&static_dir));
+            app = app.nest_service("/static", precompressed(ServeDir::new(&static_dir)));
         }
 
         let addr = format!("0.0.0.0:{}", port);
@@ -145,97 +223,260 @@ pub fn serve(
     })
 }
 
+/// Serve `.gz`, `.br`, and `.zst` variants matching the request's `Accept-Encoding`.
+fn precompressed(dir: ServeDir) -> ServeDir {
+    dir.precompressed_gzip().precompressed_br().precompressed_zstd()
+}
+
 /// Handle index page.
 async fn handle_index(State(state): State<Arc<AppState>>) -> Response {
-    let documents: Vec<&Document> = if state.include_drafts {
-        state.collection.all_sorted()
-    } else {
-        state.collection.published()
-    };
-
-    let ctx = state.engine.index_context(&documents, &state.config);
-    match state.engine.render("index.html", &ctx) {
-        Ok(html) => {
-            let html = inject_script(&html, state.port);
-            Html(html).into_response()
-        }
-        Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Template error: {}", e))
-                .into_response()
-        }
-    }
+    render_index_page(&state, None, 1)
 }
 
-/// Handle document page.
-async fn handle_document(
+/// Handle later pages of the index.
+async fn handle_index_page(
     State(state): State<Arc<AppState>>,
-    AxumPath(slug): AxumPath<String>,
+    AxumPath(number): AxumPath<usize>,
 ) -> Response {
-    let doc = state
+    render_index_page(&state, None, number)
+}
+
+/// Render page `number` of a language's index.
+fn render_index_page(state: &AppState, language: Option<&str>, number: usize) -> Response {
+    let documents: Vec<&Document> = state
         .collection
-        .documents
+        .visible(state.include_drafts, state.include_future)
+        .into_iter()
+        .filter(|d| d.language.as_deref() == language)
+        .collect();
+
+    let base_url = format!("{}/", language_prefix(language));
+    let Some((pager, documents)) = page_of(&documents, state.config.content.paginate, &base_url, number) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let mut ctx = state.engine.language_index_context(documents, &state.config, language);
+    state.engine.insert_paginator(&mut ctx, &pager, documents);
+    render_page(state, "index.html", &ctx)
+}
+
+/// Find page `number` of a listing and the documents on it.
+///
+/// Returns `None` if the listing has no such page.
+fn page_of<'a, 'd>(
+    documents: &'a [&'d Document],
+    per_page: usize,
+    base_url: &str,
+    number: usize,
+) -> Option<(Pager, &'a [&'d Document])> {
+    let pager = paginate(documents.len(), per_page, base_url)
+        .into_iter()
+        .nth(number.checked_sub(1)?)?;
+    let range = pager.range();
+    Some((pager, &documents[range]))
+}
+
+/// Handle documents, taxonomies, sections, and bundle assets by URL.
+///
+/// Anything else is served from the last build's output, e.g. feeds and
+/// search assets.
+async fn handle_page(State(state): State<Arc<AppState>>, request: Request) -> Response {
+    let url = percent_decode(request.uri().path());
+    let url = url.as_str();
+    let visible = |d: &&Document| d.is_visible(state.include_drafts, state.include_future);
+
+    if let Some(doc) = state.collection.documents.iter().filter(visible).find(|d| d.url_path() == url) {
+        return render_document_page(&state, doc);
+    }
+
+    let (listing_url, number) = split_page_url(url);
+    let segments: Vec<&str> = listing_url.trim_matches('/').split('/').collect();
+
+    // Index pages of translation languages, e.g. `/de/`.
+    if let [code] = segments[..]
+        && state.config.translation_languages().iter().any(|l| l == code)
+    {
+        return render_index_page(&state, Some(code), number);
+    }
+    if let Some(taxonomy) = state.config.content.taxonomies.iter().find(|t| **t == segments[0])
+        && listing_url.ends_with('/')
+    {
+        match segments[1..] {
+            [] if number == 1 => return render_taxonomy_page(&state, taxonomy),
+            [slug] => return render_term_page(&state, taxonomy, slug, number),
+            _ => {}
+        }
+    }
+
+    if let Some(section) = state.collection.sections.iter().find(|s| !s.is_root() && s.url_path() == listing_url) {
+        return render_section_page(&state, section, number);
+    }
+
+    // Only serve bundle files the scanner found, never arbitrary paths.
+    let asset = state.collection.documents.iter().filter(visible).find_map(|doc| {
+        let relative = url.strip_prefix(&doc.url_path())?;
+        let bundle_dir = doc.bundle_dir.as_ref()?;
+        let asset = doc.assets.iter().find(|a| a.path == Path::new(relative))?;
+        Some(bundle_dir.join(&asset.path))
+    });
+    if let Some(path) = asset {
+        return match std::fs::read(&path) {
+            Ok(data) => ([("Content-Type", guess_mime_type(&path))], data).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        };
+    }
+
+    // Builds copy theme static files to the site root.
+    let relative = url.trim_start_matches('/');
+    let theme_static = state
+        .config
+        .themes
         .iter()
-        .find(|d| d.slug() == slug);
+        .map(|theme| theme.static_dir())
+        .find(|dir| !relative.split('/').any(|s| s == "..") && dir.join(relative).is_file());
+    if let Some(dir) = theme_static {
+        return precompressed(ServeDir::new(dir)).oneshot(request).await.into_response();
+    }
 
-    match doc {
-        Some(doc) => {
-            if doc.frontmatter.draft && !state.include_drafts {
-                return StatusCode::NOT_FOUND.into_response();
-            }
+    if state.output_dir.exists() {
+        return precompressed(ServeDir::new(&state.output_dir))
+            .oneshot(request)
+            .await
+            .into_response();
+    }
 
-            let html_content = render_markdown(&doc.content);
-            let ctx = state
-                .engine
-                .document_context(doc, &state.config, &html_content);
-
-            let template = doc
-                .frontmatter
-                .template
-                .as_deref()
-                .unwrap_or(&state.config.content.default_template);
-
-            match state.engine.render(template, &ctx) {
-                Ok(html) => {
-                    let html = inject_script(&html, state.port);
-                    Html(html).into_response()
-                }
-                Err(e) => {
-                    (StatusCode::INTERNAL_SERVER_ERROR, format!("Template error: {}", e))
-                        .into_response()
-                }
-            }
+    StatusCode::NOT_FOUND.into_response()
+}
+
+/// Render a document page.
+fn render_document_page(state: &AppState, doc: &Document) -> Response {
+    let html_content = match render_document(doc, &state.config, &state.engine, BuildFeatures::DEVELOPMENT) {
+        Ok(html) => html,
+        Err(e) => return error_response(state, &e),
+    };
+    let mut ctx = state
+        .engine
+        .document_context(doc, &state.collection, &state.config, &html_content);
+    state.engine.insert_related(&mut ctx, state.related.get(doc));
+
+    let template = doc
+        .frontmatter
+        .template
+        .as_deref()
+        .unwrap_or(&state.config.content.default_template);
+
+    render_page(state, template, &ctx)
+}
+
+/// Render page `number` of a section listing.
+fn render_section_page(state: &AppState, section: &Section, number: usize) -> Response {
+    if section.frontmatter.draft && !state.include_drafts {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let html_content = match render_section(section, &state.config, &state.engine, BuildFeatures::DEVELOPMENT) {
+        Ok(html) => html,
+        Err(e) => return error_response(state, &e),
+    };
+    let documents = state.collection.section_documents(section, state.include_drafts, state.include_future);
+    let per_page = section.paginate().unwrap_or(state.config.content.paginate);
+    let Some((pager, documents)) = page_of(&documents, per_page, &section.url_path(), number) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let mut ctx = state.engine.section_context(
+        section,
+        documents,
+        &state.collection,
+        &state.config,
+        &html_content,
+    );
+    state.engine.insert_paginator(&mut ctx, &pager, documents);
+
+    let template = section.frontmatter.template.as_deref().unwrap_or("section.html");
+    render_page(state, template, &ctx)
+}
+
+/// Render a template with live reload injected.
+fn render_page(state: &AppState, template: &str, ctx: &rustmax::tera::Context) -> Response {
+    match state.engine.render(template, ctx) {
+        Ok(html) => {
+            let html = inject_script(&html, state.port);
+            Html(html).into_response()
         }
-        None => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => error_response(state, &e),
     }
 }
 
-/// Handle tag page.
-async fn handle_tag(
-    State(state): State<Arc<AppState>>,
-    AxumPath(tag): AxumPath<String>,
-) -> Response {
+/// Render page `number` of the listing of the term whose slug is `slug`.
+fn render_term_page(state: &AppState, taxonomy: &str, slug: &str, number: usize) -> Response {
+    let Some(term) = state.collection.term_by_slug(taxonomy, slug) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let term = term.as_str();
     let documents: Vec<&Document> = state
         .collection
-        .by_tag(&tag)
+        .by_term(taxonomy, term)
         .into_iter()
-        .filter(|d| state.include_drafts || !d.frontmatter.draft)
+        .filter(|d| d.is_visible(state.include_drafts, state.include_future))
         .collect();
 
     if documents.is_empty() {
         return StatusCode::NOT_FOUND.into_response();
     }
 
-    let ctx = state.engine.tag_context(&tag, &documents, &state.config);
-    match state.engine.render("tag.html", &ctx) {
-        Ok(html) => {
-            let html = inject_script(&html, state.port);
-            Html(html).into_response()
-        }
-        Err(e) => {
-            (StatusCode::INTERNAL_SERVER_ERROR, format!("Template error: {}", e))
-                .into_response()
+    let base_url = term_url(taxonomy, term);
+    let Some((pager, documents)) = page_of(&documents, state.config.content.paginate, &base_url, number) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let mut ctx = state.engine.term_context(taxonomy, term, documents, &state.config);
+    state.engine.insert_paginator(&mut ctx, &pager, documents);
+    render_page(state, state.engine.term_template(taxonomy), &ctx)
+}
+
+/// Render the term index of a taxonomy.
+fn render_taxonomy_page(state: &AppState, taxonomy: &str) -> Response {
+    let terms: Vec<(String, usize)> = state
+        .collection
+        .terms(taxonomy)
+        .into_iter()
+        .map(|term| {
+            let count = state
+                .collection
+                .by_term(taxonomy, &term)
+                .iter()
+                .filter(|d| d.is_visible(state.include_drafts, state.include_future))
+                .count();
+            (term, count)
+        })
+        .filter(|(_, count)| *count > 0)
+        .collect();
+
+    let ctx = state.engine.taxonomy_context(taxonomy, &terms, &state.config);
+    render_page(state, "taxonomy.html", &ctx)
+}
+
+/// Decode `%XX` escapes in a request path.
+fn percent_decode(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = path.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
         }
     }
+
+    String::from_utf8_lossy(&decoded).into_owned()
 }
 
 /// API: list all documents.
@@ -268,8 +509,11 @@ async fn api_document(
 
     match doc {
         Some(doc) => {
-            let export = doc.to_export();
-            match rustmax::serde_json::to_string_pretty(&export) {
+            let response = DocumentResponse {
+                document: doc.to_export(),
+                related: state.related.get(doc),
+            };
+            match rustmax::serde_json::to_string_pretty(&response) {
                 Ok(json) => (
                     StatusCode::OK,
                     [("Content-Type", "application/json")],
@@ -325,3 +569,49 @@ async fn api_suggest(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustmax::axum::body::Body;
+    use rustmax::axum::http::{Request, header};
+    use rustmax::tempfile::tempdir;
+
+    async fn content_encoding(dir: &std::path::Path, accept: &str) -> Option<String> {
+        let request = Request::get("/app.js")
+            .header(header::ACCEPT_ENCODING, accept)
+            .body(Body::empty())
+            .unwrap();
+        let response = precompressed(ServeDir::new(dir)).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response
+            .headers()
+            .get(header::CONTENT_ENCODING)
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn test_precompressed_variants() {
+        let dir = tempdir().unwrap();
+        std::fs::write(dir.path().join("app.js"), "plain").unwrap();
+        std::fs::write(dir.path().join("app.js.gz"), "gzip").unwrap();
+        std::fs::write(dir.path().join("app.js.br"), "br").unwrap();
+        std::fs::write(dir.path().join("app.js.zst"), "zstd").unwrap();
+
+        let rt = rustmax::tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            assert_eq!(content_encoding(dir.path(), "br").await.as_deref(), Some("br"));
+            assert_eq!(content_encoding(dir.path(), "zstd").await.as_deref(), Some("zstd"));
+            assert_eq!(content_encoding(dir.path(), "gzip, deflate").await.as_deref(), Some("gzip"));
+            assert_eq!(content_encoding(dir.path(), "gzip;q=0.5, br;q=1.0").await.as_deref(), Some("br"));
+            assert_eq!(content_encoding(dir.path(), "identity").await, None);
+        });
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("/tags/web%20dev/"), "/tags/web dev/");
+        assert_eq!(percent_decode("/authors/Ren%C3%A9/"), "/authors/René/");
+        assert_eq!(percent_decode("/100%/"), "/100%/");
+    }
+}
@@ -16,6 +16,8 @@ use rustmax::nom::{
 };
 use std::collections::HashMap;
 
+use crate::build::TemplateEngine;
+
 /// A parsed shortcode.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Shortcode {
@@ -206,7 +208,8 @@ pub fn extract_shortcodes(content: &str) -> Vec<(usize, Shortcode, usize)> {
             }
         }
 
-        pos += 1;
+        // Advance by a whole character to stay on a UTF-8 boundary.
+        pos += remaining.chars().next().map(char::len_utf8).unwrap_or(1);
     }
 
     results
@@ -332,6 +335,219 @@ pub fn process_shortcodes_with_context(content: &str) -> String {
     result
 }
 
+/// An error expanding a shortcode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShortcodeError {
+    /// Shortcode name.
+    pub name: String,
+    /// Line within the processed content (1-based).
+    pub line: usize,
+    /// Column within the line, in characters (1-based).
+    pub column: usize,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl ShortcodeError {
+    /// Translate a position relative to the end of `prefix` to one
+    /// relative to the start of `prefix`.
+    fn after(mut self, prefix: &str) -> Self {
+        let newlines = prefix.matches('\n').count();
+        if self.line == 1 {
+            let last_line = &prefix[prefix.rfind('\n').map(|i| i + 1).unwrap_or(0)..];
+            self.column += last_line.chars().count();
+        }
+        self.line += newlines;
+        self
+    }
+}
+
+/// Process content, failing on the first shortcode the renderer rejects.
+///
+/// The renderer gets each shortcode with its source text and reports
+/// error positions relative to that text, which are translated to
+/// positions within `content`. Text that starts like a shortcode but does
+/// not parse is an error too. Shortcodes inside fenced code blocks are
+/// left as-is so documents can show shortcode syntax literally.
+pub fn try_process_shortcodes<F>(content: &str, mut renderer: F) -> Result<String, ShortcodeError>
+where
+    F: FnMut(&Shortcode, &str) -> Result<String, ShortcodeError>,
+{
+    let fences = fenced_code_ranges(content);
+    let mut result = String::with_capacity(content.len());
+    let mut last_end = 0;
+    let mut pos = 0;
+
+    while let Some(found) = content[pos..].find("{{").map(|i| pos + i) {
+        let remaining = &content[found..];
+        let opener = remaining.starts_with("{{<") || remaining.starts_with("{{%");
+        if !opener || fences.iter().any(|range| range.contains(&found)) {
+            pos = found + 2;
+            continue;
+        }
+
+        let (start, shortcode, end) = match parse_shortcode(remaining) {
+            Ok((after, shortcode)) => (found, shortcode, content.len() - after.len()),
+            Err(_) => match parse_failure(remaining) {
+                Some(error) => return Err(error.after(&content[..found])),
+                None => {
+                    pos = found + 2;
+                    continue;
+                }
+            },
+        };
+
+        let rendered = renderer(&shortcode, &content[start..end])
+            .map_err(|e| e.after(&content[..start]))?;
+
+        result.push_str(&content[last_end..start]);
+        result.push_str(&rendered);
+        last_end = end;
+        pos = end;
+    }
+
+    result.push_str(&content[last_end..]);
+    Ok(result)
+}
+
+/// Explain why text starting with `{{<` or `{{%` is not a shortcode.
+///
+/// Returns `None` for text that does not name a shortcode, like `{{< >}}`
+/// or a stray closing tag, which is left in the output unchanged.
+fn parse_failure(input: &str) -> Option<ShortcodeError> {
+    let (_, name) = preceded(multispace0::<&str, rustmax::nom::error::Error<&str>>, parse_name)
+        .parse(&input[3..])
+        .ok()?;
+
+    let (at, message) = if input.starts_with("{{%") {
+        match parse_block_open(input) {
+            Ok(_) => (0, format!("unclosed block shortcode `{}` (missing `{{{{% /{} %}}}}`)", name, name)),
+            Err(e) => (nom_error_offset(input, e), "expected `%}}` to close the shortcode".to_string()),
+        }
+    } else {
+        match parse_inline_shortcode(input) {
+            Ok(_) => return None,
+            Err(e) => (nom_error_offset(input, e), "expected `>}}` to close the shortcode".to_string()),
+        }
+    };
+
+    let prefix = &input[..at];
+    Some(ShortcodeError { name: name.to_string(), line: 1, column: 1, message }.after(prefix))
+}
+
+/// Byte offset into `input` where a nom parser gave up.
+fn nom_error_offset(input: &str, error: rustmax::nom::Err<rustmax::nom::error::Error<&str>>) -> usize {
+    match error {
+        rustmax::nom::Err::Error(e) | rustmax::nom::Err::Failure(e) => input.len() - e.input.len(),
+        rustmax::nom::Err::Incomplete(_) => input.len(),
+    }
+}
+
+/// Expand built-in shortcodes in markdown content.
+pub fn expand_shortcodes(content: &str) -> Result<String, ShortcodeError> {
+    expand_nested(content, content, None)
+}
+
+/// Expand shortcodes, preferring collection templates over built-ins.
+///
+/// A shortcode named `callout` renders `templates/shortcodes/callout.html`
+/// if the collection has one. Block content is expanded before the
+/// enclosing shortcode is rendered, so shortcodes can be nested.
+pub fn expand_shortcodes_with_templates(
+    content: &str,
+    engine: &TemplateEngine,
+) -> Result<String, ShortcodeError> {
+    expand_nested(content, content, Some(engine))
+}
+
+/// Expand shortcodes in `content`, which is part of `document`.
+fn expand_nested(
+    content: &str,
+    document: &str,
+    engine: Option<&TemplateEngine>,
+) -> Result<String, ShortcodeError> {
+    try_process_shortcodes(content, |shortcode, source| {
+        match &shortcode.content {
+            Some(inner) => {
+                // Block content follows the opening tag.
+                let opening = source.len() - parse_block_open(source).map(|(rest, _)| rest.len()).unwrap_or(source.len());
+                let expanded = Shortcode {
+                    content: Some(
+                        expand_nested(inner, document, engine).map_err(|e| e.after(&source[..opening]))?,
+                    ),
+                    ..shortcode.clone()
+                };
+                render_shortcode(&expanded, document, engine)
+            }
+            None => render_shortcode(shortcode, document, engine),
+        }
+    })
+}
+
+/// Render one shortcode with a collection template or a built-in.
+///
+/// Errors point at the start of the shortcode.
+fn render_shortcode(
+    shortcode: &Shortcode,
+    document: &str,
+    engine: Option<&TemplateEngine>,
+) -> Result<String, ShortcodeError> {
+    let error = |message: String| ShortcodeError {
+        name: shortcode.name.clone(),
+        line: 1,
+        column: 1,
+        message,
+    };
+
+    if let Some(engine) = engine.filter(|e| e.has_shortcode(&shortcode.name)) {
+        return engine.render_shortcode(shortcode).map_err(|e| {
+            error(match e.diagnostic() {
+                Some(d) => format!("{} (at {})", d.message, d.location()),
+                None => e.to_string(),
+            })
+        });
+    }
+
+    render_builtin_with_context(shortcode, document)
+        .ok_or_else(|| error(format!("unknown shortcode `{}`", shortcode.name)))
+}
+
+/// Byte ranges of fenced code blocks (``` or ~~~) in markdown.
+fn fenced_code_ranges(content: &str) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut open: Option<(usize, &str)> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let fence = if trimmed.starts_with("```") {
+            Some("```")
+        } else if trimmed.starts_with("~~~") {
+            Some("~~~")
+        } else {
+            None
+        };
+
+        match (open, fence) {
+            (None, Some(fence)) => open = Some((offset, fence)),
+            (Some((start, opened)), Some(fence)) if opened == fence => {
+                ranges.push(start..offset + line.len());
+                open = None;
+            }
+            _ => {}
+        }
+
+        offset += line.len();
+    }
+
+    // An unclosed fence runs to the end of the document.
+    if let Some((start, _)) = open {
+        ranges.push(start..content.len());
+    }
+
+    ranges
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -408,6 +624,92 @@ mod tests {
         assert!(html.contains("Important!"));
     }
 
+    #[test]
+    fn test_extract_shortcodes_multibyte() {
+        let content = "Café ☕ {{< youtube abc >}} naïve";
+        let codes = extract_shortcodes(content);
+        assert_eq!(codes.len(), 1);
+        assert_eq!(codes[0].1.name, "youtube");
+    }
+
+    #[test]
+    fn test_expand_shortcodes() {
+        let content = "Intro\n\n{{< youtube id=\"abc\" >}}\n";
+        let result = expand_shortcodes(content).unwrap();
+        assert!(result.contains("youtube.com/embed/abc"));
+        assert!(!result.contains("{{<"));
+    }
+
+    #[test]
+    fn test_expand_shortcodes_unknown() {
+        let content = "Line one\nLine two\n{{< bogus x=\"1\" >}}\n";
+        let err = expand_shortcodes(content).unwrap_err();
+        assert_eq!(err.name, "bogus");
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains("unknown shortcode"));
+    }
+
+    #[test]
+    fn test_expand_nested_unknown_line() {
+        let content = "Intro\n\n{{% note %}}\nfine\n{{< bogus >}}\n{{% /note %}}\n";
+        let err = expand_shortcodes(content).unwrap_err();
+        assert_eq!(err.name, "bogus");
+        assert_eq!(err.line, 5);
+        assert_eq!(err.column, 1);
+
+        let content = "Intro\n\n{{% note %}}Inline {{< bogus >}}{{% /note %}}\n";
+        let err = expand_shortcodes(content).unwrap_err();
+        assert_eq!((err.line, err.column), (3, 20));
+    }
+
+    #[test]
+    fn test_expand_shortcodes_parse_errors() {
+        let content = "Intro\n\nSee  {{< youtube id=\"abc >}}\n";
+        let err = expand_shortcodes(content).unwrap_err();
+        assert_eq!(err.name, "youtube");
+        assert_eq!((err.line, err.column), (3, 21));
+        assert!(err.message.contains("`>}}`"), "{}", err.message);
+
+        let content = "Intro\n\n{{% note %}}\nNever closed.\n";
+        let err = expand_shortcodes(content).unwrap_err();
+        assert_eq!((err.line, err.column), (3, 1));
+        assert!(err.message.contains("unclosed block shortcode `note`"), "{}", err.message);
+
+        // Text that does not name a shortcode is left alone.
+        let content = "Use {{< >}} and {{%/note%}} literally.";
+        assert_eq!(expand_shortcodes(content).unwrap(), content);
+    }
+
+    #[test]
+    fn test_expand_with_templates() {
+        use rustmax::tempfile::tempdir;
+
+        let dir = tempdir().unwrap();
+        let shortcodes_dir = dir.path().join("shortcodes");
+        std::fs::create_dir_all(&shortcodes_dir).unwrap();
+        std::fs::write(
+            shortcodes_dir.join("callout.html"),
+            r#"<aside class="{{ kind }}" data-first="{{ positional[0] }}">{{ content | safe }}</aside>"#,
+        )
+        .unwrap();
+
+        let engine = TemplateEngine::new(dir.path()).unwrap();
+        let content = r#"{{% callout first kind="warning" %}}Watch {{< youtube abc >}}{{% /callout %}}"#;
+        let result = expand_shortcodes_with_templates(content, &engine).unwrap();
+
+        assert!(result.starts_with(r#"<aside class="warning" data-first="first">"#));
+        assert!(result.contains("youtube.com/embed/abc"));
+        assert!(result.ends_with("</aside>"));
+    }
+
+    #[test]
+    fn test_expand_shortcodes_skips_code_fences() {
+        let content = "```\n{{< bogus >}}\n```\n\n{{< youtube abc >}}\n";
+        let result = expand_shortcodes(content).unwrap();
+        assert!(result.contains("{{< bogus >}}"));
+        assert!(result.contains("youtube.com/embed/abc"));
+    }
+
     #[test]
     fn test_toc_shortcode() {
         let md = r#"# Title
@@ -0,0 +1,338 @@
+//! Themes: templates, static files and config defaults shared between collections.
+//!
+//! A theme is a directory under `themes/`:
+//!
+//! ```text
+//! themes/<name>/
+//! ├── theme.toml     # name, description, parent theme, config defaults
+//! ├── templates/     # page templates and `shortcodes/`
+//! └── static/        # copied to the site root
+//! ```
+//!
+//! `[build] theme` picks the theme, which may `extends` another. The
+//! collection's own `templates/` and `static/` override the theme's file by
+//! file, and the theme overrides its parent the same way. The theme's
+//! `[config]` table supplies defaults for `anthology.toml`.
+
+use rustmax::toml;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::{Error, Result};
+
+/// Directory of a collection holding its themes.
+pub const THEMES_DIR: &str = "themes";
+
+/// A theme's manifest file.
+pub const THEME_FILE: &str = "theme.toml";
+
+/// The theme used when `[build] theme` is not set. Unlike other themes,
+/// it need not be installed.
+pub const DEFAULT_THEME: &str = "default";
+
+/// Contents of a theme's `theme.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ThemeManifest {
+    /// Name to install the theme under, defaulting to its directory name.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Description of the theme.
+    #[serde(default)]
+    pub description: String,
+    /// Theme this one builds on.
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Defaults for the collection's `anthology.toml`.
+    #[serde(default)]
+    pub config: toml::Table,
+}
+
+/// An installed theme.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    /// Name of the theme, its directory under `themes/`.
+    pub name: String,
+    /// The theme's directory.
+    pub root: PathBuf,
+    /// The theme's `theme.toml`, or defaults if it has none.
+    pub manifest: ThemeManifest,
+}
+
+impl Theme {
+    /// Load the theme in `root`, reading its `theme.toml` if it has one.
+    pub fn load(root: &Path, name: &str) -> Result<Self> {
+        let manifest_path = root.join(THEME_FILE);
+        let manifest = if manifest_path.is_file() {
+            let text = std::fs::read_to_string(&manifest_path)?;
+            toml::from_str(&text).map_err(|e| {
+                Error::theme(name, format!("invalid {}: {}", THEME_FILE, e.message().trim()))
+            })?
+        } else {
+            ThemeManifest::default()
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            root: root.to_path_buf(),
+            manifest,
+        })
+    }
+
+    /// Directory of the theme's templates.
+    pub fn templates_dir(&self) -> PathBuf {
+        self.root.join("templates")
+    }
+
+    /// Directory of the theme's static files.
+    pub fn static_dir(&self) -> PathBuf {
+        self.root.join("static")
+    }
+}
+
+/// Resolve the theme `name` of the collection at `root` and the themes it
+/// extends, the named theme first.
+///
+/// No themes are used when the default theme is not installed.
+pub fn resolve(root: &Path, name: &str) -> Result<Vec<Theme>> {
+    let mut themes: Vec<Theme> = Vec::new();
+    let mut next = Some(name.to_string());
+
+    while let Some(name) = next {
+        if !is_valid_name(&name) {
+            return Err(Error::theme(&name, "theme names cannot contain path separators"));
+        }
+        if themes.iter().any(|t| t.name == name) {
+            let chain: Vec<&str> = themes.iter().map(|t| t.name.as_str()).chain([name.as_str()]).collect();
+            return Err(Error::theme(&name, format!("inheritance cycle: {}", chain.join(" -> "))));
+        }
+
+        let dir = root.join(THEMES_DIR).join(&name);
+        if !dir.is_dir() {
+            return match themes.last() {
+                None if name == DEFAULT_THEME => Ok(themes),
+                None => Err(Error::theme(&name, format!("not installed in {}/", THEMES_DIR))),
+                Some(child) => Err(Error::theme(
+                    &child.name,
+                    format!("extends `{}`, which is not installed in {}/", name, THEMES_DIR),
+                )),
+            };
+        }
+
+        let theme = Theme::load(&dir, &name)?;
+        next = theme.manifest.extends.clone();
+        themes.push(theme);
+    }
+
+    Ok(themes)
+}
+
+/// Lay the collection's config over the defaults of its themes.
+///
+/// Tables are merged key by key, so a collection setting one `[build]`
+/// key keeps the theme's other `[build]` defaults.
+pub fn merge_config(themes: &[Theme], config: toml::Table) -> toml::Table {
+    let mut merged = toml::Table::new();
+    for theme in themes.iter().rev() {
+        merge_table(&mut merged, theme.manifest.config.clone());
+    }
+    merge_table(&mut merged, config);
+    merged
+}
+
+fn merge_table(base: &mut toml::Table, over: toml::Table) {
+    for (key, value) in over {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(over)) => merge_table(base, over),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Template directories of a collection, highest priority first: the
+/// collection's `templates/`, then each theme's.
+pub fn template_dirs(root: &Path, themes: &[Theme]) -> Vec<PathBuf> {
+    std::iter::once(root.join("templates"))
+        .chain(themes.iter().map(Theme::templates_dir))
+        .collect()
+}
+
+/// Install a theme into the collection at `root`.
+///
+/// `source` is a theme directory or a git URL. Cloned themes are vendored
+/// without their `.git`, so they are committed along with the collection.
+pub fn add(root: &Path, source: &str) -> Result<Theme> {
+    let themes_dir = root.join(THEMES_DIR);
+    std::fs::create_dir_all(&themes_dir)?;
+
+    // Fetch into a scratch directory first, since the name may come from
+    // the theme's manifest.
+    let scratch = rustmax::tempfile::tempdir_in(&themes_dir)?;
+    let staged = scratch.path().join("theme");
+    let local = Path::new(source);
+    let default_name = if local.is_dir() {
+        copy_dir(local, &staged)?;
+        local.canonicalize()?.file_name().and_then(|n| n.to_str()).map(str::to_string)
+    } else if is_git_url(source) {
+        clone(source, &staged)?;
+        let _ = std::fs::remove_dir_all(staged.join(".git"));
+        let path = source.trim_end_matches('/').trim_end_matches(".git").trim_end_matches('/');
+        path.rsplit(['/', ':']).next().map(str::to_string)
+    } else {
+        return Err(Error::theme(source, "not a directory or git URL"));
+    };
+
+    if !["templates", "static", THEME_FILE].iter().any(|p| staged.join(p).exists()) {
+        return Err(Error::theme(
+            source,
+            format!("not a theme (no templates/, static/ or {})", THEME_FILE),
+        ));
+    }
+
+    let manifest = Theme::load(&staged, source)?.manifest;
+    let name = manifest
+        .name
+        .clone()
+        .or(default_name)
+        .filter(|name| is_valid_name(name))
+        .ok_or_else(|| Error::theme(source, format!("cannot tell the theme's name; set `name` in {}", THEME_FILE)))?;
+
+    let dest = themes_dir.join(&name);
+    if dest.exists() {
+        return Err(Error::theme(&name, format!("already installed in {}/", THEMES_DIR)));
+    }
+    std::fs::rename(&staged, &dest)?;
+
+    Theme::load(&dest, &name)
+}
+
+/// Whether a theme name is a plain directory name.
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+/// Whether `source` names a git repository rather than a local directory.
+fn is_git_url(source: &str) -> bool {
+    source.contains("://") || source.starts_with("git@") || source.ends_with(".git")
+}
+
+/// Clone a git repository with the `git` command.
+fn clone(url: &str, dest: &Path) -> Result<()> {
+    let output = std::process::Command::new("git")
+        // `--` keeps a source such as `--upload-pack=cmd.git` from being read as an option.
+        .args(["clone", "--depth", "1", "--quiet", "--", url])
+        .arg(dest)
+        .output()
+        .map_err(|e| Error::theme(url, format!("failed to run git: {}", e)))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::theme(url, format!("git clone failed: {}", stderr.trim())));
+    }
+    Ok(())
+}
+
+/// Copy a directory tree, leaving out `.git`.
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    use rustmax::walkdir::WalkDir;
+
+    let walker = WalkDir::new(from)
+        .into_iter()
+        .filter_entry(|entry| entry.file_name() != ".git");
+    for entry in walker {
+        let entry = entry?;
+        let dest = to.join(entry.path().strip_prefix(from).unwrap());
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&dest)?;
+        } else {
+            std::fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustmax::tempfile::tempdir;
+
+    fn write(root: &Path, path: &str, content: &str) {
+        let path = root.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_resolve_chain() {
+        let dir = tempdir().unwrap();
+        let root = dir.path();
+        assert!(resolve(root, DEFAULT_THEME).unwrap().is_empty());
+        assert!(resolve(root, "missing").unwrap_err().to_string().contains("not installed"));
+
+        write(root, "themes/base/templates/base.html", "base");
+        write(root, "themes/docs/theme.toml", "extends = \"base\"\n[config.content]\nper_page = 5\n");
+        let themes = resolve(root, "docs").unwrap();
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["docs", "base"]);
+        assert_eq!(template_dirs(root, &themes)[2], root.join("themes/base/templates"));
+
+        write(root, "themes/base/theme.toml", "extends = \"docs\"\n");
+        let err = resolve(root, "docs").unwrap_err().to_string();
+        assert!(err.contains("inheritance cycle: docs -> base -> docs"), "{}", err);
+
+        write(root, "themes/base/theme.toml", "extends = \"gone\"\n");
+        let err = resolve(root, "docs").unwrap_err().to_string();
+        assert!(err.contains("base: extends `gone`"), "{}", err);
+    }
+
+    #[test]
+    fn test_merge_config() {
+        let theme = |name: &str, config: &str| Theme {
+            name: name.to_string(),
+            root: PathBuf::from(name),
+            manifest: ThemeManifest { config: toml::from_str(config).unwrap(), ..Default::default() },
+        };
+        let themes = [
+            theme("child", "[content]\nper_page = 5\n"),
+            theme("parent", "[content]\nper_page = 20\nrelated = 3\n[build]\nminify = true\n"),
+        ];
+        let merged = merge_config(&themes, toml::from_str("[build]\nminify = false\n").unwrap());
+        assert_eq!(merged["content"]["per_page"].as_integer(), Some(5));
+        assert_eq!(merged["content"]["related"].as_integer(), Some(3));
+        assert_eq!(merged["build"]["minify"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn test_add_local_theme() {
+        let dir = tempdir().unwrap();
+        let source = dir.path().join("src/my-theme");
+        write(&source, "templates/default.html", "theme");
+        write(&source, ".git/HEAD", "ref");
+
+        let root = dir.path().join("site");
+        let theme = add(&root, source.to_str().unwrap()).unwrap();
+        assert_eq!(theme.name, "my-theme");
+        assert!(root.join("themes/my-theme/templates/default.html").is_file());
+        assert!(!root.join("themes/my-theme/.git").exists());
+        assert!(add(&root, source.to_str().unwrap()).unwrap_err().to_string().contains("already installed"));
+
+        // The manifest's name wins over the directory's.
+        write(&source, THEME_FILE, "name = \"renamed\"\n");
+        assert_eq!(add(&root, source.to_str().unwrap()).unwrap().name, "renamed");
+
+        let empty = dir.path().join("empty");
+        std::fs::create_dir_all(&empty).unwrap();
+        assert!(add(&root, empty.to_str().unwrap()).unwrap_err().to_string().contains("not a theme"));
+
+        // A source that looks like a git option is passed to git as a URL.
+        let marker = dir.path().join("marker");
+        let source = format!("--upload-pack=touch {}.git", marker.display());
+        let err = add(&root, &source).unwrap_err().to_string();
+        assert!(err.contains("repository '--upload-pack="), "{}", err);
+
+        // Only the installed themes are left behind.
+        let installed: Vec<_> = std::fs::read_dir(root.join(THEMES_DIR)).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(installed.len(), 2);
+    }
+}
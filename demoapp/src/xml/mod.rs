@@ -0,0 +1,359 @@
+//! A small XML parser for package documents and feeds.
+//!
+//! Reads well-formed XML into a tree of elements and text: tags,
+//! attributes, comments, CDATA sections, processing instructions, and the
+//! predefined and numeric character references. DTDs are skipped rather
+//! than read, so documents using other named entities are rejected.
+//! Namespace prefixes are kept as part of names (`dc:title`).
+
+use std::cell::Cell;
+use std::fmt;
+
+/// Error produced while parsing XML, with a 1-based line number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for XmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+type XmlResult<T> = std::result::Result<T, XmlError>;
+
+/// A node in an element's content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+/// An element with its attributes and content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+    /// Line of the start tag.
+    pub line: usize,
+}
+
+impl Element {
+    /// Get an attribute value by name.
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Child elements, skipping text.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    /// Child elements with a name.
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.elements().filter(move |e| e.name == name)
+    }
+
+    /// The first child element with a name.
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.name == name)
+    }
+
+    /// Text of the first child element with a name.
+    pub fn child_text(&self, name: &str) -> Option<String> {
+        self.child(name).map(Element::text)
+    }
+
+    /// All text inside the element, including that of descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for node in &self.children {
+            match node {
+                Node::Element(element) => text.push_str(&element.text()),
+                Node::Text(t) => text.push_str(t),
+            }
+        }
+        text
+    }
+
+    /// This element and all elements inside it, in document order.
+    pub fn descendants(&self) -> Vec<&Element> {
+        let mut out = vec![self];
+        for element in self.elements() {
+            out.extend(element.descendants());
+        }
+        out
+    }
+}
+
+/// Parse an XML document, returning its root element.
+pub fn parse(source: &str) -> XmlResult<Element> {
+    let mut parser = Parser { text: source, pos: 0, counted: Cell::new((0, 1)) };
+    let mut stack: Vec<Element> = Vec::new();
+    let mut root: Option<Element> = None;
+
+    while parser.pos < source.len() {
+        let rest = parser.rest();
+        if rest.starts_with("<?") {
+            parser.skip_past("?>", "unterminated processing instruction")?;
+        } else if rest.starts_with("<!--") {
+            parser.skip_past("-->", "unterminated comment")?;
+        } else if rest.starts_with("<![CDATA[") {
+            let Some(parent) = stack.last_mut() else {
+                return Err(parser.error("CDATA section outside the root element"));
+            };
+            let end = rest.find("]]>").ok_or_else(|| parser.error("unterminated CDATA section"))?;
+            parent.children.push(Node::Text(rest[9..end].to_string()));
+            parser.pos += end + 3;
+        } else if rest.starts_with("<!DOCTYPE") {
+            if root.is_some() || !stack.is_empty() {
+                return Err(parser.error("DOCTYPE after the root element"));
+            }
+            parser.skip_past(">", "unterminated DOCTYPE")?;
+        } else if rest.starts_with("</") {
+            parser.pos += 2;
+            let name = parser.name()?;
+            parser.skip_whitespace();
+            parser.expect(">")?;
+            let element = match stack.pop() {
+                Some(open) if open.name == name => open,
+                Some(open) => {
+                    return Err(parser.error(format!("expected </{}>, found </{}>", open.name, name)));
+                }
+                None => return Err(parser.error(format!("unexpected </{}>", name))),
+            };
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(Node::Element(element)),
+                None => root = Some(element),
+            }
+        } else if rest.starts_with('<') {
+            if root.is_some() {
+                return Err(parser.error("element after the root element"));
+            }
+            parser.pos += 1;
+            let (element, empty) = parser.start_tag()?;
+            match (empty, stack.last_mut()) {
+                (false, _) => stack.push(element),
+                (true, Some(parent)) => parent.children.push(Node::Element(element)),
+                (true, None) => root = Some(element),
+            }
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let run = &rest[..end];
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(Node::Text(parser.unescape(run)?)),
+                None if !run.trim().is_empty() => {
+                    return Err(parser.error("text outside the root element"));
+                }
+                None => {}
+            }
+            parser.pos += end;
+        }
+    }
+
+    if let Some(open) = stack.last() {
+        return Err(parser.error(format!("<{}> is never closed", open.name)));
+    }
+    root.ok_or_else(|| parser.error("no root element"))
+}
+
+/// Cursor over the source text.
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    /// Offset up to which lines have been counted, and the line there.
+    counted: Cell<(usize, usize)>,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    /// Line at the cursor, counted incrementally as the cursor only moves forward.
+    fn line(&self) -> usize {
+        let (offset, line) = self.counted.get();
+        let line = line + self.text[offset..self.pos].matches('\n').count();
+        self.counted.set((self.pos, line));
+        line
+    }
+
+    fn error(&self, message: impl Into<String>) -> XmlError {
+        XmlError {
+            line: self.line(),
+            message: message.into(),
+        }
+    }
+
+    fn skip_past(&mut self, end: &str, message: &str) -> XmlResult<()> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(())
+            }
+            None => Err(self.error(message)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let trimmed = self.rest().trim_start_matches([' ', '\t', '\r', '\n']).len();
+        let skipped = self.rest().len() - trimmed;
+        self.pos += skipped;
+        skipped > 0
+    }
+
+    fn expect(&mut self, token: &str) -> XmlResult<()> {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            Ok(())
+        } else {
+            Err(self.error(format!("expected {}", token)))
+        }
+    }
+
+    fn name(&mut self) -> XmlResult<String> {
+        let rest = self.rest();
+        let starts_well = rest
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_alphabetic() || c == '_' || c == ':');
+        if !starts_well {
+            return Err(self.error("expected a name"));
+        }
+        let end = rest
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | ':' | '-' | '.')))
+            .unwrap_or(rest.len());
+        let name = rest[..end].to_string();
+        self.pos += end;
+        Ok(name)
+    }
+
+    /// Parse a start tag after its `<`, returning whether it was empty.
+    fn start_tag(&mut self) -> XmlResult<(Element, bool)> {
+        let line = self.line();
+        let name = self.name()?;
+        let mut attributes: Vec<(String, String)> = Vec::new();
+
+        loop {
+            let spaced = self.skip_whitespace();
+            let empty = self.rest().starts_with("/>");
+            if empty || self.rest().starts_with('>') {
+                self.pos += if empty { 2 } else { 1 };
+                let element = Element { name, attributes, children: Vec::new(), line };
+                return Ok((element, empty));
+            }
+            if !spaced {
+                return Err(self.error(format!("malformed <{}> tag", name)));
+            }
+
+            let attr = self.name()?;
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.error(format!("attribute {} on <{}> has no value", attr, name)));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let Some(quote) = self.rest().chars().next().filter(|&c| c == '"' || c == '\'') else {
+                return Err(self.error(format!("attribute {} on <{}> is not quoted", attr, name)));
+            };
+            self.pos += 1;
+            let Some(end) = self.rest().find(quote) else {
+                return Err(self.error(format!("unterminated value for attribute {}", attr)));
+            };
+            let raw = &self.rest()[..end];
+            if raw.contains('<') {
+                return Err(self.error(format!("'<' in value of attribute {}", attr)));
+            }
+            let value = self.unescape(raw)?;
+            self.pos += end + 1;
+
+            if attributes.iter().any(|(n, _)| *n == attr) {
+                return Err(self.error(format!("duplicate attribute {} on <{}>", attr, name)));
+            }
+            attributes.push((attr, value));
+        }
+    }
+
+    /// Replace the character references in `text`.
+    fn unescape(&self, text: &str) -> XmlResult<String> {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('&') {
+            out.push_str(&rest[..i]);
+            let after = &rest[i + 1..];
+            let len = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '#'))
+                .unwrap_or(after.len());
+            if !after[len..].starts_with(';') {
+                return Err(self.error("'&' is not escaped"));
+            }
+            let reference = &after[..len];
+            let c = match reference {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => match reference.strip_prefix('#') {
+                    Some(hex) if hex.starts_with('x') => u32::from_str_radix(&hex[1..], 16).ok().and_then(char::from_u32),
+                    Some(decimal) => decimal.parse().ok().and_then(char::from_u32),
+                    None => None,
+                },
+            };
+            match c {
+                Some(c) => out.push(c),
+                None => return Err(self.error(format!("undefined entity &{};", reference))),
+            }
+            rest = &after[len + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tree() {
+        let root = parse(
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE html>\n<html a='1'><!-- x --><p id=\"a&amp;b\">x &lt; y<br/>z</p><p><![CDATA[<b>&]]></p></html>",
+        )
+        .unwrap();
+        assert_eq!(root.name, "html");
+        assert_eq!(root.attr("a"), Some("1"));
+        assert_eq!(root.line, 3);
+
+        let names: Vec<&str> = root.descendants().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["html", "p", "br", "p"]);
+        let p = root.child("p").unwrap();
+        assert_eq!(p.attr("id"), Some("a&b"));
+        assert_eq!(p.text(), "x < yz");
+        assert_eq!(root.children_named("p").nth(1).unwrap().text(), "<b>&");
+        assert_eq!(parse("<a>&#233;&#x41;</a>").unwrap().text(), "éA");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| parse(text).unwrap_err();
+        assert_eq!(error("<p>\n<b></p>").message, "expected </b>, found </p>");
+        assert_eq!(error("<p>\n<b></p>").line, 2);
+        assert_eq!(error("<p><br></p>").message, "expected </br>, found </p>");
+        assert_eq!(error("<p>a&nbsp;b</p>").message, "undefined entity &nbsp;");
+        assert_eq!(error("<p>a & b;</p>").message, "'&' is not escaped");
+        assert!(error("<input checked>").message.contains("has no value"));
+        assert!(error("<p a=\"1\" a=\"2\"/>").message.contains("duplicate"));
+        assert!(error("<p></p><p></p>").message.contains("after the root"));
+        assert!(error("<p>").message.contains("never closed"));
+        assert!(error("").message.contains("no root"));
+    }
+}
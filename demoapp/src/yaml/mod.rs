@@ -0,0 +1,690 @@
+//! A small YAML parser for data files and frontmatter.
+//!
+//! Supports the subset of YAML that hand-written configuration uses:
+//! block mappings and sequences, plain and quoted scalars, flow
+//! collections (`[a, b]`, `{a: 1}`), `|` and `>` block scalars, and
+//! comments. Anchors, aliases, tags, and multiple documents are not
+//! supported. Values are produced as JSON values.
+
+use rustmax::prelude::*;
+use rustmax::serde_json::{Map, Number, Value};
+use std::fmt;
+
+/// Error produced while parsing YAML, with a 1-based line number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YamlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for YamlError {}
+
+type YamlResult<T> = std::result::Result<T, YamlError>;
+
+/// Parse a YAML document into a JSON value.
+///
+/// An empty document parses as `null`.
+pub fn parse(source: &str) -> YamlResult<Value> {
+    let mut parser = Parser::new(source);
+    parser.skip_document_start();
+
+    let value = match parser.peek() {
+        Some((indent, _)) => parser.parse_node(indent)?,
+        None => Value::Null,
+    };
+
+    if let Some((_, line)) = parser.peek() {
+        return Err(parser.error(line, "unexpected content after document"));
+    }
+
+    Ok(value)
+}
+
+/// Line-oriented parser over the source.
+///
+/// Sequence items are parsed by blanking their `- ` marker in place, so
+/// that a mapping starting on the same line as the dash is parsed like
+/// any other block at the item's column.
+struct Parser {
+    lines: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Self {
+        Self {
+            lines: source.lines().map(str::to_string).collect(),
+            pos: 0,
+        }
+    }
+
+    fn error(&self, line: usize, message: impl Into<String>) -> YamlError {
+        YamlError {
+            line: line + 1,
+            message: message.into(),
+        }
+    }
+
+    fn skip_document_start(&mut self) {
+        if let Some((_, line)) = self.peek()
+            && self.lines[line].trim_end() == "---"
+        {
+            self.pos = line + 1;
+        }
+    }
+
+    /// Find the next line with content, returning its indent and index.
+    fn peek(&self) -> Option<(usize, usize)> {
+        (self.pos..self.lines.len()).find_map(|i| {
+            let text = strip_comment(&self.lines[i]);
+            if text.trim().is_empty() {
+                None
+            } else {
+                Some((indent_of(text), i))
+            }
+        })
+    }
+
+    /// Content of a line without indentation or trailing comment.
+    fn text(&self, line: usize) -> &str {
+        strip_comment(&self.lines[line]).trim()
+    }
+
+    fn parse_node(&mut self, indent: usize) -> YamlResult<Value> {
+        let Some((_, line)) = self.peek() else {
+            return Ok(Value::Null);
+        };
+
+        if self.lines[line][indent..].starts_with('\t') {
+            return Err(self.error(line, "tabs are not allowed in indentation"));
+        }
+
+        let text = self.text(line);
+        if is_sequence_item(text) {
+            self.parse_sequence(indent)
+        } else if find_key_colon(text).is_some() {
+            self.parse_mapping(indent)
+        } else {
+            self.pos = line + 1;
+            let text = self.text(line).to_string();
+            self.parse_inline(&text, line, indent.saturating_sub(1))
+        }
+    }
+
+    fn parse_sequence(&mut self, indent: usize) -> YamlResult<Value> {
+        let mut items = Vec::new();
+
+        while let Some((line_indent, line)) = self.peek() {
+            if line_indent < indent {
+                break;
+            }
+            if line_indent > indent {
+                return Err(self.error(line, "unexpected indentation"));
+            }
+            let text = self.text(line).to_string();
+            if !is_sequence_item(&text) {
+                break;
+            }
+
+            let rest = text[1..].trim_start();
+            if rest.is_empty() {
+                self.pos = line + 1;
+                match self.peek() {
+                    Some((child, _)) if child > indent => items.push(self.parse_node(child)?),
+                    _ => items.push(Value::Null),
+                }
+            } else if is_sequence_item(rest) || find_key_colon(rest).is_some() {
+                // Nested block starting on the dash line.
+                let raw = &self.lines[line];
+                let column = raw.len() - raw[indent + 1..].trim_start().len();
+                let blanked = format!("{}{}", " ".repeat(column), &raw[column..]);
+                self.lines[line] = blanked;
+                self.pos = line;
+                items.push(self.parse_node(column)?);
+            } else {
+                self.pos = line + 1;
+                items.push(self.parse_inline(rest, line, indent)?);
+            }
+        }
+
+        Ok(Value::Array(items))
+    }
+
+    fn parse_mapping(&mut self, indent: usize) -> YamlResult<Value> {
+        let mut map = Map::new();
+
+        while let Some((line_indent, line)) = self.peek() {
+            if line_indent < indent {
+                break;
+            }
+            if line_indent > indent {
+                return Err(self.error(line, "unexpected indentation"));
+            }
+            let text = self.text(line);
+            if is_sequence_item(text) {
+                break;
+            }
+            let Some(colon) = find_key_colon(text) else {
+                return Err(self.error(line, format!("expected `key: value`, found `{}`", text)));
+            };
+
+            let key = parse_key(text[..colon].trim()).map_err(|m| self.error(line, m))?;
+            let rest = text[colon + 1..].trim().to_string();
+            self.pos = line + 1;
+
+            let value = if rest.is_empty() {
+                match self.peek() {
+                    Some((child, _)) if child > indent => self.parse_node(child)?,
+                    // Sequences may sit at the same indent as their key.
+                    Some((child, next)) if child == indent && is_sequence_item(self.text(next)) => {
+                        self.parse_sequence(indent)?
+                    }
+                    _ => Value::Null,
+                }
+            } else {
+                self.parse_inline(&rest, line, indent)?
+            };
+
+            map.insert(key, value);
+        }
+
+        Ok(Value::Object(map))
+    }
+
+    /// Parse a value that starts on the current line.
+    ///
+    /// `parent` is the indent of the key or dash that owns the value;
+    /// continuation lines must be indented further.
+    fn parse_inline(&mut self, text: &str, line: usize, parent: usize) -> YamlResult<Value> {
+        if let Some(header) = text.strip_prefix('|') {
+            let block = self.block_scalar(parent, line, header, false)?;
+            return Ok(Value::String(block));
+        }
+        if let Some(header) = text.strip_prefix('>') {
+            let block = self.block_scalar(parent, line, header, true)?;
+            return Ok(Value::String(block));
+        }
+
+        if text.starts_with('[') || text.starts_with('{') {
+            let mut flow = text.to_string();
+            while !flow_closed(&flow) {
+                let Some((_, next)) = self.peek() else {
+                    return Err(self.error(line, "unterminated flow collection"));
+                };
+                flow.push(' ');
+                flow.push_str(self.text(next));
+                self.pos = next + 1;
+            }
+            return parse_flow(&flow).map_err(|m| self.error(line, m));
+        }
+
+        if text.starts_with('"') || text.starts_with('\'') {
+            return parse_scalar(text).map_err(|m| self.error(line, m));
+        }
+
+        // Plain scalars may continue on more-indented lines.
+        let mut plain = text.to_string();
+        while let Some((next_indent, next)) = self.peek() {
+            if next_indent <= parent {
+                break;
+            }
+            let next_text = self.text(next);
+            if is_sequence_item(next_text) || find_key_colon(next_text).is_some() {
+                break;
+            }
+            plain.push(' ');
+            plain.push_str(next_text);
+            self.pos = next + 1;
+        }
+
+        parse_scalar(&plain).map_err(|m| self.error(line, m))
+    }
+
+    /// Read a `|` (literal) or `>` (folded) block scalar.
+    fn block_scalar(&mut self, parent: usize, line: usize, header: &str, folded: bool) -> YamlResult<String> {
+        let chomp = match header.trim() {
+            "" => Chomp::Clip,
+            "-" => Chomp::Strip,
+            "+" => Chomp::Keep,
+            other => return Err(self.error(line, format!("unsupported block scalar header `{}`", other))),
+        };
+
+        let mut raw = Vec::new();
+        let mut i = line + 1;
+        while i < self.lines.len() {
+            let l = &self.lines[i];
+            if !l.trim().is_empty() && indent_of(l) <= parent {
+                break;
+            }
+            raw.push(l.as_str());
+            i += 1;
+        }
+
+        // Trailing blank lines belong to the block only for chomping.
+        let trailing = raw.iter().rev().take_while(|l| l.trim().is_empty()).count();
+        let content = &raw[..raw.len() - trailing];
+        let block_indent = content
+            .iter()
+            .find(|l| !l.trim().is_empty())
+            .map(|l| indent_of(l))
+            .unwrap_or(0);
+        if let Some(n) = content.iter().position(|l| !l.trim().is_empty() && indent_of(l) < block_indent) {
+            return Err(self.error(line + 1 + n, "block scalar line is indented less than its first line"));
+        }
+        let lines: Vec<&str> = content.iter().map(|l| &l[indent_of(l).min(block_indent)..]).collect();
+
+        let mut out = if folded {
+            fold_lines(&lines)
+        } else {
+            lines.join("\n")
+        };
+
+        if !lines.is_empty() {
+            match chomp {
+                Chomp::Strip => {}
+                Chomp::Clip => out.push('\n'),
+                Chomp::Keep => out.push_str(&"\n".repeat(trailing + 1)),
+            }
+        }
+
+        // Leave trailing blank lines for `peek` to skip.
+        self.pos = line + 1 + content.len();
+        Ok(out)
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Chomp {
+    Clip,
+    Strip,
+    Keep,
+}
+
+fn fold_lines(lines: &[&str]) -> String {
+    let mut out = String::new();
+    let mut prev_blank = true;
+    for line in lines {
+        if line.is_empty() {
+            out.push('\n');
+            prev_blank = true;
+        } else {
+            if !prev_blank {
+                out.push(' ');
+            }
+            out.push_str(line);
+            prev_blank = false;
+        }
+    }
+    out
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+/// Remove a trailing `# comment`, ignoring `#` inside quotes.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if (c == '"' || c == '\'') && opens_quote(prev) => quote = Some(c),
+            None if c == '#' && (prev == ' ' || prev == '\t') => return &line[..i],
+            None => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+/// Find the colon separating a mapping key from its value.
+///
+/// The colon must be followed by whitespace or end the line, and must
+/// not be inside quotes or a flow collection.
+fn find_key_colon(text: &str) -> Option<usize> {
+    if text.starts_with('[') || text.starts_with('{') {
+        return None;
+    }
+
+    let bytes = text.as_bytes();
+    let mut quote = None;
+    for (i, &b) in bytes.iter().enumerate() {
+        match quote {
+            Some(q) if b == q => quote = None,
+            Some(_) => {}
+            None if (b == b'"' || b == b'\'') && i == 0 => quote = Some(b),
+            None if b == b':' && bytes.get(i + 1).is_none_or(|n| *n == b' ' || *n == b'\t') => {
+                return Some(i);
+            }
+            None => {}
+        }
+    }
+    None
+}
+
+fn parse_key(key: &str) -> std::result::Result<String, String> {
+    match parse_scalar(key)? {
+        Value::String(s) => Ok(s),
+        Value::Null => Ok(String::new()),
+        other => Ok(other.to_string()),
+    }
+}
+
+fn flow_closed(text: &str) -> bool {
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut prev = ' ';
+    for c in text.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None => match c {
+                '"' | '\'' if opens_quote(prev) => quote = Some(c),
+                '[' | '{' => depth += 1,
+                ']' | '}' => depth -= 1,
+                _ => {}
+            },
+        }
+        prev = c;
+    }
+    depth <= 0
+}
+
+/// Whether a quote after `prev` starts a quoted scalar rather than
+/// being an apostrophe inside a plain one.
+fn opens_quote(prev: char) -> bool {
+    matches!(prev, ' ' | '\t' | ':' | ',' | '[' | '{' | '-')
+}
+
+/// Parse a scalar: null, boolean, number, quoted, or plain string.
+fn parse_scalar(text: &str) -> std::result::Result<Value, String> {
+    let text = text.trim();
+
+    if text.starts_with('"') || text.starts_with('\'') {
+        let mut chars = text.char_indices().peekable();
+        let s = parse_quoted(text, &mut chars)?;
+        if let Some((i, _)) = chars.peek() {
+            return Err(format!("unexpected `{}` after quoted string", &text[*i..]));
+        }
+        return Ok(Value::String(s));
+    }
+
+    Ok(match text {
+        "" | "~" | "null" | "Null" | "NULL" => Value::Null,
+        "true" | "True" | "TRUE" => Value::Bool(true),
+        "false" | "False" | "FALSE" => Value::Bool(false),
+        _ => parse_number(text).unwrap_or_else(|| Value::String(text.to_string())),
+    })
+}
+
+fn parse_number(text: &str) -> Option<Value> {
+    let digits = text.strip_prefix(['-', '+']).unwrap_or(text);
+    if digits.is_empty() || !digits.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        return None;
+    }
+    if digits.chars().all(|c| c.is_ascii_digit()) {
+        return text.parse::<i64>().ok().map(Value::from);
+    }
+    if digits.chars().all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+')) {
+        return text.parse::<f64>().ok().and_then(Number::from_f64).map(Value::Number);
+    }
+    None
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::CharIndices<'a>>;
+
+/// Parse a quoted string starting at the iterator's opening quote.
+fn parse_quoted(text: &str, chars: &mut Chars<'_>) -> std::result::Result<String, String> {
+    let (_, quote) = chars.next().ok_or("expected a quote")?;
+    let mut out = String::new();
+
+    while let Some((_, c)) = chars.next() {
+        match c {
+            '\'' if quote == '\'' => {
+                // `''` is an escaped single quote.
+                if matches!(chars.peek(), Some((_, '\''))) {
+                    chars.next();
+                    out.push('\'');
+                } else {
+                    return Ok(out);
+                }
+            }
+            '"' if quote == '"' => return Ok(out),
+            '\\' if quote == '"' => {
+                let (_, esc) = chars.next().ok_or("unterminated escape")?;
+                match esc {
+                    'n' => out.push('\n'),
+                    't' => out.push('\t'),
+                    'r' => out.push('\r'),
+                    '0' => out.push('\0'),
+                    '"' | '\\' | '/' => out.push(esc),
+                    'u' => {
+                        let hex: String = (0..4).filter_map(|_| chars.next().map(|(_, c)| c)).collect();
+                        let code = u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| format!("invalid unicode escape `\\u{}`", hex))?;
+                        out.push(code);
+                    }
+                    other => return Err(format!("unknown escape `\\{}`", other)),
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+
+    Err(format!("unterminated string in `{}`", text))
+}
+
+/// Parse a flow collection such as `[a, b]` or `{a: 1, b: [2, 3]}`.
+fn parse_flow(text: &str) -> std::result::Result<Value, String> {
+    let mut chars = text.char_indices().peekable();
+    let value = parse_flow_value(text, &mut chars)?;
+    skip_spaces(&mut chars);
+    if let Some((i, _)) = chars.peek() {
+        return Err(format!("unexpected `{}` after flow collection", &text[*i..]));
+    }
+    Ok(value)
+}
+
+fn skip_spaces(chars: &mut Chars<'_>) {
+    while matches!(chars.peek(), Some((_, c)) if c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+fn parse_flow_value(text: &str, chars: &mut Chars<'_>) -> std::result::Result<Value, String> {
+    skip_spaces(chars);
+    match chars.peek().map(|(_, c)| *c) {
+        Some('[') => {
+            chars.next();
+            let mut items = Vec::new();
+            loop {
+                skip_spaces(chars);
+                if matches!(chars.peek(), Some((_, ']'))) {
+                    chars.next();
+                    return Ok(Value::Array(items));
+                }
+                items.push(parse_flow_value(text, chars)?);
+                skip_spaces(chars);
+                match chars.next() {
+                    Some((_, ',')) => {}
+                    Some((_, ']')) => return Ok(Value::Array(items)),
+                    _ => return Err(format!("expected `,` or `]` in `{}`", text)),
+                }
+            }
+        }
+        Some('{') => {
+            chars.next();
+            let mut map = Map::new();
+            loop {
+                skip_spaces(chars);
+                if matches!(chars.peek(), Some((_, '}'))) {
+                    chars.next();
+                    return Ok(Value::Object(map));
+                }
+                let key = match parse_flow_scalar(text, chars, true)? {
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                skip_spaces(chars);
+                let value = if matches!(chars.peek(), Some((_, ':'))) {
+                    chars.next();
+                    parse_flow_value(text, chars)?
+                } else {
+                    Value::Null
+                };
+                map.insert(key, value);
+                skip_spaces(chars);
+                match chars.next() {
+                    Some((_, ',')) => {}
+                    Some((_, '}')) => return Ok(Value::Object(map)),
+                    _ => return Err(format!("expected `,` or `}}` in `{}`", text)),
+                }
+            }
+        }
+        Some(_) => parse_flow_scalar(text, chars, false),
+        None => Err(format!("unexpected end of `{}`", text)),
+    }
+}
+
+/// Parse a scalar inside a flow collection, stopping at a delimiter.
+fn parse_flow_scalar(text: &str, chars: &mut Chars<'_>, is_key: bool) -> std::result::Result<Value, String> {
+    skip_spaces(chars);
+    if matches!(chars.peek(), Some((_, '"' | '\''))) {
+        return parse_quoted(text, chars).map(Value::String);
+    }
+
+    let mut plain = String::new();
+    while let Some(&(_, c)) = chars.peek() {
+        if matches!(c, ',' | ']' | '}') || (is_key && c == ':') {
+            break;
+        }
+        plain.push(c);
+        chars.next();
+    }
+    parse_scalar(&plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rustmax::serde_json::json;
+
+    #[test]
+    fn test_parse_mappings_and_sequences() {
+        let yaml = "\
+# Site navigation.
+title: My Site
+count: 3
+ratio: 0.5
+enabled: true
+missing: ~
+links:
+  - name: Home
+    url: /
+  - name: About   # trailing comment
+    url: /about/
+tags:
+- rust
+- 'web dev'
+nested:
+  inner:
+    value: \"a # b\"
+";
+        let value = parse(yaml).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "title": "My Site",
+                "count": 3,
+                "ratio": 0.5,
+                "enabled": true,
+                "missing": null,
+                "links": [
+                    {"name": "Home", "url": "/"},
+                    {"name": "About", "url": "/about/"},
+                ],
+                "tags": ["rust", "web dev"],
+                "nested": {"inner": {"value": "a # b"}},
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_flow_collections() {
+        let value = parse("tags: [rust, \"a, b\", 2]\npoint: {x: 1, y: [2, 3]}\nempty: []\n").unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "tags": ["rust", "a, b", 2],
+                "point": {"x": 1, "y": [2, 3]},
+                "empty": [],
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_block_scalars() {
+        let yaml = "\
+literal: |
+  line one
+  line two
+
+folded: >-
+  folded
+  text
+
+  paragraph
+after: done
+";
+        let value = parse(yaml).unwrap();
+        assert_eq!(value["literal"], "line one\nline two\n");
+        assert_eq!(value["folded"], "folded text\nparagraph");
+        assert_eq!(value["after"], "done");
+    }
+
+    #[test]
+    fn test_parse_scalars() {
+        assert_eq!(parse("- 2024-01-15\n- -7\n- 1e3\n- 'it''s'\n- \"tab\\tu\\u00e9\"\n- http://x.y/z\n").unwrap(),
+            json!(["2024-01-15", -7, 1000.0, "it's", "tab\tué", "http://x.y/z"]));
+        assert_eq!(parse("").unwrap(), Value::Null);
+        assert_eq!(parse("---\nhello\n").unwrap(), json!("hello"));
+    }
+
+    #[test]
+    fn test_parse_nested_sequences() {
+        let value = parse("- - a\n  - b\n- c\n").unwrap();
+        assert_eq!(value, json!([["a", "b"], "c"]));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = parse("a: 1\n    b: 2\n").unwrap_err();
+        assert_eq!(err.line, 2);
+
+        let err = parse("a: \"unterminated\n").unwrap_err();
+        assert_eq!(err.line, 1);
+
+        assert!(parse("a: [1, 2\n").is_err());
+
+        // A block scalar line indented less than the first, but more than
+        // the key, is an error rather than a cut inside a character.
+        let err = parse("desc: |\n    first line\n  aé\n").unwrap_err();
+        assert_eq!(err.line, 3);
+        assert!(err.message.contains("indented less"), "{}", err.message);
+        assert_eq!(parse("desc: |\n    one\n \u{3000}\n    two\n").unwrap()["desc"], "one\n\u{3000}\ntwo\n");
+    }
+}
@@ -1,5 +1,6 @@
 //! Integration tests for anthology using tempfile.
 
+use anthology::features::BuildFeatures;
 use rustmax::tempfile::tempdir;
 use std::fs;
 use std::path::Path;
@@ -166,7 +167,7 @@ fn test_build_output() {
     let collection = anthology::collection::Collection::load(root, &config).unwrap();
 
     let output_dir = root.join("output");
-    anthology::build::build(&collection, &config, &output_dir, false).unwrap();
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
 
     // Check output structure.
     assert!(output_dir.exists());
@@ -200,7 +201,7 @@ fn test_build_with_static_assets() {
     let collection = anthology::collection::Collection::load(root, &config).unwrap();
 
     let output_dir = root.join("output");
-    anthology::build::build(&collection, &config, &output_dir, false).unwrap();
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
 
     // Check static assets copied.
     assert!(output_dir.join("style.css").exists());
@@ -223,7 +224,7 @@ fn test_build_excludes_drafts() {
     let collection = anthology::collection::Collection::load(root, &config).unwrap();
 
     let output_dir = root.join("output");
-    anthology::build::build(&collection, &config, &output_dir, false).unwrap();
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
 
     // Published should exist.
     assert!(output_dir.join("published/index.html").exists());
@@ -245,7 +246,7 @@ fn test_build_includes_drafts_when_requested() {
     let collection = anthology::collection::Collection::load(root, &config).unwrap();
 
     let output_dir = root.join("output");
-    anthology::build::build(&collection, &config, &output_dir, true).unwrap();
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default() | BuildFeatures::DRAFTS).unwrap();
 
     // Both should exist.
     assert!(output_dir.join("published/index.html").exists());
@@ -275,6 +276,37 @@ fn test_search_index_build() {
     assert!(index_content.contains("Python Basics"));
 }
 
+#[test]
+fn test_build_sharded_search_index() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(root, "rust", "Rust Programming", "Learn about memory safety.", false);
+    create_document(root, "python", "Python Basics", "Learn about scripting.", false);
+
+    let config_toml = fs::read_to_string(root.join("anthology.toml")).unwrap()
+        + "\n[search]\nformat = \"sharded\"\ngzip = true\n";
+    fs::write(root.join("anthology.toml"), config_toml).unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::SEARCH_INDEX).unwrap();
+
+    // The index is a manifest pointing at gzipped shards.
+    let manifest = fs::read_to_string(output_dir.join("search-index.json")).unwrap();
+    assert!(manifest.contains("\"format\":\"sharded\""));
+    assert!(manifest.contains("\"me\":\"terms-me.bin.gz\""));
+    assert!(!manifest.contains("Rust Programming"));
+
+    let shards = output_dir.join("search-index");
+    assert!(shards.join("terms-me.bin.gz").exists());
+    assert!(shards.join("lengths.bin.gz").exists());
+    assert!(shards.join("docs-0.json.gz").exists());
+}
+
 #[test]
 fn test_rss_generation() {
     let dir = tempdir().unwrap();
@@ -315,6 +347,565 @@ fn test_sitemap_generation() {
     assert!(sitemap.contains("test.example.com/page2/"));
 }
 
+#[test]
+fn test_build_features_gate_outputs() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(root, "page1", "Page One", "Content", false);
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    // Feeds and sitemap are written only when requested.
+    let output_dir = root.join("output");
+    let features = BuildFeatures::try_from_str_list("rss,sitemap").unwrap();
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    assert!(output_dir.join("page1/index.html").exists());
+    assert!(output_dir.join("rss.xml").exists());
+    assert!(output_dir.join("sitemap.xml").exists());
+    assert!(!output_dir.join("search-index.json").exists());
+    assert!(!output_dir.join("toc.css").exists());
+    assert!(!output_dir.join("highlight.css").exists());
+
+    // Search and compression follow their flags too.
+    let features = BuildFeatures::SEARCH_INDEX | BuildFeatures::COMPRESS;
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    assert!(output_dir.join("search-index.json").exists());
+    assert!(output_dir.join("search.js.gz").exists());
+    assert!(!output_dir.join("rss.xml").exists());
+}
+
+#[test]
+fn test_build_minify() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(
+        root,
+        "code",
+        "Code",
+        "Intro   text.\n\n<!--more-->\n\n```\nfn main() {\n    println!(\"hi\");\n}\n```\n",
+        false,
+    );
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    let features = BuildFeatures::default() | BuildFeatures::MINIFY;
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    let doc_html = fs::read_to_string(output_dir.join("code/index.html")).unwrap();
+    assert!(doc_html.contains("Intro text."));
+    assert!(doc_html.contains("<!--more-->"));
+    assert!(doc_html.contains("    "), "code indentation should survive");
+    assert!(!doc_html.contains("\n\n"));
+
+    let toc_css = fs::read_to_string(output_dir.join("toc.css")).unwrap();
+    assert!(!toc_css.contains("/*"));
+    assert!(!toc_css.contains("\n  "));
+}
+
+#[test]
+fn test_build_responsive_images() {
+    use rustmax::image::{ImageBuffer, Rgb, RgbImage};
+
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    fs::create_dir_all(root.join("static/img")).unwrap();
+    let img: RgbImage = ImageBuffer::from_fn(400, 200, |x, y| Rgb([x as u8, y as u8, 128]));
+    img.save(root.join("static/img/photo.png")).unwrap();
+
+    let mut config_toml = fs::read_to_string(root.join("anthology.toml")).unwrap();
+    config_toml.push_str("\n[images]\nwidths = [100, 200]\nsizes = \"50vw\"\n");
+    fs::write(root.join("anthology.toml"), config_toml).unwrap();
+
+    create_document(root, "gallery", "Gallery", "![Photo](/img/photo.png)", false);
+
+    // Bundle images follow their document: a draft's are left out, and a
+    // published one's are written under its slug.
+    for (dir, frontmatter) in [("secret", "title = \"Secret\"\ndraft = true"), ("trip", "title = \"Trip\"\nslug = \"journey\"")] {
+        fs::create_dir_all(root.join("content").join(dir)).unwrap();
+        fs::write(root.join("content").join(dir).join("index.md"), format!("---\n{}\n---\n\n![Photo](photo.png)\n", frontmatter)).unwrap();
+        img.save(root.join("content").join(dir).join("photo.png")).unwrap();
+    }
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    let features = BuildFeatures::default() | BuildFeatures::IMAGES;
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    assert!(output_dir.join("img/photo-100w.png").exists());
+    assert!(output_dir.join("img/photo-200w.webp").exists());
+    assert!(output_dir.join("img/photo.webp").exists());
+    assert!(root.join(".anthology-cache/images").exists());
+    assert!(output_dir.join("journey/photo-100w.png").exists());
+    assert!(!output_dir.join("trip").exists());
+    for name in ["photo.png", "photo.webp", "photo-100w.png", "photo-100w.webp"] {
+        assert!(!output_dir.join("secret").join(name).exists(), "{}", name);
+    }
+
+    let html = fs::read_to_string(output_dir.join("gallery/index.html")).unwrap();
+    assert!(html.contains(r#"<picture><source type="image/webp" srcset="/img/photo-100w.webp 100w, /img/photo-200w.webp 200w, /img/photo.webp 400w" sizes="50vw">"#));
+    assert!(html.contains(r#"width="400" height="200"></picture>"#));
+
+    // Without the feature, images are copied as-is.
+    let output_dir = root.join("plain");
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+    assert!(output_dir.join("img/photo.png").exists());
+    assert!(!output_dir.join("img/photo.webp").exists());
+    let html = fs::read_to_string(output_dir.join("gallery/index.html")).unwrap();
+    assert!(!html.contains("<picture>"));
+}
+
+#[test]
+fn test_build_precompression() {
+    use anthology::build::CompressionAlgorithm;
+
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    let mut config_toml = fs::read_to_string(root.join("anthology.toml")).unwrap();
+    config_toml.push_str("\n[compress]\nalgorithms = [\"br\", \"zstd\"]\nmin_size = 64\nextensions = [\"html\"]\n");
+    fs::write(root.join("anthology.toml"), config_toml).unwrap();
+
+    create_document(root, "page1", "Page One", &"Some repeated content. ".repeat(50), false);
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    let features = BuildFeatures::default() | BuildFeatures::COMPRESS;
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    let html = fs::read(output_dir.join("page1/index.html")).unwrap();
+    let br = fs::read(output_dir.join("page1/index.html.br")).unwrap();
+    assert_eq!(CompressionAlgorithm::Brotli.decompress(&br).unwrap(), html);
+    assert!(output_dir.join("page1/index.html.zst").exists());
+    assert!(!output_dir.join("page1/index.html.gz").exists());
+    assert!(!output_dir.join("search.js.br").exists());
+}
+
+#[test]
+fn test_build_page_bundles() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(root, "plain", "Plain", "No bundle here.", false);
+
+    let bundle = root.join("content/my-post");
+    fs::create_dir_all(bundle.join("files")).unwrap();
+    let doc = "---\ntitle = \"Bundled\"\ndate = \"2024-01-15\"\n---\n\n![Cat](cat.txt) [notes](files/notes.txt)\n";
+    fs::write(bundle.join("index.md"), doc).unwrap();
+    fs::write(bundle.join("cat.txt"), "meow").unwrap();
+    fs::write(bundle.join("files/notes.txt"), "notes").unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    let result = anthology::build::build_incremental(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+    assert_eq!(result.rebuilt, 2);
+
+    // Assets sit next to the page, so relative links resolve.
+    let html = fs::read_to_string(output_dir.join("my-post/index.html")).unwrap();
+    assert!(html.contains(r#"src="cat.txt""#));
+    assert_eq!(fs::read_to_string(output_dir.join("my-post/cat.txt")).unwrap(), "meow");
+    assert!(output_dir.join("my-post/files/notes.txt").exists());
+
+    // Unchanged bundles are skipped.
+    let result = anthology::build::build_incremental(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+    assert_eq!(result.skipped, 2);
+
+    // A changed asset rebuilds its bundle only.
+    fs::write(bundle.join("cat.txt"), "purr").unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    let result = anthology::build::build_incremental(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+    assert_eq!((result.rebuilt, result.skipped), (1, 1));
+    assert_eq!(fs::read_to_string(output_dir.join("my-post/cat.txt")).unwrap(), "purr");
+
+    // A missing output asset also rebuilds the bundle.
+    fs::remove_file(output_dir.join("my-post/files/notes.txt")).unwrap();
+    let result = anthology::build::build_incremental(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+    assert_eq!(result.rebuilt, 1);
+    assert!(output_dir.join("my-post/files/notes.txt").exists());
+}
+
+#[test]
+fn test_build_sections() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    fs::create_dir_all(root.join("content/blog/2026")).unwrap();
+    fs::create_dir_all(root.join("content/guides")).unwrap();
+
+    fs::write(
+        root.join("content/blog/_index.md"),
+        "---\ntitle = \"The Blog\"\ndescription = \"Posts\"\n---\n\nWelcome to the *blog*.\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("content/blog/2026/launch.md"),
+        "---\ntitle = \"Launch\"\ndate = \"2026-01-02\"\n---\n\nWe launched.\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("content/guides/setup.md"),
+        "---\ntitle = \"Setup\"\n---\n\nInstall it.\n",
+    )
+    .unwrap();
+
+    let section_template = r#"<h1>{{ title }}</h1>{{ content | safe }}
+parent={% if parent %}{{ parent.url | safe }}{% endif %}
+children={% for c in children %}{{ c.url | safe }};{% endfor %}
+pages={% for p in pages %}{{ p.url | safe }};{% endfor %}"#;
+    fs::write(root.join("templates/section.html"), section_template).unwrap();
+
+    let doc_template = r#"<h1>{{ title }}</h1>
+section={{ section.title }} parent={% if parent %}{{ parent.url | safe }}{% endif %}"#;
+    fs::write(root.join("templates/default.html"), doc_template).unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    assert_eq!(collection.documents.len(), 2);
+
+    let output_dir = root.join("output");
+    let features = BuildFeatures::default() | BuildFeatures::SITEMAP;
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    // Documents are nested under their section.
+    let launch = fs::read_to_string(output_dir.join("blog/2026/launch/index.html")).unwrap();
+    assert!(launch.contains("section=2026 parent=/blog/"));
+
+    let blog = fs::read_to_string(output_dir.join("blog/index.html")).unwrap();
+    assert!(blog.contains("<h1>The Blog</h1>"));
+    assert!(blog.contains("<em>blog</em>"));
+    assert!(blog.contains("parent=/\n"));
+    assert!(blog.contains("children=/blog/2026/;"));
+    assert!(blog.ends_with("pages="), "blog has no direct pages");
+
+    let year = fs::read_to_string(output_dir.join("blog/2026/index.html")).unwrap();
+    assert!(year.contains("<h1>2026</h1>"));
+    assert!(year.contains("pages=/blog/2026/launch/;"));
+
+    assert!(output_dir.join("guides/index.html").exists());
+    assert!(output_dir.join("guides/setup/index.html").exists());
+
+    let sitemap = fs::read_to_string(output_dir.join("sitemap.xml")).unwrap();
+    assert!(sitemap.contains("https://test.example.com/blog/2026/launch/"));
+    assert!(sitemap.contains("https://test.example.com/guides/</loc>"));
+}
+
+#[test]
+fn test_build_pagination() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    let config_toml = fs::read_to_string(root.join("anthology.toml")).unwrap()
+        + "\n[content]\npaginate = 2\n";
+    fs::write(root.join("anthology.toml"), config_toml).unwrap();
+
+    for i in 1..=5 {
+        create_document(root, &format!("doc{}", i), &format!("Doc {}", i), "Body.", false);
+    }
+
+    let listing = r#"page={{ paginator.current }}/{{ paginator.total }} items={{ paginator.items | length }}
+prev={% if paginator.prev_url %}{{ paginator.prev_url | safe }}{% endif %}
+next={% if paginator.next_url %}{{ paginator.next_url | safe }}{% endif %}"#;
+    fs::write(root.join("templates/index.html"), listing).unwrap();
+    fs::write(root.join("templates/tag.html"), listing).unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    let features = BuildFeatures::default() | BuildFeatures::SITEMAP;
+    anthology::build::build_incremental(&collection, &config, &output_dir, features).unwrap();
+
+    let first = fs::read_to_string(output_dir.join("index.html")).unwrap();
+    assert!(first.contains("page=1/3 items=2"));
+    assert!(first.contains("prev=\n"));
+    assert!(first.contains("next=/page/2/"));
+
+    let last = fs::read_to_string(output_dir.join("page/3/index.html")).unwrap();
+    assert!(last.contains("page=3/3 items=1"));
+    assert!(last.contains("prev=/page/2/"));
+
+    let tag = fs::read_to_string(output_dir.join("tags/test/page/2/index.html")).unwrap();
+    assert!(tag.contains("page=2/3"));
+    assert!(tag.contains("next=/tags/test/page/3/"));
+
+    let sitemap = fs::read_to_string(output_dir.join("sitemap.xml")).unwrap();
+    assert!(sitemap.contains("https://test.example.com/page/3/"));
+    assert!(sitemap.contains("https://test.example.com/tags/test/</loc>"));
+    assert!(sitemap.contains("https://test.example.com/tags/test/page/3/</loc>"));
+
+    // Pages no longer needed are removed on the next incremental build.
+    fs::remove_file(root.join("content/doc5.md")).unwrap();
+    fs::remove_file(root.join("content/doc4.md")).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    anthology::build::build_incremental(&collection, &config, &output_dir, features).unwrap();
+
+    assert!(output_dir.join("page/2/index.html").exists());
+    assert!(!output_dir.join("page/3").exists());
+    assert!(!output_dir.join("tags/test/page/3").exists());
+}
+
+#[test]
+fn test_build_taxonomies() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    let config_toml = fs::read_to_string(root.join("anthology.toml")).unwrap()
+        + "\n[content]\ntaxonomies = [\"tags\", \"categories\", \"series\", \"authors\"]\n";
+    fs::write(root.join("anthology.toml"), config_toml).unwrap();
+
+    fs::write(
+        root.join("content/one.md"),
+        "---\ntitle = \"One\"\ntags = [\"rust\"]\ncategories = [\"guides\", \"tools\"]\nseries = \"intro\"\nauthors = [\"Jane Doe\", \"../../pwned\"]\n---\n\nFirst.\n",
+    )
+    .unwrap();
+    fs::write(
+        root.join("content/two.md"),
+        "---\ntitle = \"Two\"\ncategories = [\"guides\"]\nauthors = \"jane doe\"\n---\n\nSecond.\n",
+    )
+    .unwrap();
+
+    fs::write(
+        root.join("templates/term.html"),
+        "{{ taxonomy }}/{{ term }}: {% for doc in documents %}{{ doc.title }};{% endfor %}",
+    )
+    .unwrap();
+    fs::write(root.join("templates/tag.html"), "tag {{ tag }}").unwrap();
+    fs::write(
+        root.join("templates/taxonomy.html"),
+        "{{ title }}: {% for t in terms %}{{ t.name }}={{ t.count }};{% endfor %}",
+    )
+    .unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    assert_eq!(collection.terms("categories"), vec!["guides", "tools"]);
+
+    let output_dir = root.join("output");
+    let features = BuildFeatures::default() | BuildFeatures::RSS;
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    let guides = fs::read_to_string(output_dir.join("categories/guides/index.html")).unwrap();
+    assert!(guides.starts_with("categories/guides: "));
+    assert!(guides.contains("One;") && guides.contains("Two;"));
+
+    let series = fs::read_to_string(output_dir.join("series/intro/index.html")).unwrap();
+    assert_eq!(series, "series/intro: One;");
+
+    // Tags keep their own template.
+    let rust = fs::read_to_string(output_dir.join("tags/rust/index.html")).unwrap();
+    assert_eq!(rust, "tag rust");
+
+    let index = fs::read_to_string(output_dir.join("categories/index.html")).unwrap();
+    assert_eq!(index, "Categories: guides=2;tools=1;");
+
+    let feed = fs::read_to_string(output_dir.join("categories/tools/rss.xml")).unwrap();
+    assert!(feed.contains("https://test.example.com/one/"));
+    assert!(!feed.contains("https://test.example.com/two/"));
+
+    // Terms are written under their slugs and keep their names for display.
+    assert_eq!(collection.terms("authors"), vec!["../../pwned", "Jane Doe"]);
+    assert!(!root.join("pwned").exists() && !output_dir.join("pwned").exists());
+    let pwned = fs::read_to_string(output_dir.join("authors/pwned/index.html")).unwrap();
+    assert_eq!(pwned, "authors/..&#x2F;..&#x2F;pwned: One;");
+    let jane = fs::read_to_string(output_dir.join("authors/jane-doe/index.html")).unwrap();
+    assert_eq!(jane, "authors/Jane Doe: One;Two;");
+    assert!(output_dir.join("authors/jane-doe/rss.xml").exists());
+    let index = fs::read_to_string(output_dir.join("authors/index.html")).unwrap();
+    assert_eq!(index, "Authors: ..&#x2F;..&#x2F;pwned=1;Jane Doe=2;");
+
+    // Taxonomy names must be usable as URL segments.
+    let mut config = config;
+    config.content.taxonomies.push("bad/name".to_string());
+    assert!(anthology::build::build(&collection, &config, &output_dir, features).is_err());
+}
+
+#[test]
+fn test_build_languages() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    let config_toml = fs::read_to_string(root.join("anthology.toml")).unwrap()
+        + "\n[languages.de]\ntitle = \"Testsammlung\"\n\n[languages.ja]\nname = \"日本語\"\n";
+    fs::write(root.join("anthology.toml"), config_toml).unwrap();
+
+    fs::create_dir_all(root.join("content/ja")).unwrap();
+    fs::write(root.join("content/hello.md"), "---\ntitle = \"Hello\"\n---\n\nHello world.\n").unwrap();
+    fs::write(root.join("content/hello.de.md"), "---\ntitle = \"Hallo\"\n---\n\nHallo Welt.\n").unwrap();
+    fs::write(root.join("content/ja/hello.md"), "---\ntitle = \"Konnichiwa\"\n---\n\nKonnichiwa.\n").unwrap();
+    fs::write(root.join("content/only.md"), "---\ntitle = \"Only English\"\n---\n\nUntranslated.\n").unwrap();
+
+    let doc_template = r#"<html lang="{{ lang }}">{{ title }}
+{% for t in translations %}{{ t.lang }}={{ t.url | safe }};{% endfor %}"#;
+    fs::write(root.join("templates/default.html"), doc_template).unwrap();
+    fs::write(
+        root.join("templates/index.html"),
+        "{{ site_title }}: {% for doc in documents %}{{ doc.title }};{% endfor %}",
+    )
+    .unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    let features = BuildFeatures::default() | BuildFeatures::SITEMAP | BuildFeatures::RSS;
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    let hello = fs::read_to_string(output_dir.join("hello/index.html")).unwrap();
+    assert!(hello.starts_with("<html lang=\"en\">Hello"));
+    assert!(hello.contains("de=/de/hello/;"));
+    assert!(hello.contains("ja=/ja/hello/;"));
+
+    let hallo = fs::read_to_string(output_dir.join("de/hello/index.html")).unwrap();
+    assert!(hallo.starts_with("<html lang=\"de\">Hallo"));
+    assert!(hallo.contains("en=/hello/;"));
+
+    // Each language has its own index, feed, and search index.
+    let index = fs::read_to_string(output_dir.join("index.html")).unwrap();
+    assert!(index.starts_with("Test Collection: "));
+    assert!(!index.contains("Hallo"));
+    let german = fs::read_to_string(output_dir.join("de/index.html")).unwrap();
+    assert_eq!(german, "Testsammlung: Hallo;");
+
+    let feed = fs::read_to_string(output_dir.join("de/rss.xml")).unwrap();
+    assert!(feed.contains("https://test.example.com/de/hello/"));
+    assert!(!fs::read_to_string(output_dir.join("rss.xml")).unwrap().contains("/de/"));
+
+    let search = fs::read_to_string(output_dir.join("ja/search-index.json")).unwrap();
+    assert!(search.contains("Konnichiwa") && !search.contains("Hallo"));
+
+    let sitemap = fs::read_to_string(output_dir.join("sitemap.xml")).unwrap();
+    assert!(sitemap.contains(
+        r#"<xhtml:link rel="alternate" hreflang="de" href="https://test.example.com/de/hello/" />"#
+    ));
+    assert!(sitemap.contains(
+        r#"<xhtml:link rel="alternate" hreflang="en" href="https://test.example.com/hello/" />"#
+    ));
+    let only = sitemap.split("<loc>https://test.example.com/only/</loc>").nth(1).unwrap();
+    assert!(only.trim_start().starts_with("</url>"), "untranslated documents have no alternates");
+}
+
+#[test]
+fn test_build_data_files() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    fs::create_dir_all(root.join("data/team")).unwrap();
+    fs::write(
+        root.join("data/nav.yaml"),
+        "# Site navigation.\n- name: Home\n  url: /\n- name: About\n  url: /about/\n",
+    )
+    .unwrap();
+    fs::write(root.join("data/team/lead.toml"), "name = \"Ada\"\n").unwrap();
+    let template = r#"<nav>{% for item in data.nav %}<a>{{ item.name }}</a>{% endfor %}</nav>{{ content | default(value="") | safe }}"#;
+    fs::write(root.join("templates/default.html"), template).unwrap();
+    fs::write(root.join("templates/index.html"), template).unwrap();
+    fs::create_dir_all(root.join("templates/shortcodes")).unwrap();
+    fs::write(root.join("templates/shortcodes/lead.html"), "Led by {{ data.team.lead.name }}.").unwrap();
+    create_document(root, "about", "About", "{{< lead >}}\n", false);
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    let output_dir = root.join("output");
+    let features = BuildFeatures::default();
+
+    let result = anthology::build::build_incremental(&collection, &config, &output_dir, features).unwrap();
+    assert_eq!(result.rebuilt, 1);
+
+    // Documents, listings, and shortcodes all see the data.
+    let doc_html = fs::read_to_string(output_dir.join("about/index.html")).unwrap();
+    assert!(doc_html.contains("<nav><a>Home</a><a>About</a></nav>"));
+    assert!(doc_html.contains("Led by Ada."));
+    let index_html = fs::read_to_string(output_dir.join("index.html")).unwrap();
+    assert!(index_html.contains("<a>About</a>"));
+
+    let result = anthology::build::build_incremental(&collection, &config, &output_dir, features).unwrap();
+    assert_eq!(result.skipped, 1);
+
+    // Changing a data file invalidates the cache.
+    fs::write(root.join("data/team/lead.toml"), "name = \"Grace\"\n").unwrap();
+    let result = anthology::build::build_incremental(&collection, &config, &output_dir, features).unwrap();
+    assert_eq!(result.rebuilt, 1);
+    let doc_html = fs::read_to_string(output_dir.join("about/index.html")).unwrap();
+    assert!(doc_html.contains("Led by Grace."));
+}
+
+#[test]
+fn test_build_related_documents() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    fs::write(
+        root.join("templates/default.html"),
+        r#"<ul>{% for doc in related %}<li>{{ doc.title }}</li>{% endfor %}</ul>"#,
+    )
+    .unwrap();
+    create_document(root, "ownership", "Ownership", "Borrowing rules and lifetimes for references.", false);
+    create_document(root, "borrowing", "Borrowing", "The borrow checker enforces lifetimes of references.", false);
+    create_document(root, "bread", "Bread", "Flour, water, and yeast.", false);
+    let config_toml = fs::read_to_string(root.join("anthology.toml")).unwrap()
+        + "\n[content]\nrelated = 1\n";
+    fs::write(root.join("anthology.toml"), config_toml).unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    let output_dir = root.join("output");
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+
+    // Shared terms outrank the tag every document has.
+    let html = fs::read_to_string(output_dir.join("ownership/index.html")).unwrap();
+    assert_eq!(html, "<ul><li>Borrowing</li></ul>");
+    let html = fs::read_to_string(output_dir.join("bread/index.html")).unwrap();
+    assert_eq!(html.matches("<li>").count(), 1);
+}
+
+#[test]
+fn test_build_features_from_config() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    let config_toml = fs::read_to_string(root.join("anthology.toml")).unwrap().replace(
+        "output_dir = \"output\"",
+        "output_dir = \"output\"\nfeatures = [\"search-index\", \"sitemap\"]\ndrafts = true",
+    );
+    fs::write(root.join("anthology.toml"), config_toml).unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let features = config.build.features().unwrap();
+    assert_eq!(
+        features,
+        BuildFeatures::SEARCH_INDEX | BuildFeatures::SITEMAP | BuildFeatures::DRAFTS
+    );
+
+    let mut bad = config.clone();
+    bad.build.features = Some(vec!["sitemaps".to_string()]);
+    assert!(bad.build.features().is_err());
+}
+
 #[test]
 fn test_json_export() {
     let dir = tempdir().unwrap();
@@ -429,3 +1020,423 @@ fn test_nested_documents() {
     assert!(titles.contains(&&"Top Level".to_string()));
     assert!(titles.contains(&&"Deep Post".to_string()));
 }
+
+#[test]
+fn test_build_expands_shortcodes() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(root, "video", "Video", "Watch this:\n\n{{< youtube abc123 >}}\n", false);
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+
+    let doc_html = fs::read_to_string(output_dir.join("video/index.html")).unwrap();
+    assert!(doc_html.contains("youtube.com/embed/abc123"));
+    assert!(!doc_html.contains("{{&lt;"));
+}
+
+#[test]
+fn test_build_unknown_shortcode_reports_line() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(root, "broken", "Broken", "First line.\n\n{{< nosuch >}}\n", false);
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    let err = anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap_err();
+
+    let message = err.to_string();
+    assert!(message.contains("broken.md:10"), "unexpected error: {}", message);
+    assert!(message.contains("nosuch"));
+}
+
+#[test]
+fn test_build_template_shortcodes() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    fs::create_dir_all(root.join("templates/shortcodes")).unwrap();
+    fs::write(
+        root.join("templates/shortcodes/figure.html"),
+        r#"<figure><img src="{{ src }}"><figcaption>{{ content | safe }}</figcaption></figure>"#,
+    )
+    .unwrap();
+    create_document(
+        root,
+        "gallery",
+        "Gallery",
+        "{{% figure src=\"cat.png\" %}}A cat {{% note %}}asleep{{% /note %}}{{% /figure %}}\n",
+        false,
+    );
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output_dir = root.join("output");
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+
+    // The collection template overrides the built-in figure shortcode.
+    let doc_html = fs::read_to_string(output_dir.join("gallery/index.html")).unwrap();
+    assert!(doc_html.contains(r#"<figure><img src="cat.png"><figcaption>A cat"#));
+    assert!(doc_html.contains("admonition note"));
+}
+
+#[test]
+fn test_check_links() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(root, "guide", "Guide", "## Setup\n\nInstall it.", false);
+    create_document(
+        root,
+        "intro",
+        "Intro",
+        "See the [guide](/guide/) and [setup](../guide/#setup).\n\nBut not [this](/missing/)\nor [that](/guide/#deploy).\n\n## Top\n\n[Back](#top) [here](#top-1) [mail](mailto:a@example.com)",
+        false,
+    );
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let options = anthology::check::LinkOptions::default();
+    let report = anthology::check::check_links(&collection, &config, &options).unwrap();
+
+    assert!(report.pages > 2);
+    assert!(report.internal > 5);
+    let broken: Vec<String> = report
+        .broken
+        .iter()
+        .map(|b| format!("{}:{} {} ({})", b.source.file_name().unwrap().to_string_lossy(), b.line.unwrap(), b.href, b.reason))
+        .collect();
+    assert_eq!(
+        broken,
+        vec![
+            "intro.md:10 /missing/ (page not found)",
+            "intro.md:11 /guide/#deploy (no `#deploy` on /guide/)",
+            "intro.md:15 #top-1 (no `#top-1` on /intro/)",
+        ]
+    );
+    // Nothing is written to the output directory.
+    assert!(!root.join("output").exists());
+}
+
+#[test]
+fn test_error_diagnostics() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    let config = anthology::collection::Config::load(root).unwrap();
+
+    // Frontmatter errors point into the TOML.
+    fs::write(root.join("content/bad.md"), "---\ntitle = \"Bad\"\ndate = 2024-01-15x\n---\n\nBody.\n").unwrap();
+    let err = anthology::collection::Collection::load(root, &config).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("bad.md:3:8: invalid TOML"), "unexpected error: {}", message);
+    assert!(message.contains("3 | date = 2024-01-15x\n  |        ^"), "unexpected error: {}", message);
+    fs::remove_file(root.join("content/bad.md")).unwrap();
+
+    // Shortcode parse errors point at the column nom stopped at.
+    create_document(root, "broken", "Broken", "First line.\n\nSee {{< youtube id=\"abc >}}\n", false);
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    let output_dir = root.join("output");
+    let err = anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap_err();
+    let diagnostic = err.diagnostic().expect("shortcode errors have a location");
+    assert_eq!((diagnostic.line, diagnostic.column), (10, 20));
+    assert!(err.to_string().contains("10 | See {{< youtube"), "unexpected error: {}", err);
+    fs::remove_file(root.join("content/broken.md")).unwrap();
+
+    // Errors in shortcode templates name the template line.
+    fs::create_dir_all(root.join("templates/shortcodes")).unwrap();
+    fs::write(root.join("templates/shortcodes/note.html"), "<aside>\n{{ content | nosuch }}\n</aside>").unwrap();
+    create_document(root, "noted", "Noted", "{{% note %}}Hi{{% /note %}}\n", false);
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    let err = anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("noted.md:8:1"), "unexpected error: {}", message);
+    assert!(message.contains("note.html:2:"), "unexpected error: {}", message);
+
+    // Template syntax errors point at the tag.
+    fs::write(root.join("templates/shortcodes/note.html"), "<aside>\n{{ content | }}\n</aside>").unwrap();
+    let err = anthology::build::TemplateEngine::new(&root.join("templates")).err().unwrap();
+    let diagnostic = err.diagnostic().unwrap_or_else(|| panic!("no location: {:?}", err));
+    assert!(diagnostic.path.ends_with("templates/shortcodes/note.html"));
+    assert_eq!(diagnostic.line, 2);
+    assert_eq!(diagnostic.source_line.as_deref(), Some("{{ content | }}"));
+}
+
+/// Helper to create a document with extra frontmatter lines.
+fn create_dated_document(root: &Path, name: &str, title: &str, extra: &str) {
+    let doc = format!(
+        "---\ntitle = \"{}\"\ndate = \"2024-01-15\"\n{}\n---\n\nDated content.",
+        title, extra
+    );
+    fs::write(root.join("content").join(format!("{}.md", name)), doc).unwrap();
+}
+
+#[test]
+fn test_build_publish_and_expiry_dates() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(root, "current", "Current Post", "Here now.", false);
+    create_dated_document(root, "scheduled", "Scheduled Post", "publish_date = \"2999-01-01\"");
+    create_dated_document(root, "expired", "Expired Post", "expiry_date = \"2000-01-01\"");
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let titles: Vec<_> = collection.published().iter().map(|d| d.frontmatter.title.clone()).collect();
+    assert_eq!(titles, vec!["Current Post"]);
+
+    let output_dir = root.join("output");
+    let features = BuildFeatures::ALL - BuildFeatures::DRAFTS - BuildFeatures::FUTURE;
+    anthology::build::build(&collection, &config, &output_dir, features).unwrap();
+
+    assert!(output_dir.join("current/index.html").exists());
+    assert!(!output_dir.join("scheduled/index.html").exists());
+    assert!(!output_dir.join("expired/index.html").exists());
+
+    for file in ["rss.xml", "sitemap.xml", "search-index.json", "index.html"] {
+        let text = fs::read_to_string(output_dir.join(file)).unwrap();
+        assert!(text.contains("Current") || text.contains("current"), "{} misses the current post", file);
+        assert!(!text.contains("Scheduled") && !text.contains("scheduled"), "{} lists the scheduled post", file);
+        assert!(!text.contains("Expired") && !text.contains("expired"), "{} lists the expired post", file);
+    }
+
+    // --build-future includes scheduled documents but never expired ones.
+    let output_dir = root.join("future");
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default() | BuildFeatures::FUTURE).unwrap();
+    assert!(output_dir.join("scheduled/index.html").exists());
+    assert!(!output_dir.join("expired/index.html").exists());
+}
+
+#[test]
+fn test_incremental_build_removes_expired_pages() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    create_document(root, "keep", "Keep", "Stays.", false);
+    create_document(root, "fading", "Fading", "Goes away.", false);
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    let output_dir = root.join("output");
+    anthology::build::build_incremental(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+    assert!(output_dir.join("fading/index.html").exists());
+
+    create_dated_document(root, "fading", "Fading", "expiry_date = \"2000-01-01\"");
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    anthology::build::build_incremental(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+
+    assert!(output_dir.join("keep/index.html").exists());
+    assert!(!output_dir.join("fading").exists());
+}
+
+#[test]
+fn test_pdf_export() {
+    use anthology::export::{PdfConfig, generate_pdf};
+    use rustmax::image::{ImageBuffer, Rgb, RgbImage};
+
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+
+    create_test_collection(root);
+    let img: RgbImage = ImageBuffer::from_fn(40, 20, |x, y| Rgb([x as u8, y as u8, 128]));
+    img.save(root.join("static/chart.png")).unwrap();
+
+    create_document(
+        root,
+        "guide",
+        "Guide",
+        "## Setup\n\nSee [the intro](intro.md) and [Rust](https://www.rust-lang.org).\n\n```rust\nfn main() {}\n```\n\n![Chart](/chart.png)",
+        false,
+    );
+    create_document(root, "intro", "Intro", "## Welcome\n\nHello.", false);
+    create_document(root, "wip", "Work In Progress", "Unfinished.", true);
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+
+    let output = root.join("book.pdf");
+    let pdf_config = PdfConfig {
+        title: Some("Field Guide".to_string()),
+        ..Default::default()
+    };
+    generate_pdf(&collection, &config, &output, &pdf_config).unwrap();
+
+    let pdf = fs::read(&output).unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+    let text = String::from_utf8_lossy(&pdf);
+    assert!(text.contains("/Outlines"));
+    let hex = |s: &str| s.encode_utf16().map(|u| format!("{:04X}", u)).collect::<String>();
+    assert!(text.contains(&hex("Field Guide")));
+    assert!(text.contains(&hex("Setup")));
+    assert!(text.contains(&hex("Welcome")));
+    assert!(!text.contains(&hex("Work In Progress")));
+    assert!(text.contains("/Subtype /Image"));
+    assert!(text.contains("/URI (https://www.rust-lang.org)"));
+    // The link to another document jumps within the PDF.
+    assert!(text.contains("/Dest ["));
+}
+
+#[test]
+fn test_import_hugo_site_builds() {
+    use anthology::import::{Source, import_site};
+
+    let dir = tempdir().unwrap();
+    let site = dir.path().join("hugo");
+    let write = |path: &str, content: &str| {
+        let path = site.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    };
+    write("hugo.toml", "title = 'Field Notes'\nbaseURL = 'https://example.com/'\n[permalinks]\nposts = '/:year/:slug/'\n");
+    write("content/_index.md", "---\ntitle: Home\n---\nWelcome.\n");
+    write("content/posts/_index.md", "---\ntitle: Posts\n---\n");
+    write(
+        "content/posts/first.md",
+        "---\ntitle: First\ndate: 2024-03-01T09:00:00Z\ntags: [rust]\ncategories: [notes]\n---\nSee {{< ref \"trip\" >}}.\n",
+    );
+    write("content/posts/trip/index.md", "+++\ntitle = 'Trip'\ndate = 2024-04-02\n+++\n![Map](map.png)\n");
+    write("content/posts/trip/map.png", "png");
+    write("static/robots.txt", "User-agent: *\n");
+
+    let root = dir.path().join("collection");
+    let report = import_site(Source::Hugo, &site, &root).unwrap();
+    // The permalink moves posts out of `posts/`, which is left a plain page.
+    assert_eq!((report.documents, report.sections, report.assets), (3, 1, 2));
+    assert!(report.notes.is_empty(), "{:?}", report.notes);
+
+    let config = anthology::collection::Config::load(&root).unwrap();
+    assert_eq!(config.collection.title, "Field Notes");
+    let collection = anthology::collection::Collection::load(&root, &config).unwrap();
+    let first = collection.documents.iter().find(|d| d.frontmatter.title == "First").unwrap();
+    assert_eq!(first.terms("categories"), ["notes"]);
+    assert!(first.content.contains("See /2024/trip/."));
+
+    let output_dir = root.join("output");
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+    assert!(output_dir.join("2024/first/index.html").exists());
+    assert!(output_dir.join("2024/trip/index.html").exists());
+    assert!(output_dir.join("2024/trip/map.png").exists());
+    assert!(output_dir.join("robots.txt").exists());
+    assert!(output_dir.join("categories/notes/index.html").exists());
+
+    // Importing over an existing collection is refused.
+    assert!(import_site(Source::Hugo, &site, &root).is_err());
+}
+
+#[test]
+fn test_yaml_and_json_frontmatter() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    create_test_collection(root);
+    let content = root.join("content");
+    fs::write(content.join("yaml.md"), "---\ntitle: From YAML\ndate: 2024-02-01\ntags: [rust]\nseries: Notes\n---\n\nYAML body.\n").unwrap();
+    fs::write(content.join("json.md"), "{\"title\": \"From JSON\", \"date\": \"2024-03-01\", \"cover\": null}\n\nJSON body.\n").unwrap();
+    fs::write(content.join("plus.md"), "+++\ntitle = \"From TOML\"\n+++\n\nTOML body.\n").unwrap();
+    create_document(root, "kept", "Kept", "# comment kept\n", false);
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    let yaml = collection.documents.iter().find(|d| d.frontmatter.title == "From YAML").unwrap();
+    assert_eq!(yaml.terms("series"), ["Notes"]);
+    assert!(collection.documents.iter().any(|d| d.frontmatter.title == "From JSON"));
+
+    // A file that fails to parse stops the conversion before anything is written.
+    let yaml_raw = fs::read_to_string(content.join("yaml.md")).unwrap();
+    fs::write(content.join("zz-broken.md"), "---\ntitle: [unclosed\n---\n").unwrap();
+    assert!(anthology::collection::convert_frontmatter(root).is_err());
+    assert_eq!(fs::read_to_string(content.join("yaml.md")).unwrap(), yaml_raw);
+    fs::remove_file(content.join("zz-broken.md")).unwrap();
+
+    let kept = fs::read_to_string(content.join("kept.md")).unwrap();
+    let mut converted = anthology::collection::convert_frontmatter(root).unwrap();
+    converted.sort();
+    let names: Vec<_> = converted.iter().map(|(p, _)| p.file_name().unwrap().to_str().unwrap()).collect();
+    assert_eq!(names, ["json.md", "plus.md", "yaml.md"]);
+    // Null values can't be written as TOML, so their keys are reported.
+    assert_eq!(converted[0].1, ["cover"]);
+    assert!(converted[1].1.is_empty() && converted[2].1.is_empty());
+    assert_eq!(fs::read_to_string(content.join("kept.md")).unwrap(), kept);
+    assert_eq!(
+        fs::read_to_string(content.join("yaml.md")).unwrap(),
+        "---\ntitle = \"From YAML\"\ndate = \"2024-02-01\"\ntags = [\"rust\"]\nseries = \"Notes\"\n---\n\nYAML body.\n"
+    );
+
+    // The converted collection reads the same, and converting again does nothing.
+    let reloaded = anthology::collection::Collection::load(root, &config).unwrap();
+    assert_eq!(reloaded.documents.len(), collection.documents.len());
+    for doc in &reloaded.documents {
+        assert_eq!(doc.frontmatter_format, Some(anthology::collection::FrontmatterFormat::Toml));
+    }
+    assert!(anthology::collection::convert_frontmatter(root).unwrap().is_empty());
+}
+
+#[test]
+fn test_build_with_theme() {
+    let dir = tempdir().unwrap();
+    let root = dir.path();
+    create_test_collection(root);
+    create_document(root, "post", "Post", "{{% badge %}}new{{% /badge %}}", false);
+    let write = |path: &str, content: &str| {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    };
+    // The collection's own template would win over the theme's.
+    fs::remove_file(root.join("templates/default.html")).unwrap();
+    write(
+        "themes/base/theme.toml",
+        "description = \"Plain\"\n[config.collection]\ndescription = \"From the theme\"\n[config.content]\nrelated = 7\n",
+    );
+    write("themes/base/templates/base.html", "<html>{{ site_description }}|{% block body %}{% endblock %}</html>");
+    write("themes/base/templates/default.html", "{% extends \"base.html\" %}{% block body %}base{% endblock %}");
+    write("themes/base/templates/shortcodes/badge.html", "<b>{{ content }}</b>");
+    write("themes/base/static/style.css", "base");
+    write("themes/base/static/app.js", "base");
+    write("themes/docs/theme.toml", "extends = \"base\"\n[config.content]\nrelated = 3\n");
+    write(
+        "themes/docs/templates/default.html",
+        "{% extends \"base.html\" %}{% block body %}docs:{{ content | safe }}{% endblock %}",
+    );
+    write("static/style.css", "site");
+    let config_text = fs::read_to_string(root.join("anthology.toml")).unwrap();
+    let with_theme = |theme: &str| config_text.replace("[build]\n", &format!("[build]\ntheme = \"{}\"\n", theme));
+    fs::write(root.join("anthology.toml"), with_theme("docs")).unwrap();
+
+    let config = anthology::collection::Config::load(root).unwrap();
+    let names: Vec<&str> = config.themes.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["docs", "base"]);
+    assert_eq!(config.collection.description, "From the theme");
+    assert_eq!(config.content.related, 3);
+
+    let collection = anthology::collection::Collection::load(root, &config).unwrap();
+    let output_dir = root.join("output");
+    anthology::build::build(&collection, &config, &output_dir, BuildFeatures::default()).unwrap();
+
+    let page = fs::read_to_string(output_dir.join("post/index.html")).unwrap();
+    assert!(page.starts_with("<html>From the theme|docs:"), "{}", page);
+    assert!(page.contains("<b>new</b>"), "{}", page);
+    assert_eq!(fs::read_to_string(output_dir.join("style.css")).unwrap(), "site");
+    assert_eq!(fs::read_to_string(output_dir.join("app.js")).unwrap(), "base");
+
+    // A theme that is not installed is an error.
+    fs::write(root.join("anthology.toml"), with_theme("gone")).unwrap();
+    let err = anthology::collection::Config::load(root).unwrap_err();
+    assert!(err.to_string().contains("gone: not installed"), "{}", err);
+}